@@ -0,0 +1,6 @@
+// Rebuild when a migration is added, `sqlx::migrate!` embeds them at compile time
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=migrations_sqlite");
+    println!("cargo:rerun-if-changed=migrations_mysql");
+}
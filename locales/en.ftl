@@ -0,0 +1,150 @@
+# Messages use a subset of the Fluent syntax: `key = text`, with `{ $name }` placeholders
+# A key missing from a locale falls back to English
+
+## Command descriptions, keyed `<command>-description`
+achievements-description = Shows the achievements a user unlocked
+activity-description = Shows what the members of the server are playing right now
+admin-description = Manages the users allowed to use admin commands
+alias-description = Manages the names a game is tracked under
+auditlog-description = Lists the resets, merges and other destructive admin commands
+backup-description = Saves a copy of the whole database, only for the bot owner
+restore-description = Replaces the whole database with a backup, only for the bot owner
+game-description = Manages the tracked games
+compare-description = Compares the playtimes of two users
+config-description = Configures the bot
+digest-description = Manages your weekly playtime digest sent by DM
+export-description = Sends you everything stored about you
+exportcsv-description = Sends the playtimes of the server as a CSV file
+exportjson-description = Sends a JSON snapshot of the games, playtimes and sessions
+fix-session-description = Corrects a user's playtime on a game, after a glitched or spoofed presence
+gameinfo-description = Shows information about a game
+gamestats-description = Shows a user's stats on a game
+gametop-description = Shows the 10 users with the most playtime on a game
+genres-description = Shows a user's playtime by genre
+goal-description = Manages your weekly playtime goals and limits
+hardreset-description = Destroys the database
+heatmap-description = Shows the hours of the day a user plays at
+ignore-description = Manages the activities that aren't tracked
+import-description = Adds the playtimes of a CSV file, from a previous bot or a spreadsheet
+language-description = Chooses the language the bot answers you in
+timezone-description = Sets the timezone your days and weeks are counted in
+topgames-description = Shows the games the server plays the most
+leaderboard-description = Shows the 10 users with the most playtime on the server
+level-description = Shows a user's level, earned by playing
+lfg-description = Manages the DMs you get when enough members play a game together
+linksteam-description = Links your Steam account and imports its playtime
+linkxbox-description = Links your Xbox account and imports its playtime
+log-description = Records a session played away from Discord
+monthly-description = Compares the playtime of a user this month with last month
+musicstats-description = Shows the 10 artists a user listened to the most
+nowplaying-description = Shows what a user is playing right now
+pause-description = Stops tracking your playtime for a while, it resumes on its own
+privacy-description = Chooses who can see your stats
+recent-description = Shows a user's last 10 gaming sessions
+sessions-description = Shows how long a user's sessions last
+reset-description = Resets the player's playtimes
+resetall-description = Resets all playtimes and games
+undo-reset-description = Restores the playtimes erased by the last reset of the past 24 hours
+rolereward-description = Manages the roles given for playtime milestones
+status-description = Shows the health of the bot
+streak-description = Shows how many days in a row a user played, vacations don't break it
+streams-description = Shows the games a user streamed
+summarize-description = Shows the 10 most played games of a user
+total-description = Shows a user's playtime across all games
+trackedgames-description = Manages the games tracked when the whitelist is enabled
+tracking-description = Enables or disables the tracking of your playtime
+trend-description = Charts a user's weekly playtime
+unlink-description = Unlinks an account and deletes the playtime imported from it
+vacation-description = Plans a period away, your streak is frozen and the weekly digests are paused during it
+voicetime-description = Shows the time a user spent in voice channels
+watch-description = Manages the DMs you get when someone starts playing
+webhook-description = Manages the URLs receiving the session and milestone events of the server
+weekdays-description = Shows the playtime of a user by day of the week
+wrapped-description = Recaps your year of gaming
+
+## Shared messages
+error-title = Something went wrong
+error-description = Your command couldn't be completed, please try again later.
+opted-out = { $user } opted out of tracking.
+privacy-hidden = { $user } keeps their stats private.
+nothing-played = { $user } hasn't played anything yet.
+no-playtime = No playtime has been tracked yet.
+rate-limited = You are using /{ $command } too fast, try again in { $seconds }s.
+anonymous = Anonymous
+permission-denied = You don't have the permission to use this command.
+
+## /language
+language-set = I will answer you in English from now on.
+language-reset = I will answer you in the language of the server from now on.
+
+## /leaderboard
+leaderboard-title = Server playtime leaderboard
+leaderboard-placement = You are #{ $rank } with { $playtime }.
+leaderboard-unranked = You aren't on this leaderboard.
+
+## /topgames
+topgames-title = Most played games ({ $period })
+topgames-by-playtime = By playtime
+topgames-by-players = By players
+topgames-players = { $players } players
+
+## /summarize
+summary-title = { $user }'s playtime summary ({ $period })
+summary-streamed = Also streamed for { $playtime }, see /streams.
+summary-rank = { $user } is #{ $rank } of { $players } tracked players (top { $percent }%).
+summary-sessions = { $sessions } sessions, { $average } on average
+summary-page = Page { $page }/{ $pages }
+summary-previous = Previous
+summary-next = Next
+view-playtime-name = View Playtime
+period-today = today
+period-week = this week
+period-month = this month
+period-year = this year
+period-all = all-time
+
+## /total
+total-title = { $user }'s total playtime
+total-playtime = Total playtime
+total-games = Games
+total-rank = Rank
+total-rank-value = #{ $rank } of { $users }
+total-imported = Imported from { $source }
+total-all-sources = All sources
+
+## /streak
+streak-title = { $user }'s streak
+streak-current = Current streak
+streak-longest = Longest streak
+streak-one-day = 1 day
+streak-days = { $days } days
+streak-vacation = On vacation from { $start } to { $end }, the streak is frozen
+
+## Confirmations of the destructive commands
+confirm-yes = Confirm
+confirm-no = Cancel
+confirm-expired = This confirmation expired, run the command again.
+confirm-cancelled = Cancelled.
+
+## Reports posted to the servers
+report-daily = Daily report
+report-weekly = Weekly report
+report-monthly = Monthly report
+report-period = From <t:{ $start }:D> to <t:{ $end }:D>
+report-top-players = Top players
+report-top-games = Top games
+report-no-games = No games were played.
+game-role-started = 🎮 { $user } just started playing { $game }.
+
+## DMs
+digest-title = Your weekly digest
+digest-playtime = Playtime
+digest-change = Versus last week
+digest-top-game = Top game
+digest-nothing = Nothing
+digest-footer = Use /digest disable to stop receiving these.
+watch-started = 👀 { $user } started playing { $game }.
+lfg-playing = 🎮 { $players } members of { $guild } are playing { $game } right now.
+lfg-the-server = the server
+goal-limit-exceeded = ⏰ You went over your limit of { $playtime } on { $game } this week.
+goal-reached = 🎯 You reached your goal of { $playtime } on { $game } this week!
@@ -0,0 +1,150 @@
+# Messages use a subset of the Fluent syntax: `key = text`, with `{ $name }` placeholders
+# A key missing from a locale falls back to English
+
+## Command descriptions, keyed `<command>-description`
+achievements-description = Affiche les succès débloqués par un utilisateur
+activity-description = Affiche ce à quoi jouent les membres du serveur en ce moment
+admin-description = Gère les utilisateurs autorisés à utiliser les commandes d'administration
+alias-description = Gère les noms sous lesquels un jeu est suivi
+auditlog-description = Liste les réinitialisations, fusions et autres commandes admin destructrices
+backup-description = Sauvegarde toute la base de données, réservé au propriétaire du bot
+restore-description = Remplace toute la base de données par une sauvegarde, réservé au propriétaire du bot
+game-description = Gère les jeux suivis
+compare-description = Compare le temps de jeu de deux utilisateurs
+config-description = Configure le bot
+digest-description = Gère le récapitulatif hebdomadaire de ton temps de jeu envoyé en MP
+export-description = T'envoie tout ce qui est enregistré à ton sujet
+exportcsv-description = Envoie le temps de jeu du serveur dans un fichier CSV
+exportjson-description = Envoie un instantané JSON des jeux, temps de jeu et sessions
+fix-session-description = Corrige le temps de jeu d'un utilisateur sur un jeu, après une présence erronée ou usurpée
+gameinfo-description = Affiche des informations sur un jeu
+gamestats-description = Affiche les statistiques d'un utilisateur sur un jeu
+gametop-description = Affiche les 10 utilisateurs ayant le plus joué à un jeu
+genres-description = Affiche le temps de jeu d'un utilisateur par genre
+goal-description = Gère tes objectifs et limites de temps de jeu hebdomadaires
+hardreset-description = Détruit la base de données
+heatmap-description = Affiche les heures de la journée auxquelles un utilisateur joue
+ignore-description = Gère les activités qui ne sont pas suivies
+import-description = Ajoute les temps de jeu d'un fichier CSV, venant d'un ancien bot ou d'un tableur
+language-description = Choisit la langue dans laquelle le bot te répond
+timezone-description = Définit le fuseau horaire dans lequel tes jours et semaines sont comptés
+topgames-description = Affiche les jeux auxquels le serveur joue le plus
+leaderboard-description = Affiche les 10 utilisateurs ayant le plus joué sur le serveur
+level-description = Affiche le niveau d'un utilisateur, gagné en jouant
+lfg-description = Gère les MP reçus quand assez de membres jouent ensemble à un jeu
+linksteam-description = Lie ton compte Steam et importe son temps de jeu
+linkxbox-description = Lie ton compte Xbox et importe son temps de jeu
+log-description = Enregistre une session jouée en dehors de Discord
+monthly-description = Compare le temps de jeu d'un utilisateur ce mois-ci avec le mois dernier
+musicstats-description = Affiche les 10 artistes les plus écoutés par un utilisateur
+nowplaying-description = Affiche ce à quoi joue un utilisateur en ce moment
+pause-description = Arrête de suivre ton temps de jeu pendant un moment, il reprend tout seul
+privacy-description = Choisit qui peut voir tes statistiques
+recent-description = Affiche les 10 dernières sessions de jeu d'un utilisateur
+sessions-description = Affiche combien de temps durent les sessions d'un utilisateur
+reset-description = Réinitialise le temps de jeu d'un joueur
+resetall-description = Réinitialise tous les temps de jeu et les jeux
+undo-reset-description = Restaure les temps de jeu effacés par la dernière réinitialisation des dernières 24 heures
+rolereward-description = Gère les rôles donnés en récompense du temps de jeu
+status-description = Affiche l'état du bot
+streak-description = Affiche combien de jours d'affilée un utilisateur a joué, les vacances ne l'interrompent pas
+streams-description = Affiche les jeux diffusés en direct par un utilisateur
+summarize-description = Affiche les 10 jeux les plus joués par un utilisateur
+total-description = Affiche le temps de jeu d'un utilisateur sur tous les jeux
+trackedgames-description = Gère les jeux suivis quand la liste blanche est activée
+tracking-description = Active ou désactive le suivi de ton temps de jeu
+trend-description = Trace le temps de jeu hebdomadaire d'un utilisateur
+unlink-description = Délie un compte et supprime le temps de jeu importé depuis celui-ci
+vacation-description = Prévoit une période d'absence, la série est gelée et les résumés hebdomadaires sont suspendus pendant celle-ci
+voicetime-description = Affiche le temps passé par un utilisateur dans les salons vocaux
+watch-description = Gère les MP reçus quand quelqu'un commence à jouer
+webhook-description = Gère les URL recevant les événements de session et de palier du serveur
+weekdays-description = Affiche le temps de jeu d'un utilisateur par jour de la semaine
+wrapped-description = Récapitule ton année de jeu
+
+## Shared messages
+error-title = Une erreur est survenue
+error-description = Ta commande n'a pas pu aboutir, réessaie plus tard.
+opted-out = { $user } a désactivé le suivi.
+privacy-hidden = { $user } garde ses statistiques privées.
+nothing-played = { $user } n'a encore joué à rien.
+no-playtime = Aucun temps de jeu n'a encore été enregistré.
+rate-limited = Tu utilises /{ $command } trop vite, réessaie dans { $seconds }s.
+anonymous = Anonyme
+permission-denied = Tu n'as pas la permission d'utiliser cette commande.
+
+## /language
+language-set = Je te répondrai en français désormais.
+language-reset = Je te répondrai dans la langue du serveur désormais.
+
+## /leaderboard
+leaderboard-title = Classement du temps de jeu du serveur
+leaderboard-placement = Tu es #{ $rank } avec { $playtime }.
+leaderboard-unranked = Tu n'es pas dans ce classement.
+
+## /topgames
+topgames-title = Jeux les plus joués ({ $period })
+topgames-by-playtime = Par temps de jeu
+topgames-by-players = Par joueurs
+topgames-players = { $players } joueurs
+
+## /summarize
+summary-title = Temps de jeu de { $user } ({ $period })
+summary-streamed = A aussi diffusé en direct pendant { $playtime }, voir /streams.
+summary-rank = { $user } est #{ $rank } sur { $players } joueurs suivis (top { $percent } %).
+summary-sessions = { $sessions } sessions, { $average } en moyenne
+summary-page = Page { $page }/{ $pages }
+summary-previous = Précédent
+summary-next = Suivant
+view-playtime-name = Voir le temps de jeu
+period-today = aujourd'hui
+period-week = cette semaine
+period-month = ce mois-ci
+period-year = cette année
+period-all = depuis le début
+
+## /total
+total-title = Temps de jeu total de { $user }
+total-playtime = Temps de jeu total
+total-games = Jeux
+total-rank = Rang
+total-rank-value = #{ $rank } sur { $users }
+total-imported = Importé depuis { $source }
+total-all-sources = Toutes sources
+
+## /streak
+streak-title = Série de { $user }
+streak-current = Série actuelle
+streak-longest = Plus longue série
+streak-one-day = 1 jour
+streak-days = { $days } jours
+streak-vacation = En vacances du { $start } au { $end }, la série est gelée
+
+## Confirmations of the destructive commands
+confirm-yes = Confirmer
+confirm-no = Annuler
+confirm-expired = Cette confirmation a expiré, relance la commande.
+confirm-cancelled = Annulé.
+
+## Reports posted to the servers
+report-daily = Rapport quotidien
+report-weekly = Rapport hebdomadaire
+report-monthly = Rapport mensuel
+report-period = Du <t:{ $start }:D> au <t:{ $end }:D>
+report-top-players = Meilleurs joueurs
+report-top-games = Jeux les plus joués
+report-no-games = Aucun jeu n'a été joué.
+game-role-started = 🎮 { $user } vient de commencer à jouer à { $game }.
+
+## DMs
+digest-title = Ton résumé hebdomadaire
+digest-playtime = Temps de jeu
+digest-change = Par rapport à la semaine dernière
+digest-top-game = Jeu le plus joué
+digest-nothing = Rien
+digest-footer = Utilise /digest disable pour ne plus les recevoir.
+watch-started = 👀 { $user } a commencé à jouer à { $game }.
+lfg-playing = 🎮 { $players } membres de { $guild } jouent à { $game } en ce moment.
+lfg-the-server = le serveur
+goal-limit-exceeded = ⏰ Tu as dépassé ta limite de { $playtime } sur { $game } cette semaine.
+goal-reached = 🎯 Tu as atteint ton objectif de { $playtime } sur { $game } cette semaine !
@@ -0,0 +1,35 @@
+CREATE TABLE IF NOT EXISTS games (
+    game_id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS game_entries (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    playtime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS game_sessions (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE OR REPLACE FUNCTION remove_session()
+    RETURNS TRIGGER
+    AS
+    $$
+    BEGIN
+        DELETE FROM game_sessions WHERE user_id = NEW.user_id AND game_id = NEW.game_id;
+        RETURN NEW;
+    END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER trigger_clear_sessions
+    AFTER INSERT ON game_entries
+    FOR EACH ROW
+    EXECUTE PROCEDURE remove_session();
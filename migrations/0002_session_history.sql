@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS session_history (
+    session_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT NOT NULL,
+    duration BIGINT NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE INDEX IF NOT EXISTS session_history_user_endtime ON session_history (user_id, endtime);
@@ -0,0 +1,3 @@
+CREATE TABLE IF NOT EXISTS admin_roles (
+    role_id BIGINT PRIMARY KEY
+);
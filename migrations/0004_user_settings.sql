@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id BIGINT PRIMARY KEY,
+    opted_out BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,13 @@
+-- Maps normalized presence names to the game they are counted as
+CREATE TABLE IF NOT EXISTS game_aliases (
+    alias TEXT PRIMARY KEY,
+    game_id BIGINT NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+-- Games that only differ by case or trademark symbols share the oldest game's alias
+INSERT INTO game_aliases (alias, game_id)
+    SELECT LOWER(TRIM(REGEXP_REPLACE(REGEXP_REPLACE(name, '[™®©]', '', 'g'), '\s+', ' ', 'g'))), MIN(game_id)
+    FROM games
+    GROUP BY 1
+    ON CONFLICT DO NOTHING;
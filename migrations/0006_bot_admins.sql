@@ -0,0 +1,3 @@
+CREATE TABLE IF NOT EXISTS bot_admins (
+    user_id BIGINT PRIMARY KEY
+);
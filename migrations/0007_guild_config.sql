@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS guild_config (
+    guild_id BIGINT PRIMARY KEY,
+    report_channel BIGINT,
+    min_session_length BIGINT,
+    locale TEXT NOT NULL DEFAULT 'en',
+    embed_color BIGINT NOT NULL DEFAULT 1752220
+);
@@ -0,0 +1,6 @@
+-- Names are stored normalized, a guild_id of 0 ignores the game in every guild
+CREATE TABLE IF NOT EXISTS ignored_games (
+    guild_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (guild_id, name)
+);
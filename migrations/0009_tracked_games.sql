@@ -0,0 +1,8 @@
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS whitelist_only BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Names are stored normalized, only these games are tracked in guilds with whitelist_only
+CREATE TABLE IF NOT EXISTS tracked_games (
+    guild_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (guild_id, name)
+);
@@ -0,0 +1,3 @@
+CREATE TABLE IF NOT EXISTS digest_subscribers (
+    user_id BIGINT PRIMARY KEY
+);
@@ -0,0 +1,11 @@
+-- Weekly playtime goals, a limit warns when it's exceeded instead of congratulating when it's reached
+CREATE TABLE IF NOT EXISTS goals (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    seconds BIGINT NOT NULL,
+    is_limit BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Start of the week the user was last notified in
+    notified_week BIGINT,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
@@ -0,0 +1,7 @@
+-- Roles given to the members whose total playtime reaches `seconds`
+CREATE TABLE IF NOT EXISTS role_rewards (
+    guild_id BIGINT NOT NULL,
+    role_id BIGINT NOT NULL,
+    seconds BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, role_id)
+);
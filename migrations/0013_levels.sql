@@ -0,0 +1,12 @@
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS xp_per_hour BIGINT NOT NULL DEFAULT 100;
+-- Reaching level n takes level_base_xp * n² XP
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS level_base_xp BIGINT NOT NULL DEFAULT 100;
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS level_channel BIGINT;
+
+-- The last level each member was announced at
+CREATE TABLE IF NOT EXISTS levels (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    level BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS unlocked_badges (
+    user_id BIGINT NOT NULL,
+    badge TEXT NOT NULL,
+    unlocked_at BIGINT NOT NULL,
+    PRIMARY KEY (user_id, badge)
+);
@@ -0,0 +1,12 @@
+-- Sessions without an endtime are still running
+CREATE TABLE IF NOT EXISTS voice_sessions (
+    session_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT
+);
+
+-- A user is in one voice channel at most
+CREATE UNIQUE INDEX IF NOT EXISTS voice_sessions_open ON voice_sessions (user_id) WHERE endtime IS NULL;
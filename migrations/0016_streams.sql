@@ -0,0 +1,19 @@
+-- Streams are kept apart from game sessions, streaming a game isn't playtime
+CREATE TABLE IF NOT EXISTS stream_sessions (
+    user_id BIGINT PRIMARY KEY,
+    game TEXT NOT NULL,
+    url TEXT,
+    starttime BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS stream_history (
+    stream_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    game TEXT NOT NULL,
+    url TEXT,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT NOT NULL,
+    duration BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS stream_history_user_endtime ON stream_history (user_id, endtime);
@@ -0,0 +1,15 @@
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS track_listening BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Listening time is counted per artist, apart from the games
+CREATE TABLE IF NOT EXISTS listen_sessions (
+    user_id BIGINT PRIMARY KEY,
+    artist TEXT NOT NULL,
+    starttime BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS listen_entries (
+    user_id BIGINT NOT NULL,
+    artist TEXT NOT NULL,
+    listentime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, artist)
+);
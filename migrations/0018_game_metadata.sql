@@ -0,0 +1,8 @@
+-- Filled from IGDB by the metadata job, a game without igdb_id had no match
+ALTER TABLE games ADD COLUMN IF NOT EXISTS igdb_id BIGINT;
+ALTER TABLE games ADD COLUMN IF NOT EXISTS title TEXT;
+ALTER TABLE games ADD COLUMN IF NOT EXISTS cover_url TEXT;
+ALTER TABLE games ADD COLUMN IF NOT EXISTS genres TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE games ADD COLUMN IF NOT EXISTS summary TEXT;
+ALTER TABLE games ADD COLUMN IF NOT EXISTS release_date BIGINT;
+ALTER TABLE games ADD COLUMN IF NOT EXISTS metadata_updated BIGINT;
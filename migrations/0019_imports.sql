@@ -0,0 +1,17 @@
+-- External accounts a user linked, like a Steam id
+CREATE TABLE IF NOT EXISTS linked_accounts (
+    user_id BIGINT NOT NULL,
+    platform TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    PRIMARY KEY (user_id, platform)
+);
+
+-- Playtime reported by another platform, kept apart from the tracked game_entries
+CREATE TABLE IF NOT EXISTS imported_entries (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    source TEXT NOT NULL,
+    playtime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id, source),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
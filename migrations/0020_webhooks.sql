@@ -0,0 +1,7 @@
+-- URLs receiving the session and milestone events of a guild
+CREATE TABLE IF NOT EXISTS webhooks (
+    webhook_id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    url TEXT NOT NULL,
+    UNIQUE (guild_id, url)
+);
@@ -0,0 +1,2 @@
+-- Overrides the locale of the guild, NULL follows it
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS locale TEXT;
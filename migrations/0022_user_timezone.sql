@@ -0,0 +1,2 @@
+-- IANA name like Europe/Paris, NULL is UTC
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS timezone TEXT;
@@ -0,0 +1,3 @@
+-- {title} is replaced by the title of the embed
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS title_template TEXT NOT NULL DEFAULT '{title}';
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS show_thumbnails BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,16 @@
+-- Rows deleted by /reset and /resetall are kept here for a day so they can be restored by /undo-reset
+CREATE TABLE IF NOT EXISTS resets (
+    reset_id BIGSERIAL PRIMARY KEY,
+    -- NULL for /resetall
+    user_id BIGINT,
+    resettime BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS reset_archive (
+    reset_id BIGINT NOT NULL,
+    table_name TEXT NOT NULL,
+    row JSONB NOT NULL,
+    FOREIGN KEY (reset_id) REFERENCES resets(reset_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reset_archive_reset_table ON reset_archive (reset_id, table_name);
@@ -0,0 +1,10 @@
+-- Destructive admin commands, shown by /auditlog
+CREATE TABLE IF NOT EXISTS audit_log (
+    entry_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    -- NULL when run outside of a server
+    guild_id BIGINT,
+    action TEXT NOT NULL,
+    details TEXT NOT NULL,
+    actiontime BIGINT NOT NULL
+);
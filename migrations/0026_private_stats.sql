@@ -0,0 +1,2 @@
+-- Whether /summarize answers only to the user when they don't choose
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS private_stats BOOLEAN NOT NULL DEFAULT FALSE;
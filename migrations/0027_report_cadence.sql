@@ -0,0 +1,2 @@
+-- How often the reports are posted to the report channel: daily, weekly or monthly
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS report_cadence TEXT NOT NULL DEFAULT 'weekly';
@@ -0,0 +1,8 @@
+-- The watcher gets a DM when the user starts playing the game, or any game when it's NULL
+-- Games are kept by name so the watches survive the resets and merges
+CREATE TABLE IF NOT EXISTS watches (
+    watcher_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    game_name TEXT
+);
+CREATE UNIQUE INDEX IF NOT EXISTS watches_unique ON watches (watcher_id, user_id, LOWER(COALESCE(game_name, '')));
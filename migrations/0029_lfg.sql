@@ -0,0 +1,10 @@
+-- The user gets a DM once at least min_players members of the guild play the game at the same time,
+-- notified is cleared when they drop below it again. Games are kept by name, like the watches
+CREATE TABLE IF NOT EXISTS lfg_subscriptions (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    game_name TEXT NOT NULL,
+    min_players BIGINT NOT NULL,
+    notified BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE UNIQUE INDEX IF NOT EXISTS lfg_subscriptions_unique ON lfg_subscriptions (guild_id, user_id, LOWER(game_name));
@@ -0,0 +1,10 @@
+-- Session starts of the game are announced in the channel, mentioning the role at most once per cooldown
+-- Games are kept by name, like the watches
+CREATE TABLE IF NOT EXISTS game_roles (
+    guild_id BIGINT NOT NULL,
+    game_name TEXT NOT NULL,
+    role_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    last_ping BIGINT
+);
+CREATE UNIQUE INDEX IF NOT EXISTS game_roles_unique ON game_roles (guild_id, LOWER(game_name));
@@ -0,0 +1,3 @@
+-- save_session deletes the sessions along with the playtime they add, in the same transaction
+DROP TRIGGER IF EXISTS trigger_clear_sessions ON game_entries;
+DROP FUNCTION IF EXISTS remove_session();
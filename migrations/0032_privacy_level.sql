@@ -0,0 +1,2 @@
+-- Who can see the user's stats: public, guild-only (the members of their guilds) or private (only them)
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS privacy_level TEXT NOT NULL DEFAULT 'public';
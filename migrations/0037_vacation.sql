@@ -0,0 +1,4 @@
+-- The user's vacation, from the start of its first day to the end of its last one in their timezone
+-- The digests aren't sent during it
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS vacation_start BIGINT;
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS vacation_end BIGINT;
@@ -0,0 +1,7 @@
+-- The rich presence of the sessions, the last one seen is kept: the state and details usually hold the mode or the map
+ALTER TABLE game_sessions ADD COLUMN IF NOT EXISTS state TEXT;
+ALTER TABLE game_sessions ADD COLUMN IF NOT EXISTS details TEXT;
+ALTER TABLE game_sessions ADD COLUMN IF NOT EXISTS party_size BIGINT;
+ALTER TABLE session_history ADD COLUMN IF NOT EXISTS state TEXT;
+ALTER TABLE session_history ADD COLUMN IF NOT EXISTS details TEXT;
+ALTER TABLE session_history ADD COLUMN IF NOT EXISTS party_size BIGINT;
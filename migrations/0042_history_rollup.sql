@@ -0,0 +1,10 @@
+-- The playtime of the pruned sessions per UTC day, `day` being the timestamp of its midnight
+CREATE TABLE IF NOT EXISTS history_rollup (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    day BIGINT NOT NULL,
+    playtime BIGINT NOT NULL,
+    sessions BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id, day),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
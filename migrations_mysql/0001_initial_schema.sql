@@ -0,0 +1,243 @@
+-- The schema of the Postgres migrations up to 0030, MySQL and MariaDB databases start from it.
+-- The session-clearing trigger is left out, MySqlStorage deletes the sessions it saves itself.
+-- Names compare exactly like in Postgres, except the game names of the watches, LFG subscriptions and game roles
+-- which ignore the case like the LOWER indexes they have there
+CREATE TABLE IF NOT EXISTS games (
+    game_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    igdb_id BIGINT,
+    title TEXT,
+    cover_url TEXT,
+    -- JSON array of names, MariaDB's JSON type is only an alias of LONGTEXT
+    genres LONGTEXT NOT NULL DEFAULT ('[]'),
+    summary TEXT,
+    release_date BIGINT,
+    metadata_updated BIGINT
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS game_entries (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    playtime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS game_sessions (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS session_history (
+    session_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT NOT NULL,
+    duration BIGINT NOT NULL,
+    INDEX session_history_user_endtime (user_id, endtime),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS admin_roles (
+    role_id BIGINT PRIMARY KEY
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id BIGINT PRIMARY KEY,
+    opted_out BOOLEAN NOT NULL DEFAULT FALSE,
+    locale VARCHAR(16),
+    timezone VARCHAR(64),
+    private_stats BOOLEAN NOT NULL DEFAULT FALSE
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS game_aliases (
+    alias VARCHAR(255) PRIMARY KEY,
+    game_id BIGINT NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS bot_admins (
+    user_id BIGINT PRIMARY KEY
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS guild_config (
+    guild_id BIGINT PRIMARY KEY,
+    report_channel BIGINT,
+    min_session_length BIGINT,
+    locale VARCHAR(16) NOT NULL DEFAULT 'en',
+    embed_color BIGINT NOT NULL DEFAULT 1752220,
+    whitelist_only BOOLEAN NOT NULL DEFAULT FALSE,
+    xp_per_hour BIGINT NOT NULL DEFAULT 100,
+    level_base_xp BIGINT NOT NULL DEFAULT 100,
+    level_channel BIGINT,
+    track_listening BOOLEAN NOT NULL DEFAULT FALSE,
+    title_template VARCHAR(255) NOT NULL DEFAULT '{title}',
+    show_thumbnails BOOLEAN NOT NULL DEFAULT TRUE,
+    report_cadence VARCHAR(16) NOT NULL DEFAULT 'weekly'
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS ignored_games (
+    guild_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    PRIMARY KEY (guild_id, name)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS tracked_games (
+    guild_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    PRIMARY KEY (guild_id, name)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS digest_subscribers (
+    user_id BIGINT PRIMARY KEY
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS goals (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    seconds BIGINT NOT NULL,
+    is_limit BOOLEAN NOT NULL DEFAULT FALSE,
+    notified_week BIGINT,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS role_rewards (
+    guild_id BIGINT NOT NULL,
+    role_id BIGINT NOT NULL,
+    seconds BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, role_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS levels (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    level BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS unlocked_badges (
+    user_id BIGINT NOT NULL,
+    badge VARCHAR(64) NOT NULL,
+    unlocked_at BIGINT NOT NULL,
+    PRIMARY KEY (user_id, badge)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+-- There are no partial indexes, starting a session checks that the user has no open one instead
+CREATE TABLE IF NOT EXISTS voice_sessions (
+    session_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT,
+    INDEX voice_sessions_user (user_id, endtime)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS stream_sessions (
+    user_id BIGINT PRIMARY KEY,
+    game VARCHAR(255) NOT NULL,
+    url TEXT,
+    starttime BIGINT NOT NULL
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS stream_history (
+    stream_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    game VARCHAR(255) NOT NULL,
+    url TEXT,
+    starttime BIGINT NOT NULL,
+    endtime BIGINT NOT NULL,
+    duration BIGINT NOT NULL,
+    INDEX stream_history_user_endtime (user_id, endtime)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS listen_sessions (
+    user_id BIGINT PRIMARY KEY,
+    artist VARCHAR(255) NOT NULL,
+    starttime BIGINT NOT NULL
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS listen_entries (
+    user_id BIGINT NOT NULL,
+    artist VARCHAR(255) NOT NULL,
+    listentime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, artist)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS linked_accounts (
+    user_id BIGINT NOT NULL,
+    platform VARCHAR(32) NOT NULL,
+    account_id VARCHAR(255) NOT NULL,
+    PRIMARY KEY (user_id, platform)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS imported_entries (
+    user_id BIGINT NOT NULL,
+    game_id BIGINT NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    playtime BIGINT NOT NULL,
+    PRIMARY KEY (user_id, game_id, source),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    webhook_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    guild_id BIGINT NOT NULL,
+    url VARCHAR(512) NOT NULL,
+    UNIQUE (guild_id, url)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS resets (
+    reset_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT,
+    resettime BIGINT NOT NULL
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+-- The rows are JSON objects
+CREATE TABLE IF NOT EXISTS reset_archive (
+    reset_id BIGINT NOT NULL,
+    table_name VARCHAR(64) NOT NULL,
+    `row` LONGTEXT NOT NULL,
+    INDEX reset_archive_reset_table (reset_id, table_name),
+    FOREIGN KEY (reset_id) REFERENCES resets(reset_id) ON DELETE CASCADE
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    entry_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    guild_id BIGINT,
+    action VARCHAR(64) NOT NULL,
+    details TEXT NOT NULL,
+    actiontime BIGINT NOT NULL
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+-- An empty game name watches every game, unique keys would let NULLs repeat
+CREATE TABLE IF NOT EXISTS watches (
+    watcher_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    game_name VARCHAR(255) COLLATE utf8mb4_unicode_ci NOT NULL DEFAULT '',
+    UNIQUE (watcher_id, user_id, game_name)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS lfg_subscriptions (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    game_name VARCHAR(255) COLLATE utf8mb4_unicode_ci NOT NULL,
+    min_players BIGINT NOT NULL,
+    notified BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (guild_id, user_id, game_name)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE IF NOT EXISTS game_roles (
+    guild_id BIGINT NOT NULL,
+    game_name VARCHAR(255) COLLATE utf8mb4_unicode_ci NOT NULL,
+    role_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    last_ping BIGINT,
+    UNIQUE (guild_id, game_name)
+) DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
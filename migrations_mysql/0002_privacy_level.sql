@@ -0,0 +1,2 @@
+-- Who can see the user's stats: public, guild-only (the members of their guilds) or private (only them)
+ALTER TABLE user_settings ADD COLUMN privacy_level VARCHAR(16) NOT NULL DEFAULT 'public';
@@ -0,0 +1,2 @@
+-- The pruning job deletes the sessions which ended before the retention window, across users
+CREATE INDEX session_history_endtime ON session_history (endtime);
@@ -0,0 +1,4 @@
+-- Sessions logged with /log are tagged manual, their playtime is kept in imported_entries like the other sources
+ALTER TABLE session_history ADD COLUMN source VARCHAR(16) NOT NULL DEFAULT 'presence';
+-- Whether the leaderboards of the guild count the manual sessions
+ALTER TABLE guild_config ADD COLUMN rank_manual_sessions BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- The tracking of the user resumes at this time, NULL when it isn't paused
+ALTER TABLE user_settings ADD COLUMN paused_until BIGINT;
@@ -0,0 +1,4 @@
+-- The user's vacation, from the start of its first day to the end of its last one in their timezone
+-- The digests aren't sent during it
+ALTER TABLE user_settings ADD COLUMN vacation_start BIGINT;
+ALTER TABLE user_settings ADD COLUMN vacation_end BIGINT;
@@ -0,0 +1,3 @@
+-- The rich presence of the sessions, the last one seen is kept: the state and details usually hold the mode or the map
+ALTER TABLE game_sessions ADD COLUMN state TEXT, ADD COLUMN details TEXT, ADD COLUMN party_size BIGINT;
+ALTER TABLE session_history ADD COLUMN state TEXT, ADD COLUMN details TEXT, ADD COLUMN party_size BIGINT;
@@ -0,0 +1,8 @@
+-- The members of the guilds, so the rankings of a guild only count its members. `seen` is when Discord last listed them
+CREATE TABLE IF NOT EXISTS guild_members (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    seen BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+CREATE INDEX guild_members_user_id ON guild_members (user_id);
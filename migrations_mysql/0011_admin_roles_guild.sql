@@ -0,0 +1,3 @@
+-- The guild the admin role belongs to, the roles added before are attributed when the bot sees their guild again
+ALTER TABLE admin_roles ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0;
+CREATE INDEX admin_roles_guild_id ON admin_roles (guild_id);
@@ -0,0 +1,244 @@
+-- The schema of the Postgres migrations up to 0030, SQLite databases start from it.
+-- The session-clearing trigger is left out, SqliteStorage deletes the sessions it saves itself
+CREATE TABLE IF NOT EXISTS games (
+    -- AUTOINCREMENT keeps the ids of reset games from being handed out again before the undo
+    game_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    igdb_id INTEGER,
+    title TEXT,
+    cover_url TEXT,
+    -- JSON array of names
+    genres TEXT NOT NULL DEFAULT '[]',
+    summary TEXT,
+    release_date INTEGER,
+    metadata_updated INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS game_entries (
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    playtime INTEGER NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS game_sessions (
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    starttime INTEGER NOT NULL,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS session_history (
+    session_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    starttime INTEGER NOT NULL,
+    endtime INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE INDEX IF NOT EXISTS session_history_user_endtime ON session_history (user_id, endtime);
+
+CREATE TABLE IF NOT EXISTS admin_roles (
+    role_id INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id INTEGER PRIMARY KEY,
+    opted_out BOOLEAN NOT NULL DEFAULT FALSE,
+    locale TEXT,
+    timezone TEXT,
+    private_stats BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS game_aliases (
+    alias TEXT PRIMARY KEY,
+    game_id INTEGER NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS bot_admins (
+    user_id INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS guild_config (
+    guild_id INTEGER PRIMARY KEY,
+    report_channel INTEGER,
+    min_session_length INTEGER,
+    locale TEXT NOT NULL DEFAULT 'en',
+    embed_color INTEGER NOT NULL DEFAULT 1752220,
+    whitelist_only BOOLEAN NOT NULL DEFAULT FALSE,
+    xp_per_hour INTEGER NOT NULL DEFAULT 100,
+    level_base_xp INTEGER NOT NULL DEFAULT 100,
+    level_channel INTEGER,
+    track_listening BOOLEAN NOT NULL DEFAULT FALSE,
+    title_template TEXT NOT NULL DEFAULT '{title}',
+    show_thumbnails BOOLEAN NOT NULL DEFAULT TRUE,
+    report_cadence TEXT NOT NULL DEFAULT 'weekly'
+);
+
+CREATE TABLE IF NOT EXISTS ignored_games (
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (guild_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS tracked_games (
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (guild_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS digest_subscribers (
+    user_id INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS goals (
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    seconds INTEGER NOT NULL,
+    is_limit BOOLEAN NOT NULL DEFAULT FALSE,
+    notified_week INTEGER,
+    PRIMARY KEY (user_id, game_id),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS role_rewards (
+    guild_id INTEGER NOT NULL,
+    role_id INTEGER NOT NULL,
+    seconds INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, role_id)
+);
+
+CREATE TABLE IF NOT EXISTS levels (
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    level INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS unlocked_badges (
+    user_id INTEGER NOT NULL,
+    badge TEXT NOT NULL,
+    unlocked_at INTEGER NOT NULL,
+    PRIMARY KEY (user_id, badge)
+);
+
+CREATE TABLE IF NOT EXISTS voice_sessions (
+    session_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    guild_id INTEGER NOT NULL,
+    channel_id INTEGER NOT NULL,
+    starttime INTEGER NOT NULL,
+    endtime INTEGER
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS voice_sessions_open ON voice_sessions (user_id) WHERE endtime IS NULL;
+
+CREATE TABLE IF NOT EXISTS stream_sessions (
+    user_id INTEGER PRIMARY KEY,
+    game TEXT NOT NULL,
+    url TEXT,
+    starttime INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS stream_history (
+    stream_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    game TEXT NOT NULL,
+    url TEXT,
+    starttime INTEGER NOT NULL,
+    endtime INTEGER NOT NULL,
+    duration INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS stream_history_user_endtime ON stream_history (user_id, endtime);
+
+CREATE TABLE IF NOT EXISTS listen_sessions (
+    user_id INTEGER PRIMARY KEY,
+    artist TEXT NOT NULL,
+    starttime INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS listen_entries (
+    user_id INTEGER NOT NULL,
+    artist TEXT NOT NULL,
+    listentime INTEGER NOT NULL,
+    PRIMARY KEY (user_id, artist)
+);
+
+CREATE TABLE IF NOT EXISTS linked_accounts (
+    user_id INTEGER NOT NULL,
+    platform TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    PRIMARY KEY (user_id, platform)
+);
+
+CREATE TABLE IF NOT EXISTS imported_entries (
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    playtime INTEGER NOT NULL,
+    PRIMARY KEY (user_id, game_id, source),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    webhook_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    UNIQUE (guild_id, url)
+);
+
+CREATE TABLE IF NOT EXISTS resets (
+    reset_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER,
+    resettime INTEGER NOT NULL
+);
+
+-- The rows are JSON objects
+CREATE TABLE IF NOT EXISTS reset_archive (
+    reset_id INTEGER NOT NULL,
+    table_name TEXT NOT NULL,
+    row TEXT NOT NULL,
+    FOREIGN KEY (reset_id) REFERENCES resets(reset_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reset_archive_reset_table ON reset_archive (reset_id, table_name);
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    entry_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    guild_id INTEGER,
+    action TEXT NOT NULL,
+    details TEXT NOT NULL,
+    actiontime INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS watches (
+    watcher_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    game_name TEXT
+);
+CREATE UNIQUE INDEX IF NOT EXISTS watches_unique ON watches (watcher_id, user_id, LOWER(COALESCE(game_name, '')));
+
+CREATE TABLE IF NOT EXISTS lfg_subscriptions (
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    game_name TEXT NOT NULL,
+    min_players INTEGER NOT NULL,
+    notified BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE UNIQUE INDEX IF NOT EXISTS lfg_subscriptions_unique ON lfg_subscriptions (guild_id, user_id, LOWER(game_name));
+
+CREATE TABLE IF NOT EXISTS game_roles (
+    guild_id INTEGER NOT NULL,
+    game_name TEXT NOT NULL,
+    role_id INTEGER NOT NULL,
+    channel_id INTEGER NOT NULL,
+    last_ping INTEGER
+);
+CREATE UNIQUE INDEX IF NOT EXISTS game_roles_unique ON game_roles (guild_id, LOWER(game_name));
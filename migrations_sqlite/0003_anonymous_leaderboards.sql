@@ -0,0 +1,2 @@
+-- The leaderboards of the guild show the placements and playtimes without the names
+ALTER TABLE guild_config ADD COLUMN anonymous_leaderboards BOOLEAN NOT NULL DEFAULT FALSE;
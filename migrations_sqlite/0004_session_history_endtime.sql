@@ -0,0 +1,2 @@
+-- The pruning job deletes the sessions which ended before the retention window, across users
+CREATE INDEX IF NOT EXISTS session_history_endtime ON session_history (endtime);
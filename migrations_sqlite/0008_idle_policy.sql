@@ -0,0 +1,10 @@
+-- Whether the users are away (Idle or DND) and since when, only valid until the bot stops
+CREATE TABLE IF NOT EXISTS user_status (
+    user_id INTEGER PRIMARY KEY,
+    away BOOLEAN NOT NULL,
+    since INTEGER NOT NULL
+);
+
+-- How the guild counts the play of away users: count, pause (after idle_minutes) or exclude
+ALTER TABLE guild_config ADD COLUMN idle_policy TEXT NOT NULL DEFAULT 'count';
+ALTER TABLE guild_config ADD COLUMN idle_minutes INTEGER NOT NULL DEFAULT 10;
@@ -0,0 +1,7 @@
+-- The rich presence of the sessions, the last one seen is kept: the state and details usually hold the mode or the map
+ALTER TABLE game_sessions ADD COLUMN state TEXT;
+ALTER TABLE game_sessions ADD COLUMN details TEXT;
+ALTER TABLE game_sessions ADD COLUMN party_size INTEGER;
+ALTER TABLE session_history ADD COLUMN state TEXT;
+ALTER TABLE session_history ADD COLUMN details TEXT;
+ALTER TABLE session_history ADD COLUMN party_size INTEGER;
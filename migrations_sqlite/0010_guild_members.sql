@@ -0,0 +1,8 @@
+-- The members of the guilds, so the rankings of a guild only count its members. `seen` is when Discord last listed them
+CREATE TABLE IF NOT EXISTS guild_members (
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    seen INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS guild_members_user_id ON guild_members (user_id);
@@ -0,0 +1,10 @@
+-- The playtime of the pruned sessions per UTC day, `day` being the timestamp of its midnight
+CREATE TABLE IF NOT EXISTS history_rollup (
+    user_id INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    day INTEGER NOT NULL,
+    playtime INTEGER NOT NULL,
+    sessions INTEGER NOT NULL,
+    PRIMARY KEY (user_id, game_id, day),
+    FOREIGN KEY (game_id) REFERENCES games(game_id)
+);
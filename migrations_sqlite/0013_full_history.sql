@@ -0,0 +1,12 @@
+-- The longest session and the sessions started before 5 AM UTC of the rolled up days, for the achievements
+ALTER TABLE history_rollup ADD COLUMN longest INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE history_rollup ADD COLUMN night_sessions INTEGER NOT NULL DEFAULT 0;
+-- The sessions, and the pruned ones as a single session per user, game and day, for the stats spanning the whole history.
+-- Only the day of the rolled up sessions is known, they are centered on its noon so it's the same day in every timezone
+CREATE VIEW IF NOT EXISTS full_history AS
+    SELECT user_id, game_id, starttime, endtime, duration, source, 1 AS sessions, duration AS longest,
+        CASE WHEN starttime % 86400 < 5 * 3600 THEN 1 ELSE 0 END AS night_sessions
+    FROM session_history
+    UNION ALL
+    SELECT user_id, game_id, day + (86400 - MIN(playtime, 86400)) / 2, day + (86400 - MIN(playtime, 86400)) / 2 + playtime, playtime, 'rollup', sessions, longest, night_sessions
+    FROM history_rollup;
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::json;
+use serenity::model::id::ChannelId;
+use serenity::prelude::Context;
+use tracing::error;
+
+use crate::db::{AchievementStats, Database, GuildConfig};
+use crate::webhooks;
+
+
+pub struct Achievement {
+    // Stored in unlocked_badges, never rename one
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub unlocked: fn(&AchievementStats) -> bool,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "night_owl",
+        name: "🦉 Night Owl",
+        description: "Start 10 sessions after midnight",
+        unlocked: |stats| stats.night_sessions >= 10,
+    },
+    Achievement {
+        id: "marathoner",
+        name: "🏃 Marathoner",
+        description: "Play a single session of 8 hours",
+        unlocked: |stats| stats.longest_session >= 8 * 3600,
+    },
+    Achievement {
+        id: "explorer",
+        name: "🧭 Explorer",
+        description: "Play 25 different games",
+        unlocked: |stats| stats.games >= 25,
+    },
+    Achievement {
+        id: "veteran",
+        name: "🎖️ Veteran",
+        description: "Play 1000 hours in total",
+        unlocked: |stats| stats.playtime >= 1000 * 3600,
+    },
+];
+
+// Unlocks the achievements the user just earned and announces them in the guild's level channel
+pub async fn check_achievements(ctx: &Context, db: &Database, config: &GuildConfig, guild_id: &i64, user_id: &i64) -> Result<()> {
+    let stats = db.get_achievement_stats(user_id).await?;
+    let unlocked: Vec<String> = db.get_unlocked_badges(user_id).await?.into_iter().map(|(badge, _)| badge).collect();
+    for achievement in ACHIEVEMENTS {
+        if unlocked.iter().any(|badge| badge == achievement.id) || !(achievement.unlocked)(&stats) {
+            continue;
+        }
+        db.unlock_badge(user_id, achievement.id, &Utc::now().timestamp()).await?;
+        webhooks::dispatch(ctx, db, guild_id, user_id, "achievement", json!({"achievement": achievement.id, "name": achievement.name})).await?;
+        if let Some(channel_id) = config.level_channel {
+            let content = format!("🏆 <@{}> unlocked **{}**: {}", user_id, achievement.name, achievement.description);
+            if let Err(why) = ChannelId(u64::try_from(channel_id)?).say(&ctx.http, content).await {
+                error!("Cannot announce {}'s achievement: {:?}", user_id, why);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Instant;
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+use crate::db::Database;
+
+
+struct ApiState {
+    db: Database,
+    api_key: String,
+}
+
+struct HealthState {
+    db: Database,
+    started_at: Instant,
+}
+
+// Serves the health check, and the read-only stats API when an API key is set
+// Stats requests need the `Authorization: Bearer <api key>` header
+pub async fn serve(db: Database, api_key: Option<String>, port: u16, started_at: Instant) -> Result<()> {
+    let mut app = Router::new()
+        .route("/healthz", get(healthz))
+        .with_state(Arc::new(HealthState { db: db.clone(), started_at }));
+    if let Some(api_key) = api_key {
+        let state = Arc::new(ApiState { db, api_key });
+        app = app.merge(Router::new()
+            .route("/api/guilds/:guild_id/leaderboard", get(guild_leaderboard))
+            .route("/api/users/:user_id/summary", get(user_summary))
+            .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+            .with_state(state));
+    }
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("HTTP server listening on port {}", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn authorize(State(state): State<Arc<ApiState>>, request: Request, next: Next) -> Result<Response, StatusCode> {
+    let authorized = request.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        // A plain comparison returns at the first differing byte, which would let the key be guessed byte by byte
+        .map_or(false, |api_key| bool::from(api_key.as_bytes().ct_eq(state.api_key.as_bytes())));
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    return Ok(next.run(request).await);
+}
+
+// Uptime monitors only look at the status code, the body helps a human reading it
+async fn healthz(State(state): State<Arc<HealthState>>) -> (StatusCode, Json<Value>) {
+    let uptime = state.started_at.elapsed().as_secs();
+    return match state.db.ping().await {
+        Ok(()) => (StatusCode::OK, Json(json!({"status": "ok", "uptime": uptime}))),
+        Err(why) => {
+            error!("The health check cannot reach the database: {:?}", why);
+            (StatusCode::SERVICE_UNAVAILABLE, Json(json!({"status": "database unreachable", "uptime": uptime})))
+        },
+    };
+}
+
+fn internal_error(why: anyhow::Error) -> StatusCode {
+    error!("Cannot handle an API request: {:?}", why);
+    return StatusCode::INTERNAL_SERVER_ERROR;
+}
+
+// The same ranking as /leaderboard in the guild, only its members are ranked
+// Only the users with public stats are named, the API doesn't know who is asking, and nobody is on a guild with anonymous leaderboards
+async fn guild_leaderboard(State(state): State<Arc<ApiState>>, Path(guild_id): Path<i64>) -> Result<Json<Value>, StatusCode> {
+    let config = state.db.get_guild_config(&guild_id).await.map_err(internal_error)?.unwrap_or_default();
+    let anonymous = config.anonymous_leaderboards;
+    let mut ranking: Vec<Value> = Vec::new();
+    for (rank, (user_id, playtime)) in state.db.get_leaderboard(Some(guild_id), config.rank_manual_sessions).await.map_err(internal_error)?.iter().enumerate() {
+        let public = !anonymous && state.db.get_privacy_level(user_id).await.map_err(internal_error)? == "public";
+        ranking.push(json!({
+            "rank": rank + 1,
+            "user_id": if public { Some(user_id.to_string()) } else { None },
+            "playtime": playtime,
+        }));
+    }
+    return Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "leaderboard": ranking,
+    })));
+}
+
+async fn user_summary(State(state): State<Arc<ApiState>>, Path(user_id): Path<i64>) -> Result<Json<Value>, StatusCode> {
+    let db = &state.db;
+    // Opted out users are hidden like on Discord, and so are the users without public stats
+    if db.is_opted_out(&user_id).await.map_err(internal_error)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if db.get_privacy_level(&user_id).await.map_err(internal_error)? != "public" {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    // Not asked from a guild, the user is ranked among everyone
+    let totals = match db.get_user_totals(&user_id, None).await.map_err(internal_error)? {
+        Some(totals) => totals,
+        None => return Err(StatusCode::NOT_FOUND),
+    };
+    let games: Vec<Value> = db.get_top_games(&user_id, None, 10, 0).await.map_err(internal_error)?.iter()
+        .map(|game| json!({
+            "game": game.name,
+            "playtime": game.playtime,
+        }))
+        .collect();
+    let sessions: Vec<Value> = db.get_recent_sessions(&user_id, 10).await.map_err(internal_error)?.iter()
+        .map(|(game_name, starttime, duration)| json!({
+            "game": game_name,
+            "starttime": starttime,
+            "duration": duration,
+        }))
+        .collect();
+    return Ok(Json(json!({
+        "user_id": user_id.to_string(),
+        "playtime": totals.playtime,
+        "games": totals.games,
+        "rank": totals.rank,
+        "ranked_users": totals.ranked_users,
+        "top_games": games,
+        "recent_sessions": sessions,
+    })));
+}
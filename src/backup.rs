@@ -0,0 +1,69 @@
+use anyhow::Result;
+use chrono::Utc;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use reqwest::Client;
+use serenity::prelude::{Context, TypeMapKey};
+use std::io::Write;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::db::Database;
+
+
+// Bigger backups can't be attached to a Discord message
+pub const MAX_ATTACHMENT_SIZE: usize = 25 * 1024 * 1024;
+
+// Object storage accepting PUTs, like a bucket behind a signing proxy
+pub struct BackupStorage {
+    http: Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl TypeMapKey for BackupStorage {
+    type Value = Arc<BackupStorage>;
+}
+
+impl BackupStorage {
+    pub fn new(url: String, token: Option<String>) -> Self {
+        return BackupStorage { http: Client::new(), url: url.trim_end_matches('/').to_string(), token };
+    }
+
+    // Returns the URL the backup was stored at
+    pub async fn upload(&self, file_name: &str, data: Vec<u8>) -> Result<String> {
+        let url = format!("{}/{}", self.url, file_name);
+        let mut request = self.http.put(&url)
+            .header("Content-Type", "application/gzip")
+            .body(data);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await?.error_for_status()?;
+        return Ok(url);
+    }
+}
+
+pub struct Backup {
+    pub file_name: String,
+    // Gzipped JSON
+    pub data: Vec<u8>,
+    // Set when the backup went to the storage, otherwise it has to be attached
+    pub uploaded_to: Option<String>,
+}
+
+// Dumps the database and uploads it when a storage is configured
+pub async fn take(ctx: &Context, db: &Database) -> Result<Backup> {
+    let dump = db.dump().await?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(&dump)?)?;
+    let data = encoder.finish()?;
+    let file_name = format!("backup-{}.json.gz", Utc::now().format("%Y%m%d-%H%M%S"));
+    info!("Took the backup {} of {} bytes", file_name, data.len());
+    let storage = ctx.data.read().await.get::<BackupStorage>().cloned();
+    let uploaded_to = match storage {
+        Some(storage) => Some(storage.upload(&file_name, data.clone()).await?),
+        None => None,
+    };
+    return Ok(Backup { file_name, data, uploaded_to });
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use redis::AsyncCommands;
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serenity::prelude::{Context, TypeMapKey};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+
+// Seconds an aggregate is served from the cache, saving a session invalidates it sooner
+const TTL: usize = 60;
+// Bumped when playtime is written, the keys of the previous generations are left to expire
+const GENERATION_KEY: &str = "gameactivitybot:generation";
+
+// Caches the leaderboards in Redis, so running them on a large server doesn't aggregate the whole history every time
+pub struct Cache {
+    connection: ConnectionManager,
+}
+
+impl TypeMapKey for Cache {
+    type Value = Arc<Cache>;
+}
+
+impl Cache {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url)?;
+        return Ok(Cache { connection: ConnectionManager::new(client).await? });
+    }
+
+    // Returns the current generation and the value cached for it
+    async fn lookup<T: DeserializeOwned>(&self, key: &str) -> Result<(u64, Option<T>)> {
+        let mut connection = self.connection.clone();
+        let generation: Option<u64> = connection.get(GENERATION_KEY).await?;
+        let generation = generation.unwrap_or(0);
+        let value: Option<String> = connection.get(generation_key(generation, key)).await?;
+        return Ok((generation, value.map(|value| serde_json::from_str(&value)).transpose()?));
+    }
+
+    // A value computed while the playtime changed is stored under the generation it was read from, which nothing reads anymore
+    async fn store<T: Serialize>(&self, generation: u64, key: &str, value: &T) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let _: () = connection.set_ex(generation_key(generation, key), serde_json::to_string(value)?, TTL).await?;
+        Ok(())
+    }
+
+    async fn bump(&self) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let _: i64 = connection.incr(GENERATION_KEY, 1).await?;
+        Ok(())
+    }
+}
+
+fn generation_key(generation: u64, key: &str) -> String {
+    return format!("gameactivitybot:{}:{}", generation, key);
+}
+
+// Returns the cached value of the key, running the query and caching its result when there is none.
+// The query runs directly without Redis, or when it can't be reached
+pub async fn cached<T, F>(ctx: &Context, key: &str, query: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T>>,
+{
+    let cache = match ctx.data.read().await.get::<Cache>().cloned() {
+        Some(cache) => cache,
+        None => return query.await,
+    };
+    let generation = match cache.lookup(key).await {
+        Ok((_, Some(value))) => return Ok(value),
+        Ok((generation, None)) => Some(generation),
+        Err(why) => {
+            warn!("Cannot read {:?} from the cache: {:?}", key, why);
+            None
+        },
+    };
+    let value = query.await?;
+    if let Some(generation) = generation {
+        if let Err(why) = cache.store(generation, key, &value).await {
+            warn!("Cannot cache {:?}: {:?}", key, why);
+        }
+    }
+    return Ok(value);
+}
+
+// Drops the cached aggregates after the playtime changed
+pub async fn invalidate(ctx: &Context) {
+    let cache = ctx.data.read().await.get::<Cache>().cloned();
+    if let Some(cache) = cache {
+        if let Err(why) = cache.bump().await {
+            warn!("Cannot invalidate the cache: {:?}", why);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use image::{ImageOutputFormat, RgbImage};
+use plotters::coord::ranged1d::SegmentValue;
+use plotters::prelude::*;
+use plotters::style::{register_font, FontStyle};
+use std::cmp::{max, min};
+use std::collections::BTreeSet;
+use std::io::Cursor;
+
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+pub const DAY: i64 = 24 * 60 * 60;
+pub const WEEK: i64 = 7 * DAY;
+
+// The host may not have any font installed, so charts use a bundled one
+static FONT: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+
+pub fn register_fonts() -> Result<()> {
+    return register_font("sans-serif", FontStyle::Normal, FONT).map_err(|_| anyhow!("Cannot load the chart font"));
+}
+
+// Splits (starttime, endtime) sessions into the playtime of each week from `first_week`,
+// a session spanning several weeks counts in each of them
+pub fn weekly_playtime(sessions: &[(i64, i64)], first_week: i64, weeks: usize) -> Vec<i64> {
+    let mut playtimes: Vec<i64> = vec![0; weeks];
+    for (starttime, endtime) in sessions {
+        for (week, playtime) in playtimes.iter_mut().enumerate() {
+            let week_start = first_week + week as i64 * WEEK;
+            let overlap = min(*endtime, week_start + WEEK) - max(*starttime, week_start);
+            if overlap > 0 {
+                *playtime += overlap;
+            }
+        }
+    }
+    return playtimes;
+}
+
+// Calls `add` with the local time and the playtime of each hour the (starttime, endtime) sessions overlap in `timezone`
+fn split_by_hour(sessions: &[(i64, i64)], timezone: Tz, mut add: impl FnMut(DateTime<Tz>, i64)) {
+    for (starttime, endtime) in sessions {
+        let mut time = *starttime;
+        while time < *endtime {
+            let local = timezone.timestamp_opt(time, 0).unwrap();
+            // Timezones are offset by whole minutes, the next hour starts at the same second everywhere
+            let hour_end = min(*endtime, time + 3600 - i64::from(local.minute() * 60 + local.second()));
+            add(local, hour_end - time);
+            time = hour_end;
+        }
+    }
+}
+
+// The playtime of each hour of the day in `timezone`
+pub fn hourly_playtime(sessions: &[(i64, i64)], timezone: Tz) -> [i64; 24] {
+    let mut playtimes = [0; 24];
+    split_by_hour(sessions, timezone, |local, playtime| playtimes[local.hour() as usize] += playtime);
+    return playtimes;
+}
+
+// The playtime of each day of the week in `timezone`, from Monday
+pub fn weekday_playtime(sessions: &[(i64, i64)], timezone: Tz) -> [i64; 7] {
+    let mut playtimes = [0; 7];
+    split_by_hour(sessions, timezone, |local, playtime| playtimes[local.weekday().num_days_from_monday() as usize] += playtime);
+    return playtimes;
+}
+
+// The days in `timezone` with some playtime
+pub fn played_days(sessions: &[(i64, i64)], timezone: Tz) -> BTreeSet<NaiveDate> {
+    let mut days = BTreeSet::new();
+    split_by_hour(sessions, timezone, |local, _| { days.insert(local.date_naive()); });
+    return days;
+}
+
+// Draws the weekly playtimes as a bar chart and returns it as a PNG
+pub fn render_weekly_chart(title: &str, first_week: i64, playtimes: &[i64], colour: u32) -> Result<Vec<u8>> {
+    let mut buffer: Vec<u8> = vec![0; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let hours: Vec<f64> = playtimes.iter().map(|playtime| *playtime as f64 / 3600.0).collect();
+        let max_hours = hours.iter().cloned().fold(1.0, f64::max);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 24))
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d((0..hours.len() as i32 - 1).into_segmented(), 0.0..max_hours * 1.1)?;
+        chart.configure_mesh()
+            .disable_x_mesh()
+            .x_labels(hours.len())
+            .x_label_formatter(&|value| match value {
+                SegmentValue::CenterOf(week) => week_label(first_week, *week),
+                _ => String::new(),
+            })
+            .y_desc("Hours")
+            .draw()?;
+        let bar_colour = RGBColor((colour >> 16) as u8, (colour >> 8) as u8, colour as u8);
+        chart.draw_series(hours.iter().enumerate().map(|(week, hours)| {
+            let week = week as i32;
+            let mut bar = Rectangle::new([(SegmentValue::Exact(week), 0.0), (SegmentValue::Exact(week + 1), *hours)], bar_colour.filled());
+            bar.set_margin(0, 0, 5, 5);
+            bar
+        }))?;
+        root.present()?;
+    }
+    let image = RgbImage::from_raw(WIDTH, HEIGHT, buffer).ok_or_else(|| anyhow!("The chart buffer doesn't match its size"))?;
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png)?;
+    return Ok(png.into_inner());
+}
+
+fn week_label(first_week: i64, week: i32) -> String {
+    return Utc.timestamp_opt(first_week + i64::from(week) * WEEK, 0).unwrap().format("%d/%m").to_string();
+}
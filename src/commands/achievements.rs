@@ -0,0 +1,33 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::achievements::ACHIEVEMENTS;
+use crate::db::Database;
+use super::{respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("achievements").description("Shows the achievements a user unlocked")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let unlocked = db.get_unlocked_badges(&user_id).await?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s achievements ({}/{})", user.name, unlocked.len(), ACHIEVEMENTS.len()));
+    for achievement in ACHIEVEMENTS {
+        let value = match unlocked.iter().find(|(badge, _)| badge == achievement.id) {
+            Some((_, unlocked_at)) => format!("{}\nUnlocked <t:{}:D>", achievement.description, unlocked_at),
+            None => format!("🔒 {}", achievement.description),
+        };
+        embed.field(achievement.name, value, true);
+    }
+    respond_embed(ctx, command, embed).await
+}
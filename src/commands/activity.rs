@@ -0,0 +1,55 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{can_view, respond_embed, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("activity").description("Shows what the members of the server are playing right now")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    // Sessions aren't stored per server, the online members are the ones with a cached presence
+    let members: Vec<i64> = ctx.cache.guild_field(guild_id, |guild| guild.presences.keys()
+            .filter_map(|user_id| i64::try_from(*user_id.as_u64()).ok())
+            .collect())
+        .unwrap_or_default();
+    let mut games: Vec<(String, Vec<String>)> = Vec::new();
+    for (game_name, user_ids) in db.get_open_sessions_by_game().await? {
+        let mut players: Vec<String> = Vec::new();
+        for user_id in user_ids.iter().filter(|user_id| members.contains(user_id)) {
+            // Private players are still counted
+            if can_view(db, ctx, Some(command.user.id), Some(guild_id), UserId(u64::try_from(*user_id)?)).await? {
+                players.push(format!("<@{}>", user_id));
+            } else {
+                players.push("Anonymous".to_string());
+            }
+        }
+        if !players.is_empty() {
+            games.push((game_name, players));
+        }
+    }
+    games.sort_by_key(|(_, players)| std::cmp::Reverse(players.len()));
+    let mut embed = CreateEmbed::default();
+    embed.title("Server activity");
+    match games.iter().map(|(_, players)| players.len()).sum::<usize>() {
+        0 => embed.description("Nobody is playing right now."),
+        1 => embed.description("1 member is playing."),
+        playing => embed.description(format!("{} members are playing.", playing)),
+    };
+    // Embeds can't have more than 25 fields
+    for (game_name, players) in games.iter().take(25) {
+        embed.field(format!("{} playing {}", players.len(), game_name), players.join(", "), false);
+    }
+    respond_embed(ctx, command, embed).await
+}
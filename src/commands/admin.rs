@@ -0,0 +1,39 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{is_owner, permission_denied, respond_ephemeral, user_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("admin").description("Manages the users allowed to use admin commands")
+        .create_option(|subcommand| { subcommand.name("add").description("Allows a user to use admin commands").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("user").description("The user").kind(CommandOptionType::User).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Disallows a user to use admin commands").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("user").description("The user").kind(CommandOptionType::User).required(true)}) })
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_owner(ctx, command.user.id).await? {
+        let subcommand = &command.data.options[0];
+        let user_id = i64::try_from(user_option(&subcommand.options, "user")?)?;
+        match subcommand.name.as_str() {
+            "add" => {
+                db.add_bot_admin(&user_id).await?;
+                message_str = format!("<@{}> can now use admin commands.", user_id);
+            },
+            "remove" => {
+                db.remove_bot_admin(&user_id).await?;
+                message_str = format!("<@{}> can no longer use admin commands.", user_id);
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        }
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,39 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, is_global_admin, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("alias").description("Manages the names a game is tracked under")
+        .create_option(|subcommand| { subcommand.name("add").description("Counts a name as a game, merging the playtime already tracked under it").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("alias").description("The name to merge").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("game").description("The game it should count as").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_global_admin(db, ctx, command.user.id).await? {
+        let subcommand = &command.data.options[0];
+        let alias = string_option(&subcommand.options, "alias")?;
+        let game_name = string_option(&subcommand.options, "game")?;
+        message_str = match db.find_game(game_name).await? {
+            Some((game_id, name)) => match db.merge_alias(alias, &game_id).await? {
+                Some(_) => {
+                    cache::invalidate(ctx).await;
+                    audit(db, &command.user.id, command.guild_id, "alias merge", format!("{} into {}", alias, name)).await?;
+                    format!("{} has been merged into {}.", alias, name)
+                },
+                None => format!("{} now counts as {}.", alias, name),
+            },
+            None => format!("{} isn't tracked.", game_name),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
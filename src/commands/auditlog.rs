@@ -0,0 +1,87 @@
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{is_admin, permission_denied, respond_ephemeral, style_embed};
+
+
+const AUDIT_PAGE_SIZE: i64 = 10;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("auditlog").description("Lists the resets, merges and other destructive admin commands")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_admin(db, command.member.as_ref()).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    let mut embed = get_audit_page(db, 0).await?;
+    style_embed(ctx, command.guild_id, &mut embed).await?;
+    let pages = get_audit_pages(db).await?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true).set_embed(embed)
+                .components(|components| audit_buttons(components, 0, pages)))
+    })
+        .await?;
+    Ok(())
+}
+
+// custom ids are formatted as `auditlog:<page>`
+pub async fn paginate(db: &Database, ctx: &Context, component: &MessageComponentInteraction) -> Result<()> {
+    let custom_id: Vec<&str> = component.data.custom_id.split(':').collect();
+    if custom_id.len() != 2 || !is_admin(db, component.member.as_ref()).await? {
+        return Ok(());
+    }
+    let page = custom_id[1].parse::<i64>()?;
+    let mut embed = get_audit_page(db, page).await?;
+    style_embed(ctx, component.guild_id, &mut embed).await?;
+    let pages = get_audit_pages(db).await?;
+    component.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|message| message.set_embed(embed)
+                .components(|components| audit_buttons(components, page, pages)))
+    })
+        .await?;
+    Ok(())
+}
+
+async fn get_audit_page(db: &Database, page: i64) -> Result<CreateEmbed> {
+    let entries: Vec<String> = db.get_audit_log(AUDIT_PAGE_SIZE, page * AUDIT_PAGE_SIZE).await?.iter()
+        .map(|(user_id, action, details, actiontime)| format!("<t:{}:f> <@{}> **{}** {}", actiontime, user_id, action, details))
+        .collect();
+    let mut embed = CreateEmbed::default();
+    embed.title("Audit log")
+        .description(if entries.is_empty() { "No destructive command was used yet.".to_string() } else { entries.join("\n") });
+    let pages = get_audit_pages(db).await?;
+    embed.footer(|footer| footer.text(format!("Page {}/{}", page + 1, pages)));
+    return Ok(embed);
+}
+
+async fn get_audit_pages(db: &Database) -> Result<i64> {
+    let entries = db.count_audit_entries().await?;
+    return Ok(std::cmp::max(1, (entries + AUDIT_PAGE_SIZE - 1) / AUDIT_PAGE_SIZE));
+}
+
+fn audit_buttons(components: &mut CreateComponents, page: i64, pages: i64) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| button
+                .custom_id(format!("auditlog:{}", page - 1))
+                .label("Previous")
+                .style(ButtonStyle::Secondary)
+                .disabled(page <= 0))
+            .create_button(|button| button
+                .custom_id(format!("auditlog:{}", page + 1))
+                .label("Next")
+                .style(ButtonStyle::Secondary)
+                .disabled(page + 1 >= pages))
+    })
+}
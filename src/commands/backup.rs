@@ -0,0 +1,42 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::prelude::*;
+use std::borrow::Cow;
+
+use anyhow::Result;
+
+use crate::backup::{self, MAX_ATTACHMENT_SIZE};
+use crate::db::Database;
+use super::{edit_response, is_owner, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("backup").description("Saves a copy of the whole database, only for the bot owner")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_owner(ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    // Dumping and uploading the database can take longer than the 3 seconds Discord waits for the answer
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true))
+    })
+        .await?;
+    let backup = backup::take(ctx, db).await?;
+    if let Some(url) = backup.uploaded_to {
+        return edit_response(ctx, command, format!("The backup was uploaded to {}.", url)).await;
+    }
+    if backup.data.len() > MAX_ATTACHMENT_SIZE {
+        return edit_response(ctx, command, "The backup is too large to be attached, configure a backup storage.".to_string()).await;
+    }
+    // The deferred response can't be given a file, the first follow-up replaces it
+    command.create_followup_message(&ctx.http, |message| message.ephemeral(true)
+        .content("Here is the backup, /restore accepts it.")
+        .add_file(AttachmentType::Bytes { data: Cow::from(backup.data), filename: backup.file_name })).await?;
+    Ok(())
+}
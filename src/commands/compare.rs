@@ -0,0 +1,61 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::UserId;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, user_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("compare").description("Compares the playtimes of two users")
+        .create_option(|option| {option.name("user1").description("The first user").kind(CommandOptionType::User).required(true)})
+        .create_option(|option| {option.name("user2").description("The second user").kind(CommandOptionType::User).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user1_id = user_option(&command.data.options, "user1")?;
+    let user2_id = user_option(&command.data.options, "user2")?;
+    let user1 = UserId(user1_id).to_user(&ctx.http).await?;
+    let user2 = UserId(user2_id).to_user(&ctx.http).await?;
+    let embed = get_comparison(db, &user1, &user2).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_comparison(db: &Database, user1: &User, user2: &User) -> Result<CreateEmbed> {
+    let user1_id = i64::try_from(*user1.id.as_u64())?;
+    let user2_id = i64::try_from(*user2.id.as_u64())?;
+    let mut embed = CreateEmbed::default()
+        .title(format!("{} vs {}", user1.name, user2.name)).to_owned();
+    for (user, user_id) in [(user1, user1_id), (user2, user2_id)] {
+        if db.is_opted_out(&user_id).await? {
+            embed.description(format!("{} opted out of tracking.", user.mention()));
+            return Ok(embed);
+        }
+    }
+
+    for (user, user_id) in [(user1, user1_id), (user2, user2_id)] {
+        let mut lines: Vec<String> = Vec::new();
+        for game in db.get_top_games(&user_id, None, 5, 0).await? {
+            lines.push(format!("{}: {}", game.name, format_playtime(game.playtime)));
+        }
+        lines.push(format!("**Total: {}**", format_playtime(db.get_total_playtime(&user_id).await?)));
+        embed.field(&user.name, lines.join("\n"), true);
+    }
+
+    let mut shared: Vec<String> = Vec::new();
+    for (game_name, playtime1, playtime2) in db.get_shared_games(&user1_id, &user2_id).await? {
+        let leader = if playtime1 >= playtime2 { &user1.name } else { &user2.name };
+        shared.push(format!("{}: {} / {} — {} leads", game_name, format_playtime(playtime1), format_playtime(playtime2), leader));
+    }
+    if shared.is_empty() {
+        shared.push("No games in common.".to_string());
+    }
+    embed.field("Shared games", shared.join("\n"), false);
+    return Ok(embed);
+}
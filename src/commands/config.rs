@@ -0,0 +1,300 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOption};
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{boolean_option, config_service, has_manage_permissions, integer_option, permission_denied, respond_ephemeral, string_option};
+
+
+// Leaves room for the titles under Discord's 256 characters
+const MAX_TEMPLATE_LENGTH: u16 = 64;
+// Minutes the play of idle members still counts with the pause policy
+const DEFAULT_IDLE_MINUTES: i64 = 10;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("config").description("Configures the bot")
+        .create_option(|group| { group.name("admins").description("Manages the roles allowed to use admin commands").kind(CommandOptionType::SubCommandGroup)
+            .create_sub_option(|subcommand| { subcommand.name("add").description("Allows a role to use admin commands").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("role").description("The role").kind(CommandOptionType::Role).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("remove").description("Disallows a role to use admin commands").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("role").description("The role").kind(CommandOptionType::Role).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("list").description("Lists the admin roles").kind(CommandOptionType::SubCommand)}) })
+        .create_option(|group| { group.name("set").description("Changes a setting of the server").kind(CommandOptionType::SubCommandGroup)
+            .create_sub_option(|subcommand| { subcommand.name("report_channel").description("Sets the channel reports are sent to").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("channel").description("The channel").kind(CommandOptionType::Channel).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("min_session_length").description("Sets the length under which sessions are discarded").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("seconds").description("The length in seconds").kind(CommandOptionType::Integer).min_int_value(0).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("locale").description("Sets the language of the bot").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("locale").description("The language").kind(CommandOptionType::String).required(true)
+                    .add_string_choice("English", "en")
+                    .add_string_choice("Français", "fr")}) })
+            .create_sub_option(|subcommand| { subcommand.name("whitelist").description("Only tracks the games added with /trackedgames").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("enabled").description("Whether the whitelist is used").kind(CommandOptionType::Boolean).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("listening").description("Tracks the time spent listening to music, shown by /musicstats").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("enabled").description("Whether listening is tracked").kind(CommandOptionType::Boolean).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("anonymous_leaderboards").description("Hides the names on the leaderboards, each member is told their own placement").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("enabled").description("Whether the leaderboards are anonymous").kind(CommandOptionType::Boolean).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("rank_manual_sessions").description("Counts the sessions logged with /log on the leaderboards").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("enabled").description("Whether the manual sessions are ranked").kind(CommandOptionType::Boolean).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("idle_policy").description("Sets how the play of idle and DND members counts").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("policy").description("The policy").kind(CommandOptionType::String).required(true)
+                    .add_string_choice("Count", "count")
+                    .add_string_choice("Pause after a while", "pause")
+                    .add_string_choice("Exclude", "exclude")})
+                .create_sub_option(|option| {option.name("minutes").description("How long the play still counts with the pause policy, 10 by default").kind(CommandOptionType::Integer).min_int_value(1).required(false)}) })
+            .create_sub_option(|subcommand| { subcommand.name("xp_per_hour").description("Sets the XP earned per hour of playtime").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("xp").description("The XP per hour").kind(CommandOptionType::Integer).min_int_value(1).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("level_curve").description("Sets the XP needed for level 1, level n needs it times n²").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("xp").description("The XP of level 1").kind(CommandOptionType::Integer).min_int_value(1).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("level_channel").description("Sets the channel level ups and achievements are announced in").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("channel").description("The channel").kind(CommandOptionType::Channel).required(true)}) }) })
+        .create_option(|group| { group.name("appearance").description("Changes how the embeds look").kind(CommandOptionType::SubCommandGroup)
+            .create_sub_option(|subcommand| { subcommand.name("color").description("Sets the color of the embeds").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("color").description("The color as hex, like #1ABC9C").kind(CommandOptionType::String).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("title").description("Sets the template of the embed titles").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("template").description("The template, {title} is replaced by the title").kind(CommandOptionType::String).max_length(MAX_TEMPLATE_LENGTH).required(true)}) })
+            .create_sub_option(|subcommand| { subcommand.name("thumbnails").description("Shows the game covers in the embeds").kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {option.name("enabled").description("Whether thumbnails are shown").kind(CommandOptionType::Boolean).required(true)}) }) })
+        .create_option(|subcommand| { subcommand.name("reports").description("Sets where and how often the reports are posted").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("channel").description("The channel").kind(CommandOptionType::Channel).required(true)})
+            .create_sub_option(|option| {option.name("cadence").description("How often the reports are posted, at midnight UTC").kind(CommandOptionType::String).required(true)
+                .add_string_choice("Daily", "daily")
+                .add_string_choice("Weekly", "weekly")
+                .add_string_choice("Monthly", "monthly")}) })
+        .create_option(|subcommand| { subcommand.name("gamerole").description("Announces the sessions of a game, mentioning a role").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("role").description("The role, none to stop the announcements").kind(CommandOptionType::Role).required(false)})
+            .create_sub_option(|option| {option.name("channel").description("The channel, this one by default").kind(CommandOptionType::Channel).required(false)}) })
+        .create_option(|subcommand| { subcommand.name("show").description("Shows the settings of the server").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if has_manage_permissions(command.member.as_ref()) {
+        let option = &command.data.options[0];
+        message_str = match option.name.as_str() {
+            "admins" => run_admins(db, &guild_id, &option.options[0]).await?,
+            "set" => run_set(ctx, &guild_id, &option.options[0]).await?,
+            "appearance" => run_appearance(ctx, &guild_id, &option.options[0]).await?,
+            "reports" => {
+                let channel_id = string_option(&option.options, "channel")?.parse::<i64>()?;
+                let cadence = string_option(&option.options, "cadence")?.to_string();
+                config_service(ctx).await?.update(&guild_id, |config| {
+                    config.report_channel = Some(channel_id);
+                    config.report_cadence = cadence.clone();
+                }).await?;
+                format!("Reports will be posted {} in <#{}>.", cadence, channel_id)
+            },
+            "gamerole" => run_game_role(db, &guild_id, command, option).await?,
+            "show" => {
+                let game_roles: Vec<String> = db.get_game_roles(&guild_id).await?.iter()
+                    .map(|(game_name, role_id, channel_id)| format!("{}: <@&{}> in <#{}>", game_name, role_id, channel_id))
+                    .collect();
+                let config = config_service(ctx).await?.get(&guild_id).await?;
+                format!("Report channel: {}\nReport cadence: {}\nMinimum session length: {}\nLocale: {}\nEmbed color: #{:06X}\nTitle template: {}\nThumbnails: {}\nWhitelist only: {}\nListening tracked: {}\nAnonymous leaderboards: {}\nManual sessions ranked: {}\nIdle and DND: {}\nXP per hour: {}\nLevel 1 XP: {}\nLevel channel: {}\nGame roles: {}",
+                    config.report_channel.map_or("none".to_string(), |channel_id| format!("<#{}>", channel_id)),
+                    config.report_cadence,
+                    config.min_session_length.map_or("default".to_string(), |seconds| format!("{}s", seconds)),
+                    config.locale,
+                    config.embed_color,
+                    config.title_template,
+                    if config.show_thumbnails { "shown" } else { "hidden" },
+                    if config.whitelist_only { "yes" } else { "no" },
+                    if config.track_listening { "yes" } else { "no" },
+                    if config.anonymous_leaderboards { "yes" } else { "no" },
+                    if config.rank_manual_sessions { "yes" } else { "no" },
+                    idle_policy_label(&config.idle_policy, config.idle_minutes),
+                    config.xp_per_hour,
+                    config.level_base_xp,
+                    config.level_channel.map_or("none".to_string(), |channel_id| format!("<#{}>", channel_id)),
+                    if game_roles.is_empty() { "none".to_string() } else { format!("\n{}", game_roles.join("\n")) })
+            },
+            option => unreachable!("Subcommand don't have a handler: {}", option),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
+
+async fn run_admins(db: &Database, guild_id: &i64, subcommand: &CommandDataOption) -> Result<String> {
+    return Ok(match subcommand.name.as_str() {
+        "add" => {
+            let role_id = string_option(&subcommand.options, "role")?.parse::<i64>()?;
+            db.add_admin_role(guild_id, &role_id).await?;
+            format!("<@&{}> can now use admin commands.", role_id)
+        },
+        "remove" => {
+            let role_id = string_option(&subcommand.options, "role")?.parse::<i64>()?;
+            db.remove_admin_role(guild_id, &role_id).await?;
+            format!("<@&{}> can no longer use admin commands.", role_id)
+        },
+        "list" => {
+            let roles: Vec<String> = db.get_admin_roles(guild_id).await?.iter().map(|role_id| format!("<@&{}>", role_id)).collect();
+            if roles.is_empty() {
+                "No admin roles are configured.".to_string()
+            } else {
+                format!("Admin roles: {}", roles.join(", "))
+            }
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    });
+}
+
+// Without a role, the game isn't announced anymore
+async fn run_game_role(db: &Database, guild_id: &i64, command: &ApplicationCommandInteraction, subcommand: &CommandDataOption) -> Result<String> {
+    let game_name = string_option(&subcommand.options, "game")?;
+    let role_id = match string_option(&subcommand.options, "role") {
+        Ok(role_id) => role_id.parse::<i64>()?,
+        Err(_) => {
+            return Ok(if db.remove_game_role(guild_id, game_name).await? {
+                format!("Sessions of {} won't be announced anymore.", game_name)
+            } else {
+                format!("Sessions of {} aren't announced.", game_name)
+            });
+        },
+    };
+    let channel_id = match string_option(&subcommand.options, "channel") {
+        Ok(channel_id) => channel_id.parse::<i64>()?,
+        Err(_) => i64::try_from(*command.channel_id.as_u64())?,
+    };
+    return Ok(match db.find_game(game_name).await? {
+        Some((_, name)) => {
+            db.set_game_role(guild_id, &name, &role_id, &channel_id).await?;
+            format!("Sessions of {} will be announced in <#{}>, mentioning <@&{}> at most once every 30 minutes.", name, channel_id, role_id)
+        },
+        None => format!("{} isn't tracked.", game_name),
+    });
+}
+
+async fn run_set(ctx: &Context, guild_id: &i64, subcommand: &CommandDataOption) -> Result<String> {
+    let config = config_service(ctx).await?;
+    return Ok(match subcommand.name.as_str() {
+        "report_channel" => {
+            let channel_id = string_option(&subcommand.options, "channel")?.parse::<i64>()?;
+            config.update(guild_id, |config| config.report_channel = Some(channel_id)).await?;
+            format!("Reports will be sent to <#{}>.", channel_id)
+        },
+        "min_session_length" => {
+            let seconds = integer_option(&subcommand.options, "seconds")?;
+            config.update(guild_id, |config| config.min_session_length = Some(seconds)).await?;
+            format!("Sessions shorter than {}s will be discarded.", seconds)
+        },
+        "locale" => {
+            let locale = string_option(&subcommand.options, "locale")?.to_string();
+            config.update(guild_id, |config| config.locale = locale.clone()).await?;
+            format!("The locale is now {}.", locale)
+        },
+        "whitelist" => {
+            let enabled = boolean_option(&subcommand.options, "enabled");
+            config.update(guild_id, |config| config.whitelist_only = enabled).await?;
+            if enabled {
+                "Only the games added with /trackedgames are tracked now.".to_string()
+            } else {
+                "Every game is tracked now.".to_string()
+            }
+        },
+        "listening" => {
+            let enabled = boolean_option(&subcommand.options, "enabled");
+            config.update(guild_id, |config| config.track_listening = enabled).await?;
+            if enabled {
+                "Listening to music is tracked now.".to_string()
+            } else {
+                "Listening to music isn't tracked anymore.".to_string()
+            }
+        },
+        "anonymous_leaderboards" => {
+            let enabled = boolean_option(&subcommand.options, "enabled");
+            config.update(guild_id, |config| config.anonymous_leaderboards = enabled).await?;
+            if enabled {
+                "The leaderboards don't show the names anymore, members are told their own placement.".to_string()
+            } else {
+                "The leaderboards show the names now.".to_string()
+            }
+        },
+        "rank_manual_sessions" => {
+            let enabled = boolean_option(&subcommand.options, "enabled");
+            config.update(guild_id, |config| config.rank_manual_sessions = enabled).await?;
+            if enabled {
+                "The sessions logged with /log count on the leaderboards now.".to_string()
+            } else {
+                "The sessions logged with /log don't count on the leaderboards anymore.".to_string()
+            }
+        },
+        "idle_policy" => {
+            let policy = string_option(&subcommand.options, "policy")?.to_string();
+            let minutes = integer_option(&subcommand.options, "minutes").unwrap_or(DEFAULT_IDLE_MINUTES);
+            config.update(guild_id, |config| {
+                config.idle_policy = policy.clone();
+                config.idle_minutes = minutes;
+            }).await?;
+            format!("The play of idle and DND members is now {}.", idle_policy_label(&policy, minutes))
+        },
+        "xp_per_hour" => {
+            let xp = integer_option(&subcommand.options, "xp")?;
+            config.update(guild_id, |config| config.xp_per_hour = xp).await?;
+            format!("Members now earn {} XP per hour of playtime.", xp)
+        },
+        "level_curve" => {
+            let xp = integer_option(&subcommand.options, "xp")?;
+            config.update(guild_id, |config| config.level_base_xp = xp).await?;
+            format!("Level 1 now takes {} XP.", xp)
+        },
+        "level_channel" => {
+            let channel_id = string_option(&subcommand.options, "channel")?.parse::<i64>()?;
+            config.update(guild_id, |config| config.level_channel = Some(channel_id)).await?;
+            format!("Level ups and achievements will be announced in <#{}>.", channel_id)
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    });
+}
+
+async fn run_appearance(ctx: &Context, guild_id: &i64, subcommand: &CommandDataOption) -> Result<String> {
+    let config = config_service(ctx).await?;
+    return Ok(match subcommand.name.as_str() {
+        "color" => {
+            let color = string_option(&subcommand.options, "color")?;
+            match i64::from_str_radix(color.trim_start_matches('#'), 16) {
+                Ok(embed_color) if embed_color <= 0xFFFFFF => {
+                    config.update(guild_id, |config| config.embed_color = embed_color).await?;
+                    format!("Embeds are now #{:06X}.", embed_color)
+                },
+                _ => format!("{} isn't a hex color.", color),
+            }
+        },
+        "title" => {
+            let template = string_option(&subcommand.options, "template")?.to_string();
+            if template.contains("{title}") {
+                config.update(guild_id, |config| config.title_template = template.clone()).await?;
+                format!("Embed titles now look like {}.", template.replace("{title}", "Weekly report"))
+            } else {
+                "The template must contain {title}.".to_string()
+            }
+        },
+        "thumbnails" => {
+            let enabled = boolean_option(&subcommand.options, "enabled");
+            config.update(guild_id, |config| config.show_thumbnails = enabled).await?;
+            if enabled {
+                "Embeds show thumbnails now.".to_string()
+            } else {
+                "Embeds don't show thumbnails anymore.".to_string()
+            }
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    });
+}
+
+// How the play of idle and DND members counts, as shown in the messages
+fn idle_policy_label(policy: &str, minutes: i64) -> String {
+    return match policy {
+        "pause" => format!("counted for {} minutes, then paused", minutes),
+        "exclude" => "not counted".to_string(),
+        _ => "counted".to_string(),
+    };
+}
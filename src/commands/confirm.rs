@@ -0,0 +1,88 @@
+use chrono::Utc;
+use serenity::builder::CreateComponents;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use crate::i18n;
+use super::{game, hardreset, is_global_admin, is_owner, locale, permission_denied, resetall};
+
+
+// Seconds an admin has to confirm a destructive command
+const CONFIRMATION_TIMEOUT: i64 = 60;
+
+// Asks the admin to confirm `action` before running it, the buttons carry the time they were created at
+// Actions on a single row carry its id after a slash, like `gamedelete/42`
+pub async fn ask(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction, action: &str, prompt: String) -> Result<()> {
+    let created_at = Utc::now().timestamp();
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true).content(prompt)
+                .components(|components| confirmation_buttons(components, &locale, action, created_at)))
+    })
+        .await?;
+    Ok(())
+}
+
+pub async fn handle(db: &Database, ctx: &Context, component: &MessageComponentInteraction) -> Result<()> {
+    let custom_id: Vec<&str> = component.data.custom_id.split(':').collect();
+    if custom_id.len() != 4 {
+        return Ok(());
+    }
+    let (action, argument) = custom_id[1].split_once('/').unwrap_or((custom_id[1], ""));
+    let created_at = custom_id[2].parse::<i64>()?;
+    // Destroying the database is left to the owner of the bot
+    let allowed = match action {
+        "hardreset" => is_owner(ctx, component.user.id).await?,
+        _ => is_global_admin(db, ctx, component.user.id).await?,
+    };
+    let locale = locale(db, ctx, &component.user.id, component.guild_id).await?;
+    let message_str = if Utc::now().timestamp() - created_at > CONFIRMATION_TIMEOUT {
+        i18n::t(&locale, "confirm-expired")
+    } else if custom_id[3] != "yes" {
+        i18n::t(&locale, "confirm-cancelled")
+    } else if !allowed {
+        permission_denied(db, ctx, &component.user.id, component.guild_id).await?
+    } else {
+        // The actions, and the backup of the hard reset, can take longer than the 3 seconds Discord waits for the answer
+        component.create_interaction_response(&ctx.http, |response| response.kind(InteractionResponseType::DeferredUpdateMessage)).await?;
+        let message_str = match action {
+            "resetall" => resetall::confirmed(db, component).await?,
+            "hardreset" => hardreset::confirmed(db, ctx, component).await?,
+            "gamedelete" => game::confirmed_delete(db, component, &argument.parse::<i64>()?).await?,
+            action => unreachable!("Action don't have a handler: {}", action),
+        };
+        // Every confirmed action removes playtime from the leaderboards
+        cache::invalidate(ctx).await;
+        component.edit_original_interaction_response(&ctx.http, |response| response.content(message_str).components(|components| components)).await?;
+        return Ok(());
+    };
+    component.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|message| message.content(message_str).components(|components| components))
+    })
+        .await?;
+    Ok(())
+}
+
+fn confirmation_buttons<'a>(components: &'a mut CreateComponents, locale: &str, action: &str, created_at: i64) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| button
+                .custom_id(format!("confirm:{}:{}:yes", action, created_at))
+                .label(i18n::t(locale, "confirm-yes"))
+                .style(ButtonStyle::Danger))
+            .create_button(|button| button
+                .custom_id(format!("confirm:{}:{}:no", action, created_at))
+                .label(i18n::t(locale, "confirm-no"))
+                .style(ButtonStyle::Secondary))
+    })
+}
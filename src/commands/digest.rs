@@ -0,0 +1,29 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::respond_ephemeral;
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("digest").description("Manages your weekly playtime digest sent by DM")
+        .create_option(|subcommand| { subcommand.name("enable").description("Sends you a digest of your playtime every week").kind(CommandOptionType::SubCommand)})
+        .create_option(|subcommand| { subcommand.name("disable").description("Stops sending you a weekly digest").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subscribed = command.data.options[0].name == "enable";
+    db.set_digest_subscription(&user_id, subscribed).await?;
+    let message_str = if subscribed {
+        "You will receive a digest of your playtime every Monday, make sure your DMs are open."
+    } else {
+        "You will no longer receive a weekly digest."
+    };
+    respond_ephemeral(ctx, command, message_str.to_string()).await
+}
@@ -0,0 +1,30 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::prelude::*;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("export").description("Sends you everything stored about you")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let export = serde_json::to_vec_pretty(&db.export_user(&user_id).await?)?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true)
+                .content("Here is everything stored about you.")
+                .add_file(AttachmentType::Bytes { data: Cow::from(export), filename: format!("{}.json", user_id) }))
+    })
+        .await?;
+    Ok(())
+}
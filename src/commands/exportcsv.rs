@@ -0,0 +1,68 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{can_view, is_admin, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("exportcsv").description("Sends the playtimes of the server as a CSV file")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    if !is_admin(db, command.member.as_ref()).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    // The members whose privacy level hides their stats from the admin are left out
+    let mut visible: HashMap<i64, bool> = HashMap::new();
+    let mut csv = String::from("user_id,game,hours,sessions\n");
+    for (user_id, game_name, playtime, sessions) in db.get_all_entries(&guild_id).await? {
+        let is_visible = match visible.get(&user_id) {
+            Some(is_visible) => *is_visible,
+            None => {
+                let is_visible = can_view(db, ctx, Some(command.user.id), command.guild_id, UserId(u64::try_from(user_id)?)).await?;
+                visible.insert(user_id, is_visible);
+                is_visible
+            },
+        };
+        if !is_visible {
+            continue;
+        }
+        csv.push_str(&csv_row(user_id, &game_name, playtime, sessions));
+    }
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true)
+                .content("Here are the playtimes of the server.")
+                .add_file(AttachmentType::Bytes { data: Cow::from(csv.into_bytes()), filename: "playtimes.csv".to_string() }))
+    })
+        .await?;
+    Ok(())
+}
+
+// A user_id,game,hours,sessions line, /import reads it back
+pub fn csv_row(user_id: i64, game_name: &str, playtime: i64, sessions: i64) -> String {
+    return format!("{},{},{:.2},{}\n", user_id, csv_field(game_name), playtime as f64 / 3600.0, sessions);
+}
+
+// Game names can contain commas and quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    return value.to_string();
+}
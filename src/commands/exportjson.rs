@@ -0,0 +1,69 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{can_view, is_admin, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("exportjson").description("Sends a JSON snapshot of the games, playtimes and sessions")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    if !is_admin(db, command.member.as_ref()).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    let mut dump = db.dump_guild(&guild_id).await?;
+    // The members whose privacy level hides their stats from the admin are left out, like in /exportcsv
+    let mut visible: HashMap<String, bool> = HashMap::new();
+    for key in ["entries", "open_sessions", "sessions"] {
+        let mut rows: Vec<Value> = Vec::new();
+        for row in dump[key].as_array().cloned().unwrap_or_default() {
+            let user_id = row["user_id"].as_str().unwrap_or_default().to_string();
+            let is_visible = match visible.get(&user_id) {
+                Some(is_visible) => *is_visible,
+                None => {
+                    let is_visible = can_view(db, ctx, Some(command.user.id), command.guild_id, UserId(user_id.parse()?)).await?;
+                    visible.insert(user_id, is_visible);
+                    is_visible
+                },
+            };
+            if is_visible {
+                rows.push(row);
+            }
+        }
+        dump[key] = Value::Array(rows);
+    }
+    // Only the games still played by someone in the snapshot are kept
+    let played: HashSet<i64> = ["entries", "open_sessions", "sessions"].iter()
+        .flat_map(|key| dump[*key].as_array().cloned().unwrap_or_default())
+        .filter_map(|row| row["game_id"].as_i64())
+        .collect();
+    if let Some(games) = dump["games"].as_array_mut() {
+        games.retain(|game| game["game_id"].as_i64().is_some_and(|game_id| played.contains(&game_id)));
+    }
+    let dump = serde_json::to_vec_pretty(&dump)?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true)
+                .content("Here is a snapshot of the server's data.")
+                .add_file(AttachmentType::Bytes { data: Cow::from(dump), filename: format!("{}.json", guild_id) }))
+    })
+        .await?;
+    Ok(())
+}
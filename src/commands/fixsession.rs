@@ -0,0 +1,41 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::UserId;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, format_playtime, integer_option, is_global_admin, permission_denied, respond_ephemeral, string_option, user_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("fix-session").description("Corrects a user's playtime on a game, after a glitched or spoofed presence")
+        .create_option(|option| {option.name("user").description("The target").kind(CommandOptionType::User).required(true)})
+        .create_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+        .create_option(|option| {option.name("minutes").description("The minutes to add, negative to remove them").kind(CommandOptionType::Integer).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_global_admin(db, ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    let user = UserId(user_option(&command.data.options, "user")?).to_user(&ctx.http).await?;
+    let game_name = string_option(&command.data.options, "game")?;
+    let minutes = integer_option(&command.data.options, "minutes")?;
+    let (game_id, name) = match db.find_game(game_name).await? {
+        Some(game) => game,
+        None => return respond_ephemeral(ctx, command, format!("{} isn't tracked.", game_name)).await,
+    };
+    if minutes == 0 {
+        return respond_ephemeral(ctx, command, "The playtime wasn't changed.".to_string()).await;
+    }
+    let playtime = db.adjust_playtime(&i64::try_from(*user.id.as_u64())?, &game_id, &(minutes * 60)).await?;
+    cache::invalidate(ctx).await;
+    audit(db, &command.user.id, command.guild_id, "fix-session", format!("{:+} minutes of {} for {}", minutes, name, user.mention())).await?;
+    let change = if minutes > 0 { format!("Added {} minutes to", minutes) } else { format!("Removed {} minutes from", -minutes) };
+    respond_ephemeral(ctx, command, format!("{} {}'s playtime on {}, it is now {}.", change, user.mention(), name, format_playtime(playtime))).await
+}
@@ -0,0 +1,80 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, confirm, is_global_admin, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("game").description("Manages the tracked games")
+        .create_option(|subcommand| { subcommand.name("rename").description("Fixes the name a game is shown under, the old name keeps counting as it").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("old").description("The game to rename").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("new").description("Its new name").kind(CommandOptionType::String).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("merge").description("Moves the playtime of a duplicate game to another and deletes it").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("source").description("The duplicate to delete").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("target").description("The game it should count as").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("delete").description("Deletes a game and all the playtime tracked on it").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game to delete").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_global_admin(db, ctx, command.user.id).await? {
+        let subcommand = &command.data.options[0];
+        message_str = match subcommand.name.as_str() {
+            "rename" => {
+                let old_name = string_option(&subcommand.options, "old")?;
+                let new_name = string_option(&subcommand.options, "new")?;
+                match db.find_game(old_name).await? {
+                    Some((game_id, name)) => if db.rename_game(&game_id, new_name).await? {
+                        cache::invalidate(ctx).await;
+                        audit(db, &command.user.id, command.guild_id, "game rename", format!("{} to {}", name, new_name.trim())).await?;
+                        format!("{} is now called {}.", name, new_name.trim())
+                    } else {
+                        format!("{} is already a game, use /alias add to merge them.", new_name.trim())
+                    },
+                    None => format!("{} isn't tracked.", old_name),
+                }
+            },
+            "merge" => {
+                let source_name = string_option(&subcommand.options, "source")?;
+                let target_name = string_option(&subcommand.options, "target")?;
+                match (db.find_game(source_name).await?, db.find_game(target_name).await?) {
+                    (Some((source_id, _)), Some((target_id, _))) if source_id == target_id => format!("{} and {} are already the same game.", source_name, target_name),
+                    (Some((source_id, source)), Some((target_id, target))) => {
+                        db.merge_games(&source_id, &target_id).await?;
+                        cache::invalidate(ctx).await;
+                        audit(db, &command.user.id, command.guild_id, "game merge", format!("{} into {}", source, target)).await?;
+                        format!("{} has been merged into {}.", source, target)
+                    },
+                    (None, _) => format!("{} isn't tracked.", source_name),
+                    (_, None) => format!("{} isn't tracked.", target_name),
+                }
+            },
+            "delete" => {
+                let game_name = string_option(&subcommand.options, "game")?;
+                match db.find_game(game_name).await? {
+                    Some((game_id, name)) => {
+                        let prompt = format!("Are you sure you want to delete {} and all the playtime tracked on it?", name);
+                        return confirm::ask(db, ctx, command, &format!("gamedelete/{}", game_id), prompt).await;
+                    },
+                    None => format!("{} isn't tracked.", game_name),
+                }
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
+
+pub async fn confirmed_delete(db: &Database, component: &MessageComponentInteraction, game_id: &i64) -> Result<String> {
+    let name = db.delete_game(game_id).await?;
+    audit(db, &component.user.id, component.guild_id, "game delete", name.clone()).await?;
+    return Ok(format!("{} has been deleted, use /ignore to stop it from being tracked again.", name));
+}
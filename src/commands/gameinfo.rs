@@ -0,0 +1,60 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("gameinfo").description("Shows information about a game")
+        .create_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let game_name = string_option(&command.data.options, "game")?;
+    let embed = get_game_info(db, game_name).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_game_info(db: &Database, game_name: &str) -> Result<CreateEmbed> {
+    let mut embed = CreateEmbed::default();
+    let (game_id, name) = match db.find_game(game_name).await? {
+        Some(game) => game,
+        None => {
+            embed.title(format!("{} isn't tracked", game_name))
+                .description("Nobody has played this game yet.");
+            return Ok(embed);
+        },
+    };
+    let (players, playtime) = db.get_game_totals(&game_id).await?;
+    match db.get_game_metadata(&game_id).await? {
+        Some(metadata) => {
+            embed.title(&metadata.title)
+                .url(format!("https://www.igdb.com/search?q={}", metadata.title.replace(' ', "+")));
+            if let Some(summary) = &metadata.summary {
+                // Some summaries are several paragraphs long
+                embed.description(summary.chars().take(1000).collect::<String>());
+            }
+            if let Some(cover_url) = &metadata.cover_url {
+                embed.thumbnail(cover_url);
+            }
+            if !metadata.genres.is_empty() {
+                embed.field("Genres", metadata.genres.join(", "), true);
+            }
+            if let Some(release_date) = metadata.release_date {
+                embed.field("Released", format!("<t:{}:D>", release_date), true);
+            }
+        },
+        None => {
+            embed.title(&name)
+                .description("No information was found about this game.");
+        },
+    }
+    embed.field("Players", players.to_string(), true)
+        .field("Total playtime", format_playtime(playtime), true);
+    return Ok(embed);
+}
@@ -0,0 +1,64 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, string_option, target_user};
+
+
+// Modes or maps listed, from the rich presence of the sessions
+const MAX_DETAILS: i64 = 5;
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("gamestats").description("Shows a user's stats on a game")
+        // Discord rejects a required option listed after an optional one
+        .create_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let game_name = string_option(&command.data.options, "game")?;
+    let embed = get_game_stats(db, &user, game_name).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_game_stats(db: &Database, user: &User, game_name: &str) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    if db.is_opted_out(&user_id).await? {
+        embed.title(format!("{}'s stats", user.name))
+            .description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let game = db.find_game(game_name).await?;
+    if game.is_none() {
+        embed.title(format!("{} isn't tracked", game_name))
+            .description("Nobody has played this game yet.");
+        return Ok(embed);
+    }
+    let (game_id, name) = game.unwrap();
+    embed.title(format!("{}'s {} stats", user.name, name));
+
+    let playtime = db.get_game_playtime(&user_id, &game_id).await?;
+    let stats = db.get_game_stats(&user_id, &game_id).await?;
+    embed.field("Total playtime", format_playtime(playtime), true)
+        .field("Sessions", stats.sessions.to_string(), true);
+    if stats.sessions > 0 {
+        embed.field("Average session", format_playtime(stats.playtime / stats.sessions), true)
+            .field("First played", format!("<t:{}:f>", stats.first_played.unwrap()), true)
+            .field("Last played", format!("<t:{}:f>", stats.last_played.unwrap()), true);
+    }
+    let details = db.get_top_details(&user_id, &game_id, MAX_DETAILS).await?;
+    if !details.is_empty() {
+        let lines: Vec<String> = details.iter().map(|(details, playtime)| format!("{}: {}", details, format_playtime(*playtime))).collect();
+        embed.field("Most played modes", lines.join("\n"), false);
+    }
+    return Ok(embed);
+}
@@ -0,0 +1,45 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{anonymous_users, format_ranking, guild_config, locale, respond_embed, reveal_placement, stored_guild_id, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("gametop").description("Shows the 10 users with the most playtime on a game")
+        .create_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let game_name = string_option(&command.data.options, "game")?;
+    let mut embed = CreateEmbed::default();
+    let config = guild_config(ctx, command.guild_id).await?;
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let mut ranking: Vec<(i64, i64)> = Vec::new();
+    match db.find_game(game_name).await? {
+        Some((game_id, name)) => {
+            ranking = db.get_game_leaderboard(stored_guild_id(command.guild_id)?, &game_id, config.rank_manual_sessions).await?;
+            let anonymous = anonymous_users(db, ctx, Some(command.user.id), command.guild_id, &ranking).await?;
+            embed.title(format!("Top {} players", name))
+                .description(format_ranking(&locale, &ranking, &anonymous));
+            if let Some(metadata) = db.get_game_metadata(&game_id).await? {
+                if let Some(cover_url) = metadata.cover_url {
+                    embed.thumbnail(cover_url);
+                }
+            }
+        },
+        None => {
+            embed.title(format!("{} isn't tracked", game_name))
+                .description("Nobody has played this game yet.");
+        },
+    }
+    respond_embed(ctx, command, embed).await?;
+    if !ranking.is_empty() && config.anonymous_leaderboards {
+        reveal_placement(ctx, command, &locale, &ranking).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::levels::progress_bar;
+use super::{format_playtime, respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("genres").description("Shows a user's playtime by genre")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_genres(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_genres(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s genres", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let genres = db.get_genre_playtime(&user_id).await?;
+    let total: i64 = genres.iter().map(|(_, playtime)| playtime).sum();
+    if total == 0 {
+        embed.description(format!("{} hasn't played anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    let lines: Vec<String> = genres.iter()
+        .take(10)
+        .map(|(genre, playtime)| format!("{} **{}%** {} — {}",
+            progress_bar(*playtime, total), playtime * 100 / total, genre.as_deref().unwrap_or("Unknown"), format_playtime(*playtime)))
+        .collect();
+    embed.description(lines.join("\n"))
+        .footer(|footer| footer.text("Games with several genres are split evenly between them"));
+    return Ok(embed);
+}
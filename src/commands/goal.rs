@@ -0,0 +1,66 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, integer_option, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("goal").description("Manages your weekly playtime goals and limits")
+        .create_option(|subcommand| { subcommand.name("set").description("Sets a weekly goal or limit on a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("hours").description("Hours per week").kind(CommandOptionType::Integer).min_int_value(1).max_int_value(168).required(true)})
+            .create_sub_option(|option| {option.name("kind").description("Whether to be told when reaching or exceeding it, goal by default").kind(CommandOptionType::String).required(false)
+                .add_string_choice("Goal", "goal")
+                .add_string_choice("Limit", "limit")}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Removes your goal on a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists your goals").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    let message_str = match subcommand.name.as_str() {
+        "set" => {
+            let game_name = string_option(&subcommand.options, "game")?;
+            let hours = integer_option(&subcommand.options, "hours")?;
+            let is_limit = string_option(&subcommand.options, "kind").map_or(false, |kind| kind == "limit");
+            match db.find_game(game_name).await? {
+                Some((game_id, name)) => {
+                    db.set_goal(&user_id, &game_id, &(hours * 3600), is_limit).await?;
+                    if is_limit {
+                        format!("You will be told when you play {} more than {}h in a week.", name, hours)
+                    } else {
+                        format!("You will be told when you reach {}h of {} in a week.", hours, name)
+                    }
+                },
+                None => format!("{} isn't tracked.", game_name),
+            }
+        },
+        "remove" => {
+            let game_name = string_option(&subcommand.options, "game")?;
+            match db.find_game(game_name).await? {
+                Some((game_id, name)) if db.remove_goal(&user_id, &game_id).await? => format!("Your goal on {} was removed.", name),
+                _ => format!("You don't have a goal on {}.", game_name),
+            }
+        },
+        "list" => {
+            let goals: Vec<String> = db.get_user_goals(&user_id).await?.iter()
+                .map(|(name, seconds, is_limit)| format!("{}: {} {} per week", name, if *is_limit { "at most" } else { "at least" }, format_playtime(*seconds)))
+                .collect();
+            if goals.is_empty() {
+                "You don't have any goals.".to_string()
+            } else {
+                goals.join("\n")
+            }
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
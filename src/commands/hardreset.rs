@@ -0,0 +1,45 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::prelude::*;
+use std::borrow::Cow;
+
+use anyhow::Result;
+
+use crate::backup::{self, MAX_ATTACHMENT_SIZE};
+use crate::db::Database;
+use super::{audit, confirm, is_owner, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("hardreset").description("Destroys the database")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_owner(ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    confirm::ask(db, ctx, command, "hardreset", "Are you sure you want to destroy and rebuild the database?".to_string()).await
+}
+
+// A backup is taken first, without a backup storage it's sent to the owner before anything is destroyed
+pub async fn confirmed(db: &Database, ctx: &Context, component: &MessageComponentInteraction) -> Result<String> {
+    let backup = backup::take(ctx, db).await?;
+    let backed_up_to = match backup.uploaded_to {
+        Some(url) => url,
+        None if backup.data.len() > MAX_ATTACHMENT_SIZE => {
+            return Ok("The backup is too large to be attached, configure a backup storage before destroying the database.".to_string());
+        },
+        None => {
+            // Nothing is destroyed when the backup couldn't be sent
+            component.create_followup_message(&ctx.http, |message| message.ephemeral(true)
+                .content("Here is a backup of the database, taken before destroying it.")
+                .add_file(AttachmentType::Bytes { data: Cow::from(backup.data), filename: backup.file_name.clone() })).await?;
+            backup.file_name.clone()
+        },
+    };
+    db.hardreset().await?;
+    audit(db, &component.user.id, component.guild_id, "hardreset", format!("Backed up as {}", backup.file_name)).await?;
+    return Ok(format!("Successfully reconstructed the database, the previous one was backed up to {}.", backed_up_to));
+}
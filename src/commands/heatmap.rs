@@ -0,0 +1,66 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::chart::hourly_playtime;
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user, timezone};
+
+
+// From no playtime to the busiest hour
+const SHADES: [&str; 5] = ["⬛", "🟦", "🟩", "🟨", "🟥"];
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("heatmap").description("Shows the hours of the day a user plays at")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_heatmap(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_heatmap(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("When {} plays", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let sessions = db.get_sessions_since(&user_id, &0).await?;
+    if sessions.is_empty() {
+        embed.description(format!("{} hasn't played anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    // The hours are the ones of the player, not of whoever asked
+    let timezone = timezone(db, &user.id).await?;
+    let playtimes = hourly_playtime(&sessions, timezone);
+    let busiest = (0..24).max_by_key(|hour| playtimes[*hour]).unwrap_or(0);
+    let rows: Vec<String> = (0..4)
+        .map(|row| {
+            let cells: String = (row * 6..row * 6 + 6).map(|hour| shade(playtimes[hour], playtimes[busiest])).collect();
+            format!("`{:02}h` {} `{:02}h`", row * 6, cells, row * 6 + 5)
+        })
+        .collect();
+    embed.description(rows.join("\n"))
+        .field("Busiest hour", format!("{:02}h-{:02}h, {} in total", busiest, (busiest + 1) % 24, format_playtime(playtimes[busiest])), true)
+        .field("Timezone", timezone.name(), true)
+        .footer(|footer| footer.text(format!("{} none to {} most", SHADES[0], SHADES[SHADES.len() - 1])));
+    return Ok(embed);
+}
+
+// Any playtime gets at least the lightest shade
+fn shade(playtime: i64, most: i64) -> &'static str {
+    if playtime <= 0 || most <= 0 {
+        return SHADES[0];
+    }
+    let level = (playtime * (SHADES.len() as i64 - 1) + most - 1) / most;
+    return SHADES[level as usize];
+}
@@ -0,0 +1,64 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{boolean_option, is_admin, is_owner, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("ignore").description("Manages the activities that aren't tracked")
+        .create_option(|subcommand| { subcommand.name("add").description("Stops tracking an activity").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("name").description("The activity's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("global").description("Ignores it in every server, only for the bot owner").kind(CommandOptionType::Boolean).required(false)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Resumes tracking an activity").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("name").description("The activity's name").kind(CommandOptionType::String).required(true)})
+            .create_sub_option(|option| {option.name("global").description("Removes it from the global list, only for the bot owner").kind(CommandOptionType::Boolean).required(false)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the ignored activities").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let subcommand = &command.data.options[0];
+    let global = boolean_option(&subcommand.options, "global");
+    let allowed = if global { is_owner(ctx, command.user.id).await? } else { is_admin(db, command.member.as_ref()).await? };
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if allowed {
+        // The global list is stored under the guild id 0
+        let target_id = if global { 0 } else { guild_id };
+        message_str = match subcommand.name.as_str() {
+            "add" => {
+                let name = string_option(&subcommand.options, "name")?;
+                db.add_ignored_game(&target_id, name).await?;
+                format!("{} is no longer tracked.", name)
+            },
+            "remove" => {
+                let name = string_option(&subcommand.options, "name")?;
+                if db.remove_ignored_game(&target_id, name).await? {
+                    format!("{} is tracked again.", name)
+                } else {
+                    format!("{} isn't ignored.", name)
+                }
+            },
+            "list" => {
+                let ignored: Vec<String> = db.get_ignored_games(&guild_id).await?.iter()
+                    .map(|(ignored_guild_id, name)| if *ignored_guild_id == 0 { format!("{} (global)", name) } else { name.to_string() })
+                    .collect();
+                if ignored.is_empty() {
+                    "No activities are ignored.".to_string()
+                } else {
+                    format!("Ignored activities: {}", ignored.join(", "))
+                }
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,208 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::AttachmentId;
+use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, boolean_option, edit_response, format_playtime, is_global_admin, permission_denied, respond_ephemeral, string_option};
+
+
+// Keeps the import's transaction short, larger files have to be split
+const MAX_ROWS: usize = 5000;
+// Keeps the answer under Discord's 2000 characters
+const MAX_REPORTED_ERRORS: usize = 10;
+
+// (line, user id, game name, seconds)
+type Row = (usize, i64, String, i64);
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("import").description("Adds the playtimes of a CSV file, from a previous bot or a spreadsheet")
+        .create_option(|option| {option.name("file").description("A CSV file of user_id,game,seconds rows, or one made by /exportcsv").kind(CommandOptionType::Attachment).required(true)})
+        .create_option(|option| {option.name("dry_run").description("Only checks the file and previews the import").kind(CommandOptionType::Boolean).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    if !is_global_admin(db, ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    // Downloading and importing the file can take longer than the 3 seconds Discord waits for the answer
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true))
+    })
+        .await?;
+    let attachment_id = AttachmentId(string_option(&command.data.options, "file")?.parse::<u64>()?);
+    let attachment = match command.data.resolved.attachments.get(&attachment_id) {
+        Some(attachment) => attachment,
+        None => return edit_response(ctx, command, "The file couldn't be found.".to_string()).await,
+    };
+    let csv = match String::from_utf8(attachment.download().await?) {
+        Ok(csv) => csv,
+        Err(_) => return edit_response(ctx, command, format!("{} isn't a CSV file.", attachment.filename)).await,
+    };
+    let (rows, mut errors) = parse_rows(&csv);
+    if rows.len() > MAX_ROWS {
+        return edit_response(ctx, command, format!("{} has {} rows, split it into files of {} rows at most.", attachment.filename, rows.len(), MAX_ROWS)).await;
+    }
+    // The playtime is global, a server can only import the one of its own members
+    let members: HashSet<i64> = db.get_guild_members(&guild_id).await?.into_iter().collect();
+    // The users who opted out aren't tracked, the import can't bring their playtime back
+    let mut opted_out: HashMap<i64, bool> = HashMap::new();
+    for (line, user_id, _, _) in &rows {
+        if !members.contains(user_id) {
+            errors.push((*line, format!("<@{}> isn't a member of the server", user_id)));
+            continue;
+        }
+        if !opted_out.contains_key(user_id) {
+            opted_out.insert(*user_id, db.is_opted_out(user_id).await?);
+        }
+        if opted_out[user_id] {
+            errors.push((*line, format!("<@{}> opted out of tracking", user_id)));
+        }
+    }
+    errors.sort_by_key(|(line, _)| *line);
+    if !errors.is_empty() {
+        let reported: Vec<String> = errors.iter().take(MAX_REPORTED_ERRORS).map(|(line, error)| format!("Line {}: {}", line, error)).collect();
+        let mut message = format!("Nothing was imported, {} rows of {} are invalid:\n{}", errors.len(), attachment.filename, reported.join("\n"));
+        if errors.len() > MAX_REPORTED_ERRORS {
+            message.push_str(&format!("\n...and {} more", errors.len() - MAX_REPORTED_ERRORS));
+        }
+        return edit_response(ctx, command, message).await;
+    }
+    if rows.is_empty() {
+        return edit_response(ctx, command, format!("{} has no rows to import.", attachment.filename)).await;
+    }
+    let rows: Vec<(i64, String, i64)> = rows.into_iter().map(|(_, user_id, game_name, playtime)| (user_id, game_name, playtime)).collect();
+    let summary = format!("{} rows adding {} to {} users on {} games",
+        rows.len(),
+        format_playtime(rows.iter().map(|(_, _, playtime)| playtime).sum()),
+        rows.iter().map(|(user_id, _, _)| user_id).collect::<HashSet<_>>().len(),
+        rows.iter().map(|(_, game_name, _)| game_name.to_lowercase()).collect::<HashSet<_>>().len());
+    if boolean_option(&command.data.options, "dry_run") {
+        return edit_response(ctx, command, format!("Nothing was imported, {} has {}.", attachment.filename, summary)).await;
+    }
+    db.import_playtime(&rows).await?;
+    cache::invalidate(ctx).await;
+    audit(db, &command.user.id, command.guild_id, "import", format!("{} from {}", summary, attachment.filename)).await?;
+    edit_response(ctx, command, format!("Imported {}.", summary)).await
+}
+
+// Returns the valid rows and the (line, error) pairs of the others
+// The header made by /exportcsv or a spreadsheet is skipped, the user_id,game,hours,sessions rows of /exportcsv are read
+// as hours rounded to the second and their sessions are ignored
+fn parse_rows(csv: &str) -> (Vec<Row>, Vec<(usize, String)>) {
+    let mut rows: Vec<Row> = Vec::new();
+    let mut errors: Vec<(usize, String)> = Vec::new();
+    for (index, line) in csv.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = match csv_fields(line) {
+            Some(fields) => fields,
+            None => {
+                errors.push((line_number, "a quote isn't closed".to_string()));
+                continue;
+            },
+        };
+        if index == 0 && fields[0].trim().eq_ignore_ascii_case("user_id") {
+            continue;
+        }
+        if fields.len() != 3 && fields.len() != 4 {
+            errors.push((line_number, format!("expected user_id,game,seconds or user_id,game,hours,sessions, found {} fields", fields.len())));
+            continue;
+        }
+        let user_id = match fields[0].trim().parse::<u64>().ok().filter(|user_id| *user_id > 0).and_then(|user_id| i64::try_from(user_id).ok()) {
+            Some(user_id) => user_id,
+            None => {
+                errors.push((line_number, format!("{} isn't a user id", fields[0].trim())));
+                continue;
+            },
+        };
+        let game_name = fields[1].trim();
+        if game_name.is_empty() {
+            errors.push((line_number, "the game is missing".to_string()));
+            continue;
+        }
+        if fields.len() == 4 {
+            match fields[2].trim().parse::<f64>().map(|hours| (hours * 3600.0).round()) {
+                // /exportcsv lists the games played for less than 18 seconds as 0.00 hours, there is nothing to add
+                Ok(0.0) => {},
+                Ok(seconds) if seconds > 0.0 && seconds < i64::MAX as f64 => rows.push((line_number, user_id, game_name.to_string(), seconds as i64)),
+                _ => errors.push((line_number, format!("{} isn't a positive number of hours", fields[2].trim()))),
+            }
+            continue;
+        }
+        match fields[2].trim().parse::<i64>() {
+            Ok(seconds) if seconds > 0 => rows.push((line_number, user_id, game_name.to_string(), seconds)),
+            _ => errors.push((line_number, format!("{} isn't a positive number of seconds", fields[2].trim()))),
+        }
+    }
+    return (rows, errors);
+}
+
+// Splits a line into its fields, quoted fields can contain commas and doubled quotes
+// None when a quote isn't closed
+fn csv_fields(line: &str) -> Option<Vec<String>> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    return Some(fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_rows;
+    use super::super::exportcsv::csv_row;
+
+    #[test]
+    fn parses_the_rows_after_the_header() {
+        let (rows, errors) = parse_rows("user_id,game,seconds\n1,Factorio,3600\n\n2,\"Papers, Please\",60\n");
+        assert!(errors.is_empty());
+        assert_eq!(rows, vec![(2, 1, "Factorio".to_string(), 3600), (4, 2, "Papers, Please".to_string(), 60)]);
+    }
+
+    #[test]
+    fn reports_every_invalid_row() {
+        let (rows, errors) = parse_rows("1,Factorio\nabc,Factorio,60\n1,,60\n1,Factorio,-5\n1,\"Factorio,60\n1,Factorio,60");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(errors.len(), 5);
+        assert_eq!(errors[1], (2, "abc isn't a user id".to_string()));
+    }
+
+    #[test]
+    fn parses_the_rows_of_exportcsv() {
+        let csv = format!("user_id,game,hours,sessions\n{}{}{}", csv_row(1, "Factorio", 5400, 3), csv_row(2, "Papers, Please", 900, 1), csv_row(3, "Celeste", 10, 1));
+        let (rows, errors) = parse_rows(&csv);
+        assert!(errors.is_empty());
+        assert_eq!(rows, vec![(2, 1, "Factorio".to_string(), 5400), (3, 2, "Papers, Please".to_string(), 900)]);
+    }
+}
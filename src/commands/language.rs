@@ -0,0 +1,34 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::i18n;
+use super::{locale, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("language").description("Chooses the language the bot answers you in")
+        .create_option(|option| {option.name("language").description("The language").kind(CommandOptionType::String).required(true)
+            .add_string_choice("Server default", "default")
+            .add_string_choice("English", "en")
+            .add_string_choice("Français", "fr")})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let language = string_option(&command.data.options, "language")?;
+    let message_key = if i18n::is_supported(language) {
+        db.set_user_locale(&user_id, Some(language)).await?;
+        "language-set"
+    } else {
+        db.set_user_locale(&user_id, None).await?;
+        "language-reset"
+    };
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    respond_ephemeral(ctx, command, i18n::t(&locale, message_key)).await
+}
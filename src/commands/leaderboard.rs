@@ -0,0 +1,35 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use crate::i18n;
+use super::{anonymous_users, format_ranking, guild_config, locale, respond_embed, reveal_placement, stored_guild_id};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("leaderboard").description("Shows the 10 users with the most playtime on the server")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let config = guild_config(ctx, command.guild_id).await?;
+    // Only the guild's members are ranked, everyone is in DMs
+    let guild_id = stored_guild_id(command.guild_id)?;
+    let key = format!("leaderboard:{}:{}", guild_id.map_or("all".to_string(), |guild_id| guild_id.to_string()), if config.rank_manual_sessions { "manual" } else { "tracked" });
+    let ranking = cache::cached(ctx, &key, db.get_leaderboard(guild_id, config.rank_manual_sessions)).await?;
+    // Not cached, the privacy depends on who is asking
+    let anonymous = anonymous_users(db, ctx, Some(command.user.id), command.guild_id, &ranking).await?;
+    let description = if ranking.is_empty() { i18n::t(&locale, "no-playtime") } else { format_ranking(&locale, &ranking, &anonymous) };
+    let embed = CreateEmbed::default()
+        .title(i18n::t(&locale, "leaderboard-title"))
+        .description(description).to_owned();
+    respond_embed(ctx, command, embed).await?;
+    if config.anonymous_leaderboards {
+        reveal_placement(ctx, command, &locale, &ranking).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::levels::{level_for_xp, progress_bar, xp, xp_for_level};
+use super::{guild_config, respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("level").description("Shows a user's level, earned by playing")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let config = guild_config(ctx, command.guild_id).await?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s level", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return respond_embed(ctx, command, embed).await;
+    }
+    let total_xp = xp(db.get_total_playtime(&user_id).await?, config.xp_per_hour);
+    let level = level_for_xp(total_xp, config.level_base_xp);
+    let level_xp = xp_for_level(level, config.level_base_xp);
+    let next_level_xp = xp_for_level(level + 1, config.level_base_xp);
+    embed.field("Level", level.to_string(), true)
+        .field("XP", total_xp.to_string(), true)
+        .field(format!("Progress to level {}", level + 1),
+            format!("{} {}/{}", progress_bar(total_xp - level_xp, next_level_xp - level_xp), total_xp - level_xp, next_level_xp - level_xp), false);
+    respond_embed(ctx, command, embed).await
+}
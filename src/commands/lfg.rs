@@ -0,0 +1,63 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{integer_option, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("lfg").description("Manages the DMs you get when enough members play a game together")
+        .create_option(|subcommand| { subcommand.name("notify").description("Sends you a DM when enough members of the server play a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+            .create_sub_option(|option| {option.name("min_players").description("How many members must be playing").kind(CommandOptionType::Integer).min_int_value(2).max_int_value(100).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Stops the DMs about a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the games you get DMs about").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    let message_str = match subcommand.name.as_str() {
+        "notify" => {
+            let game_name = string_option(&subcommand.options, "game")?;
+            let min_players = integer_option(&subcommand.options, "min_players")?;
+            match db.find_game(game_name).await? {
+                Some((_, name)) => {
+                    db.set_lfg_subscription(&guild_id, &user_id, &name, &min_players).await?;
+                    format!("You will get a DM when {} members of the server play {}, make sure your DMs are open.", min_players, name)
+                },
+                None => format!("{} isn't tracked.", game_name),
+            }
+        },
+        "remove" => {
+            let game_name = string_option(&subcommand.options, "game")?;
+            if db.remove_lfg_subscription(&guild_id, &user_id, game_name).await? {
+                format!("You will no longer get DMs about {}.", game_name)
+            } else {
+                format!("You don't get DMs about {}.", game_name)
+            }
+        },
+        "list" => {
+            let subscriptions: Vec<String> = db.get_lfg_subscriptions(&guild_id, &user_id).await?.iter()
+                .map(|(game_name, min_players)| format!("{}: {} players", game_name, min_players))
+                .collect();
+            if subscriptions.is_empty() {
+                "You don't get DMs about any game.".to_string()
+            } else {
+                subscriptions.join("\n")
+            }
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
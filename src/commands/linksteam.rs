@@ -0,0 +1,41 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::steam::{self, Steam, STEAM};
+use super::{format_playtime, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("linksteam").description("Links your Steam account and imports its playtime")
+        .create_option(|option| {option.name("steamid").description("Your SteamID64 or the custom name of your profile URL").kind(CommandOptionType::String).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let steam = match ctx.data.read().await.get::<Steam>().cloned() {
+        Some(steam) => steam,
+        None => return respond_ephemeral(ctx, command, "Steam import isn't configured on this bot.".to_string()).await,
+    };
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    if db.is_opted_out(&user_id).await? {
+        return respond_ephemeral(ctx, command, "You opted out of tracking, opt back in with /tracking to import your playtime.".to_string()).await;
+    }
+    let input = string_option(&command.data.options, "steamid")?.trim();
+    let steam_id = match steam.resolve_id(input).await? {
+        Some(steam_id) => steam_id,
+        None => return respond_ephemeral(ctx, command, format!("{} isn't a Steam profile.", input)).await,
+    };
+    let message_str = match steam::import(db, &steam, &user_id, &steam_id).await? {
+        Some((games, playtime)) => {
+            db.link_account(&user_id, STEAM, &steam_id).await?;
+            format!("Linked your Steam account, imported {} of playtime on {} games. It is synced again every day.", format_playtime(playtime), games)
+        },
+        None => "Your game details are private, make them public in your Steam privacy settings and try again.".to_string(),
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
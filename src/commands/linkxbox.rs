@@ -0,0 +1,41 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::xbox::{self, Xbox, XBOX};
+use super::{format_playtime, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("linkxbox").description("Links your Xbox account and imports its playtime")
+        .create_option(|option| {option.name("gamertag").description("Your gamertag").kind(CommandOptionType::String).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let xbox = match ctx.data.read().await.get::<Xbox>().cloned() {
+        Some(xbox) => xbox,
+        None => return respond_ephemeral(ctx, command, "Xbox import isn't configured on this bot.".to_string()).await,
+    };
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    if db.is_opted_out(&user_id).await? {
+        return respond_ephemeral(ctx, command, "You opted out of tracking, opt back in with /tracking to import your playtime.".to_string()).await;
+    }
+    let gamertag = string_option(&command.data.options, "gamertag")?.trim();
+    let xuid = match xbox.resolve_xuid(gamertag).await? {
+        Some(xuid) => xuid,
+        None => return respond_ephemeral(ctx, command, format!("{} isn't a gamertag.", gamertag)).await,
+    };
+    let message_str = match xbox::import(db, &xbox, &user_id, &xuid).await? {
+        (0, _) => "No playtime was found, make sure your game history is public in your Xbox privacy settings and try again.".to_string(),
+        (games, playtime) => {
+            db.link_account(&user_id, XBOX, &xuid).await?;
+            format!("Linked your Xbox account, imported {} of playtime on {} games. It is synced again every day.", format_playtime(playtime), games)
+        },
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,45 @@
+use chrono::{Duration, NaiveDate, Utc};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{format_playtime, integer_option, local_midnight, respond_ephemeral, string_option, timezone};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("log").description("Records a session played away from Discord")
+        .create_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)})
+        .create_option(|option| {option.name("minutes").description("How long you played").kind(CommandOptionType::Integer).min_int_value(1).max_int_value(1440).required(true)})
+        .create_option(|option| {option.name("date").description("The day you played, as YYYY-MM-DD, today by default").kind(CommandOptionType::String).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    if db.is_opted_out(&user_id).await? {
+        return respond_ephemeral(ctx, command, "You opted out of tracking, use /tracking to opt back in.".to_string()).await;
+    }
+    let game_name = string_option(&command.data.options, "game")?;
+    let minutes = integer_option(&command.data.options, "minutes")?;
+    let timezone = timezone(db, &command.user.id).await?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let date = match string_option(&command.data.options, "date").ok() {
+        Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) if date <= today => date,
+            Ok(_) => return respond_ephemeral(ctx, command, "You can't log a session in the future.".to_string()).await,
+            Err(_) => return respond_ephemeral(ctx, command, format!("{} isn't a date, use the YYYY-MM-DD format.", date)).await,
+        },
+        None => today,
+    };
+    // The session ends now when it was played today, at the end of the day otherwise
+    let endtime = std::cmp::min(Utc::now().timestamp(), local_midnight(date + Duration::days(1), timezone));
+    let duration = minutes * 60;
+    let name = db.log_session(&user_id, game_name, &(endtime - duration), &duration).await?;
+    cache::invalidate(ctx).await;
+    respond_ephemeral(ctx, command, format!("Logged {} of {} on {}.", format_playtime(duration), name, date.format("%Y-%m-%d"))).await
+}
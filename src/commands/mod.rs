@@ -0,0 +1,661 @@
+use chrono::{Utc, TimeZone, Duration, Datelike, NaiveDate};
+use chrono_tz::Tz;
+use serenity::builder::{CreateApplicationCommands, CreateEmbed};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::application_command::{CommandDataOption, CommandDataOptionValue};
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::user::User;
+use serenity::utils::Colour;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use std::time::Instant;
+use tracing::{error, info};
+
+use crate::config::ConfigService;
+use crate::db::{Database, GuildConfig};
+use crate::errors::{self, ErrorContext};
+use crate::i18n;
+use crate::ratelimit::RateLimiter;
+
+mod achievements;
+mod activity;
+mod admin;
+mod alias;
+mod auditlog;
+mod backup;
+mod compare;
+mod config;
+mod confirm;
+mod digest;
+mod export;
+mod exportcsv;
+mod exportjson;
+mod fixsession;
+mod game;
+mod gameinfo;
+mod gamestats;
+mod gametop;
+mod genres;
+mod goal;
+mod hardreset;
+mod heatmap;
+mod language;
+mod ignore;
+mod import;
+mod leaderboard;
+mod level;
+mod lfg;
+mod linksteam;
+mod linkxbox;
+mod log;
+mod monthly;
+mod musicstats;
+mod nowplaying;
+mod pause;
+mod privacy;
+mod recent;
+mod reset;
+mod resetall;
+mod restore;
+mod sessions;
+mod rolereward;
+mod status;
+mod streak;
+mod streams;
+mod summarize;
+mod timezone;
+mod topgames;
+mod total;
+mod trackedgames;
+mod tracking;
+mod trend;
+mod undoreset;
+mod unlink;
+mod vacation;
+mod voicetime;
+mod watch;
+mod webhook;
+mod weekdays;
+mod wrapped;
+
+
+pub fn register(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    commands
+        .create_application_command(|command| summarize::register(command))
+        .create_application_command(|command| leaderboard::register(command))
+        .create_application_command(|command| gametop::register(command))
+        .create_application_command(|command| topgames::register(command))
+        .create_application_command(|command| compare::register(command))
+        .create_application_command(|command| gamestats::register(command))
+        .create_application_command(|command| total::register(command))
+        .create_application_command(|command| recent::register(command))
+        .create_application_command(|command| sessions::register(command))
+        .create_application_command(|command| nowplaying::register(command))
+        .create_application_command(|command| activity::register(command))
+        .create_application_command(|command| trend::register(command))
+        .create_application_command(|command| heatmap::register(command))
+        .create_application_command(|command| weekdays::register(command))
+        .create_application_command(|command| monthly::register(command))
+        .create_application_command(|command| wrapped::register(command))
+        .create_application_command(|command| level::register(command))
+        .create_application_command(|command| achievements::register(command))
+        .create_application_command(|command| voicetime::register(command))
+        .create_application_command(|command| streams::register(command))
+        .create_application_command(|command| musicstats::register(command))
+        .create_application_command(|command| gameinfo::register(command))
+        .create_application_command(|command| genres::register(command))
+        .create_application_command(|command| linksteam::register(command))
+        .create_application_command(|command| linkxbox::register(command))
+        .create_application_command(|command| unlink::register(command))
+        .create_application_command(|command| status::register(command))
+        .create_application_command(|command| reset::register(command))
+        .create_application_command(|command| resetall::register(command))
+        .create_application_command(|command| undoreset::register(command))
+        .create_application_command(|command| fixsession::register(command))
+        .create_application_command(|command| log::register(command))
+        .create_application_command(|command| import::register(command))
+        .create_application_command(|command| pause::register(command))
+        .create_application_command(|command| vacation::register(command))
+        .create_application_command(|command| streak::register(command))
+        .create_application_command(|command| hardreset::register(command))
+        .create_application_command(|command| tracking::register(command))
+        .create_application_command(|command| digest::register(command))
+        .create_application_command(|command| goal::register(command))
+        .create_application_command(|command| watch::register(command))
+        .create_application_command(|command| lfg::register(command))
+        .create_application_command(|command| export::register(command))
+        .create_application_command(|command| exportcsv::register(command))
+        .create_application_command(|command| exportjson::register(command))
+        .create_application_command(|command| config::register(command))
+        .create_application_command(|command| alias::register(command))
+        .create_application_command(|command| game::register(command))
+        .create_application_command(|command| admin::register(command))
+        .create_application_command(|command| auditlog::register(command))
+        .create_application_command(|command| backup::register(command))
+        .create_application_command(|command| restore::register(command))
+        .create_application_command(|command| ignore::register(command))
+        .create_application_command(|command| trackedgames::register(command))
+        .create_application_command(|command| rolereward::register(command))
+        .create_application_command(|command| webhook::register(command))
+        .create_application_command(|command| language::register(command))
+        .create_application_command(|command| timezone::register(command))
+        .create_application_command(|command| privacy::register(command))
+        .create_application_command(|command| summarize::register_context_menu(command));
+    localize_descriptions(commands)
+}
+
+// Adds the translated descriptions of the commands, Discord shows them in the user's client language
+fn localize_descriptions(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    // Only slash commands have a description
+    for command in commands.0.iter_mut().filter(|command| command.get("type").is_none()) {
+        let name = command["name"].as_str().unwrap_or_default().to_string();
+        let localizations: serde_json::Map<String, serde_json::Value> = i18n::locales()
+            .filter(|locale| *locale != i18n::DEFAULT_LOCALE)
+            .filter_map(|locale| i18n::translation(locale, &format!("{}-description", name)).map(|description| (locale.to_string(), description.into())))
+            .collect();
+        if let Some(command) = command.as_object_mut() {
+            command.insert("description_localizations".to_string(), localizations.into());
+        }
+    }
+    commands
+}
+
+// Runs in the span of the command, with the time it took
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) {
+    let started = Instant::now();
+    match rate_limit(db, ctx, command).await {
+        Ok(true) => return,
+        Ok(false) => {},
+        Err(why) => error!("Cannot rate limit /{}: {:?}", command.data.name, why),
+    }
+    match hidden_stats(db, ctx, command).await {
+        Ok(true) => return,
+        Ok(false) => {},
+        Err(why) => error!("Cannot check the privacy of /{}: {:?}", command.data.name, why),
+    }
+    let result = match command.data.name.as_str() {
+        "summarize" => summarize::run(db, ctx, command).await,
+        "leaderboard" => leaderboard::run(db, ctx, command).await,
+        "gametop" => gametop::run(db, ctx, command).await,
+        "topgames" => topgames::run(db, ctx, command).await,
+        "compare" => compare::run(db, ctx, command).await,
+        "gamestats" => gamestats::run(db, ctx, command).await,
+        "total" => total::run(db, ctx, command).await,
+        "recent" => recent::run(db, ctx, command).await,
+        "sessions" => sessions::run(db, ctx, command).await,
+        "nowplaying" => nowplaying::run(db, ctx, command).await,
+        "activity" => activity::run(db, ctx, command).await,
+        "trend" => trend::run(db, ctx, command).await,
+        "heatmap" => heatmap::run(db, ctx, command).await,
+        "weekdays" => weekdays::run(db, ctx, command).await,
+        "monthly" => monthly::run(db, ctx, command).await,
+        "wrapped" => wrapped::run(db, ctx, command).await,
+        "level" => level::run(db, ctx, command).await,
+        "achievements" => achievements::run(db, ctx, command).await,
+        "voicetime" => voicetime::run(db, ctx, command).await,
+        "streams" => streams::run(db, ctx, command).await,
+        "musicstats" => musicstats::run(db, ctx, command).await,
+        "gameinfo" => gameinfo::run(db, ctx, command).await,
+        "genres" => genres::run(db, ctx, command).await,
+        "linksteam" => linksteam::run(db, ctx, command).await,
+        "linkxbox" => linkxbox::run(db, ctx, command).await,
+        "unlink" => unlink::run(db, ctx, command).await,
+        "status" => status::run(db, ctx, command).await,
+        "reset" => reset::run(db, ctx, command).await,
+        "resetall" => resetall::run(db, ctx, command).await,
+        "undo-reset" => undoreset::run(db, ctx, command).await,
+        "fix-session" => fixsession::run(db, ctx, command).await,
+        "log" => log::run(db, ctx, command).await,
+        "import" => import::run(db, ctx, command).await,
+        "pause" => pause::run(db, ctx, command).await,
+        "vacation" => vacation::run(db, ctx, command).await,
+        "streak" => streak::run(db, ctx, command).await,
+        "hardreset" => hardreset::run(db, ctx, command).await,
+        "tracking" => tracking::run(db, ctx, command).await,
+        "digest" => digest::run(db, ctx, command).await,
+        "goal" => goal::run(db, ctx, command).await,
+        "watch" => watch::run(db, ctx, command).await,
+        "lfg" => lfg::run(db, ctx, command).await,
+        "export" => export::run(db, ctx, command).await,
+        "exportcsv" => exportcsv::run(db, ctx, command).await,
+        "exportjson" => exportjson::run(db, ctx, command).await,
+        "config" => config::run(db, ctx, command).await,
+        "alias" => alias::run(db, ctx, command).await,
+        "game" => game::run(db, ctx, command).await,
+        "admin" => admin::run(db, ctx, command).await,
+        "auditlog" => auditlog::run(db, ctx, command).await,
+        "backup" => backup::run(db, ctx, command).await,
+        "restore" => restore::run(db, ctx, command).await,
+        "ignore" => ignore::run(db, ctx, command).await,
+        "trackedgames" => trackedgames::run(db, ctx, command).await,
+        "rolereward" => rolereward::run(db, ctx, command).await,
+        "webhook" => webhook::run(db, ctx, command).await,
+        "language" => language::run(db, ctx, command).await,
+        "timezone" => timezone::run(db, ctx, command).await,
+        "privacy" => privacy::run(db, ctx, command).await,
+        summarize::VIEW_PLAYTIME => summarize::run_context_menu(db, ctx, command).await,
+        command => unreachable!("Command don't have a handler: {}", command),
+    };
+    match result {
+        Ok(()) => info!(elapsed = ?started.elapsed(), "Handled /{}", command.data.name),
+        Err(why) => {
+            error!(elapsed = ?started.elapsed(), "/{} failed: {:?}", command.data.name, why);
+            errors::report(&format!("/{} failed: {:?}", command.data.name, why), ErrorContext {
+                user_id: Some(command.user.id.0),
+                guild_id: command.guild_id.map(|guild_id| guild_id.0),
+                command: Some(command.data.name.clone()),
+            });
+            respond_error(db, ctx, command).await;
+        },
+    }
+}
+
+// Tells the user to wait when they ran the command too often, returns whether they have to
+async fn rate_limit(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<bool> {
+    let rate_limiter = ctx.data.read().await.get::<RateLimiter>().cloned().ok_or_else(|| anyhow!("The rate limiter isn't registered"))?;
+    let wait = match rate_limiter.acquire(*command.user.id.as_u64(), &command.data.name) {
+        Some(wait) => wait,
+        None => return Ok(false),
+    };
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let seconds = wait.as_secs() + 1;
+    respond_ephemeral(ctx, command, i18n::tr(&locale, "rate-limited", &[("command", command.data.name.clone()), ("seconds", seconds.to_string())])).await?;
+    return Ok(true);
+}
+
+// The commands showing the stats of the users given as options
+const STATS_COMMANDS: [&str; 20] = [
+    "summarize", "compare", "gamestats", "total", "recent", "sessions", "nowplaying", "trend", "heatmap", "weekdays",
+    "monthly", "level", "achievements", "voicetime", "streams", "musicstats", "genres", "wrapped", "streak", summarize::VIEW_PLAYTIME,
+];
+
+// Tells the user when someone they asked about hides their stats from them, returns whether one does
+async fn hidden_stats(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<bool> {
+    if !STATS_COMMANDS.contains(&command.data.name.as_str()) {
+        return Ok(false);
+    }
+    // The users of the options, and the target of the context menu
+    for user in command.data.resolved.users.values() {
+        if !can_view(db, ctx, Some(command.user.id), command.guild_id, user.id).await? {
+            let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+            respond_ephemeral(ctx, command, i18n::tr(&locale, "privacy-hidden", &[("user", user.mention().to_string())])).await?;
+            return Ok(true);
+        }
+    }
+    return Ok(false);
+}
+
+// Whether the viewer can see the user's stats in the guild, guild-only stats are shown in the guilds the user is a member of.
+// Reports posted to a guild have no viewer
+pub async fn can_view(db: &Database, ctx: &Context, viewer_id: Option<UserId>, guild_id: Option<GuildId>, user_id: UserId) -> Result<bool> {
+    if viewer_id == Some(user_id) {
+        return Ok(true);
+    }
+    return match db.get_privacy_level(&i64::try_from(*user_id.as_u64())?).await?.as_str() {
+        "private" => Ok(false),
+        // Checks the cache before asking Discord
+        "guild-only" => Ok(match guild_id {
+            Some(guild_id) => guild_id.member(ctx, user_id).await.is_ok(),
+            None => false,
+        }),
+        _ => Ok(true),
+    };
+}
+
+// The users of a ranking shown as anonymous: the ones the viewer can't see the stats of, or everyone on a guild with anonymous leaderboards
+pub async fn anonymous_users(db: &Database, ctx: &Context, viewer_id: Option<UserId>, guild_id: Option<GuildId>, ranking: &[(i64, i64)]) -> Result<Vec<i64>> {
+    if guild_config(ctx, guild_id).await?.anonymous_leaderboards {
+        return Ok(ranking.iter().map(|(user_id, _)| *user_id).collect());
+    }
+    let mut anonymous: Vec<i64> = Vec::new();
+    for (user_id, _) in ranking {
+        if !can_view(db, ctx, viewer_id, guild_id, UserId(u64::try_from(*user_id)?)).await? {
+            anonymous.push(*user_id);
+        }
+    }
+    return Ok(anonymous);
+}
+
+// Every autocompleted option is a game name, except for /timezone
+pub async fn autocomplete(db: &Database, ctx: &Context, autocomplete: &AutocompleteInteraction) -> Result<()> {
+    if autocomplete.data.name == "timezone" {
+        return timezone::autocomplete(ctx, autocomplete).await;
+    }
+    // Options of a subcommand are nested under it
+    let focused = autocomplete.data.options.iter()
+        .flat_map(|option| std::iter::once(option).chain(option.options.iter()))
+        .find(|option| option.focused);
+    if focused.is_none() {
+        return Ok(());
+    }
+    let input = focused.unwrap().value.as_ref().and_then(|value| value.as_str()).unwrap_or("");
+    let game_names = db.search_games(input).await?;
+    autocomplete.create_autocomplete_response(&ctx.http, |response| {
+        for game_name in &game_names {
+            response.add_string_choice(game_name, game_name);
+        }
+        response
+    })
+        .await?;
+    Ok(())
+}
+
+pub async fn component(db: &Database, ctx: &Context, component: &MessageComponentInteraction) -> Result<()> {
+    if component.data.custom_id.starts_with("summary:") {
+        summarize::paginate(db, ctx, component).await?;
+    } else if component.data.custom_id.starts_with("confirm:") {
+        confirm::handle(db, ctx, component).await?;
+    } else if component.data.custom_id.starts_with("auditlog:") {
+        auditlog::paginate(db, ctx, component).await?;
+    }
+    Ok(())
+}
+
+// Embeds are sent with the appearance configured for the guild
+async fn respond_embed(ctx: &Context, command: &ApplicationCommandInteraction, mut embed: CreateEmbed) -> Result<()> {
+    style_embed(ctx, command.guild_id, &mut embed).await?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.set_embed(embed))
+    })
+        .await?;
+    Ok(())
+}
+
+// Tells the viewer of an anonymous leaderboard their own placement, only they see it
+async fn reveal_placement(ctx: &Context, command: &ApplicationCommandInteraction, locale: &str, ranking: &[(i64, i64)]) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let content = match ranking.iter().position(|(ranked_id, _)| *ranked_id == user_id) {
+        Some(rank) => i18n::tr(locale, "leaderboard-placement", &[("rank", (rank + 1).to_string()), ("playtime", format_playtime(ranking[rank].1))]),
+        None => i18n::t(locale, "leaderboard-unranked"),
+    };
+    command.create_followup_message(&ctx.http, |message| message.ephemeral(true).content(content)).await?;
+    Ok(())
+}
+
+async fn respond_ephemeral(ctx: &Context, command: &ApplicationCommandInteraction, content: String) -> Result<()> {
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true).content(content))
+    })
+        .await?;
+    Ok(())
+}
+
+// Replaces the deferred response, which stays ephemeral
+async fn edit_response(ctx: &Context, command: &ApplicationCommandInteraction, content: String) -> Result<()> {
+    command.edit_original_interaction_response(&ctx.http, |response| response.content(content)).await?;
+    Ok(())
+}
+
+// Tells the user their command failed instead of leaving the interaction unanswered
+async fn respond_error(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) {
+    // The database may be why the command failed
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await.unwrap_or_else(|_| i18n::DEFAULT_LOCALE.to_string());
+    let embed = CreateEmbed::default()
+        .colour(Colour::RED)
+        .title(i18n::t(&locale, "error-title"))
+        .description(i18n::t(&locale, "error-description")).to_owned();
+    let response = command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true).set_embed(embed))
+    }).await;
+    if let Err(why) = response {
+        error!("Cannot respond to /{}: {:?}", command.data.name, why);
+    }
+}
+
+// Records a destructive admin command in the audit log
+async fn audit(db: &Database, user_id: &UserId, guild_id: Option<GuildId>, action: &str, details: String) -> Result<()> {
+    db.add_audit_entry(&i64::try_from(*user_id.as_u64())?, stored_guild_id(guild_id)?, action, &details, &Utc::now().timestamp()).await
+}
+
+// The id the guild is stored under, None outside of a guild
+pub fn stored_guild_id(guild_id: Option<GuildId>) -> Result<Option<i64>> {
+    return Ok(guild_id.map(|guild_id| i64::try_from(*guild_id.as_u64())).transpose()?);
+}
+
+// The language to answer in: the user's choice, else the guild's
+pub async fn locale(db: &Database, ctx: &Context, user_id: &UserId, guild_id: Option<GuildId>) -> Result<String> {
+    let user_id = i64::try_from(*user_id.as_u64())?;
+    if let Some(locale) = db.get_user_locale(&user_id).await?.filter(|locale| i18n::is_supported(locale)) {
+        return Ok(locale);
+    }
+    let locale = guild_config(ctx, guild_id).await?.locale;
+    return Ok(if i18n::is_supported(&locale) { locale } else { i18n::DEFAULT_LOCALE.to_string() });
+}
+
+async fn config_service(ctx: &Context) -> Result<Arc<ConfigService>> {
+    return ctx.data.read().await.get::<ConfigService>().cloned().ok_or_else(|| anyhow!("The config service isn't registered"));
+}
+
+// Interactions outside of a guild use the default configuration
+pub async fn guild_config(ctx: &Context, guild_id: Option<GuildId>) -> Result<GuildConfig> {
+    return match guild_id {
+        Some(guild_id) => config_service(ctx).await?.get(&i64::try_from(*guild_id.as_u64())?).await,
+        None => Ok(GuildConfig::default()),
+    };
+}
+
+// Applies the colour, title template and thumbnail setting of a guild to a built embed
+pub fn apply_appearance(embed: &mut CreateEmbed, config: &GuildConfig) -> Result<()> {
+    embed.colour(Colour::new(u32::try_from(config.embed_color)?));
+    if let Some(title) = embed.0.get("title").and_then(|title| title.as_str()).map(|title| title.to_string()) {
+        embed.title(config.title_template.replace("{title}", &title));
+    }
+    if !config.show_thumbnails {
+        embed.0.remove("thumbnail");
+    }
+    Ok(())
+}
+
+async fn style_embed(ctx: &Context, guild_id: Option<GuildId>, embed: &mut CreateEmbed) -> Result<()> {
+    apply_appearance(embed, &guild_config(ctx, guild_id).await?)
+}
+
+fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Result<&'a str> {
+    return options.iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| anyhow!("Missing '{}' option", name));
+}
+
+fn integer_option(options: &[CommandDataOption], name: &str) -> Result<i64> {
+    return options.iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_i64())
+        .ok_or_else(|| anyhow!("Missing '{}' option", name));
+}
+
+// Unset boolean options are false
+fn boolean_option(options: &[CommandDataOption], name: &str) -> bool {
+    return options.iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+}
+
+// Discord sends the user along with the option, it doesn't have to be fetched
+fn resolved_user<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a User> {
+    return options.iter()
+        .find(|option| option.name == name)
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user),
+            _ => None,
+        });
+}
+
+fn user_option(options: &[CommandDataOption], name: &str) -> Result<u64> {
+    return resolved_user(options, name).map(|user| *user.id.as_u64()).ok_or_else(|| anyhow!("Missing '{}' option", name));
+}
+
+// Stat commands are about whoever ran them when no user is given
+fn target_user(command: &ApplicationCommandInteraction) -> User {
+    return resolved_user(&command.data.options, "user").unwrap_or(&command.user).clone();
+}
+
+// Answers the commands the user isn't allowed to use, in their language
+async fn permission_denied(db: &Database, ctx: &Context, user_id: &UserId, guild_id: Option<GuildId>) -> Result<String> {
+    return Ok(i18n::t(&locale(db, ctx, user_id, guild_id).await?, "permission-denied"));
+}
+
+fn has_manage_permissions(member: Option<&Member>) -> bool {
+    let permissions = member.and_then(|member| member.permissions);
+    return permissions.map_or(false, |permissions| permissions.administrator() || permissions.manage_guild());
+}
+
+// Members with the Administrator or Manage Server permission, one of the admin roles of their guild, or listed as bot admins are admins of their guild
+async fn is_admin(db: &Database, member: Option<&Member>) -> Result<bool> {
+    if member.is_none() {
+        return Ok(false);
+    }
+    if has_manage_permissions(member) {
+        return Ok(true);
+    }
+    if db.is_bot_admin(&i64::try_from(*member.unwrap().user.id.as_u64())?).await? {
+        return Ok(true);
+    }
+    let role_ids: Vec<i64> = member.unwrap().roles.iter().filter_map(|role_id| i64::try_from(*role_id.as_u64()).ok()).collect();
+    return db.has_admin_role(&i64::try_from(*member.unwrap().guild_id.as_u64())?, role_ids).await;
+}
+
+// The playtimes and games are shared by every guild, only the owner of the bot and the bot admins can change them
+async fn is_global_admin(db: &Database, ctx: &Context, user_id: UserId) -> Result<bool> {
+    if db.is_bot_admin(&i64::try_from(*user_id.as_u64())?).await? {
+        return Ok(true);
+    }
+    return is_owner(ctx, user_id).await;
+}
+
+// The owner of the bot's application, or a member of its team
+async fn is_owner(ctx: &Context, user_id: UserId) -> Result<bool> {
+    let application = ctx.http.get_current_application_info().await?;
+    if application.owner.id == user_id {
+        return Ok(true);
+    }
+    return Ok(application.team.map_or(false, |team| team.members.iter().any(|member| member.user.id == user_id)));
+}
+
+// The start of the period in the timezone, None means all-time
+pub fn period_start(period: &str, timezone: Tz) -> Option<i64> {
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let start = match period {
+        "today" => today,
+        "week" => today - Duration::days(i64::from(today.weekday().num_days_from_monday())),
+        "month" => today.with_day(1).unwrap(),
+        "year" => NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap(),
+        _ => return None,
+    };
+    return Some(local_midnight(start, timezone));
+}
+
+// The timestamp of the start of `date` in `timezone`
+pub fn local_midnight(date: NaiveDate, timezone: Tz) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    // Some DST changes skip midnight, the day then starts at the same time as in UTC
+    return timezone.from_local_datetime(&midnight).earliest().unwrap_or_else(|| timezone.from_utc_datetime(&midnight)).timestamp();
+}
+
+// The translation key of a period's name
+fn period_key(period: &str) -> &'static str {
+    match period {
+        "today" => "period-today",
+        "week" => "period-week",
+        "month" => "period-month",
+        "year" => "period-year",
+        _ => "period-all",
+    }
+}
+
+// Users without a valid timezone are on UTC
+pub fn user_timezone(timezone: Option<&str>) -> Tz {
+    return timezone.and_then(|timezone| timezone.parse::<Tz>().ok()).unwrap_or(Tz::UTC);
+}
+
+pub async fn timezone(db: &Database, user_id: &UserId) -> Result<Tz> {
+    let timezone = db.get_user_timezone(&i64::try_from(*user_id.as_u64())?).await?;
+    return Ok(user_timezone(timezone.as_deref()));
+}
+
+// Formats a number of seconds like "3d 7h 12m", durations under a minute are shown in seconds
+pub fn format_playtime(playtime: i64) -> String {
+    if playtime < 60 {
+        return format!("{}s", playtime.max(0));
+    }
+    let units = [(playtime / 86400, "d"), (playtime % 86400 / 3600, "h"), (playtime % 3600 / 60, "m")];
+    let parts: Vec<String> = units.iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+    return parts.join(" ");
+}
+
+// A bar of up to `width` blocks for `value` relative to `most`, with eighths of a block for the remainders
+pub fn bar(value: i64, most: i64, width: i64) -> Result<String> {
+    let eighths = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let length = value * width * 8 / std::cmp::max(most, 1);
+    let mut bar = "█".repeat(usize::try_from(length / 8)?);
+    if length % 8 > 0 {
+        bar.push(eighths[usize::try_from(length % 8 - 1)?]);
+    }
+    return Ok(bar);
+}
+
+// Formats (user_id, playtime) pairs as a ranked list, with medals for the podium
+pub fn format_ranking(locale: &str, ranking: &[(i64, i64)], anonymous: &[i64]) -> String {
+    if ranking.is_empty() {
+        return i18n::t(locale, "no-playtime");
+    }
+    let medals = ["🥇", "🥈", "🥉"];
+    let mut lines: Vec<String> = Vec::new();
+    for (rank, (user_id, playtime)) in ranking.iter().enumerate() {
+        let placement = if rank < medals.len() { medals[rank].to_string() } else { format!("**#{}**", rank + 1) };
+        let name = if anonymous.contains(user_id) { i18n::t(locale, "anonymous") } else { format!("<@{}>", user_id) };
+        lines.push(format!("{} {} — {}", placement, name, format_playtime(*playtime)));
+    }
+    return lines.join("\n");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::format_playtime;
+
+    #[test]
+    fn formats_short_durations_in_seconds() {
+        assert_eq!(format_playtime(0), "0s");
+        assert_eq!(format_playtime(59), "59s");
+    }
+
+    #[test]
+    fn formats_minutes_and_hours() {
+        assert_eq!(format_playtime(60), "1m");
+        assert_eq!(format_playtime(3600), "1h");
+        assert_eq!(format_playtime(2 * 3600 + 5 * 60 + 30), "2h 5m");
+    }
+
+    #[test]
+    fn formats_more_than_a_day() {
+        assert_eq!(format_playtime(86400), "1d");
+        assert_eq!(format_playtime(3 * 86400 + 7 * 3600 + 12 * 60), "3d 7h 12m");
+        assert_eq!(format_playtime(25 * 3600), "1d 1h");
+    }
+
+    #[test]
+    fn formats_more_than_a_month() {
+        assert_eq!(format_playtime(45 * 86400 + 30 * 60), "45d 30m");
+        assert_eq!(format_playtime(400 * 86400 + 23 * 3600 + 59 * 60 + 59), "400d 23h 59m");
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{Datelike, Months, Utc};
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, local_midnight, respond_embed, target_user, timezone};
+
+
+// Games compared, by playtime this month then last month
+const MONTHLY_GAMES: usize = 10;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("monthly").description("Compares the playtime of a user this month with last month")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_monthly(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_monthly(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s month", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    // The months are the ones of the player, not of whoever asked
+    let timezone = timezone(db, &user.id).await?;
+    let now = Utc::now();
+    let first_day = now.with_timezone(&timezone).date_naive().with_day(1).unwrap();
+    let month_start = local_midnight(first_day, timezone);
+    let last_month_start = local_midnight(first_day - Months::new(1), timezone);
+    let current = db.get_user_period_games(&user_id, &month_start, &now.timestamp()).await?;
+    let previous = db.get_user_period_games(&user_id, &last_month_start, &month_start).await?;
+    if current.is_empty() && previous.is_empty() {
+        embed.description(format!("{} didn't play anything this month or last month.", user.mention()));
+        return Ok(embed);
+    }
+
+    let total: i64 = current.iter().map(|game| game.playtime).sum();
+    let last_total: i64 = previous.iter().map(|game| game.playtime).sum();
+    embed.description(format!("**{}** this month, {} last month {}", format_playtime(total), format_playtime(last_total), delta(total, last_total)));
+    // Games only played last month come after the ones played this month
+    let last_playtimes: HashMap<&str, i64> = previous.iter().map(|game| (game.name.as_str(), game.playtime)).collect();
+    let mut games: Vec<(&str, i64, i64)> = current.iter()
+        .map(|game| (game.name.as_str(), game.playtime, last_playtimes.get(game.name.as_str()).copied().unwrap_or(0)))
+        .collect();
+    games.extend(previous.iter()
+        .filter(|game| !current.iter().any(|current| current.name == game.name))
+        .map(|game| (game.name.as_str(), 0, game.playtime)));
+    for (name, playtime, last_playtime) in games.into_iter().take(MONTHLY_GAMES) {
+        let value = match last_playtime {
+            0 => format!("{} (new)", format_playtime(playtime)),
+            _ => format!("{} {}", format_playtime(playtime), delta(playtime, last_playtime)),
+        };
+        embed.field(name, value, true);
+    }
+    embed.footer(|footer| footer.text(format!("Since {}", first_day.format("%d/%m/%Y"))));
+    return Ok(embed);
+}
+
+// The change from last month, with an arrow pointing up or down
+fn delta(playtime: i64, last_playtime: i64) -> String {
+    if playtime > last_playtime {
+        return format!("(▲ {})", format_playtime(playtime - last_playtime));
+    }
+    if playtime < last_playtime {
+        return format!("(▼ {})", format_playtime(last_playtime - playtime));
+    }
+    return "(=)".to_string();
+}
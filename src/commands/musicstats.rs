@@ -0,0 +1,43 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("musicstats").description("Shows the 10 artists a user listened to the most")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_music_stats(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_music_stats(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s music stats", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let listen_time = db.get_listen_time(&user_id).await?;
+    if listen_time == 0 {
+        embed.description(format!("{} hasn't listened to anything yet, or this server doesn't track listening.", user.mention()));
+        return Ok(embed);
+    }
+    embed.description(format!("Listened for {} in total.", format_playtime(listen_time)));
+    for (artist, listentime) in db.get_top_artists(&user_id, 10).await? {
+        embed.field(artist, format_playtime(listentime), true);
+    }
+    return Ok(embed);
+}
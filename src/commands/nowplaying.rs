@@ -0,0 +1,44 @@
+use chrono::Utc;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("nowplaying").description("Shows what a user is playing right now")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{} is playing", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return respond_embed(ctx, command, embed).await;
+    }
+    let sessions = db.get_open_sessions(&user_id).await?;
+    if sessions.is_empty() {
+        embed.description(format!("{} isn't playing anything.", user.mention()));
+        return respond_embed(ctx, command, embed).await;
+    }
+    // The rich presence is the last one the sessions got, whichever server it came from
+    let currenttime = Utc::now().timestamp();
+    for session in sessions {
+        let mut lines: Vec<String> = vec![format!("For {}, since <t:{}:t>", format_playtime(currenttime - session.starttime), session.starttime)];
+        lines.extend(session.details.into_iter().chain(session.state));
+        if let Some(party_size) = session.party_size.filter(|party_size| *party_size > 1) {
+            lines.push(format!("In a party of {}", party_size));
+        }
+        embed.field(session.name, lines.join("\n"), false);
+    }
+    respond_embed(ctx, command, embed).await
+}
@@ -0,0 +1,40 @@
+use chrono::Utc;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{integer_option, respond_ephemeral};
+
+
+// Minutes paused when no duration is given
+const DEFAULT_PAUSE: i64 = 60;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("pause").description("Stops tracking your playtime for a while, it resumes on its own")
+        .create_option(|option| {option.name("minutes").description("How long the tracking is paused, an hour by default").kind(CommandOptionType::Integer).min_int_value(1).max_int_value(10080).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    if db.is_opted_out(&user_id).await? {
+        return respond_ephemeral(ctx, command, "Your playtime isn't tracked, you opted out.".to_string()).await;
+    }
+    let minutes = integer_option(&command.data.options, "minutes").unwrap_or(DEFAULT_PAUSE);
+    let currenttime = Utc::now().timestamp();
+    // What was played before the pause is kept, like when the user goes offline
+    if !db.save_session(&user_id, &[], None, &currenttime).await?.is_empty() {
+        cache::invalidate(ctx).await;
+    }
+    db.save_stream(&user_id, None, None).await?;
+    db.save_listen(&user_id, None).await?;
+    db.end_voice_session(&user_id, &currenttime).await?;
+    let until = currenttime + minutes * 60;
+    db.set_paused_until(&user_id, Some(until)).await?;
+    respond_ephemeral(ctx, command, format!("Your playtime isn't tracked until <t:{}:f>, use /tracking on to resume it sooner.", until)).await
+}
@@ -0,0 +1,48 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{boolean_option, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("privacy").description("Chooses who can see your stats")
+        .create_option(|subcommand| { subcommand.name("set").description("Chooses who can see your stats").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("level").description("Who can see your stats").kind(CommandOptionType::String).required(true)
+                .add_string_choice("Everyone", "public")
+                .add_string_choice("The members of your servers", "guild-only")
+                .add_string_choice("Only you", "private")}) })
+        .create_option(|subcommand| { subcommand.name("replies").description("Chooses whether your stats are only shown to you by default").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("private").description("Whether /summarize only answers to you").kind(CommandOptionType::Boolean).required(true)}) })
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    let message_str = match subcommand.name.as_str() {
+        "set" => {
+            let level = string_option(&subcommand.options, "level")?;
+            db.set_privacy_level(&user_id, level).await?;
+            match level {
+                "private" => "Your stats are now only shown to you, the leaderboards show you as anonymous.",
+                "guild-only" => "Your stats are now only shown to the members of your servers.",
+                _ => "Your stats are now shown to everyone.",
+            }
+        },
+        _ => {
+            let private = boolean_option(&subcommand.options, "private");
+            db.set_private_stats(&user_id, private).await?;
+            if private {
+                "Your replies are now only shown to you, unless you ask otherwise."
+            } else {
+                "Your replies are now shown to the channel, unless you ask otherwise."
+            }
+        },
+    };
+    respond_ephemeral(ctx, command, message_str.to_string()).await
+}
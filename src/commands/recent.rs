@@ -0,0 +1,46 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user};
+
+
+const RECENT_SESSIONS: i64 = 10;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("recent").description("Shows a user's last 10 gaming sessions")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_recent(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_recent(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s recent sessions", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let sessions = db.get_recent_sessions(&user_id, RECENT_SESSIONS).await?;
+    if sessions.is_empty() {
+        embed.description(format!("{} hasn't played anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    // Discord renders the relative timestamps in the reader's timezone
+    let lines: Vec<String> = sessions.iter()
+        .map(|(game_name, starttime, duration)| format!("**{}** <t:{}:R> for {}", game_name, starttime, format_playtime(*duration)))
+        .collect();
+    embed.description(lines.join("\n"));
+    return Ok(embed);
+}
@@ -0,0 +1,31 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::UserId;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, is_global_admin, permission_denied, respond_ephemeral, user_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("reset").description("Resets the player's playtimes")
+        .create_option(|option| {option.name("user").description("The target").kind(CommandOptionType::User).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_global_admin(db, ctx, command.user.id).await? {
+        let user_id = user_option(&command.data.options, "user")?;
+        let user = UserId(user_id).to_user(&ctx.http).await?;
+        db.reset(&i64::try_from(*user.id.as_u64())?).await?;
+        cache::invalidate(ctx).await;
+        audit(db, &command.user.id, command.guild_id, "reset", format!("{}'s playtimes", user.mention())).await?;
+        message_str = format!("Successfully reseted {}'s playtimes, /undo-reset can restore them for 24 hours.", user.mention());
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,27 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{audit, confirm, is_global_admin, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("resetall").description("Resets all playtimes and games")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_global_admin(db, ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    confirm::ask(db, ctx, command, "resetall", "Are you sure you want to reset all playtimes and games?".to_string()).await
+}
+
+pub async fn confirmed(db: &Database, component: &MessageComponentInteraction) -> Result<String> {
+    db.resetall().await?;
+    audit(db, &component.user.id, component.guild_id, "resetall", "All playtimes and games".to_string()).await?;
+    return Ok("Successfully reseted all playtimes and games, /undo-reset can restore them for 24 hours.".to_string());
+}
@@ -0,0 +1,58 @@
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::AttachmentId;
+use serenity::prelude::*;
+use std::io::Read;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, config_service, edit_response, is_owner, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("restore").description("Replaces the whole database with a backup, only for the bot owner")
+        .create_option(|option| {option.name("backup").description("The file made by /backup").kind(CommandOptionType::Attachment).required(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    if !is_owner(ctx, command.user.id).await? {
+        return respond_ephemeral(ctx, command, permission_denied(db, ctx, &command.user.id, command.guild_id).await?).await;
+    }
+    // Downloading and restoring the backup can take longer than the 3 seconds Discord waits for the answer
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(true))
+    })
+        .await?;
+    let attachment_id = AttachmentId(string_option(&command.data.options, "backup")?.parse::<u64>()?);
+    let attachment = match command.data.resolved.attachments.get(&attachment_id) {
+        Some(attachment) => attachment,
+        None => return edit_response(ctx, command, "The backup couldn't be found.".to_string()).await,
+    };
+    let mut json = String::new();
+    let dump: Option<Value> = GzDecoder::new(attachment.download().await?.as_slice()).read_to_string(&mut json).ok()
+        .and_then(|_| serde_json::from_str(&json).ok());
+    let message_str = match dump {
+        Some(dump) if dump["tables"].is_object() => {
+            let schema_version = db.get_schema_version().await?;
+            if dump["schema_version"].as_i64() != schema_version {
+                format!("The backup was made with the schema version {}, the database is at {}.", dump["schema_version"], schema_version.unwrap_or_default())
+            } else {
+                db.restore(dump["tables"].as_object().unwrap()).await?;
+                config_service(ctx).await?.clear().await;
+                cache::invalidate(ctx).await;
+                audit(db, &command.user.id, command.guild_id, "restore", attachment.filename.clone()).await?;
+                "Successfully restored the backup.".to_string()
+            }
+        },
+        _ => format!("{} isn't a backup made by /backup.", attachment.filename),
+    };
+    edit_response(ctx, command, message_str).await
+}
@@ -0,0 +1,60 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, integer_option, is_admin, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("rolereward").description("Manages the roles given for playtime milestones")
+        .create_option(|subcommand| { subcommand.name("add").description("Gives a role to the members reaching a total playtime").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("hours").description("The total playtime in hours").kind(CommandOptionType::Integer).min_int_value(1).required(true)})
+            .create_sub_option(|option| {option.name("role").description("The role").kind(CommandOptionType::Role).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Stops giving a role").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("role").description("The role").kind(CommandOptionType::Role).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the role rewards").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_admin(db, command.member.as_ref()).await? {
+        let subcommand = &command.data.options[0];
+        message_str = match subcommand.name.as_str() {
+            "add" => {
+                let hours = integer_option(&subcommand.options, "hours")?;
+                let role_id = string_option(&subcommand.options, "role")?.parse::<i64>()?;
+                db.add_role_reward(&guild_id, &role_id, &(hours * 3600)).await?;
+                format!("<@&{}> will be given to the members reaching {}h of playtime.", role_id, hours)
+            },
+            "remove" => {
+                let role_id = string_option(&subcommand.options, "role")?.parse::<i64>()?;
+                if db.remove_role_reward(&guild_id, &role_id).await? {
+                    format!("<@&{}> will no longer be given.", role_id)
+                } else {
+                    format!("<@&{}> isn't a reward.", role_id)
+                }
+            },
+            "list" => {
+                let rewards: Vec<String> = db.get_role_rewards(&guild_id).await?.iter()
+                    .map(|(role_id, seconds)| format!("<@&{}>: {}", role_id, format_playtime(*seconds)))
+                    .collect();
+                if rewards.is_empty() {
+                    "No role rewards are configured.".to_string()
+                } else {
+                    rewards.join("\n")
+                }
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
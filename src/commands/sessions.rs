@@ -0,0 +1,96 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{bar, format_playtime, respond_embed, string_option, target_user};
+
+
+// Upper bounds of the histogram buckets in seconds, the last one has none
+const BUCKETS: [(i64, &str); 6] = [
+    (15 * 60, "< 15m"),
+    (30 * 60, "15m-30m"),
+    (60 * 60, "30m-1h"),
+    (2 * 60 * 60, "1h-2h"),
+    (4 * 60 * 60, "2h-4h"),
+    (i64::MAX, "4h+"),
+];
+
+// Characters of the longest bar
+const BAR_WIDTH: i64 = 16;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("sessions").description("Shows how long a user's sessions last")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+        .create_option(|option| {option.name("game").description("Only the sessions of this game").kind(CommandOptionType::String).required(false).set_autocomplete(true)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let game_name = string_option(&command.data.options, "game").ok();
+    let embed = get_sessions(db, &user, game_name).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_sessions(db: &Database, user: &User, game_name: Option<&str>) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s sessions", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let game_id = match game_name {
+        Some(game_name) => match db.find_game(game_name).await? {
+            Some((game_id, name)) => {
+                embed.title(format!("{}'s {} sessions", user.name, name));
+                Some(game_id)
+            },
+            None => {
+                embed.title(format!("{} isn't tracked", game_name))
+                    .description("Nobody has played this game yet.");
+                return Ok(embed);
+            },
+        },
+        None => None,
+    };
+    let durations = db.get_session_durations(&user_id, game_id).await?;
+    if durations.is_empty() {
+        embed.description(format!("{} hasn't played anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    let count = i64::try_from(durations.len())?;
+    embed.field("Sessions", count.to_string(), true)
+        .field("Average", format_playtime(durations.iter().sum::<i64>() / count), true)
+        .field("Median", format_playtime(median(&durations)), true)
+        .field("Longest", format_playtime(durations[durations.len() - 1]), true)
+        .field("Distribution", format!("```\n{}\n```", histogram(&durations)?), false);
+    return Ok(embed);
+}
+
+// The durations are sorted, an even count averages the two middle ones
+fn median(durations: &[i64]) -> i64 {
+    let lower = durations[(durations.len() - 1) / 2];
+    let upper = durations[durations.len() / 2];
+    return (lower + upper) / 2;
+}
+
+// One bar per bucket, scaled to the fullest one
+fn histogram(durations: &[i64]) -> Result<String> {
+    let mut counts = [0i64; BUCKETS.len()];
+    for duration in durations {
+        let bucket = BUCKETS.iter().position(|(bound, _)| duration < bound).unwrap_or(BUCKETS.len() - 1);
+        counts[bucket] += 1;
+    }
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+    for ((_, label), count) in BUCKETS.iter().zip(counts) {
+        lines.push(format!("{:>7} {:<width$} {}", label, bar(count, most, BAR_WIDTH)?, count, width = usize::try_from(BAR_WIDTH)?));
+    }
+    return Ok(lines.join("\n"));
+}
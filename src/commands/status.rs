@@ -0,0 +1,43 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::bridge::gateway::ShardId;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::status::{ShardManagerContainer, StartedAt};
+use super::{format_playtime, respond_embed};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("status").description("Shows the health of the bot")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let (shard_manager, started_at) = {
+        let data = ctx.data.read().await;
+        (data.get::<ShardManagerContainer>().cloned(), data.get::<StartedAt>().cloned())
+    };
+    // The latency is only known after the first heartbeat
+    let latency = match shard_manager {
+        Some(shard_manager) => shard_manager.lock().await.runners.lock().await
+            .get(&ShardId(ctx.shard_id))
+            .and_then(|runner| runner.latency),
+        None => None,
+    };
+    let mut embed = CreateEmbed::default();
+    embed.title("Bot status")
+        .field("Gateway latency", latency.map_or("unknown".to_string(), |latency| format!("{}ms", latency.as_millis())), true)
+        .field("Uptime", started_at.map_or("unknown".to_string(), |started_at| format_playtime(i64::try_from(started_at.elapsed().as_secs()).unwrap_or(i64::MAX))), true);
+    match db.ping().await {
+        Ok(()) => {
+            embed.field("Database", "✅ Connected", true)
+                .field("Open sessions", db.count_open_sessions().await?.to_string(), true);
+        },
+        Err(_) => {
+            embed.field("Database", "❌ Unreachable", true);
+        },
+    }
+    respond_embed(ctx, command, embed).await
+}
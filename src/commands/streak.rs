@@ -0,0 +1,112 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::chart::played_days;
+use crate::db::Database;
+use crate::i18n;
+use super::{locale, respond_embed, target_user, timezone};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("streak").description("Shows how many days in a row a user played, vacations don't break it")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let embed = get_streak(db, &locale, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_streak(db: &Database, locale: &str, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(i18n::tr(locale, "streak-title", &[("user", user.name.clone())]));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(i18n::tr(locale, "opted-out", &[("user", user.mention().to_string())]));
+        return Ok(embed);
+    }
+    let sessions = db.get_sessions_since(&user_id, &0).await?;
+    if sessions.is_empty() {
+        embed.description(i18n::tr(locale, "nothing-played", &[("user", user.mention().to_string())]));
+        return Ok(embed);
+    }
+    // The days are the ones of the player, like the vacation they planned
+    let timezone = timezone(db, &user.id).await?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let vacation = db.get_vacation(&user_id).await?.map(|(start, end)| (
+        timezone.timestamp_opt(start, 0).unwrap().date_naive(),
+        timezone.timestamp_opt(end, 0).unwrap().date_naive(),
+    ));
+    let (current, longest) = streaks(&played_days(&sessions, timezone), vacation, today);
+    embed.field(i18n::t(locale, "streak-current"), format_days(locale, current), true)
+        .field(i18n::t(locale, "streak-longest"), format_days(locale, longest), true);
+    if let Some((start, end)) = vacation.filter(|(start, end)| *start <= today && today < *end) {
+        let dates = [("start", start.format("%Y-%m-%d").to_string()), ("end", end.pred_opt().unwrap().format("%Y-%m-%d").to_string())];
+        embed.footer(|footer| footer.text(i18n::tr(locale, "streak-vacation", &dates)));
+    }
+    return Ok(embed);
+}
+
+fn format_days(locale: &str, days: i64) -> String {
+    return if days == 1 { i18n::t(locale, "streak-one-day") } else { i18n::tr(locale, "streak-days", &[("days", days.to_string())]) };
+}
+
+// The (current, longest) streaks of days played in a row up to `today`
+// The days of the vacation, from its first day to the day it ends, neither break nor extend a streak
+// and today only breaks it once it is over
+fn streaks(played: &BTreeSet<NaiveDate>, vacation: Option<(NaiveDate, NaiveDate)>, today: NaiveDate) -> (i64, i64) {
+    let frozen = |day: NaiveDate| vacation.map_or(false, |(start, end)| start <= day && day < end);
+    let mut current = 0;
+    let mut longest = 0;
+    let mut day = match played.iter().next() {
+        Some(day) => *day,
+        None => return (0, 0),
+    };
+    while day <= today {
+        if played.contains(&day) {
+            current += 1;
+            longest = std::cmp::max(longest, current);
+        } else if day < today && !frozen(day) {
+            current = 0;
+        }
+        day = day.succ_opt().unwrap();
+    }
+    return (current, longest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::streaks;
+    use chrono::NaiveDate;
+    use std::collections::BTreeSet;
+
+    fn day(day: u32) -> NaiveDate {
+        return NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+    }
+
+    #[test]
+    fn counts_the_days_played_in_a_row() {
+        let played: BTreeSet<NaiveDate> = [1, 2, 3, 5, 6].into_iter().map(day).collect();
+        assert_eq!(streaks(&played, None, day(6)), (2, 3));
+        // Today isn't over yet
+        assert_eq!(streaks(&played, None, day(7)), (2, 3));
+        assert_eq!(streaks(&played, None, day(8)), (0, 3));
+    }
+
+    #[test]
+    fn freezes_the_streak_during_a_vacation() {
+        let played: BTreeSet<NaiveDate> = [1, 2, 3, 10].into_iter().map(day).collect();
+        assert_eq!(streaks(&played, Some((day(4), day(10))), day(10)), (4, 4));
+        assert_eq!(streaks(&played, Some((day(5), day(10))), day(10)), (1, 3));
+    }
+}
@@ -0,0 +1,52 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user};
+
+
+const RECENT_STREAMS: i64 = 5;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("streams").description("Shows the games a user streamed")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_streams(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_streams(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s streams", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let stream_time = db.get_stream_time(&user_id, None).await?;
+    if stream_time == 0 {
+        embed.description(format!("{} hasn't streamed anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    embed.description(format!("Streamed for {} in total.", format_playtime(stream_time)));
+    for (game_name, duration) in db.get_top_streamed_games(&user_id, 10).await? {
+        embed.field(game_name, format_playtime(duration), true);
+    }
+    let lines: Vec<String> = db.get_recent_streams(&user_id, RECENT_STREAMS).await?.iter()
+        .map(|(game_name, url, starttime, duration)| match url {
+            Some(url) => format!("[{}]({}) <t:{}:R> for {}", game_name, url, starttime, format_playtime(*duration)),
+            None => format!("**{}** <t:{}:R> for {}", game_name, starttime, format_playtime(*duration)),
+        })
+        .collect();
+    embed.field("Recent streams", lines.join("\n"), false);
+    return Ok(embed);
+}
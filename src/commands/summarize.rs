@@ -0,0 +1,203 @@
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::model::application::command::{CommandOptionType, CommandType};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::{GuildId, UserId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+use chrono_tz::Tz;
+
+use anyhow::{anyhow, Result};
+
+use crate::db::Database;
+use crate::i18n;
+use super::{can_view, format_playtime, locale, period_key, period_start, stored_guild_id, style_embed, target_user, timezone};
+
+
+const SUMMARY_PAGE_SIZE: i64 = 10;
+
+// Name of the user context menu command, shown under Apps when right-clicking a member
+pub const VIEW_PLAYTIME: &str = "View Playtime";
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("summarize").description("Shows the 10 most played games of a user")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+        .create_option(|option| {option.name("period").description("The time range to summarize").kind(CommandOptionType::String).required(false)
+            .add_string_choice("Today", "today")
+            .add_string_choice("This week", "week")
+            .add_string_choice("This month", "month")
+            .add_string_choice("This year", "year")
+            .add_string_choice("All-time", "all")})
+        .create_option(|option| {option.name("private").description("Only shows the summary to you, /privacy replies sets the default").kind(CommandOptionType::Boolean).required(false)})
+}
+
+// Context menu commands have no description, their name is translated instead
+pub fn register_context_menu(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name(VIEW_PLAYTIME).kind(CommandType::User);
+    for locale in i18n::locales().filter(|locale| *locale != i18n::DEFAULT_LOCALE) {
+        if let Some(name) = i18n::translation(locale, "view-playtime-name") {
+            command.name_localized(locale, name);
+        }
+    }
+    command
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let period = command.data.options.iter()
+        .find(|option| option.name == "period")
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or("all");
+    let private = command.data.options.iter()
+        .find(|option| option.name == "private")
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_bool());
+    respond_summary(db, ctx, command, &user, period, private).await
+}
+
+// The all-time summary of the right-clicked member
+pub async fn run_context_menu(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = command.data.target_id.ok_or_else(|| anyhow!("The context menu has no target"))?.to_user_id();
+    let user = match command.data.resolved.users.get(&user_id) {
+        Some(user) => user.clone(),
+        None => user_id.to_user(&ctx.http).await?,
+    };
+    respond_summary(db, ctx, command, &user, "all", None).await
+}
+
+// Without a `private` choice, the invoker's default from /privacy replies is used
+async fn respond_summary(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction, user: &User, period: &str, private: Option<bool>) -> Result<()> {
+    let user_id = *user.id.as_u64();
+    let private = match private {
+        Some(private) => private,
+        None => db.get_private_stats(&i64::try_from(*command.user.id.as_u64())?).await?,
+    };
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    // Periods start at midnight for whoever asked
+    let timezone = timezone(db, &command.user.id).await?;
+    let mut embed = get_summary(db, &locale, timezone, user, command.guild_id, period, 0).await?;
+    style_embed(ctx, command.guild_id, &mut embed).await?;
+    let pages = get_summary_pages(db, &i64::try_from(user_id)?, period_start(period, timezone)).await?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.ephemeral(private).set_embed(embed)
+                .components(|components| summary_buttons(components, &locale, user_id, period, 0, pages)))
+    })
+        .await?;
+    Ok(())
+}
+
+// custom ids are formatted as `summary:<user_id>:<period>:<page>`
+pub async fn paginate(db: &Database, ctx: &Context, component: &MessageComponentInteraction) -> Result<()> {
+    let custom_id: Vec<&str> = component.data.custom_id.split(':').collect();
+    if custom_id.len() != 4 {
+        return Ok(());
+    }
+    let user_id = custom_id[1].parse::<u64>()?;
+    let period = custom_id[2];
+    let page = custom_id[3].parse::<i64>()?;
+    let user = UserId(user_id).to_user(&ctx.http).await?;
+    // In the language and timezone of whoever clicked
+    let locale = locale(db, ctx, &component.user.id, component.guild_id).await?;
+    // The user may have hidden their stats since the summary was sent
+    if !can_view(db, ctx, Some(component.user.id), component.guild_id, user.id).await? {
+        component.create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.ephemeral(true).content(i18n::tr(&locale, "privacy-hidden", &[("user", user.mention().to_string())])))
+        })
+            .await?;
+        return Ok(());
+    }
+    let timezone = timezone(db, &component.user.id).await?;
+    let mut embed = get_summary(db, &locale, timezone, &user, component.guild_id, period, page).await?;
+    style_embed(ctx, component.guild_id, &mut embed).await?;
+    let pages = get_summary_pages(db, &i64::try_from(user_id)?, period_start(period, timezone)).await?;
+    component.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|message| message.set_embed(embed)
+                .components(|components| summary_buttons(components, &locale, user_id, period, page, pages)))
+    })
+        .await?;
+    Ok(())
+}
+
+// The rank is among the members of the guild it's asked in
+async fn get_summary(db: &Database, locale: &str, timezone: Tz, user: &User, guild_id: Option<GuildId>, period: &str, page: i64) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default()
+        .title(i18n::tr(locale, "summary-title", &[("user", user.name.clone()), ("period", i18n::t(locale, period_key(period)))])).to_owned();
+    if db.is_opted_out(&user_id).await? {
+        embed.description(i18n::tr(locale, "opted-out", &[("user", user.mention().to_string())]));
+        return Ok(embed);
+    }
+
+    let start = period_start(period, timezone);
+    let mut description: Vec<String> = Vec::new();
+    if let Some((rank, players)) = db.get_user_rank(&user_id, stored_guild_id(guild_id)?, start).await? {
+        description.push(i18n::tr(locale, "summary-rank", &[
+            ("user", user.name.clone()),
+            ("rank", rank.to_string()),
+            ("players", players.to_string()),
+            ("percent", top_percent(rank, players).to_string()),
+        ]));
+    }
+    let stream_time = db.get_stream_time(&user_id, start).await?;
+    if stream_time > 0 {
+        description.push(i18n::tr(locale, "summary-streamed", &[("playtime", format_playtime(stream_time))]));
+    }
+    if !description.is_empty() {
+        embed.description(description.join("\n"));
+    }
+
+    let games = db.get_summary_games(&user_id, start, SUMMARY_PAGE_SIZE, page * SUMMARY_PAGE_SIZE).await?;
+    // The cover of the most played game of the page
+    if let Some(game) = games.first() {
+        if let Some(cover_url) = db.get_cover_url(&game.name).await? {
+            embed.thumbnail(cover_url);
+        }
+    }
+    for game in games {
+        let value = match game.average_session {
+            Some(average) => format!("{}\n{}", format_playtime(game.playtime), i18n::tr(locale, "summary-sessions", &[("sessions", game.sessions.to_string()), ("average", format_playtime(average))])),
+            None => format_playtime(game.playtime),
+        };
+        embed.field(game.name, value, true);
+    }
+
+    let pages = get_summary_pages(db, &user_id, start).await?;
+    embed.footer(|footer| footer.text(i18n::tr(locale, "summary-page", &[("page", (page + 1).to_string()), ("pages", pages.to_string())])));
+    return Ok(embed);
+}
+
+// The share of the players ranked at or above `rank`, at least 1%
+fn top_percent(rank: i64, players: i64) -> i64 {
+    return std::cmp::max(1, rank * 100 / std::cmp::max(players, 1));
+}
+
+async fn get_summary_pages(db: &Database, user_id: &i64, start: Option<i64>) -> Result<i64> {
+    let games = db.count_games(user_id, start).await?;
+    return Ok(std::cmp::max(1, (games + SUMMARY_PAGE_SIZE - 1) / SUMMARY_PAGE_SIZE));
+}
+
+// Adds the previous/next buttons of a summary page, the page state is kept in the custom ids
+fn summary_buttons<'a>(components: &'a mut CreateComponents, locale: &str, user_id: u64, period: &str, page: i64, pages: i64) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| button
+                .custom_id(format!("summary:{}:{}:{}", user_id, period, page - 1))
+                .label(i18n::t(locale, "summary-previous"))
+                .style(ButtonStyle::Secondary)
+                .disabled(page <= 0))
+            .create_button(|button| button
+                .custom_id(format!("summary:{}:{}:{}", user_id, period, page + 1))
+                .label(i18n::t(locale, "summary-next"))
+                .style(ButtonStyle::Secondary)
+                .disabled(page + 1 >= pages))
+    })
+}
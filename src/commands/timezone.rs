@@ -0,0 +1,75 @@
+use chrono::Utc;
+use chrono_tz::{Tz, TZ_VARIANTS};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{respond_ephemeral, string_option, timezone};
+
+
+// Discord shows at most 25 suggestions
+const MAX_SUGGESTIONS: usize = 25;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("timezone").description("Sets the timezone your days and weeks are counted in")
+        .create_option(|subcommand| { subcommand.name("set").description("Sets your timezone").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("timezone").description("The timezone, like Europe/Paris").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("reset").description("Goes back to UTC").kind(CommandOptionType::SubCommand)})
+        .create_option(|subcommand| { subcommand.name("show").description("Shows your timezone").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    let message_str = match subcommand.name.as_str() {
+        "set" => {
+            let name = string_option(&subcommand.options, "timezone")?;
+            match name.parse::<Tz>() {
+                Ok(tz) => {
+                    db.set_user_timezone(&user_id, Some(tz.name())).await?;
+                    format!("Your days and weeks now start at midnight in {}.", tz.name())
+                },
+                Err(_) => format!("{} isn't a timezone, pick one of the suggestions.", name),
+            }
+        },
+        "reset" => {
+            db.set_user_timezone(&user_id, None).await?;
+            "Your days and weeks now start at midnight UTC.".to_string()
+        },
+        "show" => {
+            let tz = timezone(db, &command.user.id).await?;
+            format!("Your timezone is {}, it's {} there.", tz.name(), Utc::now().with_timezone(&tz).format("%H:%M"))
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
+
+pub async fn autocomplete(ctx: &Context, autocomplete: &AutocompleteInteraction) -> Result<()> {
+    let input = autocomplete.data.options.iter()
+        .flat_map(|option| option.options.iter())
+        .find(|option| option.focused)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let names: Vec<&str> = TZ_VARIANTS.iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&input))
+        .take(MAX_SUGGESTIONS)
+        .collect();
+    autocomplete.create_autocomplete_response(&ctx.http, |response| {
+        for name in &names {
+            response.add_string_choice(name, name);
+        }
+        response
+    })
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use crate::i18n;
+use super::{format_playtime, locale, period_key, period_start, respond_embed, stored_guild_id, timezone};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("topgames").description("Shows the games the server plays the most")
+        .create_option(|option| {option.name("period").description("The time range to rank").kind(CommandOptionType::String).required(false)
+            .add_string_choice("Today", "today")
+            .add_string_choice("This week", "week")
+            .add_string_choice("This month", "month")
+            .add_string_choice("This year", "year")
+            .add_string_choice("All-time", "all")})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let period = command.data.options.iter()
+        .find(|option| option.name == "period")
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or("all");
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let start = period_start(period, timezone(db, &command.user.id).await?);
+    // Only the guild's members count, everyone does in DMs
+    let guild_id = stored_guild_id(command.guild_id)?;
+    // The period start depends on the user's timezone, so it's part of the key
+    let key = format!("topgames:{}:{}", guild_id.map_or("all".to_string(), |guild_id| guild_id.to_string()), start.map_or("all".to_string(), |start| start.to_string()));
+    let by_playtime = cache::cached(ctx, &format!("{}:playtime", key), db.get_server_top_games(guild_id, start, false)).await?;
+    let mut embed = CreateEmbed::default()
+        .title(i18n::tr(&locale, "topgames-title", &[("period", i18n::t(&locale, period_key(period)))])).to_owned();
+    if by_playtime.is_empty() {
+        embed.description(i18n::t(&locale, "no-playtime"));
+        return respond_embed(ctx, command, embed).await;
+    }
+    let by_players = cache::cached(ctx, &format!("{}:players", key), db.get_server_top_games(guild_id, start, true)).await?;
+    embed.field(i18n::t(&locale, "topgames-by-playtime"), format_games(&by_playtime, |(_, playtime, _)| format_playtime(*playtime)), true);
+    embed.field(i18n::t(&locale, "topgames-by-players"), format_games(&by_players, |(_, _, players)| i18n::tr(&locale, "topgames-players", &[("players", players.to_string())])), true);
+    respond_embed(ctx, command, embed).await
+}
+
+// One numbered line per game, followed by its stat
+fn format_games(games: &[(String, i64, i64)], stat: impl Fn(&(String, i64, i64)) -> String) -> String {
+    return games.iter().enumerate()
+        .map(|(rank, game)| format!("**{}.** {} — {}", rank + 1, game.0, stat(game)))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
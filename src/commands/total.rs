@@ -0,0 +1,65 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::i18n;
+use super::{format_playtime, locale, respond_embed, stored_guild_id, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("total").description("Shows a user's playtime across all games")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let locale = locale(db, ctx, &command.user.id, command.guild_id).await?;
+    let embed = get_total(db, &locale, &user, stored_guild_id(command.guild_id)?).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+// The rank is among the guild's members, or everyone in DMs
+async fn get_total(db: &Database, locale: &str, user: &User, guild_id: Option<i64>) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(i18n::tr(locale, "total-title", &[("user", user.name.clone())]));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(i18n::tr(locale, "opted-out", &[("user", user.mention().to_string())]));
+        return Ok(embed);
+    }
+    match db.get_user_totals(&user_id, guild_id).await? {
+        Some(totals) => {
+            embed.field(i18n::t(locale, "total-playtime"), format_playtime(totals.playtime), true)
+                .field(i18n::t(locale, "total-games"), totals.games.to_string(), true)
+                .field(i18n::t(locale, "total-rank"), i18n::tr(locale, "total-rank-value", &[("rank", totals.rank.to_string()), ("users", totals.ranked_users.to_string())]), true);
+        },
+        None => {
+            embed.description(i18n::tr(locale, "nothing-played", &[("user", user.mention().to_string())]));
+        },
+    }
+    // Imported playtime isn't ranked, the other platforms may count idle time
+    let imported = db.get_imported_playtime(&user_id).await?;
+    for (source, playtime) in &imported {
+        embed.field(i18n::tr(locale, "total-imported", &[("source", source_label(source).to_string())]), format_playtime(*playtime), true);
+    }
+    if !imported.is_empty() {
+        let tracked = db.get_total_playtime(&user_id).await?;
+        embed.field(i18n::t(locale, "total-all-sources"), format_playtime(tracked + imported.iter().map(|(_, playtime)| playtime).sum::<i64>()), true);
+    }
+    return Ok(embed);
+}
+
+fn source_label(source: &str) -> &str {
+    match source {
+        "steam" => "Steam",
+        "xbox" => "Xbox",
+        "manual" => "/log",
+        source => source,
+    }
+}
@@ -0,0 +1,56 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{is_admin, permission_denied, respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("trackedgames").description("Manages the games tracked when the whitelist is enabled")
+        .create_option(|subcommand| { subcommand.name("add").description("Tracks a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Stops tracking a game").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("game").description("The game's name").kind(CommandOptionType::String).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the tracked games").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_admin(db, command.member.as_ref()).await? {
+        let subcommand = &command.data.options[0];
+        message_str = match subcommand.name.as_str() {
+            "add" => {
+                let game_name = string_option(&subcommand.options, "game")?;
+                db.add_tracked_game(&guild_id, game_name).await?;
+                format!("{} is now tracked.", game_name)
+            },
+            "remove" => {
+                let game_name = string_option(&subcommand.options, "game")?;
+                if db.remove_tracked_game(&guild_id, game_name).await? {
+                    format!("{} is no longer tracked.", game_name)
+                } else {
+                    format!("{} wasn't tracked.", game_name)
+                }
+            },
+            "list" => {
+                let games = db.get_tracked_games(&guild_id).await?;
+                if games.is_empty() {
+                    "No games were added, enable the whitelist with /config set whitelist to only track some.".to_string()
+                } else {
+                    format!("Tracked games: {}", games.join(", "))
+                }
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,36 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::respond_ephemeral;
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("tracking").description("Enables or disables the tracking of your playtime")
+        .create_option(|subcommand| { subcommand.name("on").description("Resumes tracking your playtime").kind(CommandOptionType::SubCommand)})
+        .create_option(|subcommand| { subcommand.name("off").description("Stops tracking your playtime").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let opted_out = command.data.options[0].name == "off";
+    db.set_opted_out(&user_id, opted_out).await?;
+    // Also ends a pause
+    if !opted_out {
+        db.set_paused_until(&user_id, None).await?;
+    }
+    // The leaderboards leave out the users who opted out
+    cache::invalidate(ctx).await;
+    let message_str = if opted_out {
+        "Your playtime is no longer tracked."
+    } else {
+        "Your playtime is now tracked."
+    };
+    respond_ephemeral(ctx, command, message_str.to_string()).await
+}
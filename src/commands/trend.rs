@@ -0,0 +1,54 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::prelude::*;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::chart::{render_weekly_chart, weekly_playtime, WEEK};
+use crate::db::Database;
+use super::{apply_appearance, guild_config, integer_option, period_start, respond_embed, target_user, timezone};
+
+
+const DEFAULT_WEEKS: i64 = 8;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("trend").description("Charts a user's weekly playtime")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+        .create_option(|option| {option.name("weeks").description("How many weeks to chart, 8 by default").kind(CommandOptionType::Integer).min_int_value(2).max_int_value(52).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let config = guild_config(ctx, command.guild_id).await?;
+    if db.is_opted_out(&user_id).await? {
+        let mut embed = CreateEmbed::default();
+        embed.title(format!("{}'s playtime trend", user.name))
+            .description(format!("{} opted out of tracking.", user.mention()));
+        return respond_embed(ctx, command, embed).await;
+    }
+    let weeks = integer_option(&command.data.options, "weeks").unwrap_or(DEFAULT_WEEKS);
+    // The current week is the last bar, weeks start in the timezone of whoever asked
+    let first_week = period_start("week", timezone(db, &command.user.id).await?).unwrap() - (weeks - 1) * WEEK;
+    let sessions = db.get_sessions_since(&user_id, &first_week).await?;
+    let playtimes = weekly_playtime(&sessions, first_week, usize::try_from(weeks)?);
+    let title = format!("{}'s weekly playtime", user.name);
+    let chart = render_weekly_chart(&title, first_week, &playtimes, u32::try_from(config.embed_color)?)?;
+    let mut embed = CreateEmbed::default();
+    embed.title(&title).image("attachment://trend.png");
+    apply_appearance(&mut embed, &config)?;
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message
+                .add_file(AttachmentType::Bytes { data: Cow::from(chart), filename: "trend.png".to_string() })
+                .set_embed(embed))
+    })
+        .await?;
+    Ok(())
+}
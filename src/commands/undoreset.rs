@@ -0,0 +1,34 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+
+use anyhow::Result;
+
+use crate::cache;
+use crate::db::Database;
+use super::{audit, is_global_admin, permission_denied, respond_ephemeral};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("undo-reset").description("Restores the playtimes erased by the last reset of the past 24 hours")
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_global_admin(db, ctx, command.user.id).await? {
+        let restored = match db.undo_reset().await? {
+            Some(Some(user_id)) => Some(format!("<@{}>'s playtimes", user_id)),
+            Some(None) => Some("All playtimes and games".to_string()),
+            None => None,
+        };
+        message_str = match restored {
+            Some(restored) => {
+                cache::invalidate(ctx).await;
+                audit(db, &command.user.id, command.guild_id, "undo-reset", restored.clone()).await?;
+                format!("Successfully restored: {}.", restored)
+            },
+            None => "There is no reset from the past 24 hours to undo.".to_string(),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
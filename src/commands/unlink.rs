@@ -0,0 +1,29 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{respond_ephemeral, string_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("unlink").description("Unlinks an account and deletes the playtime imported from it")
+        .create_option(|option| {option.name("platform").description("The platform").kind(CommandOptionType::String).required(true)
+            .add_string_choice("Steam", "steam")
+            .add_string_choice("Xbox", "xbox")})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let platform = string_option(&command.data.options, "platform")?;
+    let message_str = if db.unlink_account(&user_id, platform).await? {
+        "Your account was unlinked and its playtime deleted."
+    } else {
+        "You didn't link an account on this platform."
+    };
+    respond_ephemeral(ctx, command, message_str.to_string()).await
+}
@@ -0,0 +1,54 @@
+use chrono::{Duration, NaiveDate, Utc};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{local_midnight, respond_ephemeral, string_option, timezone};
+
+
+// Longer absences can be planned again once they started
+const MAX_VACATION_DAYS: i64 = 366;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("vacation").description("Plans a period away, your streak is frozen and the weekly digests are paused during it")
+        .create_option(|subcommand| { subcommand.name("set").description("Plans your vacation, replacing the previous one").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("start").description("The first day, as YYYY-MM-DD").kind(CommandOptionType::String).required(true)})
+            .create_sub_option(|option| {option.name("end").description("The last day, as YYYY-MM-DD").kind(CommandOptionType::String).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("cancel").description("Cancels or ends your vacation").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    if subcommand.name == "cancel" {
+        db.set_vacation(&user_id, None).await?;
+        return respond_ephemeral(ctx, command, "You aren't on vacation anymore.".to_string()).await;
+    }
+    let mut dates: Vec<NaiveDate> = Vec::new();
+    for name in ["start", "end"] {
+        let date = string_option(&subcommand.options, name)?;
+        match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => dates.push(date),
+            Err(_) => return respond_ephemeral(ctx, command, format!("{} isn't a date, use the YYYY-MM-DD format.", date)).await,
+        }
+    }
+    let (start, end) = (dates[0], dates[1]);
+    let timezone = timezone(db, &command.user.id).await?;
+    let message_str = if end < start {
+        "Your vacation can't end before it starts.".to_string()
+    } else if end < Utc::now().with_timezone(&timezone).date_naive() {
+        "Your vacation is already over.".to_string()
+    } else if (end - start).num_days() >= MAX_VACATION_DAYS {
+        format!("A vacation lasts {} days at most.", MAX_VACATION_DAYS)
+    } else {
+        // The last day is included
+        db.set_vacation(&user_id, Some((local_midnight(start, timezone), local_midnight(end + Duration::days(1), timezone)))).await?;
+        format!("You are on vacation from {} to {}, your streak is frozen and the weekly digests are paused until then.", start.format("%Y-%m-%d"), end.format("%Y-%m-%d"))
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,43 @@
+use chrono::Utc;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{format_playtime, respond_embed, target_user};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("voicetime").description("Shows the time a user spent in voice channels")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_voice_time(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_voice_time(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s voice time", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let (voice_time, playing_time) = db.get_voice_time(&user_id, &Utc::now().timestamp()).await?;
+    if voice_time == 0 {
+        embed.description(format!("{} hasn't been in a voice channel yet.", user.mention()));
+        return Ok(embed);
+    }
+    embed.field("In voice", format_playtime(voice_time), true)
+        .field("While playing", format_playtime(playing_time), true)
+        .field("Without a game", format_playtime(voice_time - playing_time), true);
+    return Ok(embed);
+}
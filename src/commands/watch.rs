@@ -0,0 +1,74 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use super::{can_view, respond_ephemeral, string_option, user_option};
+
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("watch").description("Manages the DMs you get when someone starts playing")
+        .create_option(|subcommand| { subcommand.name("add").description("Sends you a DM when a user starts playing").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("user").description("The user to watch").kind(CommandOptionType::User).required(true)})
+            .create_sub_option(|option| {option.name("game").description("Only this game, any game by default").kind(CommandOptionType::String).required(false).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Stops watching a user").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("user").description("The watched user").kind(CommandOptionType::User).required(true)})
+            .create_sub_option(|option| {option.name("game").description("The watched game, none for the watch on any game").kind(CommandOptionType::String).required(false).set_autocomplete(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the users you watch").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let watcher_id = i64::try_from(*command.user.id.as_u64())?;
+    let subcommand = &command.data.options[0];
+    let message_str = match subcommand.name.as_str() {
+        "add" => {
+            let user_id = i64::try_from(user_option(&subcommand.options, "user")?)?;
+            // Tracked games are watched under their tracked name, the others as typed
+            let game_name = match string_option(&subcommand.options, "game") {
+                Ok(game_name) => Some(db.find_game(game_name).await?.map_or(game_name.to_string(), |(_, name)| name)),
+                Err(_) => None,
+            };
+            if user_id == watcher_id {
+                "You can't watch yourself.".to_string()
+            } else if db.is_opted_out(&user_id).await? {
+                format!("<@{}> opted out of tracking.", user_id)
+            } else if !can_view(db, ctx, Some(command.user.id), command.guild_id, UserId(u64::try_from(user_id)?)).await? {
+                format!("<@{}> keeps their stats private.", user_id)
+            } else if !db.add_watch(&watcher_id, &user_id, game_name.as_deref()).await? {
+                format!("You already watch <@{}>{}.", user_id, watched_game(game_name.as_deref()))
+            } else {
+                format!("You will get a DM when <@{}> starts playing {}, make sure your DMs are open.", user_id, game_name.as_deref().unwrap_or("anything"))
+            }
+        },
+        "remove" => {
+            let user_id = i64::try_from(user_option(&subcommand.options, "user")?)?;
+            let game_name = string_option(&subcommand.options, "game").ok();
+            if db.remove_watch(&watcher_id, &user_id, game_name).await? {
+                format!("You no longer watch <@{}>{}.", user_id, watched_game(game_name))
+            } else {
+                format!("You don't watch <@{}>{}.", user_id, watched_game(game_name))
+            }
+        },
+        "list" => {
+            let watches: Vec<String> = db.get_watches(&watcher_id).await?.iter()
+                .map(|(user_id, game_name)| format!("<@{}>: {}", user_id, game_name.as_deref().unwrap_or("any game")))
+                .collect();
+            if watches.is_empty() {
+                "You don't watch anyone.".to_string()
+            } else {
+                watches.join("\n")
+            }
+        },
+        subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+    };
+    respond_ephemeral(ctx, command, message_str).await
+}
+
+fn watched_game(game_name: Option<&str>) -> String {
+    return game_name.map_or(String::new(), |game_name| format!(" on {}", game_name));
+}
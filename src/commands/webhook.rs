@@ -0,0 +1,67 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::webhooks;
+use super::{integer_option, is_admin, permission_denied, respond_ephemeral, string_option};
+
+
+const MAX_WEBHOOKS: usize = 5;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("webhook").description("Manages the URLs receiving the session and milestone events of the server")
+        .create_option(|subcommand| { subcommand.name("add").description("Sends the events to a URL").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("url").description("The URL, events are POSTed as JSON").kind(CommandOptionType::String).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("remove").description("Stops sending the events to a URL").kind(CommandOptionType::SubCommand)
+            .create_sub_option(|option| {option.name("id").description("The webhook's id, see /webhook list").kind(CommandOptionType::Integer).required(true)}) })
+        .create_option(|subcommand| { subcommand.name("list").description("Lists the webhooks").kind(CommandOptionType::SubCommand)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => return respond_ephemeral(ctx, command, "This command can only be used in a server.".to_string()).await,
+    };
+    let mut message_str = permission_denied(db, ctx, &command.user.id, command.guild_id).await?;
+    if is_admin(db, command.member.as_ref()).await? {
+        let subcommand = &command.data.options[0];
+        message_str = match subcommand.name.as_str() {
+            "add" => {
+                let url = string_option(&subcommand.options, "url")?.trim();
+                if !webhooks::is_allowed(url).await {
+                    format!("{} isn't an https URL of a public server.", url)
+                } else if db.get_webhooks(&guild_id).await?.len() >= MAX_WEBHOOKS {
+                    format!("A server can't have more than {} webhooks.", MAX_WEBHOOKS)
+                } else {
+                    db.add_webhook(&guild_id, url).await?;
+                    format!("Events will be sent to {}.", url)
+                }
+            },
+            "remove" => {
+                let webhook_id = integer_option(&subcommand.options, "id")?;
+                if db.remove_webhook(&guild_id, &webhook_id).await? {
+                    format!("Webhook {} was removed.", webhook_id)
+                } else {
+                    format!("There is no webhook {}.", webhook_id)
+                }
+            },
+            "list" => {
+                let webhooks: Vec<String> = db.get_webhooks(&guild_id).await?.iter()
+                    .map(|(webhook_id, url)| format!("`{}` {}", webhook_id, url))
+                    .collect();
+                if webhooks.is_empty() {
+                    "No webhooks are configured.".to_string()
+                } else {
+                    webhooks.join("\n")
+                }
+            },
+            subcommand => unreachable!("Subcommand don't have a handler: {}", subcommand),
+        };
+    }
+    respond_ephemeral(ctx, command, message_str).await
+}
@@ -0,0 +1,58 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use crate::chart::weekday_playtime;
+use crate::db::Database;
+use super::{bar, format_playtime, respond_embed, target_user, timezone};
+
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Characters of the longest bar
+const BAR_WIDTH: i64 = 16;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("weekdays").description("Shows the playtime of a user by day of the week")
+        .create_option(|option| {option.name("user").description("The target, yourself by default").kind(CommandOptionType::User).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user = target_user(command);
+    let embed = get_weekdays(db, &user).await?;
+    respond_embed(ctx, command, embed).await
+}
+
+async fn get_weekdays(db: &Database, user: &User) -> Result<CreateEmbed> {
+    let user_id = i64::try_from(*user.id.as_u64())?;
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{}'s week", user.name));
+    if db.is_opted_out(&user_id).await? {
+        embed.description(format!("{} opted out of tracking.", user.mention()));
+        return Ok(embed);
+    }
+    let sessions = db.get_sessions_since(&user_id, &0).await?;
+    if sessions.is_empty() {
+        embed.description(format!("{} hasn't played anything yet.", user.mention()));
+        return Ok(embed);
+    }
+    // The days are the ones of the player, not of whoever asked
+    let timezone = timezone(db, &user.id).await?;
+    let playtimes = weekday_playtime(&sessions, timezone);
+    let most = playtimes.iter().copied().max().unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+    for (weekday, playtime) in WEEKDAYS.iter().zip(playtimes) {
+        lines.push(format!("{} {:<width$} {}", weekday, bar(playtime, most, BAR_WIDTH)?, format_playtime(playtime), width = usize::try_from(BAR_WIDTH)?));
+    }
+    let total: i64 = playtimes.iter().sum();
+    let weekend = (playtimes[5] + playtimes[6]) * 100 / std::cmp::max(total, 1);
+    embed.description(format!("```\n{}\n```", lines.join("\n")))
+        .field("Weekend share", format!("{}% of the playtime", weekend), true)
+        .field("Timezone", timezone.name(), true);
+    return Ok(embed);
+}
@@ -0,0 +1,83 @@
+use chrono::{Datelike, Months, NaiveDate, TimeZone, Utc};
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::*;
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, Result};
+
+use crate::db::Database;
+use super::{format_playtime, integer_option, local_midnight, respond_embed, style_embed, timezone};
+
+
+const WRAPPED_GAMES: usize = 5;
+const MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("wrapped").description("Recaps your year of gaming")
+        .create_option(|option| {option.name("year").description("The year to recap, this one by default").kind(CommandOptionType::Integer).min_int_value(2000).max_int_value(9999).required(false)})
+}
+
+pub async fn run(db: &Database, ctx: &Context, command: &ApplicationCommandInteraction) -> Result<()> {
+    let user_id = i64::try_from(*command.user.id.as_u64())?;
+    // The year starts at midnight on January 1st for the user
+    let timezone = timezone(db, &command.user.id).await?;
+    let year = match integer_option(&command.data.options, "year") {
+        Ok(year) => i32::try_from(year)?,
+        Err(_) => Utc::now().with_timezone(&timezone).year(),
+    };
+    let first_day = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| anyhow!("Invalid year {}", year))?;
+    let months: Vec<NaiveDate> = (0..=12).map(|month| first_day + Months::new(month)).collect();
+    let bounds: Vec<i64> = months.iter().map(|month| local_midnight(*month, timezone)).collect();
+    let (start, end) = (bounds[0], bounds[12]);
+
+    let mut recap = CreateEmbed::default();
+    recap.title(format!("{}'s {} Wrapped", command.user.name, year));
+    if db.is_opted_out(&user_id).await? {
+        recap.description(format!("{} opted out of tracking.", command.user.mention()));
+        return respond_embed(ctx, command, recap).await;
+    }
+    let games = db.get_user_period_games(&user_id, &start, &end).await?;
+    if games.is_empty() {
+        recap.description(format!("You didn't play anything in {}.", year));
+        return respond_embed(ctx, command, recap).await;
+    }
+    let total: i64 = games.iter().map(|game| game.playtime).sum();
+    recap.description(format!("You played **{}** across **{}** different games.", format_playtime(total), games.len()));
+    if let Some((game_name, starttime, duration)) = db.get_longest_session(&user_id, &start, &end).await? {
+        let date = timezone.timestamp_opt(starttime, 0).unwrap().format("%d/%m");
+        recap.field("Longest session", format!("{} of {} on {}", format_playtime(duration), game_name, date), true);
+    }
+    let mut busiest: Option<(NaiveDate, i64)> = None;
+    for (month, bounds) in months.iter().zip(bounds.windows(2)) {
+        let playtime = db.get_user_period_playtime(&user_id, &bounds[0], &bounds[1]).await?;
+        if busiest.map_or(true, |(_, most)| playtime > most) {
+            busiest = Some((*month, playtime));
+        }
+    }
+    if let Some((month, playtime)) = busiest {
+        recap.field("Busiest month", format!("{}, {}", month.format("%B"), format_playtime(playtime)), true);
+    }
+
+    let top: Vec<String> = games.iter().take(WRAPPED_GAMES).enumerate()
+        .map(|(rank, game)| {
+            let placement = if rank < MEDALS.len() { MEDALS[rank].to_string() } else { format!("**#{}**", rank + 1) };
+            format!("{} {} - {}", placement, game.name, format_playtime(game.playtime))
+        })
+        .collect();
+    let mut top_games = CreateEmbed::default();
+    top_games.title(format!("Top {} games", top.len())).description(top.join("\n"));
+    let mut embeds = vec![recap, top_games];
+    for embed in embeds.iter_mut() {
+        style_embed(ctx, command.guild_id, embed).await?;
+    }
+    command.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|message| message.add_embeds(embeds))
+    })
+        .await?;
+    Ok(())
+}
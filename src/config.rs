@@ -0,0 +1,45 @@
+use anyhow::Result;
+use serenity::prelude::{RwLock, TypeMapKey};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::db::{Database, GuildConfig};
+
+
+// Caches the guilds' configuration so handlers don't query it on every event
+pub struct ConfigService {
+    db: Database,
+    cache: RwLock<HashMap<i64, GuildConfig>>,
+}
+
+impl TypeMapKey for ConfigService {
+    type Value = Arc<ConfigService>;
+}
+
+impl ConfigService {
+    pub fn new(db: Database) -> Self {
+        return ConfigService { db, cache: RwLock::new(HashMap::new()) };
+    }
+
+    pub async fn get(&self, guild_id: &i64) -> Result<GuildConfig> {
+        if let Some(config) = self.cache.read().await.get(guild_id) {
+            return Ok(config.clone());
+        }
+        let config = self.db.get_guild_config(guild_id).await?.unwrap_or_default();
+        self.cache.write().await.insert(*guild_id, config.clone());
+        return Ok(config);
+    }
+
+    // Forgets the cached configurations, after the table was changed behind the service's back
+    pub async fn clear(&self) {
+        self.cache.write().await.clear();
+    }
+
+    pub async fn update<F: FnOnce(&mut GuildConfig)>(&self, guild_id: &i64, change: F) -> Result<GuildConfig> {
+        let mut config = self.get(guild_id).await?;
+        change(&mut config);
+        self.db.save_guild_config(guild_id, &config).await?;
+        self.cache.write().await.insert(*guild_id, config.clone());
+        return Ok(config);
+    }
+}
@@ -0,0 +1,1833 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{clean_game_name, game_key, AchievementStats, GameEntry, GameMetadata, GameStats, GameSummary, GuildConfig, RichPresence, Session, Storage, UserTotals, RESET_UNDO_WINDOW};
+
+
+struct Game {
+    name: String,
+    metadata: Option<GameMetadata>,
+    metadata_updated: Option<i64>,
+}
+
+struct HistoryEntry {
+    user_id: i64,
+    game_id: i64,
+    starttime: i64,
+    endtime: i64,
+    // Logged with /log rather than tracked from a presence
+    manual: bool,
+    rich_presence: RichPresence,
+}
+
+// The pruned sessions of a user's day on a game
+#[derive(Default)]
+struct Rollup {
+    playtime: i64,
+    sessions: i64,
+    longest: i64,
+    // Started before 5 AM UTC
+    night_sessions: i64,
+}
+
+// A row of full_history: a session, or the rollup of a day centered on its noon
+struct Played {
+    user_id: i64,
+    game_id: i64,
+    starttime: i64,
+    endtime: i64,
+    manual: bool,
+    sessions: i64,
+    longest: i64,
+    night_sessions: i64,
+}
+
+struct Stream {
+    user_id: i64,
+    game: String,
+    url: Option<String>,
+    starttime: i64,
+    endtime: i64,
+}
+
+struct VoiceSession {
+    user_id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    starttime: i64,
+    endtime: Option<i64>,
+}
+
+#[derive(Default)]
+struct UserSettings {
+    opted_out: bool,
+    locale: Option<String>,
+    timezone: Option<String>,
+    private_stats: bool,
+    // None until the user chooses, the stats are then public
+    privacy_level: Option<String>,
+    paused_until: Option<i64>,
+    vacation: Option<(i64, i64)>,
+}
+
+struct Goal {
+    seconds: i64,
+    is_limit: bool,
+    notified_week: Option<i64>,
+}
+
+struct LfgSubscription {
+    game_name: String,
+    min_players: i64,
+    notified: bool,
+}
+
+struct GameRole {
+    game_name: String,
+    role_id: i64,
+    channel_id: i64,
+    last_ping: Option<i64>,
+}
+
+struct AuditEntry {
+    user_id: i64,
+    action: String,
+    details: String,
+    actiontime: i64,
+}
+
+struct Reset {
+    user_id: Option<i64>,
+    resettime: i64,
+    archived: Tables,
+}
+
+// The tables of the schema, keyed like their primary keys
+#[derive(Default)]
+struct Tables {
+    // Shared by the serial ids
+    last_id: i64,
+    games: BTreeMap<i64, Game>,
+    aliases: BTreeMap<String, i64>,
+    // (user id, game id) keys
+    entries: BTreeMap<(i64, i64), i64>,
+    sessions: BTreeMap<(i64, i64), i64>,
+    // Of the running sessions, the ones left by an ended session are replaced when the game is played again
+    rich_presences: BTreeMap<(i64, i64), RichPresence>,
+    history: Vec<HistoryEntry>,
+    // (user id, game id, UTC day) keys of the pruned history
+    history_rollup: BTreeMap<(i64, i64, i64), Rollup>,
+    // (game, url, start time) of the running streams
+    streams: BTreeMap<i64, (String, Option<String>, i64)>,
+    stream_history: Vec<Stream>,
+    // (artist, start time) of the running listening sessions
+    listens: BTreeMap<i64, (String, i64)>,
+    // (away, since) of the users
+    statuses: BTreeMap<i64, (bool, i64)>,
+    listen_entries: BTreeMap<(i64, String), i64>,
+    linked_accounts: BTreeMap<(i64, String), String>,
+    // (user id, game id, source) keys
+    imported: BTreeMap<(i64, i64, String), i64>,
+    ignored_games: BTreeSet<(i64, String)>,
+    tracked_games: BTreeSet<(i64, String)>,
+    user_settings: BTreeMap<i64, UserSettings>,
+    audit_log: Vec<AuditEntry>,
+    // (guild id, role id) of the admin roles
+    admin_roles: BTreeSet<(i64, i64)>,
+    bot_admins: BTreeSet<i64>,
+    guild_configs: BTreeMap<i64, GuildConfig>,
+    digest_subscribers: BTreeSet<i64>,
+    goals: BTreeMap<(i64, i64), Goal>,
+    // (watcher id, user id, game name) of the watches
+    watches: Vec<(i64, i64, Option<String>)>,
+    // (guild id, user id, lowercased game name) keys
+    lfg_subscriptions: BTreeMap<(i64, i64, String), LfgSubscription>,
+    // (guild id, lowercased game name) keys
+    game_roles: BTreeMap<(i64, String), GameRole>,
+    // (guild id, role id) keys
+    role_rewards: BTreeMap<(i64, i64), i64>,
+    // (guild id, user id) keys
+    levels: BTreeMap<(i64, i64), i64>,
+    unlocked_badges: BTreeMap<(i64, String), i64>,
+    voice_sessions: Vec<VoiceSession>,
+    // (guild id, url) of the webhooks
+    webhooks: BTreeMap<i64, (i64, String)>,
+    // (guild id, user id) keys, when Discord last listed the member
+    guild_members: BTreeMap<(i64, i64), i64>,
+    resets: Vec<Reset>,
+}
+
+// Keeps everything in memory, so the commands and handlers can be tested without a database
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+    // Sessions shorter than this many seconds are discarded
+    min_session_length: i64,
+    // Sessions longer than this many seconds are clamped
+    max_session_length: i64,
+}
+
+impl MemoryStorage {
+    pub fn new(min_session_length: i64, max_session_length: i64) -> Self {
+        return MemoryStorage { tables: Mutex::new(Tables::default()), min_session_length, max_session_length };
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        return self.tables.lock().expect("The tables were poisoned");
+    }
+}
+
+impl Tables {
+    fn next_id(&mut self) -> i64 {
+        self.last_id += 1;
+        return self.last_id;
+    }
+
+    fn game_name(&self, game_id: &i64) -> String {
+        return self.games.get(game_id).map_or(String::new(), |game| game.name.clone());
+    }
+
+    fn is_opted_out(&self, user_id: &i64) -> bool {
+        return self.user_settings.get(user_id).map_or(false, |settings| settings.opted_out);
+    }
+
+    fn resolve_game(&mut self, game_name: &str) -> i64 {
+        let alias = game_key(game_name);
+        if let Some(game_id) = self.aliases.get(&alias) {
+            return *game_id;
+        }
+        let name = clean_game_name(game_name);
+        let game_id = match self.games.iter().find(|(_, game)| game.name == name) {
+            Some((game_id, _)) => *game_id,
+            None => {
+                let game_id = self.next_id();
+                self.games.insert(game_id, Game { name, metadata: None, metadata_updated: None });
+                game_id
+            },
+        };
+        self.aliases.insert(alias, game_id);
+        return game_id;
+    }
+
+    fn merge_games(&mut self, old_game_id: &i64, game_id: &i64) {
+        for ((user_id, _), playtime) in split_off(&mut self.entries, |(_, entry_game_id)| entry_game_id == old_game_id) {
+            *self.entries.entry((user_id, *game_id)).or_insert(0) += playtime;
+        }
+        for ((user_id, _), starttime) in split_off(&mut self.sessions, |(_, session_game_id)| session_game_id == old_game_id) {
+            self.sessions.entry((user_id, *game_id)).or_insert(starttime);
+        }
+        for ((user_id, _), goal) in split_off(&mut self.goals, |(_, goal_game_id)| goal_game_id == old_game_id) {
+            self.goals.entry((user_id, *game_id)).or_insert(goal);
+        }
+        for ((user_id, _, source), playtime) in split_off(&mut self.imported, |(_, imported_game_id, _)| imported_game_id == old_game_id) {
+            self.imported.entry((user_id, *game_id, source)).or_insert(playtime);
+        }
+        for entry in self.history.iter_mut().filter(|entry| entry.game_id == *old_game_id) {
+            entry.game_id = *game_id;
+        }
+        for ((user_id, _, day), rollup) in split_off(&mut self.history_rollup, |(_, rollup_game_id, _)| rollup_game_id == old_game_id) {
+            self.add_rollup((user_id, *game_id, day), rollup);
+        }
+        for alias_game_id in self.aliases.values_mut().filter(|alias_game_id| *alias_game_id == old_game_id) {
+            *alias_game_id = *game_id;
+        }
+        self.games.remove(old_game_id);
+    }
+
+    // Takes out the rows a reset archives, only the user's ones when `user_id` is set, and drops the running sessions
+    fn archive(&mut self, user_id: Option<i64>) -> Tables {
+        let of_user = |row_user_id: &i64| user_id.map_or(true, |user_id| *row_user_id == user_id);
+        self.sessions.retain(|(session_user_id, _), _| !of_user(session_user_id));
+        self.streams.retain(|stream_user_id, _| !of_user(stream_user_id));
+        self.listens.retain(|listen_user_id, _| !of_user(listen_user_id));
+        self.voice_sessions.retain(|session| !of_user(&session.user_id));
+        let (history, kept): (Vec<HistoryEntry>, Vec<HistoryEntry>) = std::mem::take(&mut self.history).into_iter().partition(|entry| of_user(&entry.user_id));
+        self.history = kept;
+        let (stream_history, kept): (Vec<Stream>, Vec<Stream>) = std::mem::take(&mut self.stream_history).into_iter().partition(|stream| of_user(&stream.user_id));
+        self.stream_history = kept;
+        let mut archived = Tables {
+            entries: split_off(&mut self.entries, |(entry_user_id, _)| of_user(entry_user_id)),
+            history,
+            history_rollup: split_off(&mut self.history_rollup, |(rollup_user_id, _, _)| of_user(rollup_user_id)),
+            levels: split_off(&mut self.levels, |(_, level_user_id)| of_user(level_user_id)),
+            unlocked_badges: split_off(&mut self.unlocked_badges, |(badge_user_id, _)| of_user(badge_user_id)),
+            stream_history,
+            listen_entries: split_off(&mut self.listen_entries, |(listen_user_id, _)| of_user(listen_user_id)),
+            imported: split_off(&mut self.imported, |(imported_user_id, _, _)| of_user(imported_user_id)),
+            ..Tables::default()
+        };
+        // Like in PgStorage, the games and goals are only reset by /resetall, the linked accounts only by /reset
+        if user_id.is_none() {
+            archived.games = std::mem::take(&mut self.games);
+            archived.aliases = std::mem::take(&mut self.aliases);
+            archived.goals = std::mem::take(&mut self.goals);
+        } else {
+            archived.linked_accounts = split_off(&mut self.linked_accounts, |(account_user_id, _)| of_user(account_user_id));
+        }
+        return archived;
+    }
+
+    // Puts back archived rows the way undo_reset does in PgStorage
+    fn restore(&mut self, archived: Tables) {
+        // Games tracked again since the reset are matched by name
+        let mut game_ids: HashMap<i64, i64> = HashMap::new();
+        for (game_id, game) in archived.games {
+            match self.games.iter().find(|(_, current)| current.name == game.name) {
+                Some((current_id, _)) => { game_ids.insert(game_id, *current_id); },
+                None => { self.games.insert(game_id, game); },
+            }
+        }
+        // The rows of games deleted since the reset are dropped
+        let restored_id = |games: &BTreeMap<i64, Game>, game_id: i64| game_ids.get(&game_id).copied().or(Some(game_id).filter(|game_id| games.contains_key(game_id)));
+        for (alias, game_id) in archived.aliases {
+            if let Some(game_id) = restored_id(&self.games, game_id) {
+                self.aliases.entry(alias).or_insert(game_id);
+            }
+        }
+        for ((user_id, game_id), playtime) in archived.entries {
+            if let Some(game_id) = restored_id(&self.games, game_id) {
+                *self.entries.entry((user_id, game_id)).or_insert(0) += playtime;
+            }
+        }
+        for mut entry in archived.history {
+            if let Some(game_id) = restored_id(&self.games, entry.game_id) {
+                entry.game_id = game_id;
+                self.history.push(entry);
+            }
+        }
+        for ((user_id, game_id, day), rollup) in archived.history_rollup {
+            if let Some(game_id) = restored_id(&self.games, game_id) {
+                self.add_rollup((user_id, game_id, day), rollup);
+            }
+        }
+        for ((user_id, game_id), goal) in archived.goals {
+            if let Some(game_id) = restored_id(&self.games, game_id) {
+                self.goals.entry((user_id, game_id)).or_insert(goal);
+            }
+        }
+        for ((user_id, game_id, source), playtime) in archived.imported {
+            if let Some(game_id) = restored_id(&self.games, game_id) {
+                self.imported.entry((user_id, game_id, source)).or_insert(playtime);
+            }
+        }
+        for (key, level) in archived.levels {
+            let current = self.levels.entry(key).or_insert(level);
+            *current = std::cmp::max(*current, level);
+        }
+        for (key, unlocked_at) in archived.unlocked_badges {
+            self.unlocked_badges.entry(key).or_insert(unlocked_at);
+        }
+        self.stream_history.extend(archived.stream_history);
+        for (key, listentime) in archived.listen_entries {
+            *self.listen_entries.entry(key).or_insert(0) += listentime;
+        }
+        for (key, account_id) in archived.linked_accounts {
+            self.linked_accounts.entry(key).or_insert(account_id);
+        }
+    }
+
+    // Adds pruned sessions to the rollup of their user, game and day
+    fn add_rollup(&mut self, key: (i64, i64, i64), added: Rollup) {
+        let rollup = self.history_rollup.entry(key).or_default();
+        rollup.playtime += added.playtime;
+        rollup.sessions += added.sessions;
+        rollup.longest = std::cmp::max(rollup.longest, added.longest);
+        rollup.night_sessions += added.night_sessions;
+    }
+
+    // The sessions and the rolled up days, like the full_history view of PgStorage
+    fn full_history(&self) -> Vec<Played> {
+        let sessions = self.history.iter().map(|entry| Played {
+            user_id: entry.user_id,
+            game_id: entry.game_id,
+            starttime: entry.starttime,
+            endtime: entry.endtime,
+            manual: entry.manual,
+            sessions: 1,
+            longest: entry.endtime - entry.starttime,
+            night_sessions: i64::from(entry.starttime.rem_euclid(24 * 60 * 60) < 5 * 60 * 60),
+        });
+        let days = self.history_rollup.iter().map(|((user_id, game_id, day), rollup)| {
+            let starttime = day + (24 * 60 * 60 - std::cmp::min(rollup.playtime, 24 * 60 * 60)) / 2;
+            Played {
+                user_id: *user_id,
+                game_id: *game_id,
+                starttime,
+                endtime: starttime + rollup.playtime,
+                manual: false,
+                sessions: rollup.sessions,
+                longest: rollup.longest,
+                night_sessions: rollup.night_sessions,
+            }
+        });
+        return sessions.chain(days).collect();
+    }
+
+    // Records a reset and forgets the ones that can't be undone anymore
+    fn start_reset(&mut self, user_id: Option<i64>, archived: Tables) -> Result<()> {
+        let resettime = currenttime()?;
+        self.resets.retain(|reset| reset.resettime >= resettime - RESET_UNDO_WINDOW);
+        self.resets.push(Reset { user_id, resettime, archived });
+        Ok(())
+    }
+
+    // Whether the user is a member of the guild, everyone is without `guild_id`
+    fn is_member(&self, guild_id: Option<i64>, user_id: &i64) -> bool {
+        return guild_id.map_or(true, |guild_id| self.guild_members.contains_key(&(guild_id, *user_id)));
+    }
+
+    // Playtime per game name of the sessions between `start` and `end` of the users `counted` keeps
+    fn period_playtime(&self, start: &i64, end: &i64, counted: impl Fn(&i64) -> bool) -> Vec<GameEntry> {
+        let mut playtime: BTreeMap<String, i64> = BTreeMap::new();
+        for entry in self.full_history().iter()
+            .filter(|entry| entry.endtime > *start && entry.starttime < *end)
+            .filter(|entry| counted(&entry.user_id)) {
+            *playtime.entry(self.game_name(&entry.game_id)).or_insert(0) += std::cmp::min(entry.endtime, *end) - std::cmp::max(entry.starttime, *start);
+        }
+        return sorted_entries(playtime);
+    }
+
+    // Total playtime of the users that didn't opt out, by user
+    fn user_playtime(&self) -> BTreeMap<i64, (i64, i64)> {
+        let mut totals: BTreeMap<i64, (i64, i64)> = BTreeMap::new();
+        for ((user_id, _), playtime) in self.entries.iter().filter(|((user_id, _), _)| !self.is_opted_out(user_id)) {
+            let total = totals.entry(*user_id).or_insert((0, 0));
+            total.0 += playtime;
+            total.1 += 1;
+        }
+        return totals;
+    }
+
+    // Returns (user id, playtime) pairs of the manual sessions, of a single game when `game_id` is set,
+    // nothing without `include_manual`
+    fn manual_playtime(&self, include_manual: bool, game_id: Option<&i64>) -> Vec<(i64, i64)> {
+        return self.imported.iter()
+            .filter(|((user_id, imported_game_id, source), _)| include_manual && source == "manual"
+                && game_id.map_or(true, |game_id| imported_game_id == game_id) && !self.is_opted_out(user_id))
+            .map(|((user_id, _, _), playtime)| (*user_id, *playtime))
+            .collect();
+    }
+}
+
+fn currenttime() -> Result<i64> {
+    return Ok(i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?);
+}
+
+// Removes the rows matching `predicate` from `map` and returns them
+fn split_off<K: Ord, V>(map: &mut BTreeMap<K, V>, predicate: impl Fn(&K) -> bool) -> BTreeMap<K, V> {
+    let (taken, kept): (BTreeMap<K, V>, BTreeMap<K, V>) = std::mem::take(map).into_iter().partition(|(key, _)| predicate(key));
+    *map = kept;
+    return taken;
+}
+
+// Applies LIMIT and OFFSET
+fn page<T>(rows: Vec<T>, limit: i64, offset: i64) -> Vec<T> {
+    return rows.into_iter()
+        .skip(usize::try_from(offset).unwrap_or(0))
+        .take(usize::try_from(limit).unwrap_or(0))
+        .collect();
+}
+
+// Most played first, ties by name
+fn sorted_entries(playtime: BTreeMap<String, i64>) -> Vec<GameEntry> {
+    let mut entries: Vec<GameEntry> = playtime.into_iter().map(|(name, playtime)| GameEntry { name, playtime }).collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.playtime));
+    return entries;
+}
+
+// Highest total first, ties by user id
+fn sorted_totals(totals: BTreeMap<i64, i64>) -> Vec<(i64, i64)> {
+    let mut totals: Vec<(i64, i64)> = totals.into_iter().collect();
+    totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    return totals;
+}
+
+// Game names of the watches are compared case insensitively, like the unique index
+fn is_watch((watch_watcher_id, watch_user_id, watch_game): &(i64, i64, Option<String>), watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> bool {
+    return watch_watcher_id == watcher_id && watch_user_id == user_id
+        && watch_game.as_deref().map(str::to_lowercase) == game_name.map(str::to_lowercase);
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn save_session(&self, user_id: &i64, playing: &[i64], min_session_length: Option<i64>, endtime: &i64) -> Result<Vec<(String, i64)>> {
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let mut tables = self.tables();
+        let ended = split_off(&mut tables.sessions, |(session_user_id, game_id)| session_user_id == user_id && !playing.contains(game_id));
+        let mut saved: Vec<(String, i64)> = Vec::new();
+        for ((_, game_id), starttime) in ended {
+            let playtime = std::cmp::min(endtime - starttime, self.max_session_length);
+            if playtime >= min_session_length {
+                let rich_presence = tables.rich_presences.get(&(*user_id, game_id)).cloned().unwrap_or_default();
+                tables.history.push(HistoryEntry { user_id: *user_id, game_id, starttime: endtime - playtime, endtime: *endtime, manual: false, rich_presence });
+                *tables.entries.entry((*user_id, game_id)).or_insert(0) += playtime;
+                saved.push((tables.game_name(&game_id), playtime));
+            }
+        }
+        return Ok(saved);
+    }
+
+    async fn get_top_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameEntry>> {
+        let tables = self.tables();
+        let entries = match start {
+            Some(start) => tables.period_playtime(&start, &i64::MAX, |entry_user_id| entry_user_id == user_id),
+            None => sorted_entries(tables.entries.iter()
+                .filter(|((entry_user_id, _), _)| entry_user_id == user_id)
+                .map(|((_, game_id), playtime)| (tables.game_name(game_id), *playtime))
+                .collect()),
+        };
+        return Ok(page(entries, limit, offset));
+    }
+
+    async fn get_summary_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameSummary>> {
+        let games = self.get_top_games(user_id, start, limit, offset).await?;
+        let tables = self.tables();
+        let mut summaries: Vec<GameSummary> = Vec::new();
+        for game in games {
+            let played: Vec<Played> = tables.full_history().into_iter()
+                .filter(|entry| entry.user_id == *user_id && tables.game_name(&entry.game_id) == game.name)
+                .filter(|entry| start.map_or(true, |start| entry.endtime > start))
+                .collect();
+            let sessions: i64 = played.iter().map(|entry| entry.sessions).sum();
+            let duration: i64 = played.iter().map(|entry| entry.endtime - entry.starttime).sum();
+            summaries.push(GameSummary {
+                name: game.name,
+                playtime: game.playtime,
+                sessions,
+                average_session: if sessions > 0 { Some(duration / sessions) } else { None },
+            });
+        }
+        return Ok(summaries);
+    }
+
+    async fn count_games(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let tables = self.tables();
+        let count = match start {
+            Some(start) => tables.full_history().iter()
+                .filter(|entry| entry.user_id == *user_id && entry.endtime > start)
+                .map(|entry| entry.game_id)
+                .collect::<BTreeSet<i64>>().len(),
+            None => tables.entries.keys().filter(|(entry_user_id, _)| entry_user_id == user_id).count(),
+        };
+        return Ok(i64::try_from(count)?);
+    }
+
+    async fn get_game_playtime(&self, user_id: &i64, game_id: &i64) -> Result<i64> {
+        return Ok(self.tables().entries.get(&(*user_id, *game_id)).copied().unwrap_or(0));
+    }
+
+    async fn adjust_playtime(&self, user_id: &i64, game_id: &i64, seconds: &i64) -> Result<i64> {
+        let mut tables = self.tables();
+        let playtime = (tables.entries.get(&(*user_id, *game_id)).copied().unwrap_or(0) + seconds).max(0);
+        if playtime == 0 {
+            tables.entries.remove(&(*user_id, *game_id));
+        } else {
+            tables.entries.insert((*user_id, *game_id), playtime);
+        }
+        return Ok(playtime);
+    }
+
+    async fn log_session(&self, user_id: &i64, game_name: &str, starttime: &i64, duration: &i64) -> Result<String> {
+        let mut tables = self.tables();
+        let game_id = tables.resolve_game(game_name);
+        tables.history.push(HistoryEntry { user_id: *user_id, game_id, starttime: *starttime, endtime: starttime + duration, manual: true, rich_presence: RichPresence::default() });
+        *tables.imported.entry((*user_id, game_id, "manual".to_string())).or_insert(0) += duration;
+        return Ok(tables.game_name(&game_id));
+    }
+
+    async fn import_playtime(&self, rows: &[(i64, String, i64)]) -> Result<()> {
+        let mut tables = self.tables();
+        for (user_id, game_name, playtime) in rows {
+            let game_id = tables.resolve_game(game_name);
+            *tables.entries.entry((*user_id, game_id)).or_insert(0) += playtime;
+        }
+        Ok(())
+    }
+
+    async fn get_game_stats(&self, user_id: &i64, game_id: &i64) -> Result<GameStats> {
+        let tables = self.tables();
+        let sessions: Vec<Played> = tables.full_history().into_iter().filter(|entry| entry.user_id == *user_id && entry.game_id == *game_id).collect();
+        return Ok(GameStats {
+            sessions: sessions.iter().map(|entry| entry.sessions).sum(),
+            playtime: sessions.iter().map(|entry| entry.endtime - entry.starttime).sum(),
+            first_played: sessions.iter().map(|entry| entry.starttime).min(),
+            last_played: sessions.iter().map(|entry| entry.endtime).max(),
+        });
+    }
+
+    async fn get_top_details(&self, user_id: &i64, game_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        let tables = self.tables();
+        let mut playtimes: BTreeMap<String, i64> = BTreeMap::new();
+        for entry in tables.history.iter().filter(|entry| entry.user_id == *user_id && entry.game_id == *game_id) {
+            if let Some(details) = entry.rich_presence.details.as_ref().or(entry.rich_presence.state.as_ref()) {
+                *playtimes.entry(details.clone()).or_insert(0) += entry.endtime - entry.starttime;
+            }
+        }
+        return Ok(page(sorted_entries(playtimes), limit, 0).into_iter()
+            .map(|entry| (entry.name, entry.playtime))
+            .collect());
+    }
+
+    async fn get_total_playtime(&self, user_id: &i64) -> Result<i64> {
+        return Ok(self.tables().entries.iter()
+            .filter(|((entry_user_id, _), _)| entry_user_id == user_id)
+            .map(|(_, playtime)| playtime)
+            .sum());
+    }
+
+    async fn get_open_sessions(&self, user_id: &i64) -> Result<Vec<Session>> {
+        let tables = self.tables();
+        let mut sessions: Vec<Session> = tables.sessions.iter()
+            .filter(|((session_user_id, _), _)| session_user_id == user_id)
+            .map(|((_, game_id), starttime)| {
+                let rich_presence = tables.rich_presences.get(&(*user_id, *game_id)).cloned().unwrap_or_default();
+                Session {
+                    game_id: *game_id,
+                    name: tables.game_name(game_id),
+                    starttime: *starttime,
+                    state: rich_presence.state,
+                    details: rich_presence.details,
+                    party_size: rich_presence.party_size,
+                }
+            })
+            .collect();
+        sessions.sort_by_key(|session| session.starttime);
+        return Ok(sessions);
+    }
+
+    async fn get_open_sessions_by_game(&self) -> Result<Vec<(String, Vec<i64>)>> {
+        let tables = self.tables();
+        let mut players: BTreeMap<String, Vec<(i64, i64)>> = BTreeMap::new();
+        for ((user_id, game_id), starttime) in tables.sessions.iter() {
+            players.entry(tables.game_name(game_id)).or_default().push((*starttime, *user_id));
+        }
+        let mut games: Vec<(String, Vec<i64>)> = players.into_iter()
+            .map(|(name, mut players)| {
+                players.sort();
+                (name, players.into_iter().map(|(_, user_id)| user_id).collect())
+            })
+            .collect();
+        games.sort_by_key(|(_, user_ids)| std::cmp::Reverse(user_ids.len()));
+        return Ok(games);
+    }
+
+    async fn get_recent_sessions(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64, i64)>> {
+        let tables = self.tables();
+        let mut sessions: Vec<&HistoryEntry> = tables.history.iter().filter(|entry| entry.user_id == *user_id).collect();
+        sessions.sort_by_key(|entry| std::cmp::Reverse(entry.endtime));
+        return Ok(page(sessions, limit, 0).into_iter()
+            .map(|entry| (tables.game_name(&entry.game_id), entry.starttime, entry.endtime - entry.starttime))
+            .collect());
+    }
+
+    async fn get_longest_session(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<(String, i64, i64)>> {
+        let tables = self.tables();
+        return Ok(tables.full_history().iter()
+            .filter(|entry| entry.user_id == *user_id && entry.starttime >= *start && entry.starttime < *end)
+            .max_by_key(|entry| entry.longest)
+            .map(|entry| (tables.game_name(&entry.game_id), entry.starttime, entry.longest)));
+    }
+
+    async fn get_session_durations(&self, user_id: &i64, game_id: Option<i64>) -> Result<Vec<i64>> {
+        let mut durations: Vec<i64> = self.tables().history.iter()
+            .filter(|entry| entry.user_id == *user_id && game_id.map_or(true, |game_id| entry.game_id == game_id))
+            .map(|entry| entry.endtime - entry.starttime)
+            .collect();
+        durations.sort();
+        return Ok(durations);
+    }
+
+    async fn get_period_leaderboard(&self, guild_id: &i64, start: &i64, end: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        let tables = self.tables();
+        let mut totals: BTreeMap<i64, i64> = BTreeMap::new();
+        for entry in tables.full_history().iter().filter(|entry| entry.endtime > *start && entry.starttime < *end && (include_manual || !entry.manual) && !tables.is_opted_out(&entry.user_id)) {
+            *totals.entry(entry.user_id).or_insert(0) += std::cmp::min(entry.endtime, *end) - std::cmp::max(entry.starttime, *start);
+        }
+        totals.retain(|user_id, _| tables.is_member(Some(*guild_id), user_id));
+        return Ok(page(sorted_totals(totals), 10, 0));
+    }
+
+    async fn get_period_top_games(&self, guild_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        let tables = self.tables();
+        return Ok(page(tables.period_playtime(start, end, |user_id| !tables.is_opted_out(user_id) && tables.is_member(Some(*guild_id), user_id)), 10, 0));
+    }
+
+    async fn get_server_top_games(&self, guild_id: Option<i64>, start: Option<i64>, by_players: bool) -> Result<Vec<(String, i64, i64)>> {
+        let tables = self.tables();
+        let played: Vec<(i64, i64, i64)> = match start {
+            Some(start) => tables.full_history().iter()
+                .filter(|entry| entry.endtime > start)
+                .map(|entry| (entry.user_id, entry.game_id, entry.endtime - std::cmp::max(entry.starttime, start)))
+                .collect(),
+            None => tables.entries.iter().map(|((user_id, game_id), playtime)| (*user_id, *game_id, *playtime)).collect(),
+        };
+        let mut games: BTreeMap<String, (i64, BTreeSet<i64>)> = BTreeMap::new();
+        for (user_id, game_id, playtime) in played.into_iter().filter(|(user_id, _, _)| !tables.is_opted_out(user_id) && tables.is_member(guild_id, user_id)) {
+            let game = games.entry(tables.game_name(&game_id)).or_default();
+            game.0 += playtime;
+            game.1.insert(user_id);
+        }
+        let mut games: Vec<(String, i64, i64)> = games.into_iter()
+            .map(|(name, (playtime, players))| Ok((name, playtime, i64::try_from(players.len())?)))
+            .collect::<Result<Vec<(String, i64, i64)>>>()?;
+        games.sort_by_key(|(_, playtime, players)| std::cmp::Reverse(if by_players { (*players, *playtime) } else { (*playtime, 0) }));
+        return Ok(page(games, 10, 0));
+    }
+
+    async fn get_user_period_playtime(&self, user_id: &i64, start: &i64, end: &i64) -> Result<i64> {
+        return Ok(self.tables().period_playtime(start, end, |entry_user_id| entry_user_id == user_id).iter().map(|entry| entry.playtime).sum());
+    }
+
+    async fn get_user_period_games(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        return Ok(self.tables().period_playtime(start, end, |entry_user_id| entry_user_id == user_id));
+    }
+
+    async fn get_user_period_top_game(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<GameEntry>> {
+        return Ok(self.tables().period_playtime(start, end, |entry_user_id| entry_user_id == user_id).into_iter().next());
+    }
+
+    async fn get_sessions_since(&self, user_id: &i64, start: &i64) -> Result<Vec<(i64, i64)>> {
+        return Ok(self.tables().full_history().iter()
+            .filter(|entry| entry.user_id == *user_id && entry.endtime > *start)
+            .map(|entry| (entry.starttime, entry.endtime))
+            .collect());
+    }
+
+    async fn get_user_totals(&self, user_id: &i64, guild_id: Option<i64>) -> Result<Option<UserTotals>> {
+        let tables = self.tables();
+        let mut totals = tables.user_playtime();
+        // The user is ranked even after leaving the guild
+        totals.retain(|total_user_id, _| total_user_id == user_id || tables.is_member(guild_id, total_user_id));
+        let (playtime, games) = match totals.get(user_id) {
+            Some(total) => *total,
+            None => return Ok(None),
+        };
+        return Ok(Some(UserTotals {
+            playtime,
+            games,
+            rank: i64::try_from(totals.values().filter(|(total, _)| *total > playtime).count())? + 1,
+            ranked_users: i64::try_from(totals.len())?,
+        }));
+    }
+
+    async fn get_user_rank(&self, user_id: &i64, guild_id: Option<i64>, start: Option<i64>) -> Result<Option<(i64, i64)>> {
+        let tables = self.tables();
+        let mut totals: BTreeMap<i64, i64> = match start {
+            Some(start) => {
+                let mut totals: BTreeMap<i64, i64> = BTreeMap::new();
+                for entry in tables.full_history().iter().filter(|entry| entry.endtime > start && !tables.is_opted_out(&entry.user_id)) {
+                    *totals.entry(entry.user_id).or_insert(0) += entry.endtime - std::cmp::max(entry.starttime, start);
+                }
+                totals
+            },
+            None => tables.user_playtime().into_iter().map(|(user_id, (playtime, _))| (user_id, playtime)).collect(),
+        };
+        totals.retain(|total_user_id, _| total_user_id == user_id || tables.is_member(guild_id, total_user_id));
+        let playtime = match totals.get(user_id) {
+            Some(playtime) => *playtime,
+            None => return Ok(None),
+        };
+        let rank = i64::try_from(totals.values().filter(|total| **total > playtime).count())? + 1;
+        return Ok(Some((rank, i64::try_from(totals.len())?)));
+    }
+
+    async fn get_shared_games(&self, user1_id: &i64, user2_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        let tables = self.tables();
+        let mut shared: Vec<(String, i64, i64)> = tables.entries.iter()
+            .filter(|((user_id, _), _)| user_id == user1_id)
+            .filter_map(|((_, game_id), playtime1)| tables.entries.get(&(*user2_id, *game_id))
+                .map(|playtime2| (tables.game_name(game_id), *playtime1, *playtime2)))
+            .collect();
+        shared.sort_by_key(|(_, playtime1, playtime2)| std::cmp::Reverse(playtime1 + playtime2));
+        return Ok(page(shared, 10, 0));
+    }
+
+    async fn get_leaderboard(&self, guild_id: Option<i64>, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        let tables = self.tables();
+        let mut totals: BTreeMap<i64, i64> = tables.user_playtime().into_iter().map(|(user_id, (playtime, _))| (user_id, playtime)).collect();
+        for (user_id, playtime) in tables.manual_playtime(include_manual, None) {
+            *totals.entry(user_id).or_insert(0) += playtime;
+        }
+        totals.retain(|user_id, _| tables.is_member(guild_id, user_id));
+        return Ok(page(sorted_totals(totals), 10, 0));
+    }
+
+    async fn get_game_leaderboard(&self, guild_id: Option<i64>, game_id: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        let tables = self.tables();
+        let mut totals: BTreeMap<i64, i64> = tables.entries.iter()
+            .filter(|((user_id, entry_game_id), _)| entry_game_id == game_id && !tables.is_opted_out(user_id))
+            .map(|((user_id, _), playtime)| (*user_id, *playtime))
+            .collect();
+        for (user_id, playtime) in tables.manual_playtime(include_manual, Some(game_id)) {
+            *totals.entry(user_id).or_insert(0) += playtime;
+        }
+        totals.retain(|user_id, _| tables.is_member(guild_id, user_id));
+        return Ok(page(sorted_totals(totals), 10, 0));
+    }
+
+    async fn find_game(&self, game_name: &str) -> Result<Option<(i64, String)>> {
+        let tables = self.tables();
+        if let Some(game_id) = tables.aliases.get(&game_key(game_name)) {
+            return Ok(Some((*game_id, tables.game_name(game_id))));
+        }
+        let searched = game_name.trim().to_lowercase();
+        return Ok(tables.games.iter()
+            .filter(|(_, game)| game.name.to_lowercase().contains(&searched))
+            .min_by_key(|(_, game)| (game.name.to_lowercase() != searched, game.name.len()))
+            .map(|(game_id, game)| (*game_id, game.name.clone())));
+    }
+
+    async fn search_games(&self, game_name: &str) -> Result<Vec<String>> {
+        let searched = game_name.to_lowercase();
+        let mut names: Vec<String> = self.tables().games.values()
+            .filter(|game| game.name.to_lowercase().contains(&searched))
+            .map(|game| game.name.clone())
+            .collect();
+        names.sort();
+        return Ok(page(names, 25, 0));
+    }
+
+    async fn register_session(&self, user_id: &i64, game_name: &str, starttime: &i64, rich_presence: &RichPresence) -> Result<(i64, bool)> {
+        let currenttime = currenttime()?;
+        let mut tables = self.tables();
+        let game_id = tables.resolve_game(game_name);
+        tables.rich_presences.insert((*user_id, game_id), rich_presence.clone());
+        if tables.sessions.contains_key(&(*user_id, game_id)) {
+            return Ok((game_id, false));
+        }
+        let mut starttime: i64 = *starttime;
+        if starttime > currenttime || currenttime - starttime > self.max_session_length {
+            starttime = currenttime;
+        }
+        // A session can't start before the previous one ended
+        let last_endtime = tables.history.iter()
+            .filter(|entry| entry.user_id == *user_id && entry.game_id == game_id)
+            .map(|entry| entry.endtime)
+            .max();
+        tables.sessions.insert((*user_id, game_id), last_endtime.map_or(starttime, |endtime| std::cmp::max(starttime, endtime)));
+        return Ok((game_id, true));
+    }
+
+    async fn register_stream(&self, user_id: &i64, game_name: &str, url: Option<&str>) -> Result<()> {
+        let currenttime = currenttime()?;
+        self.tables().streams.entry(*user_id).or_insert((clean_game_name(game_name), url.map(str::to_string), currenttime));
+        Ok(())
+    }
+
+    async fn save_stream(&self, user_id: &i64, streaming: Option<&str>, min_session_length: Option<i64>) -> Result<()> {
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let currenttime = currenttime()?;
+        let mut tables = self.tables();
+        match tables.streams.get(user_id) {
+            Some((game, _, _)) if streaming.map(clean_game_name).as_ref() != Some(game) => {},
+            _ => return Ok(()),
+        }
+        if let Some((game, url, starttime)) = tables.streams.remove(user_id) {
+            let duration = std::cmp::min(currenttime - starttime, self.max_session_length);
+            if duration >= min_session_length {
+                tables.stream_history.push(Stream { user_id: *user_id, game, url, starttime: currenttime - duration, endtime: currenttime });
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_stream_time(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let start = start.unwrap_or(0);
+        return Ok(self.tables().stream_history.iter()
+            .filter(|stream| stream.user_id == *user_id && stream.endtime > start)
+            .map(|stream| stream.endtime - std::cmp::max(stream.starttime, start))
+            .sum());
+    }
+
+    async fn get_top_streamed_games(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        let mut streamtime: BTreeMap<String, i64> = BTreeMap::new();
+        for stream in self.tables().stream_history.iter().filter(|stream| stream.user_id == *user_id) {
+            *streamtime.entry(stream.game.clone()).or_insert(0) += stream.endtime - stream.starttime;
+        }
+        return Ok(page(sorted_entries(streamtime).into_iter().map(|entry| (entry.name, entry.playtime)).collect(), limit, 0));
+    }
+
+    async fn get_recent_streams(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, Option<String>, i64, i64)>> {
+        let tables = self.tables();
+        let mut streams: Vec<&Stream> = tables.stream_history.iter().filter(|stream| stream.user_id == *user_id).collect();
+        streams.sort_by_key(|stream| std::cmp::Reverse(stream.endtime));
+        return Ok(page(streams, limit, 0).into_iter()
+            .map(|stream| (stream.game.clone(), stream.url.clone(), stream.starttime, stream.endtime - stream.starttime))
+            .collect());
+    }
+
+    async fn register_listen(&self, user_id: &i64, artist: &str) -> Result<()> {
+        let currenttime = currenttime()?;
+        self.tables().listens.entry(*user_id).or_insert((artist.to_string(), currenttime));
+        Ok(())
+    }
+
+    async fn save_listen(&self, user_id: &i64, listening: Option<&str>) -> Result<()> {
+        let currenttime = currenttime()?;
+        let mut tables = self.tables();
+        match tables.listens.get(user_id) {
+            Some((artist, _)) if listening != Some(artist.as_str()) => {},
+            _ => return Ok(()),
+        }
+        if let Some((artist, starttime)) = tables.listens.remove(user_id) {
+            *tables.listen_entries.entry((*user_id, artist)).or_insert(0) += std::cmp::min(currenttime - starttime, self.max_session_length);
+        }
+        Ok(())
+    }
+
+    async fn get_top_artists(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        let listentime: BTreeMap<String, i64> = self.tables().listen_entries.iter()
+            .filter(|((listen_user_id, _), _)| listen_user_id == user_id)
+            .map(|((_, artist), listentime)| (artist.clone(), *listentime))
+            .collect();
+        return Ok(page(sorted_entries(listentime).into_iter().map(|entry| (entry.name, entry.playtime)).collect(), limit, 0));
+    }
+
+    async fn get_listen_time(&self, user_id: &i64) -> Result<i64> {
+        return Ok(self.tables().listen_entries.iter()
+            .filter(|((listen_user_id, _), _)| listen_user_id == user_id)
+            .map(|(_, listentime)| listentime)
+            .sum());
+    }
+
+    async fn get_games_to_enrich(&self, before: &i64, limit: i64) -> Result<Vec<(i64, String)>> {
+        let tables = self.tables();
+        let mut games: Vec<(&i64, &Game)> = tables.games.iter()
+            .filter(|(_, game)| game.metadata_updated.map_or(true, |updated| updated < *before))
+            .collect();
+        games.sort_by_key(|(_, game)| game.metadata_updated);
+        return Ok(page(games, limit, 0).into_iter().map(|(game_id, game)| (*game_id, game.name.clone())).collect());
+    }
+
+    async fn set_game_metadata(&self, game_id: &i64, metadata: Option<&GameMetadata>, updated: &i64) -> Result<()> {
+        if let Some(game) = self.tables().games.get_mut(game_id) {
+            game.metadata = metadata.cloned();
+            game.metadata_updated = Some(*updated);
+        }
+        Ok(())
+    }
+
+    async fn get_game_metadata(&self, game_id: &i64) -> Result<Option<GameMetadata>> {
+        return Ok(self.tables().games.get(game_id).and_then(|game| game.metadata.clone()));
+    }
+
+    async fn get_genre_playtime(&self, user_id: &i64) -> Result<Vec<(Option<String>, i64)>> {
+        let tables = self.tables();
+        let mut playtime: BTreeMap<Option<String>, i64> = BTreeMap::new();
+        for ((_, game_id), game_playtime) in tables.entries.iter().filter(|((entry_user_id, _), _)| entry_user_id == user_id) {
+            let genres: Vec<String> = tables.games.get(game_id)
+                .and_then(|game| game.metadata.as_ref())
+                .map_or(Vec::new(), |metadata| metadata.genres.clone());
+            let share = game_playtime / std::cmp::max(i64::try_from(genres.len())?, 1);
+            if genres.is_empty() {
+                *playtime.entry(None).or_insert(0) += share;
+            }
+            for genre in genres {
+                *playtime.entry(Some(genre)).or_insert(0) += share;
+            }
+        }
+        let mut genres: Vec<(Option<String>, i64)> = playtime.into_iter().collect();
+        genres.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+        return Ok(genres);
+    }
+
+    async fn get_cover_url(&self, game_name: &str) -> Result<Option<String>> {
+        return Ok(self.tables().games.values()
+            .find(|game| game.name == game_name)
+            .and_then(|game| game.metadata.as_ref())
+            .and_then(|metadata| metadata.cover_url.clone()));
+    }
+
+    async fn get_game_totals(&self, game_id: &i64) -> Result<(i64, i64)> {
+        let tables = self.tables();
+        let playtimes: Vec<i64> = tables.entries.iter()
+            .filter(|((user_id, entry_game_id), _)| entry_game_id == game_id && !tables.is_opted_out(user_id))
+            .map(|(_, playtime)| *playtime)
+            .collect();
+        return Ok((i64::try_from(playtimes.len())?, playtimes.iter().sum()));
+    }
+
+    async fn link_account(&self, user_id: &i64, platform: &str, account_id: &str) -> Result<()> {
+        self.tables().linked_accounts.insert((*user_id, platform.to_string()), account_id.to_string());
+        Ok(())
+    }
+
+    async fn unlink_account(&self, user_id: &i64, platform: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        tables.imported.retain(|(imported_user_id, _, source), _| imported_user_id != user_id || source != platform);
+        return Ok(tables.linked_accounts.remove(&(*user_id, platform.to_string())).is_some());
+    }
+
+    async fn get_linked_accounts(&self, platform: &str) -> Result<Vec<(i64, String)>> {
+        return Ok(self.tables().linked_accounts.iter()
+            .filter(|((_, account_platform), _)| account_platform == platform)
+            .map(|((user_id, _), account_id)| (*user_id, account_id.clone()))
+            .collect());
+    }
+
+    async fn replace_imported_entries(&self, user_id: &i64, source: &str, entries: &[(String, i64)]) -> Result<()> {
+        let mut tables = self.tables();
+        tables.imported.retain(|(imported_user_id, _, imported_source), _| imported_user_id != user_id || imported_source != source);
+        for (game_name, playtime) in entries {
+            let game_id = tables.resolve_game(game_name);
+            *tables.imported.entry((*user_id, game_id, source.to_string())).or_insert(0) += playtime;
+        }
+        Ok(())
+    }
+
+    async fn get_imported_playtime(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        let mut playtime: BTreeMap<String, i64> = BTreeMap::new();
+        for ((_, _, source), imported) in self.tables().imported.iter().filter(|((imported_user_id, _, _), _)| imported_user_id == user_id) {
+            *playtime.entry(source.clone()).or_insert(0) += imported;
+        }
+        return Ok(playtime.into_iter().collect());
+    }
+
+    async fn merge_alias(&self, alias: &str, game_id: &i64) -> Result<Option<i64>> {
+        let alias = game_key(alias);
+        let mut tables = self.tables();
+        let old_game_id = tables.aliases.get(&alias).copied().filter(|old_game_id| old_game_id != game_id);
+        if let Some(old_game_id) = old_game_id {
+            tables.merge_games(&old_game_id, game_id);
+        }
+        tables.aliases.insert(alias, *game_id);
+        return Ok(old_game_id);
+    }
+
+    async fn merge_games(&self, old_game_id: &i64, game_id: &i64) -> Result<()> {
+        self.tables().merge_games(old_game_id, game_id);
+        Ok(())
+    }
+
+    async fn delete_game(&self, game_id: &i64) -> Result<String> {
+        let mut tables = self.tables();
+        let game = tables.games.remove(game_id).ok_or_else(|| anyhow!("There is no game {}", game_id))?;
+        tables.entries.retain(|(_, entry_game_id), _| entry_game_id != game_id);
+        tables.sessions.retain(|(_, session_game_id), _| session_game_id != game_id);
+        tables.history.retain(|entry| entry.game_id != *game_id);
+        tables.history_rollup.retain(|(_, rollup_game_id, _), _| rollup_game_id != game_id);
+        tables.goals.retain(|(_, goal_game_id), _| goal_game_id != game_id);
+        tables.imported.retain(|(_, imported_game_id, _), _| imported_game_id != game_id);
+        tables.aliases.retain(|_, alias_game_id| alias_game_id != game_id);
+        return Ok(game.name);
+    }
+
+    async fn rename_game(&self, game_id: &i64, game_name: &str) -> Result<bool> {
+        let name = clean_game_name(game_name);
+        let mut tables = self.tables();
+        let taken = tables.games.iter().any(|(other_id, game)| other_id != game_id && game.name.to_lowercase() == name.to_lowercase())
+            || tables.aliases.get(&game_key(&name)).map_or(false, |alias_game_id| alias_game_id != game_id);
+        if taken {
+            return Ok(false);
+        }
+        let game = tables.games.get_mut(game_id).ok_or_else(|| anyhow!("There is no game {}", game_id))?;
+        let old_name = std::mem::replace(&mut game.name, name.clone());
+        for alias in [game_key(&old_name), game_key(&name)] {
+            tables.aliases.insert(alias, *game_id);
+        }
+        return Ok(true);
+    }
+
+    async fn is_ignored(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let tables = self.tables();
+        let name = game_key(game_name);
+        return Ok(tables.ignored_games.contains(&(0, name.clone())) || tables.ignored_games.contains(&(*guild_id, name)));
+    }
+
+    async fn add_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        self.tables().ignored_games.insert((*guild_id, game_key(game_name)));
+        Ok(())
+    }
+
+    async fn remove_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        return Ok(self.tables().ignored_games.remove(&(*guild_id, game_key(game_name))));
+    }
+
+    async fn get_ignored_games(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(self.tables().ignored_games.iter()
+            .filter(|(ignored_guild_id, _)| *ignored_guild_id == 0 || ignored_guild_id == guild_id)
+            .cloned()
+            .collect());
+    }
+
+    async fn is_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let tables = self.tables();
+        let name = game_key(game_name);
+        let game_id = tables.aliases.get(&name);
+        return Ok(tables.tracked_games.iter()
+            .filter(|(tracked_guild_id, _)| tracked_guild_id == guild_id)
+            .any(|(_, tracked)| *tracked == name || (game_id.is_some() && tables.aliases.get(tracked) == game_id)));
+    }
+
+    async fn add_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        self.tables().tracked_games.insert((*guild_id, game_key(game_name)));
+        Ok(())
+    }
+
+    async fn remove_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        return Ok(self.tables().tracked_games.remove(&(*guild_id, game_key(game_name))));
+    }
+
+    async fn get_tracked_games(&self, guild_id: &i64) -> Result<Vec<String>> {
+        return Ok(self.tables().tracked_games.iter()
+            .filter(|(tracked_guild_id, _)| tracked_guild_id == guild_id)
+            .map(|(_, name)| name.clone())
+            .collect());
+    }
+
+    async fn is_opted_out(&self, user_id: &i64) -> Result<bool> {
+        return Ok(self.tables().is_opted_out(user_id));
+    }
+
+    async fn set_opted_out(&self, user_id: &i64, opted_out: bool) -> Result<()> {
+        let mut tables = self.tables();
+        tables.user_settings.entry(*user_id).or_default().opted_out = opted_out;
+        if opted_out {
+            tables.sessions.retain(|(session_user_id, _), _| session_user_id != user_id);
+            tables.voice_sessions.retain(|session| session.user_id != *user_id || session.endtime.is_some());
+            tables.streams.remove(user_id);
+            tables.listens.remove(user_id);
+        }
+        Ok(())
+    }
+
+    async fn get_user_locale(&self, user_id: &i64) -> Result<Option<String>> {
+        return Ok(self.tables().user_settings.get(user_id).and_then(|settings| settings.locale.clone()));
+    }
+
+    async fn set_user_locale(&self, user_id: &i64, locale: Option<&str>) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().locale = locale.map(str::to_string);
+        Ok(())
+    }
+
+    async fn get_user_timezone(&self, user_id: &i64) -> Result<Option<String>> {
+        return Ok(self.tables().user_settings.get(user_id).and_then(|settings| settings.timezone.clone()));
+    }
+
+    async fn set_user_timezone(&self, user_id: &i64, timezone: Option<&str>) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().timezone = timezone.map(str::to_string);
+        Ok(())
+    }
+
+    async fn get_private_stats(&self, user_id: &i64) -> Result<bool> {
+        return Ok(self.tables().user_settings.get(user_id).map_or(false, |settings| settings.private_stats));
+    }
+
+    async fn set_private_stats(&self, user_id: &i64, private: bool) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().private_stats = private;
+        Ok(())
+    }
+
+    async fn get_privacy_level(&self, user_id: &i64) -> Result<String> {
+        let level = self.tables().user_settings.get(user_id).and_then(|settings| settings.privacy_level.clone());
+        return Ok(level.unwrap_or_else(|| "public".to_string()));
+    }
+
+    async fn set_privacy_level(&self, user_id: &i64, level: &str) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().privacy_level = Some(level.to_string());
+        Ok(())
+    }
+
+    async fn get_paused_until(&self, user_id: &i64) -> Result<Option<i64>> {
+        return Ok(self.tables().user_settings.get(user_id).and_then(|settings| settings.paused_until));
+    }
+
+    async fn set_paused_until(&self, user_id: &i64, until: Option<i64>) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().paused_until = until;
+        Ok(())
+    }
+
+    async fn get_vacation(&self, user_id: &i64) -> Result<Option<(i64, i64)>> {
+        return Ok(self.tables().user_settings.get(user_id).and_then(|settings| settings.vacation));
+    }
+
+    async fn set_vacation(&self, user_id: &i64, vacation: Option<(i64, i64)>) -> Result<()> {
+        self.tables().user_settings.entry(*user_id).or_default().vacation = vacation;
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, user_id: &i64, _guild_id: Option<i64>, action: &str, details: &str, actiontime: &i64) -> Result<()> {
+        self.tables().audit_log.push(AuditEntry { user_id: *user_id, action: action.to_string(), details: details.to_string(), actiontime: *actiontime });
+        Ok(())
+    }
+
+    async fn get_audit_log(&self, limit: i64, offset: i64) -> Result<Vec<(i64, String, String, i64)>> {
+        let entries = self.tables().audit_log.iter().rev()
+            .map(|entry| (entry.user_id, entry.action.clone(), entry.details.clone(), entry.actiontime))
+            .collect();
+        return Ok(page(entries, limit, offset));
+    }
+
+    async fn count_audit_entries(&self) -> Result<i64> {
+        return Ok(i64::try_from(self.tables().audit_log.len())?);
+    }
+
+    // Dumps are made of the PostgreSQL tables, the tests have no use for them
+    async fn dump(&self) -> Result<Value> {
+        return Err(anyhow!("Backups need a PostgreSQL database"));
+    }
+
+    async fn restore(&self, _tables: &serde_json::Map<String, Value>) -> Result<()> {
+        return Err(anyhow!("Backups need a PostgreSQL database"));
+    }
+
+    async fn get_schema_version(&self) -> Result<Option<i64>> {
+        return Ok(None);
+    }
+
+    async fn export_user(&self, user_id: &i64) -> Result<Value> {
+        // The getters lock the tables themselves
+        let settings = json!({
+            "locale": self.get_user_locale(user_id).await?,
+            "timezone": self.get_user_timezone(user_id).await?,
+            "privacy_level": self.get_privacy_level(user_id).await?,
+            "private_stats": self.get_private_stats(user_id).await?,
+            "paused_until": self.get_paused_until(user_id).await?,
+            "vacation": self.get_vacation(user_id).await?.map(|(start, end)| json!({"start": start, "end": end})),
+            "digest": self.tables().digest_subscribers.contains(user_id),
+        });
+        let goals: Vec<Value> = self.get_user_goals(user_id).await?.into_iter().map(|(name, seconds, is_limit)| json!({
+            "game": name,
+            "seconds": seconds,
+            "is_limit": is_limit,
+        })).collect();
+        let watches: Vec<Value> = self.get_watches(user_id).await?.into_iter().map(|(watched_id, game_name)| json!({
+            "user_id": watched_id.to_string(),
+            "game": game_name,
+        })).collect();
+        let badges: Vec<Value> = self.get_unlocked_badges(user_id).await?.into_iter().map(|(badge, unlocked_at)| json!({
+            "badge": badge,
+            "unlocked_at": unlocked_at,
+        })).collect();
+        let tables = self.tables();
+        let mut games: Vec<(&i64, &i64)> = tables.entries.iter()
+            .filter(|((entry_user_id, _), _)| entry_user_id == user_id)
+            .map(|((_, game_id), playtime)| (game_id, playtime))
+            .collect();
+        games.sort_by_key(|(_, playtime)| std::cmp::Reverse(**playtime));
+        let mut sessions: Vec<&HistoryEntry> = tables.history.iter().filter(|entry| entry.user_id == *user_id).collect();
+        sessions.sort_by_key(|entry| entry.starttime);
+        let mut voice_sessions: Vec<&VoiceSession> = tables.voice_sessions.iter().filter(|session| session.user_id == *user_id).collect();
+        voice_sessions.sort_by_key(|session| session.starttime);
+        let mut streams: Vec<&Stream> = tables.stream_history.iter().filter(|stream| stream.user_id == *user_id).collect();
+        streams.sort_by_key(|stream| stream.starttime);
+        let mut listening: Vec<(&String, &i64)> = tables.listen_entries.iter()
+            .filter(|((listen_user_id, _), _)| listen_user_id == user_id)
+            .map(|((_, artist), listentime)| (artist, listentime))
+            .collect();
+        listening.sort_by_key(|(_, listentime)| std::cmp::Reverse(**listentime));
+        let mut imported: Vec<(&i64, &String, &i64)> = tables.imported.iter()
+            .filter(|((imported_user_id, _, _), _)| imported_user_id == user_id)
+            .map(|((_, game_id, source), playtime)| (game_id, source, playtime))
+            .collect();
+        imported.sort_by_key(|(_, source, playtime)| (source.to_string(), std::cmp::Reverse(**playtime)));
+        return Ok(json!({
+            "user_id": user_id.to_string(),
+            "opted_out": tables.is_opted_out(user_id),
+            "games": games.iter().map(|(game_id, playtime)| json!({
+                "game": tables.game_name(game_id),
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "open_sessions": tables.sessions.iter()
+                .filter(|((session_user_id, _), _)| session_user_id == user_id)
+                .map(|((_, game_id), starttime)| json!({
+                    "game": tables.game_name(game_id),
+                    "starttime": starttime,
+                })).collect::<Vec<Value>>(),
+            "sessions": sessions.iter().map(|entry| json!({
+                "game": tables.game_name(&entry.game_id),
+                "starttime": entry.starttime,
+                "endtime": entry.endtime,
+                "duration": entry.endtime - entry.starttime,
+                "state": entry.rich_presence.state,
+                "details": entry.rich_presence.details,
+                "party_size": entry.rich_presence.party_size,
+            })).collect::<Vec<Value>>(),
+            "history_rollup": tables.history_rollup.iter()
+                .filter(|((rollup_user_id, _, _), _)| rollup_user_id == user_id)
+                .map(|((_, game_id, day), rollup)| json!({
+                    "game": tables.game_name(game_id),
+                    "day": day,
+                    "playtime": rollup.playtime,
+                    "sessions": rollup.sessions,
+                })).collect::<Vec<Value>>(),
+            "voice_sessions": voice_sessions.iter().map(|session| json!({
+                "guild_id": session.guild_id.to_string(),
+                "channel_id": session.channel_id.to_string(),
+                "starttime": session.starttime,
+                "endtime": session.endtime,
+            })).collect::<Vec<Value>>(),
+            "streams": streams.iter().map(|stream| json!({
+                "game": stream.game,
+                "url": stream.url,
+                "starttime": stream.starttime,
+                "endtime": stream.endtime,
+                "duration": stream.endtime - stream.starttime,
+            })).collect::<Vec<Value>>(),
+            "listening": listening.iter().map(|(artist, listentime)| json!({
+                "artist": artist,
+                "listentime": listentime,
+            })).collect::<Vec<Value>>(),
+            "imported": imported.iter().map(|(game_id, source, playtime)| json!({
+                "game": tables.game_name(game_id),
+                "source": source,
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "linked_accounts": tables.linked_accounts.iter()
+                .filter(|((account_user_id, _), _)| account_user_id == user_id)
+                .map(|((_, platform), account_id)| json!({
+                    "platform": platform,
+                    "account_id": account_id,
+                })).collect::<Vec<Value>>(),
+            "settings": settings,
+            "goals": goals,
+            "watches": watches,
+            "lfg_subscriptions": tables.lfg_subscriptions.iter()
+                .filter(|((_, subscriber_id, _), _)| subscriber_id == user_id)
+                .map(|((guild_id, _, _), subscription)| json!({
+                    "guild_id": guild_id.to_string(),
+                    "game": subscription.game_name,
+                    "min_players": subscription.min_players,
+                })).collect::<Vec<Value>>(),
+            "levels": tables.levels.iter()
+                .filter(|((_, level_user_id), _)| level_user_id == user_id)
+                .map(|((guild_id, _), level)| json!({
+                    "guild_id": guild_id.to_string(),
+                    "level": level,
+                })).collect::<Vec<Value>>(),
+            "badges": badges,
+        }));
+    }
+
+    async fn get_all_entries(&self, guild_id: &i64) -> Result<Vec<(i64, String, i64, i64)>> {
+        let tables = self.tables();
+        let mut entries: Vec<(i64, String, i64, i64)> = Vec::new();
+        for ((user_id, game_id), playtime) in tables.entries.iter()
+            .filter(|((user_id, _), _)| !tables.is_opted_out(user_id) && tables.is_member(Some(*guild_id), user_id)) {
+            let sessions = tables.full_history().iter().filter(|entry| entry.user_id == *user_id && entry.game_id == *game_id).map(|entry| entry.sessions).sum();
+            entries.push((*user_id, tables.game_name(game_id), *playtime, sessions));
+        }
+        entries.sort_by_key(|(user_id, _, playtime, _)| (*user_id, std::cmp::Reverse(*playtime)));
+        return Ok(entries);
+    }
+
+    async fn dump_guild(&self, guild_id: &i64) -> Result<Value> {
+        let tables = self.tables();
+        let config = tables.guild_configs.get(guild_id).cloned().unwrap_or_default();
+        let is_member = |user_id: &i64| tables.is_member(Some(*guild_id), user_id) && !tables.is_opted_out(user_id);
+        let played = |game_id: &i64| tables.entries.keys().chain(tables.sessions.keys())
+            .any(|(user_id, played_game_id)| played_game_id == game_id && is_member(user_id));
+        return Ok(json!({
+            "guild_id": guild_id.to_string(),
+            "config": config.to_json(),
+            "games": tables.games.iter().filter(|(game_id, _)| played(game_id)).map(|(game_id, game)| json!({
+                "game_id": game_id,
+                "name": game.name,
+                "aliases": tables.aliases.iter()
+                    .filter(|(_, alias_game_id)| *alias_game_id == game_id)
+                    .map(|(alias, _)| alias)
+                    .collect::<Vec<&String>>(),
+            })).collect::<Vec<Value>>(),
+            "entries": tables.entries.iter().filter(|((user_id, _), _)| is_member(user_id)).map(|((user_id, game_id), playtime)| json!({
+                "user_id": user_id.to_string(),
+                "game_id": game_id,
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "open_sessions": tables.sessions.iter().filter(|((user_id, _), _)| is_member(user_id)).map(|((user_id, game_id), starttime)| json!({
+                "user_id": user_id.to_string(),
+                "game_id": game_id,
+                "starttime": starttime,
+            })).collect::<Vec<Value>>(),
+            "sessions": tables.history.iter().filter(|entry| is_member(&entry.user_id)).map(|entry| json!({
+                "user_id": entry.user_id.to_string(),
+                "game_id": entry.game_id,
+                "starttime": entry.starttime,
+                "endtime": entry.endtime,
+                "duration": entry.endtime - entry.starttime,
+            })).collect::<Vec<Value>>(),
+        }));
+    }
+
+    async fn has_admin_role(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<bool> {
+        let tables = self.tables();
+        return Ok(role_ids.iter().any(|role_id| tables.admin_roles.contains(&(*guild_id, *role_id))));
+    }
+
+    async fn add_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        let mut tables = self.tables();
+        tables.admin_roles.retain(|(_, admin_role_id)| admin_role_id != role_id);
+        tables.admin_roles.insert((*guild_id, *role_id));
+        Ok(())
+    }
+
+    async fn remove_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        self.tables().admin_roles.remove(&(*guild_id, *role_id));
+        Ok(())
+    }
+
+    async fn get_admin_roles(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(self.tables().admin_roles.iter()
+            .filter(|(admin_guild_id, _)| admin_guild_id == guild_id)
+            .map(|(_, role_id)| *role_id)
+            .collect());
+    }
+
+    async fn claim_admin_roles(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<()> {
+        let mut tables = self.tables();
+        for role_id in role_ids {
+            if tables.admin_roles.remove(&(0, role_id)) {
+                tables.admin_roles.insert((*guild_id, role_id));
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_bot_admin(&self, user_id: &i64) -> Result<bool> {
+        return Ok(self.tables().bot_admins.contains(user_id));
+    }
+
+    async fn add_bot_admin(&self, user_id: &i64) -> Result<()> {
+        self.tables().bot_admins.insert(*user_id);
+        Ok(())
+    }
+
+    async fn remove_bot_admin(&self, user_id: &i64) -> Result<()> {
+        self.tables().bot_admins.remove(user_id);
+        Ok(())
+    }
+
+    async fn get_guild_config(&self, guild_id: &i64) -> Result<Option<GuildConfig>> {
+        return Ok(self.tables().guild_configs.get(guild_id).cloned());
+    }
+
+    async fn save_guild_config(&self, guild_id: &i64, config: &GuildConfig) -> Result<()> {
+        self.tables().guild_configs.insert(*guild_id, config.clone());
+        Ok(())
+    }
+
+    async fn set_digest_subscription(&self, user_id: &i64, subscribed: bool) -> Result<()> {
+        let mut tables = self.tables();
+        if subscribed {
+            tables.digest_subscribers.insert(*user_id);
+        } else {
+            tables.digest_subscribers.remove(user_id);
+        }
+        Ok(())
+    }
+
+    async fn get_digest_subscribers(&self) -> Result<Vec<(i64, Option<String>)>> {
+        let tables = self.tables();
+        return Ok(tables.digest_subscribers.iter()
+            .filter(|user_id| !tables.is_opted_out(user_id))
+            .map(|user_id| (*user_id, tables.user_settings.get(user_id).and_then(|settings| settings.timezone.clone())))
+            .collect());
+    }
+
+    async fn set_goal(&self, user_id: &i64, game_id: &i64, seconds: &i64, is_limit: bool) -> Result<()> {
+        self.tables().goals.insert((*user_id, *game_id), Goal { seconds: *seconds, is_limit, notified_week: None });
+        Ok(())
+    }
+
+    async fn remove_goal(&self, user_id: &i64, game_id: &i64) -> Result<bool> {
+        return Ok(self.tables().goals.remove(&(*user_id, *game_id)).is_some());
+    }
+
+    async fn get_user_goals(&self, user_id: &i64) -> Result<Vec<(String, i64, bool)>> {
+        let tables = self.tables();
+        let mut goals: Vec<(String, i64, bool)> = tables.goals.iter()
+            .filter(|((goal_user_id, _), _)| goal_user_id == user_id)
+            .map(|((_, game_id), goal)| (tables.game_name(game_id), goal.seconds, goal.is_limit))
+            .collect();
+        goals.sort();
+        return Ok(goals);
+    }
+
+    async fn get_goal_users(&self) -> Result<Vec<(i64, Option<String>)>> {
+        let tables = self.tables();
+        let user_ids: BTreeSet<i64> = tables.goals.keys().map(|(user_id, _)| *user_id).collect();
+        return Ok(user_ids.into_iter()
+            .filter(|user_id| !tables.is_opted_out(user_id))
+            .map(|user_id| (user_id, tables.user_settings.get(&user_id).and_then(|settings| settings.timezone.clone())))
+            .collect());
+    }
+
+    async fn get_reached_goals(&self, user_id: &i64, week_start: &i64, currenttime: &i64) -> Result<Vec<(i64, String, i64, bool)>> {
+        let tables = self.tables();
+        let mut reached: Vec<(i64, String, i64, bool)> = Vec::new();
+        for ((_, game_id), goal) in tables.goals.iter().filter(|((goal_user_id, _), goal)| goal_user_id == user_id && goal.notified_week != Some(*week_start)) {
+            let played: i64 = tables.history.iter()
+                .filter(|entry| entry.user_id == *user_id && entry.game_id == *game_id && entry.endtime > *week_start)
+                .map(|entry| entry.endtime - std::cmp::max(entry.starttime, *week_start))
+                .sum::<i64>()
+                + tables.sessions.get(&(*user_id, *game_id)).map_or(0, |starttime| currenttime - std::cmp::max(*starttime, *week_start));
+            if played >= goal.seconds {
+                reached.push((*game_id, tables.game_name(game_id), goal.seconds, goal.is_limit));
+            }
+        }
+        return Ok(reached);
+    }
+
+    async fn set_goal_notified(&self, user_id: &i64, game_id: &i64, week_start: &i64) -> Result<()> {
+        if let Some(goal) = self.tables().goals.get_mut(&(*user_id, *game_id)) {
+            goal.notified_week = Some(*week_start);
+        }
+        Ok(())
+    }
+
+    async fn add_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let mut tables = self.tables();
+        if tables.watches.iter().any(|watch| is_watch(watch, watcher_id, user_id, game_name)) {
+            return Ok(false);
+        }
+        tables.watches.push((*watcher_id, *user_id, game_name.map(str::to_string)));
+        return Ok(true);
+    }
+
+    async fn remove_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let mut tables = self.tables();
+        let count = tables.watches.len();
+        tables.watches.retain(|watch| !is_watch(watch, watcher_id, user_id, game_name));
+        return Ok(tables.watches.len() < count);
+    }
+
+    async fn get_watches(&self, watcher_id: &i64) -> Result<Vec<(i64, Option<String>)>> {
+        let mut watches: Vec<(i64, Option<String>)> = self.tables().watches.iter()
+            .filter(|(watch_watcher_id, _, _)| watch_watcher_id == watcher_id)
+            .map(|(_, user_id, game_name)| (*user_id, game_name.clone()))
+            .collect();
+        watches.sort();
+        return Ok(watches);
+    }
+
+    async fn get_watchers(&self, user_id: &i64, game_name: &str) -> Result<Vec<i64>> {
+        let watchers: BTreeSet<i64> = self.tables().watches.iter()
+            .filter(|(_, watch_user_id, watch_game)| watch_user_id == user_id
+                && watch_game.as_ref().map_or(true, |watch_game| watch_game.to_lowercase() == game_name.to_lowercase()))
+            .map(|(watcher_id, _, _)| *watcher_id)
+            .collect();
+        return Ok(watchers.into_iter().collect());
+    }
+
+    async fn set_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str, min_players: &i64) -> Result<()> {
+        self.tables().lfg_subscriptions.insert((*guild_id, *user_id, game_name.to_lowercase()), LfgSubscription {
+            game_name: game_name.to_string(),
+            min_players: *min_players,
+            notified: false,
+        });
+        Ok(())
+    }
+
+    async fn remove_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<bool> {
+        return Ok(self.tables().lfg_subscriptions.remove(&(*guild_id, *user_id, game_name.to_lowercase())).is_some());
+    }
+
+    async fn get_lfg_subscriptions(&self, guild_id: &i64, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        let mut subscriptions: Vec<(String, i64)> = self.tables().lfg_subscriptions.iter()
+            .filter(|((subscription_guild_id, subscription_user_id, _), _)| subscription_guild_id == guild_id && subscription_user_id == user_id)
+            .map(|(_, subscription)| (subscription.game_name.clone(), subscription.min_players))
+            .collect();
+        subscriptions.sort();
+        return Ok(subscriptions);
+    }
+
+    async fn get_lfg_subscribers(&self, guild_id: &i64, game_name: &str) -> Result<Vec<(i64, i64, bool)>> {
+        let game_name = game_name.to_lowercase();
+        return Ok(self.tables().lfg_subscriptions.iter()
+            .filter(|((subscription_guild_id, _, subscription_game), _)| subscription_guild_id == guild_id && *subscription_game == game_name)
+            .map(|((_, user_id, _), subscription)| (*user_id, subscription.min_players, subscription.notified))
+            .collect());
+    }
+
+    async fn set_lfg_notified(&self, guild_id: &i64, user_id: &i64, game_name: &str, notified: bool) -> Result<()> {
+        if let Some(subscription) = self.tables().lfg_subscriptions.get_mut(&(*guild_id, *user_id, game_name.to_lowercase())) {
+            subscription.notified = notified;
+        }
+        Ok(())
+    }
+
+    async fn set_game_role(&self, guild_id: &i64, game_name: &str, role_id: &i64, channel_id: &i64) -> Result<()> {
+        let mut tables = self.tables();
+        let game_role = tables.game_roles.entry((*guild_id, game_name.to_lowercase())).or_insert(GameRole {
+            game_name: game_name.to_string(),
+            role_id: *role_id,
+            channel_id: *channel_id,
+            last_ping: None,
+        });
+        game_role.role_id = *role_id;
+        game_role.channel_id = *channel_id;
+        Ok(())
+    }
+
+    async fn remove_game_role(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        return Ok(self.tables().game_roles.remove(&(*guild_id, game_name.to_lowercase())).is_some());
+    }
+
+    async fn get_game_roles(&self, guild_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        let mut game_roles: Vec<(String, i64, i64)> = self.tables().game_roles.iter()
+            .filter(|((role_guild_id, _), _)| role_guild_id == guild_id)
+            .map(|(_, game_role)| (game_role.game_name.clone(), game_role.role_id, game_role.channel_id))
+            .collect();
+        game_roles.sort();
+        return Ok(game_roles);
+    }
+
+    async fn get_game_role(&self, guild_id: &i64, game_name: &str) -> Result<Option<(i64, i64, Option<i64>)>> {
+        return Ok(self.tables().game_roles.get(&(*guild_id, game_name.to_lowercase()))
+            .map(|game_role| (game_role.role_id, game_role.channel_id, game_role.last_ping)));
+    }
+
+    async fn set_game_role_pinged(&self, guild_id: &i64, game_name: &str, pingtime: &i64) -> Result<()> {
+        if let Some(game_role) = self.tables().game_roles.get_mut(&(*guild_id, game_name.to_lowercase())) {
+            game_role.last_ping = Some(*pingtime);
+        }
+        Ok(())
+    }
+
+    async fn add_role_reward(&self, guild_id: &i64, role_id: &i64, seconds: &i64) -> Result<()> {
+        self.tables().role_rewards.insert((*guild_id, *role_id), *seconds);
+        Ok(())
+    }
+
+    async fn remove_role_reward(&self, guild_id: &i64, role_id: &i64) -> Result<bool> {
+        return Ok(self.tables().role_rewards.remove(&(*guild_id, *role_id)).is_some());
+    }
+
+    async fn get_role_rewards(&self, guild_id: &i64) -> Result<Vec<(i64, i64)>> {
+        let mut rewards: Vec<(i64, i64)> = self.tables().role_rewards.iter()
+            .filter(|((reward_guild_id, _), _)| reward_guild_id == guild_id)
+            .map(|((_, role_id), seconds)| (*role_id, *seconds))
+            .collect();
+        rewards.sort_by_key(|(_, seconds)| *seconds);
+        return Ok(rewards);
+    }
+
+    async fn get_level(&self, guild_id: &i64, user_id: &i64) -> Result<i64> {
+        return Ok(self.tables().levels.get(&(*guild_id, *user_id)).copied().unwrap_or(0));
+    }
+
+    async fn set_level(&self, guild_id: &i64, user_id: &i64, level: &i64) -> Result<()> {
+        self.tables().levels.insert((*guild_id, *user_id), *level);
+        Ok(())
+    }
+
+    async fn get_achievement_stats(&self, user_id: &i64) -> Result<AchievementStats> {
+        let tables = self.tables();
+        let sessions: Vec<Played> = tables.full_history().into_iter().filter(|entry| entry.user_id == *user_id).collect();
+        let playtimes: Vec<i64> = tables.entries.iter()
+            .filter(|((entry_user_id, _), _)| entry_user_id == user_id)
+            .map(|(_, playtime)| *playtime)
+            .collect();
+        return Ok(AchievementStats {
+            night_sessions: sessions.iter().map(|entry| entry.night_sessions).sum(),
+            longest_session: sessions.iter().map(|entry| entry.longest).max().unwrap_or(0),
+            games: i64::try_from(playtimes.len())?,
+            playtime: playtimes.iter().sum(),
+        });
+    }
+
+    async fn get_unlocked_badges(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(self.tables().unlocked_badges.iter()
+            .filter(|((badge_user_id, _), _)| badge_user_id == user_id)
+            .map(|((_, badge), unlocked_at)| (badge.clone(), *unlocked_at))
+            .collect());
+    }
+
+    async fn unlock_badge(&self, user_id: &i64, badge: &str, unlocked_at: &i64) -> Result<()> {
+        self.tables().unlocked_badges.entry((*user_id, badge.to_string())).or_insert(*unlocked_at);
+        Ok(())
+    }
+
+    async fn start_voice_session(&self, user_id: &i64, guild_id: &i64, channel_id: &i64, starttime: &i64) -> Result<()> {
+        let mut tables = self.tables();
+        if !tables.voice_sessions.iter().any(|session| session.user_id == *user_id && session.endtime.is_none()) {
+            tables.voice_sessions.push(VoiceSession { user_id: *user_id, guild_id: *guild_id, channel_id: *channel_id, starttime: *starttime, endtime: None });
+        }
+        Ok(())
+    }
+
+    async fn end_voice_session(&self, user_id: &i64, endtime: &i64) -> Result<()> {
+        for session in self.tables().voice_sessions.iter_mut().filter(|session| session.user_id == *user_id && session.endtime.is_none()) {
+            session.endtime = Some(*endtime);
+        }
+        Ok(())
+    }
+
+    async fn end_all_voice_sessions(&self, endtime: &i64) -> Result<()> {
+        for session in self.tables().voice_sessions.iter_mut().filter(|session| session.endtime.is_none()) {
+            session.endtime = Some(*endtime);
+        }
+        Ok(())
+    }
+
+    async fn get_voice_users(&self) -> Result<Vec<i64>> {
+        return Ok(self.tables().voice_sessions.iter()
+            .filter(|session| session.endtime.is_none())
+            .map(|session| session.user_id)
+            .collect());
+    }
+
+    async fn get_voice_time(&self, user_id: &i64, currenttime: &i64) -> Result<(i64, i64)> {
+        let tables = self.tables();
+        let mut voice_time: i64 = 0;
+        let mut playing_time: i64 = 0;
+        for session in tables.voice_sessions.iter().filter(|session| session.user_id == *user_id) {
+            let endtime = session.endtime.unwrap_or(*currenttime);
+            let duration = endtime - session.starttime;
+            let played: i64 = tables.history.iter()
+                .filter(|entry| entry.user_id == *user_id && entry.endtime > session.starttime && entry.starttime < endtime)
+                .map(|entry| std::cmp::min(endtime, entry.endtime) - std::cmp::max(session.starttime, entry.starttime))
+                .sum::<i64>()
+                + tables.sessions.iter()
+                    .filter(|((session_user_id, _), starttime)| session_user_id == user_id && **starttime < endtime)
+                    .map(|(_, starttime)| endtime - std::cmp::max(session.starttime, *starttime))
+                    .sum::<i64>();
+            voice_time += duration;
+            playing_time += std::cmp::min(duration, played);
+        }
+        return Ok((voice_time, playing_time));
+    }
+
+    async fn add_webhook(&self, guild_id: &i64, url: &str) -> Result<()> {
+        let mut tables = self.tables();
+        if !tables.webhooks.values().any(|(webhook_guild_id, webhook_url)| webhook_guild_id == guild_id && webhook_url == url) {
+            let webhook_id = tables.next_id();
+            tables.webhooks.insert(webhook_id, (*guild_id, url.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn remove_webhook(&self, guild_id: &i64, webhook_id: &i64) -> Result<bool> {
+        let mut tables = self.tables();
+        if tables.webhooks.get(webhook_id).map_or(true, |(webhook_guild_id, _)| webhook_guild_id != guild_id) {
+            return Ok(false);
+        }
+        return Ok(tables.webhooks.remove(webhook_id).is_some());
+    }
+
+    async fn get_webhooks(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(self.tables().webhooks.iter()
+            .filter(|(_, (webhook_guild_id, _))| webhook_guild_id == guild_id)
+            .map(|(webhook_id, (_, url))| (*webhook_id, url.clone()))
+            .collect());
+    }
+
+    async fn get_report_channels(&self, cadence: &str) -> Result<Vec<(i64, i64)>> {
+        return Ok(self.tables().guild_configs.iter()
+            .filter(|(_, config)| config.report_cadence == cadence)
+            .filter_map(|(guild_id, config)| config.report_channel.map(|channel_id| (*guild_id, channel_id)))
+            .collect());
+    }
+
+    async fn add_guild_members(&self, guild_id: &i64, user_ids: &[i64], seen: &i64) -> Result<()> {
+        let mut tables = self.tables();
+        for user_id in user_ids {
+            tables.guild_members.insert((*guild_id, *user_id), *seen);
+        }
+        Ok(())
+    }
+
+    async fn remove_guild_member(&self, guild_id: &i64, user_id: &i64) -> Result<()> {
+        self.tables().guild_members.remove(&(*guild_id, *user_id));
+        Ok(())
+    }
+
+    async fn prune_guild_members(&self, guild_id: &i64, before: &i64) -> Result<()> {
+        self.tables().guild_members.retain(|(member_guild_id, _), seen| member_guild_id != guild_id || *seen >= *before);
+        Ok(())
+    }
+
+    async fn get_guild_members(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(self.tables().guild_members.keys()
+            .filter(|(member_guild_id, _)| member_guild_id == guild_id)
+            .map(|(_, user_id)| *user_id)
+            .collect());
+    }
+
+    async fn get_user_guilds(&self, user_id: &i64) -> Result<Vec<i64>> {
+        return Ok(self.tables().guild_members.keys()
+            .filter(|(_, member_user_id)| member_user_id == user_id)
+            .map(|(guild_id, _)| *guild_id)
+            .collect());
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn count_open_sessions(&self) -> Result<i64> {
+        return Ok(i64::try_from(self.tables().sessions.len())?);
+    }
+
+    async fn prune_history(&self, before: &i64) -> Result<u64> {
+        let mut tables = self.tables();
+        let (pruned, kept): (Vec<HistoryEntry>, Vec<HistoryEntry>) = std::mem::take(&mut tables.history).into_iter().partition(|entry| entry.endtime < *before);
+        tables.history = kept;
+        for entry in &pruned {
+            let duration = entry.endtime - entry.starttime;
+            tables.add_rollup((entry.user_id, entry.game_id, entry.starttime - entry.starttime.rem_euclid(24 * 60 * 60)), Rollup {
+                playtime: duration,
+                sessions: 1,
+                longest: duration,
+                night_sessions: i64::from(entry.starttime.rem_euclid(24 * 60 * 60) < 5 * 60 * 60),
+            });
+        }
+        return Ok(u64::try_from(pruned.len())?);
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_session_users(&self) -> Result<Vec<i64>> {
+        let tables = self.tables();
+        let user_ids: BTreeSet<i64> = tables.sessions.keys().map(|(user_id, _)| *user_id)
+            .chain(tables.streams.keys().copied())
+            .chain(tables.listens.keys().copied())
+            .collect();
+        return Ok(user_ids.into_iter().collect());
+    }
+
+    async fn update_status(&self, user_id: &i64, away: bool, currenttime: &i64) -> Result<Option<(bool, i64)>> {
+        let mut tables = self.tables();
+        let previous = tables.statuses.get(user_id).copied();
+        if previous.map(|(was_away, _)| was_away) != Some(away) {
+            tables.statuses.insert(*user_id, (away, *currenttime));
+        }
+        return Ok(previous);
+    }
+
+    async fn resetall(&self) -> Result<()> {
+        let mut tables = self.tables();
+        let archived = tables.archive(None);
+        tables.start_reset(None, archived)
+    }
+
+    async fn reset(&self, user_id: &i64) -> Result<()> {
+        let mut tables = self.tables();
+        let archived = tables.archive(Some(*user_id));
+        tables.start_reset(Some(*user_id), archived)
+    }
+
+    async fn undo_reset(&self) -> Result<Option<Option<i64>>> {
+        let currenttime = currenttime()?;
+        let mut tables = self.tables();
+        return Ok(match tables.resets.pop() {
+            Some(reset) if reset.resettime >= currenttime - RESET_UNDO_WINDOW => {
+                tables.restore(reset.archived);
+                Some(reset.user_id)
+            },
+            _ => None,
+        });
+    }
+
+    async fn hardreset(&self) -> Result<()> {
+        let mut tables = self.tables();
+        tables.archive(None);
+        tables.resets.clear();
+        Ok(())
+    }
+}
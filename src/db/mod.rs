@@ -0,0 +1,608 @@
+mod postgres;
+#[cfg(feature = "mysql")]
+mod mysql;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(test)]
+mod memory;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::Value;
+use std::sync::Arc;
+
+pub use postgres::PgStorage;
+#[cfg(feature = "mysql")]
+pub use mysql::MySqlStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+#[cfg(test)]
+pub use memory::MemoryStorage;
+
+
+pub struct GameEntry {
+    pub name: String,
+    pub playtime: i64,
+}
+
+pub struct GameSummary {
+    pub name: String,
+    pub playtime: i64,
+    pub sessions: i64,
+    // Of the whole sessions, None when only imported playtime is known
+    pub average_session: Option<i64>,
+}
+
+pub struct Session {
+    pub game_id: i64,
+    pub name: String,
+    pub starttime: i64,
+    // The last rich presence seen during the session
+    pub state: Option<String>,
+    pub details: Option<String>,
+    pub party_size: Option<i64>,
+}
+
+// What the game tells about the play, the state and details usually hold the mode or the map
+#[derive(Clone, Default)]
+pub struct RichPresence {
+    pub state: Option<String>,
+    pub details: Option<String>,
+    pub party_size: Option<i64>,
+}
+
+pub struct GameStats {
+    pub sessions: i64,
+    pub playtime: i64,
+    pub first_played: Option<i64>,
+    pub last_played: Option<i64>,
+}
+
+pub struct UserTotals {
+    pub playtime: i64,
+    pub games: i64,
+    pub rank: i64,
+    pub ranked_users: i64,
+}
+
+#[derive(Clone)]
+pub struct GameMetadata {
+    pub igdb_id: i64,
+    // The canonical title, presence names are often shortened or localized
+    pub title: String,
+    pub cover_url: Option<String>,
+    pub genres: Vec<String>,
+    pub summary: Option<String>,
+    pub release_date: Option<i64>,
+}
+
+pub struct AchievementStats {
+    // Sessions started between midnight and 5am UTC
+    pub night_sessions: i64,
+    pub longest_session: i64,
+    pub games: i64,
+    pub playtime: i64,
+}
+
+#[derive(Clone)]
+pub struct GuildConfig {
+    pub report_channel: Option<i64>,
+    // daily, weekly or monthly
+    pub report_cadence: String,
+    // Overrides the bot wide minimum session length
+    pub min_session_length: Option<i64>,
+    pub locale: String,
+    pub embed_color: i64,
+    // Only the games in tracked_games are tracked
+    pub whitelist_only: bool,
+    pub xp_per_hour: i64,
+    // Reaching level n takes level_base_xp * n² XP
+    pub level_base_xp: i64,
+    // Level ups are announced there
+    pub level_channel: Option<i64>,
+    // Listening activities are tracked into listen_entries
+    pub track_listening: bool,
+    // Embed titles are rendered through it, {title} is the original title
+    pub title_template: String,
+    pub show_thumbnails: bool,
+    // Rankings are posted without the names, each member is told their own placement
+    pub anonymous_leaderboards: bool,
+    // The sessions logged with /log count on the leaderboards
+    pub rank_manual_sessions: bool,
+    // How the play of Idle and DND users counts: count, pause after idle_minutes, or exclude
+    pub idle_policy: String,
+    pub idle_minutes: i64,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        // Teal
+        return GuildConfig {
+            report_channel: None,
+            report_cadence: "weekly".to_string(),
+            min_session_length: None,
+            locale: "en".to_string(),
+            embed_color: 0x1ABC9C,
+            whitelist_only: false,
+            xp_per_hour: 100,
+            level_base_xp: 100,
+            level_channel: None,
+            track_listening: false,
+            title_template: "{title}".to_string(),
+            show_thumbnails: true,
+            anonymous_leaderboards: false,
+            rank_manual_sessions: false,
+            idle_policy: "count".to_string(),
+            idle_minutes: 10,
+        };
+    }
+}
+
+impl GuildConfig {
+    // The whole configuration as it appears in /exportjson, the ids are strings since they don't fit in a JavaScript number
+    pub fn to_json(&self) -> Value {
+        return serde_json::json!({
+            "report_channel": self.report_channel.map(|channel_id| channel_id.to_string()),
+            "report_cadence": self.report_cadence,
+            "min_session_length": self.min_session_length,
+            "locale": self.locale,
+            "embed_color": self.embed_color,
+            "whitelist_only": self.whitelist_only,
+            "xp_per_hour": self.xp_per_hour,
+            "level_base_xp": self.level_base_xp,
+            "level_channel": self.level_channel.map(|channel_id| channel_id.to_string()),
+            "track_listening": self.track_listening,
+            "title_template": self.title_template,
+            "show_thumbnails": self.show_thumbnails,
+            "anonymous_leaderboards": self.anonymous_leaderboards,
+            "rank_manual_sessions": self.rank_manual_sessions,
+            "idle_policy": self.idle_policy,
+            "idle_minutes": self.idle_minutes,
+        });
+    }
+}
+
+// Seconds a reset can be undone for
+const RESET_UNDO_WINDOW: i64 = 24 * 60 * 60;
+
+// What the commands and handlers are given, a PgStorage, a SqliteStorage or a MySqlStorage in the bot and a MemoryStorage in the tests
+pub type Database = Arc<dyn Storage>;
+
+// Everything the bot stores
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Saves the user's sessions of every game that isn't in `playing` as ending at `endtime`,
+    // the bot wide minimum length is used when `min_session_length` isn't set
+    // Returns (game name, playtime) of the sessions that added playtime
+    async fn save_session(&self, user_id: &i64, playing: &[i64], min_session_length: Option<i64>, endtime: &i64) -> Result<Vec<(String, i64)>>;
+
+    // Counts only the playtime after `start` when it is set
+    async fn get_top_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameEntry>>;
+
+    // Same as get_top_games, with the number of sessions and their average length
+    async fn get_summary_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameSummary>>;
+
+    async fn count_games(&self, user_id: &i64, start: Option<i64>) -> Result<i64>;
+
+    async fn get_game_playtime(&self, user_id: &i64, game_id: &i64) -> Result<i64>;
+
+    // Adds `seconds` to the user's playtime on the game, or removes them when negative, without going under 0
+    // Returns the corrected playtime, the history is left untouched
+    async fn adjust_playtime(&self, user_id: &i64, game_id: &i64, seconds: &i64) -> Result<i64>;
+
+    // Records a session played away from Discord, its playtime is imported under the manual source
+    // so the leaderboards can leave it out. Returns the name of the game
+    async fn log_session(&self, user_id: &i64, game_name: &str, starttime: &i64, duration: &i64) -> Result<String>;
+
+    // Adds the (user id, game name, playtime) rows to the tracked playtime, all of them or none
+    // The history is left untouched, like after /fix-session
+    async fn import_playtime(&self, rows: &[(i64, String, i64)]) -> Result<()>;
+
+    async fn get_game_stats(&self, user_id: &i64, game_id: &i64) -> Result<GameStats>;
+
+    // Returns (details, playtime) pairs of the user's sessions of the game, most played first
+    // The state stands in for the details the game doesn't set
+    async fn get_top_details(&self, user_id: &i64, game_id: &i64, limit: i64) -> Result<Vec<(String, i64)>>;
+
+    async fn get_total_playtime(&self, user_id: &i64) -> Result<i64>;
+
+    // The user's running sessions, oldest first
+    async fn get_open_sessions(&self, user_id: &i64) -> Result<Vec<Session>>;
+
+    // Returns (game name, user ids) pairs of the games being played, most played first
+    async fn get_open_sessions_by_game(&self) -> Result<Vec<(String, Vec<i64>)>>;
+
+    // Returns (game name, start time, duration) of the user's last sessions, most recent first
+    async fn get_recent_sessions(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64, i64)>>;
+
+    // Returns (game name, start time, duration) of the user's longest session started between `start` and `end`
+    async fn get_longest_session(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<(String, i64, i64)>>;
+
+    // The durations of the user's sessions, of a single game when `game_id` is set, shortest first
+    async fn get_session_durations(&self, user_id: &i64, game_id: Option<i64>) -> Result<Vec<i64>>;
+
+    // Returns (user id, playtime) pairs of the guild's members who played the most between `start` and `end`
+    async fn get_period_leaderboard(&self, guild_id: &i64, start: &i64, end: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>>;
+
+    // The games the guild's members played the most between `start` and `end`
+    async fn get_period_top_games(&self, guild_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>>;
+
+    // The 10 games with the most playtime since `start`, or the most players, as (name, playtime, players)
+    // Only the guild's members count when `guild_id` is set
+    async fn get_server_top_games(&self, guild_id: Option<i64>, start: Option<i64>, by_players: bool) -> Result<Vec<(String, i64, i64)>>;
+
+    async fn get_user_period_playtime(&self, user_id: &i64, start: &i64, end: &i64) -> Result<i64>;
+
+    // The playtime of every game the user played between `start` and `end`, most played first
+    async fn get_user_period_games(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>>;
+
+    // The game the user played the most between `start` and `end`
+    async fn get_user_period_top_game(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<GameEntry>>;
+
+    // Returns (start time, end time) of the user's sessions that ended after `start`
+    async fn get_sessions_since(&self, user_id: &i64, start: &i64) -> Result<Vec<(i64, i64)>>;
+
+    // Ranks the user by total playtime among the users that didn't opt out, None when nothing was tracked
+    // With `guild_id`, the user is only ranked among the guild's members
+    async fn get_user_totals(&self, user_id: &i64, guild_id: Option<i64>) -> Result<Option<UserTotals>>;
+
+    // The user's (rank, ranked users) by playtime since `start`, among the guild's members when `guild_id` is set
+    // None when they didn't play
+    async fn get_user_rank(&self, user_id: &i64, guild_id: Option<i64>, start: Option<i64>) -> Result<Option<(i64, i64)>>;
+
+    // Returns (game name, first user's playtime, second user's playtime) for the games both users played
+    async fn get_shared_games(&self, user1_id: &i64, user2_id: &i64) -> Result<Vec<(String, i64, i64)>>;
+
+    // The manual sessions only count with `include_manual`, only the guild's members are ranked when `guild_id` is set
+    async fn get_leaderboard(&self, guild_id: Option<i64>, include_manual: bool) -> Result<Vec<(i64, i64)>>;
+
+    async fn get_game_leaderboard(&self, guild_id: Option<i64>, game_id: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>>;
+
+    async fn find_game(&self, game_name: &str) -> Result<Option<(i64, String)>>;
+
+    async fn search_games(&self, game_name: &str) -> Result<Vec<String>>;
+
+    // Returns the id of the game and whether a session was started, an already running session of it is kept as is
+    // apart from its rich presence
+    async fn register_session(&self, user_id: &i64, game_name: &str, starttime: &i64, rich_presence: &RichPresence) -> Result<(i64, bool)>;
+
+    async fn register_stream(&self, user_id: &i64, game_name: &str, url: Option<&str>) -> Result<()>;
+
+    // Saves the user's stream unless it is still of `streaming`, with the same length limits as game sessions
+    async fn save_stream(&self, user_id: &i64, streaming: Option<&str>, min_session_length: Option<i64>) -> Result<()>;
+
+    // Counts only the streams after `start` when it is set
+    async fn get_stream_time(&self, user_id: &i64, start: Option<i64>) -> Result<i64>;
+
+    // Returns (game name, stream time) pairs of the games the user streamed the most
+    async fn get_top_streamed_games(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>>;
+
+    // Returns (game name, url, start time, duration) of the user's last streams, most recent first
+    async fn get_recent_streams(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, Option<String>, i64, i64)>>;
+
+    async fn register_listen(&self, user_id: &i64, artist: &str) -> Result<()>;
+
+    // Saves the user's listening session unless it is still of `listening`
+    // Songs are shorter than most minimum session lengths, so only the maximum applies
+    async fn save_listen(&self, user_id: &i64, listening: Option<&str>) -> Result<()>;
+
+    // Returns (artist, listening time) pairs of the artists the user listened to the most
+    async fn get_top_artists(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>>;
+
+    async fn get_listen_time(&self, user_id: &i64) -> Result<i64>;
+
+    // Returns (game id, name) of the games never enriched or last enriched before `before`, new games first
+    async fn get_games_to_enrich(&self, before: &i64, limit: i64) -> Result<Vec<(i64, String)>>;
+
+    // A game without metadata is still marked as updated so it isn't looked up again until the next refresh
+    async fn set_game_metadata(&self, game_id: &i64, metadata: Option<&GameMetadata>, updated: &i64) -> Result<()>;
+
+    async fn get_game_metadata(&self, game_id: &i64) -> Result<Option<GameMetadata>>;
+
+    // Returns (genre, playtime) pairs, the playtime of a game is split evenly between its genres
+    // Games without metadata are counted under a None genre
+    async fn get_genre_playtime(&self, user_id: &i64) -> Result<Vec<(Option<String>, i64)>>;
+
+    async fn get_cover_url(&self, game_name: &str) -> Result<Option<String>>;
+
+    // Returns (players, total playtime) of a game, without the users that opted out
+    async fn get_game_totals(&self, game_id: &i64) -> Result<(i64, i64)>;
+
+    async fn link_account(&self, user_id: &i64, platform: &str, account_id: &str) -> Result<()>;
+
+    // Also deletes the playtime imported from the platform
+    async fn unlink_account(&self, user_id: &i64, platform: &str) -> Result<bool>;
+
+    // Returns (user id, account id) pairs of the accounts linked on a platform
+    async fn get_linked_accounts(&self, platform: &str) -> Result<Vec<(i64, String)>>;
+
+    // Replaces everything imported from `source` for the user with `entries`, (game name, playtime) pairs
+    async fn replace_imported_entries(&self, user_id: &i64, source: &str, entries: &[(String, i64)]) -> Result<()>;
+
+    // Returns (source, playtime) pairs of the user's imported playtime
+    async fn get_imported_playtime(&self, user_id: &i64) -> Result<Vec<(String, i64)>>;
+
+    // Makes `alias` count as `game_id`, merging the game it used to count as along with its playtime
+    // Returns the id of the merged game, if there was one
+    async fn merge_alias(&self, alias: &str, game_id: &i64) -> Result<Option<i64>>;
+
+    // Moves everything tracked under `old_game_id` to `game_id`, summing the playtimes, and deletes `old_game_id`
+    async fn merge_games(&self, old_game_id: &i64, game_id: &i64) -> Result<()>;
+
+    // Deletes a game along with everything tracked under it, returns its name
+    async fn delete_game(&self, game_id: &i64) -> Result<String>;
+
+    // Changes the canonical name of a game, its old name stays an alias of it
+    // Returns false when the new name already belongs to another game
+    async fn rename_game(&self, game_id: &i64, game_name: &str) -> Result<bool>;
+
+    async fn is_ignored(&self, guild_id: &i64, game_name: &str) -> Result<bool>;
+
+    async fn add_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<()>;
+
+    // Returns whether the game was ignored
+    async fn remove_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<bool>;
+
+    // Returns (guild id, name) pairs of the games ignored in the guild and globally
+    async fn get_ignored_games(&self, guild_id: &i64) -> Result<Vec<(i64, String)>>;
+
+    // Names that are aliases of the same game as a tracked name are tracked too
+    async fn is_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool>;
+
+    async fn add_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<()>;
+
+    // Returns whether the game was tracked
+    async fn remove_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool>;
+
+    async fn get_tracked_games(&self, guild_id: &i64) -> Result<Vec<String>>;
+
+    async fn is_opted_out(&self, user_id: &i64) -> Result<bool>;
+
+    async fn set_opted_out(&self, user_id: &i64, opted_out: bool) -> Result<()>;
+
+    async fn get_user_locale(&self, user_id: &i64) -> Result<Option<String>>;
+
+    async fn set_user_locale(&self, user_id: &i64, locale: Option<&str>) -> Result<()>;
+
+    async fn get_user_timezone(&self, user_id: &i64) -> Result<Option<String>>;
+
+    async fn set_user_timezone(&self, user_id: &i64, timezone: Option<&str>) -> Result<()>;
+
+    // Whether the user's stats are only shown to them by default
+    async fn get_private_stats(&self, user_id: &i64) -> Result<bool>;
+
+    async fn set_private_stats(&self, user_id: &i64, private: bool) -> Result<()>;
+
+    // Who can see the user's stats: public, guild-only or private
+    async fn get_privacy_level(&self, user_id: &i64) -> Result<String>;
+
+    async fn set_privacy_level(&self, user_id: &i64, level: &str) -> Result<()>;
+
+    // The time the tracking of the user resumes at, None when it was never paused
+    async fn get_paused_until(&self, user_id: &i64) -> Result<Option<i64>>;
+
+    async fn set_paused_until(&self, user_id: &i64, until: Option<i64>) -> Result<()>;
+
+    // The (start, end) times of the user's vacation, None when none is planned
+    async fn get_vacation(&self, user_id: &i64) -> Result<Option<(i64, i64)>>;
+
+    async fn set_vacation(&self, user_id: &i64, vacation: Option<(i64, i64)>) -> Result<()>;
+
+    async fn add_audit_entry(&self, user_id: &i64, guild_id: Option<i64>, action: &str, details: &str, actiontime: &i64) -> Result<()>;
+
+    // Returns (user_id, action, details, actiontime) of the entries, latest first
+    async fn get_audit_log(&self, limit: i64, offset: i64) -> Result<Vec<(i64, String, String, i64)>>;
+
+    async fn count_audit_entries(&self) -> Result<i64>;
+
+    // Every row of every table along with the version of the schema they follow, for /backup
+    async fn dump(&self) -> Result<Value>;
+
+    // Replaces every row with the ones of a dump, which must follow the current schema
+    async fn restore(&self, tables: &serde_json::Map<String, Value>) -> Result<()>;
+
+    // The version of the last applied migration
+    async fn get_schema_version(&self) -> Result<Option<i64>>;
+
+    // Gathers everything stored about a user, for the /export command
+    async fn export_user(&self, user_id: &i64) -> Result<Value>;
+
+    // Returns (user id, game name, playtime, session count) for every entry of the guild's members that didn't opt out
+    async fn get_all_entries(&self, guild_id: &i64) -> Result<Vec<(i64, String, i64, i64)>>;
+
+    // Snapshot of the data tracked for the guild's members and of the guild's configuration, for backups and external tooling
+    async fn dump_guild(&self, guild_id: &i64) -> Result<Value>;
+
+    async fn has_admin_role(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<bool>;
+
+    async fn add_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()>;
+
+    async fn remove_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()>;
+
+    async fn get_admin_roles(&self, guild_id: &i64) -> Result<Vec<i64>>;
+
+    // Attributes the admin roles added before they were stored per guild to the guild that has them
+    async fn claim_admin_roles(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<()>;
+
+    async fn is_bot_admin(&self, user_id: &i64) -> Result<bool>;
+
+    async fn add_bot_admin(&self, user_id: &i64) -> Result<()>;
+
+    async fn remove_bot_admin(&self, user_id: &i64) -> Result<()>;
+
+    async fn get_guild_config(&self, guild_id: &i64) -> Result<Option<GuildConfig>>;
+
+    async fn save_guild_config(&self, guild_id: &i64, config: &GuildConfig) -> Result<()>;
+
+    async fn set_digest_subscription(&self, user_id: &i64, subscribed: bool) -> Result<()>;
+
+    // Users who opted out of tracking don't get digests, returned with their timezone
+    async fn get_digest_subscribers(&self) -> Result<Vec<(i64, Option<String>)>>;
+
+    async fn set_goal(&self, user_id: &i64, game_id: &i64, seconds: &i64, is_limit: bool) -> Result<()>;
+
+    // Returns whether the user had a goal on the game
+    async fn remove_goal(&self, user_id: &i64, game_id: &i64) -> Result<bool>;
+
+    // Returns (game name, seconds, is limit) for each of the user's goals
+    async fn get_user_goals(&self, user_id: &i64) -> Result<Vec<(String, i64, bool)>>;
+
+    // Users with goals, with their timezone
+    async fn get_goal_users(&self) -> Result<Vec<(i64, Option<String>)>>;
+
+    // Returns the goals reached since `week_start` that weren't notified yet, as (game id, game name, seconds, is limit)
+    // The running session counts too so limits are noticed while playing
+    async fn get_reached_goals(&self, user_id: &i64, week_start: &i64, currenttime: &i64) -> Result<Vec<(i64, String, i64, bool)>>;
+
+    async fn set_goal_notified(&self, user_id: &i64, game_id: &i64, week_start: &i64) -> Result<()>;
+
+    // Returns whether the watch is new, a None game watches every game
+    async fn add_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool>;
+
+    // Returns whether the watcher had that watch
+    async fn remove_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool>;
+
+    // Returns (user id, game name) for each of the watcher's watches
+    async fn get_watches(&self, watcher_id: &i64) -> Result<Vec<(i64, Option<String>)>>;
+
+    // The users watching the user play the game or any game
+    async fn get_watchers(&self, user_id: &i64, game_name: &str) -> Result<Vec<i64>>;
+
+    async fn set_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str, min_players: &i64) -> Result<()>;
+
+    // Returns whether the user was subscribed to the game
+    async fn remove_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<bool>;
+
+    // Returns (game name, min players) for each of the user's subscriptions in the guild
+    async fn get_lfg_subscriptions(&self, guild_id: &i64, user_id: &i64) -> Result<Vec<(String, i64)>>;
+
+    // Returns (user id, min players, notified) for each subscriber of the game in the guild
+    async fn get_lfg_subscribers(&self, guild_id: &i64, game_name: &str) -> Result<Vec<(i64, i64, bool)>>;
+
+    async fn set_lfg_notified(&self, guild_id: &i64, user_id: &i64, game_name: &str, notified: bool) -> Result<()>;
+
+    async fn set_game_role(&self, guild_id: &i64, game_name: &str, role_id: &i64, channel_id: &i64) -> Result<()>;
+
+    // Returns whether the game had a role
+    async fn remove_game_role(&self, guild_id: &i64, game_name: &str) -> Result<bool>;
+
+    // Returns (game name, role id, channel id) for each of the guild's game roles
+    async fn get_game_roles(&self, guild_id: &i64) -> Result<Vec<(String, i64, i64)>>;
+
+    // Returns (role id, channel id, last ping) of the game's role
+    async fn get_game_role(&self, guild_id: &i64, game_name: &str) -> Result<Option<(i64, i64, Option<i64>)>>;
+
+    async fn set_game_role_pinged(&self, guild_id: &i64, game_name: &str, pingtime: &i64) -> Result<()>;
+
+    async fn add_role_reward(&self, guild_id: &i64, role_id: &i64, seconds: &i64) -> Result<()>;
+
+    // Returns whether the role was a reward
+    async fn remove_role_reward(&self, guild_id: &i64, role_id: &i64) -> Result<bool>;
+
+    // Returns (role id, seconds) pairs of the guild's rewards, lowest threshold first
+    async fn get_role_rewards(&self, guild_id: &i64) -> Result<Vec<(i64, i64)>>;
+
+    // Returns the last level the member was announced at
+    async fn get_level(&self, guild_id: &i64, user_id: &i64) -> Result<i64>;
+
+    async fn set_level(&self, guild_id: &i64, user_id: &i64, level: &i64) -> Result<()>;
+
+    async fn get_achievement_stats(&self, user_id: &i64) -> Result<AchievementStats>;
+
+    // Returns (badge, unlock time) pairs of the user's unlocked badges
+    async fn get_unlocked_badges(&self, user_id: &i64) -> Result<Vec<(String, i64)>>;
+
+    async fn unlock_badge(&self, user_id: &i64, badge: &str, unlocked_at: &i64) -> Result<()>;
+
+    async fn start_voice_session(&self, user_id: &i64, guild_id: &i64, channel_id: &i64, starttime: &i64) -> Result<()>;
+
+    async fn end_voice_session(&self, user_id: &i64, endtime: &i64) -> Result<()>;
+
+    async fn end_all_voice_sessions(&self, endtime: &i64) -> Result<()>;
+
+    async fn get_voice_users(&self) -> Result<Vec<i64>>;
+
+    // Returns the user's (voice time, voice time while playing), running sessions count up to currenttime
+    async fn get_voice_time(&self, user_id: &i64, currenttime: &i64) -> Result<(i64, i64)>;
+
+    async fn add_webhook(&self, guild_id: &i64, url: &str) -> Result<()>;
+
+    async fn remove_webhook(&self, guild_id: &i64, webhook_id: &i64) -> Result<bool>;
+
+    // Returns (webhook id, url) pairs of the guild's webhooks
+    async fn get_webhooks(&self, guild_id: &i64) -> Result<Vec<(i64, String)>>;
+
+    // Returns (guild id, channel id) pairs of the guilds with a report channel and the given cadence
+    async fn get_report_channels(&self, cadence: &str) -> Result<Vec<(i64, i64)>>;
+
+    // Records the users as members of the guild, listed by Discord at `seen`
+    async fn add_guild_members(&self, guild_id: &i64, user_ids: &[i64], seen: &i64) -> Result<()>;
+
+    async fn remove_guild_member(&self, guild_id: &i64, user_id: &i64) -> Result<()>;
+
+    // Forgets the members of the guild Discord didn't list since `before`
+    async fn prune_guild_members(&self, guild_id: &i64, before: &i64) -> Result<()>;
+
+    // The members of the guild, as last seen by the bot
+    async fn get_guild_members(&self, guild_id: &i64) -> Result<Vec<i64>>;
+
+    // The guilds the user is a member of
+    async fn get_user_guilds(&self, user_id: &i64) -> Result<Vec<i64>>;
+
+    // Fails when the database can't be reached
+    async fn ping(&self) -> Result<()>;
+
+    async fn count_open_sessions(&self) -> Result<i64>;
+
+    // Deletes the history of the sessions which ended before `before`, the playtime they added is kept
+    // and their playtime and count are first rolled up per user, game and UTC day of their start
+    // Returns how many sessions were deleted
+    async fn prune_history(&self, before: &i64) -> Result<u64>;
+
+    async fn migrate(&self) -> Result<()>;
+
+    // Saves every running session, before the bot stops
+    async fn save_all_sessions(&self) -> Result<()> {
+        let currenttime = Utc::now().timestamp();
+        for user_id in self.get_session_users().await? {
+            self.save_session(&user_id, &[], None, &currenttime).await?;
+            self.save_stream(&user_id, None, None).await?;
+            self.save_listen(&user_id, None).await?;
+        }
+        Ok(())
+    }
+
+    // Returns the users with a running game session, stream or listening session
+    async fn get_session_users(&self) -> Result<Vec<i64>>;
+
+    // Records whether the user is away (Idle or DND), `since` only changes when they switch between away and not
+    // Returns the previous (away, since), None when the bot didn't know it
+    async fn update_status(&self, user_id: &i64, away: bool, currenttime: &i64) -> Result<Option<(bool, i64)>>;
+
+    async fn resetall(&self) -> Result<()>;
+
+    async fn reset(&self, user_id: &i64) -> Result<()>;
+
+    // Restores the rows of the last reset made less than a day ago
+    // Returns the user it targeted, None for /resetall, or nothing when there is no reset to undo
+    async fn undo_reset(&self) -> Result<Option<Option<i64>>>;
+
+    async fn hardreset(&self) -> Result<()>;
+}
+
+// Presence names of the same game vary in case and trademark symbols
+fn clean_game_name(game_name: &str) -> String {
+    let stripped: String = game_name.chars().filter(|c| !matches!(c, '™' | '®' | '©')).collect();
+    return stripped.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+// A LIKE pattern of the names containing `text`, its wildcards are escaped with a backslash
+fn contains_pattern(text: &str) -> String {
+    return format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+}
+
+// The key a game name is looked up by in game_aliases, must match the normalization of the 0005 migration
+fn game_key(game_name: &str) -> String {
+    return clean_game_name(game_name).to_lowercase();
+}
@@ -0,0 +1,2507 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use log::LevelFilter;
+use sqlx::mysql::{MySql, MySqlArguments, MySqlConnection, MySqlPool};
+use sqlx::query::Query;
+use sqlx::{query, query_as, query_scalar, ConnectOptions, Transaction};
+use tracing::{info, warn};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{clean_game_name, contains_pattern, game_key, AchievementStats, GameEntry, GameMetadata, GameStats, GameSummary, GuildConfig, RichPresence, Session, Storage, UserTotals, RESET_UNDO_WINDOW};
+
+
+// Queries taking longer are logged as warnings
+const SLOW_QUERY: Duration = Duration::from_millis(250);
+
+// (game id, name, start time, state, details, party size) of a running session
+type SessionRow = (i64, String, i64, Option<String>, Option<String>, Option<i64>);
+
+// Tables of the sessions still running and of the statuses, their rows are only valid until the bot stops
+const RUNNING_SESSION_TABLES: [&str; 5] = ["game_sessions", "voice_sessions", "stream_sessions", "listen_sessions", "user_status"];
+
+// What an inserted row does when its key is already taken
+enum Duplicates {
+    Fail,
+    Skip,
+    // The assignments of ON DUPLICATE KEY UPDATE
+    Update(&'static str),
+}
+
+// Tables moved to reset_archive by the resets, in the order they are restored,
+// with how a restored row is combined with the one tracked since the reset
+const ARCHIVED_TABLES: [(&str, Duplicates); 12] = [
+    ("games", Duplicates::Skip),
+    ("game_aliases", Duplicates::Skip),
+    ("game_entries", Duplicates::Update("playtime=playtime+VALUES(playtime)")),
+    ("session_history", Duplicates::Skip),
+    ("history_rollup", Duplicates::Update("playtime=playtime+VALUES(playtime), sessions=sessions+VALUES(sessions),
+                                          longest=GREATEST(longest, VALUES(longest)), night_sessions=night_sessions+VALUES(night_sessions)")),
+    ("goals", Duplicates::Skip),
+    ("levels", Duplicates::Update("level=GREATEST(level, VALUES(level))")),
+    ("unlocked_badges", Duplicates::Skip),
+    ("stream_history", Duplicates::Skip),
+    ("listen_entries", Duplicates::Update("listentime=listentime+VALUES(listentime)")),
+    ("imported_entries", Duplicates::Skip),
+    ("linked_accounts", Duplicates::Skip),
+];
+
+// Text columns holding JSON, dumped as JSON instead of strings
+const JSON_COLUMNS: [(&str, &str); 2] = [("games", "genres"), ("reset_archive", "row")];
+
+// Rows inserted by a single statement when restoring
+const INSERT_BATCH: usize = 100;
+
+// For deployments whose hosting already provides MySQL 8 or MariaDB 10.6, with the same behaviour as PgStorage.
+// The presence updates are written as they come instead of being batched
+pub struct MySqlStorage {
+    pool: MySqlPool,
+    // Sessions shorter than this many seconds are discarded
+    min_session_length: i64,
+    // Sessions longer than this many seconds are clamped
+    max_session_length: i64,
+}
+
+impl MySqlStorage {
+    pub fn new(pool: MySqlPool, min_session_length: i64, max_session_length: i64) -> Self {
+        // Every query is logged with its duration at the debug level, the slow ones are warned about.
+        // Only the connections opened from now on use it
+        let connect_options = (*pool.connect_options()).clone()
+            .log_statements(LevelFilter::Debug)
+            .log_slow_statements(LevelFilter::Warn, SLOW_QUERY);
+        pool.set_connect_options(connect_options);
+        return MySqlStorage { pool, min_session_length, max_session_length };
+    }
+
+    // Opens the database of a mysql: URL, MariaDB servers use the same ones
+    pub async fn connect(url: &str, min_session_length: i64, max_session_length: i64) -> Result<Self> {
+        let pool = MySqlPool::connect(url).await?;
+        return Ok(MySqlStorage::new(pool, min_session_length, max_session_length));
+    }
+
+    // Returns the game a presence name counts as, adding it when it was never seen before
+    async fn resolve_game(&self, game_name: &str) -> Result<i64> {
+        let alias = game_key(game_name);
+        let game_id: Option<i64> = query_scalar("SELECT game_id FROM game_aliases WHERE alias=?;")
+            .bind(&alias)
+                                            .fetch_optional(&self.pool).await?;
+        if let Some(game_id) = game_id {
+            return Ok(game_id);
+        }
+        // LAST_INSERT_ID(game_id) makes the existing game's id the inserted one when another presence update added it first
+        let result = query("INSERT INTO games (name) VALUES (?) ON DUPLICATE KEY UPDATE game_id=LAST_INSERT_ID(game_id);")
+            .bind(clean_game_name(game_name))
+                                            .execute(&self.pool).await?;
+        let game_id = i64::try_from(result.last_insert_id())?;
+        self.set_alias(&alias, &game_id).await?;
+        return Ok(game_id);
+    }
+
+    async fn set_alias(&self, alias: &str, game_id: &i64) -> Result<()> {
+        query("INSERT INTO game_aliases (alias, game_id) VALUES (?, ?) ON DUPLICATE KEY UPDATE game_id=VALUES(game_id);")
+            .bind(alias)
+            .bind(game_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_tables(&self) -> Result<Vec<String>> {
+        return Ok(query_scalar("SELECT CAST(table_name AS CHAR) FROM information_schema.tables
+                               WHERE table_schema=DATABASE() AND table_type='BASE TABLE' AND table_name<>'_sqlx_migrations' ORDER BY table_name;")
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    // Records a reset and forgets the ones that can't be undone anymore
+    async fn start_reset(&self, transaction: &mut Transaction<'_, MySql>, user_id: Option<&i64>) -> Result<i64> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query("DELETE FROM resets WHERE resettime < ?;")
+            .bind(currenttime - RESET_UNDO_WINDOW)
+            .execute(&mut **transaction).await?;
+        let result = query("INSERT INTO resets (user_id, resettime) VALUES (?, ?);")
+            .bind(user_id)
+            .bind(currenttime)
+                                            .execute(&mut **transaction).await?;
+        return Ok(i64::try_from(result.last_insert_id())?);
+    }
+}
+
+#[async_trait]
+impl Storage for MySqlStorage {
+    async fn save_session(&self, user_id: &i64, playing: &[i64], min_session_length: Option<i64>, endtime: &i64) -> Result<Vec<(String, i64)>> {
+        let mut saved: Vec<(String, i64)> = Vec::new();
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let statement = format!("SELECT game_id, name, starttime, state, details, party_size FROM game_sessions NATURAL JOIN games WHERE user_id=? AND {};",
+                                not_in("game_id", playing.len()));
+        let mut sessions_query = query_as(&statement).bind(user_id);
+        for game_id in playing {
+            sessions_query = sessions_query.bind(game_id);
+        }
+        let sessions: Vec<SessionRow> = sessions_query.fetch_all(&self.pool).await?;
+        // There is no trigger clearing the sessions, each one is deleted along with the playtime it added
+        let mut transaction = self.pool.begin().await?;
+        for (game_id, name, starttime, state, details, party_size) in sessions {
+            let mut playtime: i64 = endtime - starttime;
+            // Sessions left open while the bot missed the game being closed would count the whole time
+            if playtime > self.max_session_length {
+                warn!(user_id, game = %name, duration = playtime, "Clamping the session to {}s", self.max_session_length);
+                playtime = self.max_session_length;
+            }
+            if playtime >= min_session_length {
+                info!(user_id, game = %name, duration = playtime, "Saving the session");
+                query("INSERT INTO session_history (user_id, game_id, starttime, endtime, duration, state, details, party_size) VALUES (?, ?, ?, ?, ?, ?, ?, ?);")
+                    .bind(user_id)
+                    .bind(game_id)
+                    .bind(endtime - playtime)
+                    .bind(endtime)
+                    .bind(playtime)
+                    .bind(state)
+                    .bind(details)
+                    .bind(party_size)
+                    .execute(&mut *transaction).await?;
+                query("INSERT INTO game_entries (user_id, game_id, playtime) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE playtime=playtime+VALUES(playtime);")
+                    .bind(user_id)
+                    .bind(game_id)
+                    .bind(playtime)
+                    .execute(&mut *transaction).await?;
+                saved.push((name, playtime));
+            } else {
+                info!(user_id, game = %name, duration = playtime, "Discarding the session, it is shorter than {}s", min_session_length);
+            }
+            query("DELETE FROM game_sessions WHERE user_id=? AND game_id=?;")
+                .bind(user_id)
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        return Ok(saved);
+    }
+
+    async fn get_top_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameEntry>> {
+        let rows: Vec<(String, i64)> = match start {
+            Some(start) => query_as("SELECT name, CAST(SUM(endtime - GREATEST(starttime, ?)) AS SIGNED) AS playtime FROM full_history NATURAL JOIN games
+                                    WHERE user_id=? AND endtime > ? GROUP BY name ORDER BY playtime DESC LIMIT ? OFFSET ?;")
+                .bind(start)
+                .bind(user_id)
+                .bind(start)
+                .bind(limit)
+                .bind(offset)
+                                            .fetch_all(&self.pool).await?,
+            None => query_as("SELECT name, playtime FROM game_entries NATURAL JOIN games WHERE user_id=? ORDER BY playtime DESC LIMIT ? OFFSET ?;")
+                .bind(user_id)
+                .bind(limit)
+                .bind(offset)
+                                            .fetch_all(&self.pool).await?,
+        };
+        return Ok(rows.into_iter().map(|(name, playtime)| GameEntry { name, playtime }).collect());
+    }
+
+    async fn get_summary_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameSummary>> {
+        let rows: Vec<(String, i64, i64, Option<i64>)> = match start {
+            Some(start) => query_as("SELECT name, CAST(SUM(endtime - GREATEST(starttime, ?)) AS SIGNED) AS playtime, CAST(SUM(sessions) AS SIGNED),
+                                    CAST(SUM(duration) DIV SUM(sessions) AS SIGNED)
+                                    FROM full_history NATURAL JOIN games
+                                    WHERE user_id=? AND endtime > ? GROUP BY name ORDER BY playtime DESC LIMIT ? OFFSET ?;")
+                .bind(start)
+                .bind(user_id)
+                .bind(start)
+                .bind(limit)
+                .bind(offset)
+                                            .fetch_all(&self.pool).await?,
+            // The sessions are counted separately, joining them would repeat the playtime of the entry
+            None => query_as("SELECT name, playtime, COALESCE(sessions, 0), average_session FROM game_entries NATURAL JOIN games
+                                    LEFT JOIN (SELECT game_id, CAST(SUM(sessions) AS SIGNED) AS sessions, CAST(SUM(duration) DIV SUM(sessions) AS SIGNED) AS average_session FROM full_history
+                                               WHERE user_id=? GROUP BY game_id) history USING (game_id)
+                                    WHERE user_id=? ORDER BY playtime DESC LIMIT ? OFFSET ?;")
+                .bind(user_id)
+                .bind(user_id)
+                .bind(limit)
+                .bind(offset)
+                                            .fetch_all(&self.pool).await?,
+        };
+        return Ok(rows.into_iter()
+            .map(|(name, playtime, sessions, average_session)| GameSummary { name, playtime, sessions, average_session })
+            .collect());
+    }
+
+    async fn count_games(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let count: i64 = match start {
+            Some(start) => query_scalar("SELECT COUNT(DISTINCT game_id) FROM full_history WHERE user_id=? AND endtime > ?;")
+                .bind(user_id)
+                .bind(start)
+                                            .fetch_one(&self.pool).await?,
+            None => query_scalar("SELECT COUNT(*) FROM game_entries WHERE user_id=?;")
+                .bind(user_id)
+                                            .fetch_one(&self.pool).await?,
+        };
+        return Ok(count);
+    }
+
+    async fn get_game_playtime(&self, user_id: &i64, game_id: &i64) -> Result<i64> {
+        let playtime: Option<i64> = query_scalar("SELECT playtime FROM game_entries WHERE user_id=? AND game_id=?;")
+            .bind(user_id)
+            .bind(game_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(playtime.unwrap_or(0));
+    }
+
+    // MySQL has no RETURNING, the corrected playtime is read back in the transaction
+    async fn adjust_playtime(&self, user_id: &i64, game_id: &i64, seconds: &i64) -> Result<i64> {
+        let mut transaction = self.pool.begin().await?;
+        query("INSERT INTO game_entries (user_id, game_id, playtime) VALUES (?, ?, GREATEST(?, 0))
+               ON DUPLICATE KEY UPDATE playtime=GREATEST(playtime+?, 0);")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(seconds)
+            .bind(seconds)
+            .execute(&mut *transaction).await?;
+        let playtime: i64 = query_scalar("SELECT playtime FROM game_entries WHERE user_id=? AND game_id=?;")
+            .bind(user_id)
+            .bind(game_id)
+                                            .fetch_one(&mut *transaction).await?;
+        // The game would still be listed with no playtime
+        if playtime == 0 {
+            query("DELETE FROM game_entries WHERE user_id=? AND game_id=?;")
+                .bind(user_id)
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        return Ok(playtime);
+    }
+
+    async fn log_session(&self, user_id: &i64, game_name: &str, starttime: &i64, duration: &i64) -> Result<String> {
+        let game_id = self.resolve_game(game_name).await?;
+        let mut transaction = self.pool.begin().await?;
+        query("INSERT INTO session_history (user_id, game_id, starttime, endtime, duration, source) VALUES (?, ?, ?, ?, ?, 'manual');")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(starttime)
+            .bind(starttime + duration)
+            .bind(duration)
+            .execute(&mut *transaction).await?;
+        query("INSERT INTO imported_entries (user_id, game_id, source, playtime) VALUES (?, ?, 'manual', ?) ON DUPLICATE KEY UPDATE playtime=playtime+VALUES(playtime);")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(duration)
+            .execute(&mut *transaction).await?;
+        let name: String = query_scalar("SELECT name FROM games WHERE game_id=?;")
+            .bind(game_id)
+                                            .fetch_one(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Ok(name);
+    }
+
+    async fn import_playtime(&self, rows: &[(i64, String, i64)]) -> Result<()> {
+        // The known games are looked up at once, only the new ones are added one by one
+        let mut aliases: Vec<String> = rows.iter().map(|(_, game_name, _)| game_key(game_name)).collect();
+        aliases.sort();
+        aliases.dedup();
+        let mut game_ids: HashMap<String, i64> = HashMap::new();
+        if !aliases.is_empty() {
+            let statement = format!("SELECT alias, game_id FROM game_aliases WHERE alias IN ({});", placeholders(aliases.len()));
+            let mut aliases_query = query_as::<_, (String, i64)>(&statement);
+            for alias in &aliases {
+                aliases_query = aliases_query.bind(alias);
+            }
+            game_ids.extend(aliases_query.fetch_all(&self.pool).await?);
+        }
+        for (_, game_name, _) in rows {
+            if let Entry::Vacant(entry) = game_ids.entry(game_key(game_name)) {
+                entry.insert(self.resolve_game(game_name).await?);
+            }
+        }
+        let mut transaction = self.pool.begin().await?;
+        for (user_id, game_name, playtime) in rows {
+            let game_id = game_ids[&game_key(game_name)];
+            query("INSERT INTO game_entries (user_id, game_id, playtime) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE playtime=playtime+VALUES(playtime);")
+                .bind(user_id)
+                .bind(game_id)
+                .bind(playtime)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_game_stats(&self, user_id: &i64, game_id: &i64) -> Result<GameStats> {
+        let (sessions, playtime, first_played, last_played): (i64, i64, Option<i64>, Option<i64>) =
+            query_as("SELECT CAST(COALESCE(SUM(sessions), 0) AS SIGNED), CAST(COALESCE(SUM(duration), 0) AS SIGNED), MIN(starttime), MAX(endtime) FROM full_history WHERE user_id=? AND game_id=?;")
+            .bind(user_id)
+            .bind(game_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(GameStats { sessions, playtime, first_played, last_played });
+    }
+
+    async fn get_top_details(&self, user_id: &i64, game_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT COALESCE(details, state), CAST(SUM(duration) AS SIGNED) FROM session_history
+                           WHERE user_id=? AND game_id=? AND COALESCE(details, state) IS NOT NULL GROUP BY 1 ORDER BY 2 DESC, 1 LIMIT ?;")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_total_playtime(&self, user_id: &i64) -> Result<i64> {
+        return Ok(query_scalar("SELECT CAST(COALESCE(SUM(playtime), 0) AS SIGNED) FROM game_entries WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_open_sessions(&self, user_id: &i64) -> Result<Vec<Session>> {
+        let rows: Vec<SessionRow> = query_as("SELECT game_id, name, starttime, state, details, party_size FROM game_sessions NATURAL JOIN games WHERE user_id=? ORDER BY starttime;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        return Ok(rows.into_iter()
+            .map(|(game_id, name, starttime, state, details, party_size)| Session { game_id, name, starttime, state, details, party_size })
+            .collect());
+    }
+
+    async fn get_open_sessions_by_game(&self) -> Result<Vec<(String, Vec<i64>)>> {
+        let rows: Vec<(String, i64)> = query_as("SELECT name, user_id FROM game_sessions NATURAL JOIN games ORDER BY name, starttime;")
+                                            .fetch_all(&self.pool).await?;
+        let mut games: Vec<(String, Vec<i64>)> = Vec::new();
+        for (name, user_id) in rows {
+            match games.last_mut() {
+                Some((last_name, user_ids)) if *last_name == name => user_ids.push(user_id),
+                _ => games.push((name, vec![user_id])),
+            }
+        }
+        // The sort is stable, games with as many players stay sorted by name
+        games.sort_by_key(|(_, user_ids)| std::cmp::Reverse(user_ids.len()));
+        return Ok(games);
+    }
+
+    async fn get_recent_sessions(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query_as("SELECT name, starttime, duration FROM session_history NATURAL JOIN games WHERE user_id=? ORDER BY endtime DESC LIMIT ?;")
+            .bind(user_id)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_longest_session(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<(String, i64, i64)>> {
+        return Ok(query_as("SELECT name, starttime, longest FROM full_history NATURAL JOIN games
+                           WHERE user_id=? AND starttime >= ? AND starttime < ? ORDER BY longest DESC LIMIT 1;")
+            .bind(user_id)
+            .bind(start)
+            .bind(end)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn get_session_durations(&self, user_id: &i64, game_id: Option<i64>) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT duration FROM session_history WHERE user_id=? AND (? IS NULL OR game_id=?) ORDER BY duration;")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(game_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_period_leaderboard(&self, guild_id: &i64, start: &i64, end: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT user_id, CAST(SUM(LEAST(endtime, ?) - GREATEST(starttime, ?)) AS SIGNED) AS playtime FROM full_history
+                           WHERE endtime > ? AND starttime < ? AND (source<>'manual' OR ?) AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                           AND user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?)
+                           GROUP BY user_id ORDER BY playtime DESC LIMIT 10;")
+            .bind(end)
+            .bind(start)
+            .bind(start)
+            .bind(end)
+            .bind(include_manual)
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_period_top_games(&self, guild_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        let rows: Vec<(String, i64)> = query_as("SELECT name, CAST(SUM(LEAST(endtime, ?) - GREATEST(starttime, ?)) AS SIGNED) AS playtime
+                                                FROM full_history NATURAL JOIN games
+                                                WHERE endtime > ? AND starttime < ? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                                                AND user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?)
+                                                GROUP BY name ORDER BY playtime DESC LIMIT 10;")
+            .bind(end)
+            .bind(start)
+            .bind(start)
+            .bind(end)
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?;
+        return Ok(rows.into_iter().map(|(name, playtime)| GameEntry { name, playtime }).collect());
+    }
+
+    async fn get_server_top_games(&self, guild_id: Option<i64>, start: Option<i64>, by_players: bool) -> Result<Vec<(String, i64, i64)>> {
+        // The ranking key comes first so the other one breaks the ties
+        return Ok(match start {
+            Some(start) => query_as("SELECT name, CAST(SUM(endtime - GREATEST(starttime, ?)) AS SIGNED) AS playtime, COUNT(DISTINCT user_id) AS players
+                                    FROM full_history NATURAL JOIN games
+                                    WHERE endtime > ? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                                    AND (? IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                                    GROUP BY name ORDER BY CASE WHEN ? THEN players ELSE playtime END DESC, playtime DESC LIMIT 10;")
+                .bind(start)
+                .bind(start)
+                .bind(guild_id)
+                .bind(guild_id)
+                .bind(by_players)
+                                            .fetch_all(&self.pool).await?,
+            None => query_as("SELECT name, CAST(SUM(playtime) AS SIGNED) AS playtime, COUNT(user_id) AS players FROM game_entries NATURAL JOIN games
+                             WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                             AND (? IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                             GROUP BY name ORDER BY CASE WHEN ? THEN players ELSE playtime END DESC, playtime DESC LIMIT 10;")
+                .bind(guild_id)
+                .bind(guild_id)
+                .bind(by_players)
+                                            .fetch_all(&self.pool).await?,
+        });
+    }
+
+    async fn get_user_period_playtime(&self, user_id: &i64, start: &i64, end: &i64) -> Result<i64> {
+        return Ok(query_scalar("SELECT CAST(COALESCE(SUM(LEAST(endtime, ?) - GREATEST(starttime, ?)), 0) AS SIGNED) FROM full_history
+                               WHERE user_id=? AND endtime > ? AND starttime < ?;")
+            .bind(end)
+            .bind(start)
+            .bind(user_id)
+            .bind(start)
+            .bind(end)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_user_period_games(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        let rows: Vec<(String, i64)> = query_as("SELECT name, CAST(SUM(LEAST(endtime, ?) - GREATEST(starttime, ?)) AS SIGNED) AS playtime
+                                                FROM full_history NATURAL JOIN games
+                                                WHERE user_id=? AND endtime > ? AND starttime < ? GROUP BY name ORDER BY playtime DESC, name;")
+            .bind(end)
+            .bind(start)
+            .bind(user_id)
+            .bind(start)
+            .bind(end)
+                                            .fetch_all(&self.pool).await?;
+        return Ok(rows.into_iter().map(|(name, playtime)| GameEntry { name, playtime }).collect());
+    }
+
+    async fn get_user_period_top_game(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<GameEntry>> {
+        let row: Option<(String, i64)> = query_as("SELECT name, CAST(SUM(LEAST(endtime, ?) - GREATEST(starttime, ?)) AS SIGNED) AS playtime
+                                                  FROM full_history NATURAL JOIN games
+                                                  WHERE user_id=? AND endtime > ? AND starttime < ? GROUP BY name ORDER BY playtime DESC LIMIT 1;")
+            .bind(end)
+            .bind(start)
+            .bind(user_id)
+            .bind(start)
+            .bind(end)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map(|(name, playtime)| GameEntry { name, playtime }));
+    }
+
+    async fn get_sessions_since(&self, user_id: &i64, start: &i64) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT starttime, endtime FROM full_history WHERE user_id=? AND endtime > ?;")
+            .bind(user_id)
+            .bind(start)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_user_totals(&self, user_id: &i64, guild_id: Option<i64>) -> Result<Option<UserTotals>> {
+        // RANK is a reserved word, and returns an unsigned number. The user is ranked even after leaving the guild
+        let row: Option<(i64, i64, i64, i64)> = query_as("SELECT total, games, user_rank, ranked_users FROM (
+                                                             SELECT user_id, CAST(SUM(playtime) AS SIGNED) AS total, COUNT(*) AS games,
+                                                                 CAST(RANK() OVER (ORDER BY SUM(playtime) DESC) AS SIGNED) AS user_rank,
+                                                                 CAST(COUNT(*) OVER () AS SIGNED) AS ranked_users
+                                                             FROM game_entries
+                                                             WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                                                             AND (? IS NULL OR user_id=? OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                                                             GROUP BY user_id
+                                                         ) totals WHERE user_id=?;")
+            .bind(guild_id)
+            .bind(user_id)
+            .bind(guild_id)
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map(|(playtime, games, rank, ranked_users)| UserTotals { playtime, games, rank, ranked_users }));
+    }
+
+    async fn get_user_rank(&self, user_id: &i64, guild_id: Option<i64>, start: Option<i64>) -> Result<Option<(i64, i64)>> {
+        let rank = match start {
+            Some(start) => query_as("SELECT user_rank, ranked_users FROM (
+                                        SELECT user_id, CAST(RANK() OVER (ORDER BY SUM(endtime - GREATEST(starttime, ?)) DESC) AS SIGNED) AS user_rank,
+                                            CAST(COUNT(*) OVER () AS SIGNED) AS ranked_users
+                                        FROM full_history
+                                        WHERE endtime > ? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                                        AND (? IS NULL OR user_id=? OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                                        GROUP BY user_id
+                                    ) ranks WHERE user_id=?;")
+                .bind(start)
+                .bind(start)
+                .bind(guild_id)
+                .bind(user_id)
+                .bind(guild_id)
+                .bind(user_id)
+                                            .fetch_optional(&self.pool).await?,
+            None => self.get_user_totals(user_id, guild_id).await?.map(|totals| (totals.rank, totals.ranked_users)),
+        };
+        return Ok(rank);
+    }
+
+    async fn get_shared_games(&self, user1_id: &i64, user2_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query_as("SELECT name, first.playtime, second.playtime FROM game_entries first
+                           JOIN game_entries second ON first.game_id=second.game_id
+                           JOIN games ON games.game_id=first.game_id
+                           WHERE first.user_id=? AND second.user_id=?
+                           ORDER BY first.playtime + second.playtime DESC LIMIT 10;")
+            .bind(user1_id)
+            .bind(user2_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_leaderboard(&self, guild_id: Option<i64>, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT user_id, CAST(SUM(playtime) AS SIGNED) AS total FROM
+                           (SELECT user_id, playtime FROM game_entries
+                           UNION ALL SELECT user_id, playtime FROM imported_entries WHERE source='manual' AND ?) entries
+                           WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                           AND (? IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                           GROUP BY user_id ORDER BY total DESC LIMIT 10;")
+            .bind(include_manual)
+            .bind(guild_id)
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_game_leaderboard(&self, guild_id: Option<i64>, game_id: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT user_id, CAST(SUM(playtime) AS SIGNED) AS total FROM
+                           (SELECT user_id, game_id, playtime FROM game_entries
+                           UNION ALL SELECT user_id, game_id, playtime FROM imported_entries WHERE source='manual' AND ?) entries
+                           WHERE game_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                           AND (? IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?))
+                           GROUP BY user_id ORDER BY total DESC LIMIT 10;")
+            .bind(include_manual)
+            .bind(game_id)
+            .bind(guild_id)
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn find_game(&self, game_name: &str) -> Result<Option<(i64, String)>> {
+        let row: Option<(i64, String)> = query_as("SELECT game_id, name FROM game_aliases NATURAL JOIN games WHERE alias=?;")
+            .bind(game_key(game_name))
+                                            .fetch_optional(&self.pool).await?;
+        if row.is_some() {
+            return Ok(row);
+        }
+        // The names compare exactly, both sides are lowered like ILIKE
+        let name = game_name.trim().to_lowercase();
+        // The backslash is also the escape character of MySQL strings
+        return Ok(query_as(r"SELECT game_id, name FROM games WHERE LOWER(name) LIKE ? ESCAPE '\\' ORDER BY LOWER(name)=? DESC, CHAR_LENGTH(name) LIMIT 1;")
+            .bind(contains_pattern(&name))
+            .bind(&name)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn search_games(&self, game_name: &str) -> Result<Vec<String>> {
+        return Ok(query_scalar(r"SELECT name FROM games WHERE LOWER(name) LIKE ? ESCAPE '\\' ORDER BY name LIMIT 25;")
+            .bind(contains_pattern(&game_name.to_lowercase()))
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn register_session(&self, user_id: &i64, game_name: &str, starttime: &i64, rich_presence: &RichPresence) -> Result<(i64, bool)> {
+        let game_id: i64 = self.resolve_game(game_name).await?;
+        // The start timestamp comes from the client, don't trust one in the future or older than a session can be
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let mut starttime: i64 = *starttime;
+        if starttime > currenttime || currenttime - starttime > self.max_session_length {
+            warn!("{:?}'s session of {:?} starts at {:?}, starting it now instead", user_id, game_name, starttime);
+            starttime = currenttime;
+        }
+        // A session can't start before the previous one ended, the presence start is still the same after a restart that saved it
+        let result = query("INSERT IGNORE INTO game_sessions (user_id, game_id, starttime)
+                           SELECT ?, ?, GREATEST(?, COALESCE(MAX(endtime), ?)) FROM session_history WHERE user_id=? AND game_id=?;")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(starttime)
+            .bind(starttime)
+            .bind(user_id)
+            .bind(game_id)
+                                            .execute(&self.pool).await?;
+        let started = result.rows_affected() > 0;
+        if started {
+            info!(user_id, game_id, "Registered the session");
+        }
+        query("UPDATE game_sessions SET state=?, details=?, party_size=? WHERE user_id=? AND game_id=?;")
+            .bind(&rich_presence.state)
+            .bind(&rich_presence.details)
+            .bind(rich_presence.party_size)
+            .bind(user_id)
+            .bind(game_id)
+            .execute(&self.pool).await?;
+        return Ok((game_id, started));
+    }
+
+    async fn register_stream(&self, user_id: &i64, game_name: &str, url: Option<&str>) -> Result<()> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query("INSERT IGNORE INTO stream_sessions (user_id, game, url, starttime) VALUES (?, ?, ?, ?);")
+            .bind(user_id)
+            .bind(clean_game_name(game_name))
+            .bind(url)
+            .bind(currenttime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn save_stream(&self, user_id: &i64, streaming: Option<&str>, min_session_length: Option<i64>) -> Result<()> {
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let stream: Option<(String, Option<String>, i64)> = query_as("SELECT game, url, starttime FROM stream_sessions WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        let (game, url, starttime) = match stream {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+        if streaming.map(clean_game_name).as_ref() == Some(&game) {
+            return Ok(());
+        }
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let duration: i64 = std::cmp::min(currenttime - starttime, self.max_session_length);
+        if duration >= min_session_length {
+            info!(user_id, game = %game, duration, "Saving the stream");
+            query("INSERT INTO stream_history (user_id, game, url, starttime, endtime, duration) VALUES (?, ?, ?, ?, ?, ?);")
+                .bind(user_id)
+                .bind(&game)
+                .bind(url)
+                .bind(currenttime - duration)
+                .bind(currenttime)
+                .bind(duration)
+                .execute(&self.pool).await?;
+        }
+        query("DELETE FROM stream_sessions WHERE user_id=?;")
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_stream_time(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let start = start.unwrap_or(0);
+        return Ok(query_scalar("SELECT CAST(COALESCE(SUM(endtime - GREATEST(starttime, ?)), 0) AS SIGNED) FROM stream_history WHERE user_id=? AND endtime > ?;")
+            .bind(start)
+            .bind(user_id)
+            .bind(start)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_top_streamed_games(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT game, CAST(SUM(duration) AS SIGNED) AS streamtime FROM stream_history WHERE user_id=? GROUP BY game ORDER BY streamtime DESC LIMIT ?;")
+            .bind(user_id)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_recent_streams(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, Option<String>, i64, i64)>> {
+        return Ok(query_as("SELECT game, url, starttime, duration FROM stream_history WHERE user_id=? ORDER BY endtime DESC LIMIT ?;")
+            .bind(user_id)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn register_listen(&self, user_id: &i64, artist: &str) -> Result<()> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query("INSERT IGNORE INTO listen_sessions (user_id, artist, starttime) VALUES (?, ?, ?);")
+            .bind(user_id)
+            .bind(artist)
+            .bind(currenttime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn save_listen(&self, user_id: &i64, listening: Option<&str>) -> Result<()> {
+        let session: Option<(String, i64)> = query_as("SELECT artist, starttime FROM listen_sessions WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        let (artist, starttime) = match session {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+        if listening == Some(artist.as_str()) {
+            return Ok(());
+        }
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let listentime: i64 = std::cmp::min(currenttime - starttime, self.max_session_length);
+        query("INSERT INTO listen_entries (user_id, artist, listentime) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE listentime=listentime+VALUES(listentime);")
+            .bind(user_id)
+            .bind(&artist)
+            .bind(listentime)
+            .execute(&self.pool).await?;
+        query("DELETE FROM listen_sessions WHERE user_id=?;")
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_top_artists(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT artist, listentime FROM listen_entries WHERE user_id=? ORDER BY listentime DESC LIMIT ?;")
+            .bind(user_id)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_listen_time(&self, user_id: &i64) -> Result<i64> {
+        return Ok(query_scalar("SELECT CAST(COALESCE(SUM(listentime), 0) AS SIGNED) FROM listen_entries WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_games_to_enrich(&self, before: &i64, limit: i64) -> Result<Vec<(i64, String)>> {
+        // NULLs sort first
+        return Ok(query_as("SELECT game_id, name FROM games WHERE metadata_updated IS NULL OR metadata_updated < ? ORDER BY metadata_updated LIMIT ?;")
+            .bind(before)
+            .bind(limit)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn set_game_metadata(&self, game_id: &i64, metadata: Option<&GameMetadata>, updated: &i64) -> Result<()> {
+        query("UPDATE games SET igdb_id=?, title=?, cover_url=?, genres=?, summary=?, release_date=?, metadata_updated=? WHERE game_id=?;")
+            .bind(metadata.map(|metadata| metadata.igdb_id))
+            .bind(metadata.map(|metadata| metadata.title.as_str()))
+            .bind(metadata.and_then(|metadata| metadata.cover_url.as_deref()))
+            .bind(serde_json::to_string(metadata.map_or(&[] as &[String], |metadata| metadata.genres.as_slice()))?)
+            .bind(metadata.and_then(|metadata| metadata.summary.as_deref()))
+            .bind(metadata.and_then(|metadata| metadata.release_date))
+            .bind(updated)
+            .bind(game_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_game_metadata(&self, game_id: &i64) -> Result<Option<GameMetadata>> {
+        let row: Option<(i64, String, Option<String>, String, Option<String>, Option<i64>)> =
+            query_as("SELECT igdb_id, title, cover_url, genres, summary, release_date FROM games WHERE game_id=? AND igdb_id IS NOT NULL;")
+            .bind(game_id)
+                                            .fetch_optional(&self.pool).await?;
+        return match row {
+            Some((igdb_id, title, cover_url, genres, summary, release_date)) => Ok(Some(GameMetadata {
+                igdb_id,
+                title,
+                cover_url,
+                genres: serde_json::from_str(&genres)?,
+                summary,
+                release_date,
+            })),
+            None => Ok(None),
+        };
+    }
+
+    async fn get_genre_playtime(&self, user_id: &i64) -> Result<Vec<(Option<String>, i64)>> {
+        // An empty genre list still joins a single NULL genre, DIV divides like the integers of Postgres
+        return Ok(query_as("SELECT CAST(genre.name AS CHAR), CAST(SUM(playtime DIV GREATEST(JSON_LENGTH(genres), 1)) AS SIGNED) AS playtime FROM game_entries
+                           JOIN games ON games.game_id=game_entries.game_id
+                           LEFT JOIN JSON_TABLE(games.genres, '$[*]' COLUMNS (name VARCHAR(255) PATH '$')) genre ON TRUE
+                           WHERE user_id=? GROUP BY genre.name ORDER BY playtime DESC;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_cover_url(&self, game_name: &str) -> Result<Option<String>> {
+        let cover_url: Option<Option<String>> = query_scalar("SELECT cover_url FROM games WHERE name=?;")
+            .bind(game_name)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(cover_url.flatten());
+    }
+
+    async fn get_game_totals(&self, game_id: &i64) -> Result<(i64, i64)> {
+        return Ok(query_as("SELECT COUNT(*), CAST(COALESCE(SUM(playtime), 0) AS SIGNED) FROM game_entries
+                           WHERE game_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out);")
+            .bind(game_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn link_account(&self, user_id: &i64, platform: &str, account_id: &str) -> Result<()> {
+        query("INSERT INTO linked_accounts (user_id, platform, account_id) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE account_id=VALUES(account_id);")
+            .bind(user_id)
+            .bind(platform)
+            .bind(account_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn unlink_account(&self, user_id: &i64, platform: &str) -> Result<bool> {
+        let result = query("DELETE FROM linked_accounts WHERE user_id=? AND platform=?;")
+            .bind(user_id)
+            .bind(platform)
+                                            .execute(&self.pool).await?;
+        query("DELETE FROM imported_entries WHERE user_id=? AND source=?;")
+            .bind(user_id)
+            .bind(platform)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_linked_accounts(&self, platform: &str) -> Result<Vec<(i64, String)>> {
+        return Ok(query_as("SELECT user_id, account_id FROM linked_accounts WHERE platform=?;")
+            .bind(platform)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn replace_imported_entries(&self, user_id: &i64, source: &str, entries: &[(String, i64)]) -> Result<()> {
+        let mut game_ids: Vec<i64> = Vec::new();
+        for (game_name, _) in entries {
+            game_ids.push(self.resolve_game(game_name).await?);
+        }
+        let mut transaction = self.pool.begin().await?;
+        query("DELETE FROM imported_entries WHERE user_id=? AND source=?;")
+            .bind(user_id)
+            .bind(source)
+            .execute(&mut *transaction).await?;
+        // Two names of the platform can be aliases of the same game
+        for (game_id, (_, playtime)) in game_ids.iter().zip(entries) {
+            query("INSERT INTO imported_entries (user_id, game_id, source, playtime) VALUES (?, ?, ?, ?) ON DUPLICATE KEY UPDATE playtime=playtime+VALUES(playtime);")
+                .bind(user_id)
+                .bind(game_id)
+                .bind(source)
+                .bind(playtime)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_imported_playtime(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT source, CAST(SUM(playtime) AS SIGNED) FROM imported_entries WHERE user_id=? GROUP BY source ORDER BY source;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn merge_alias(&self, alias: &str, game_id: &i64) -> Result<Option<i64>> {
+        let alias = game_key(alias);
+        let old_game_id: Option<i64> = query_scalar("SELECT game_id FROM game_aliases WHERE alias=?;")
+            .bind(&alias)
+                                            .fetch_optional(&self.pool).await?;
+        let old_game_id = old_game_id.filter(|old_game_id| old_game_id != game_id);
+        if let Some(old_game_id) = old_game_id {
+            self.merge_games(&old_game_id, game_id).await?;
+        }
+        self.set_alias(&alias, game_id).await?;
+        return Ok(old_game_id);
+    }
+
+    async fn merge_games(&self, old_game_id: &i64, game_id: &i64) -> Result<()> {
+        info!("Merging game {:?} into {:?}", old_game_id, game_id);
+        let mut transaction = self.pool.begin().await?;
+        // A table can't be read by a subquery of the statement changing it, the duplicates are joined instead.
+        // Every statement takes the merged game's id first
+        let statements = [
+            "UPDATE game_entries target JOIN game_entries merged ON target.user_id=merged.user_id
+             SET target.playtime=target.playtime+merged.playtime WHERE merged.game_id=? AND target.game_id=?;",
+            "DELETE merged FROM game_entries merged JOIN game_entries target ON target.user_id=merged.user_id WHERE merged.game_id=? AND target.game_id=?;",
+            "DELETE merged FROM game_sessions merged JOIN game_sessions target ON target.user_id=merged.user_id WHERE merged.game_id=? AND target.game_id=?;",
+            "DELETE merged FROM goals merged JOIN goals target ON target.user_id=merged.user_id WHERE merged.game_id=? AND target.game_id=?;",
+            // Imports are overwritten by the next sync, the duplicates can be dropped
+            "DELETE merged FROM imported_entries merged JOIN imported_entries target ON target.user_id=merged.user_id AND target.source=merged.source
+             WHERE merged.game_id=? AND target.game_id=?;",
+        ];
+        for statement in statements {
+            query(statement)
+                .bind(old_game_id)
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        query("INSERT INTO history_rollup (user_id, game_id, day, playtime, sessions, longest, night_sessions)
+               SELECT merged.user_id, ?, merged.day, merged.playtime, merged.sessions, merged.longest, merged.night_sessions FROM history_rollup merged WHERE merged.game_id=?
+               ON DUPLICATE KEY UPDATE playtime=history_rollup.playtime+VALUES(playtime), sessions=history_rollup.sessions+VALUES(sessions),
+               longest=GREATEST(history_rollup.longest, VALUES(longest)), night_sessions=history_rollup.night_sessions+VALUES(night_sessions);")
+            .bind(game_id)
+            .bind(old_game_id)
+            .execute(&mut *transaction).await?;
+        query("DELETE FROM history_rollup WHERE game_id=?;")
+            .bind(old_game_id)
+            .execute(&mut *transaction).await?;
+        for table in ["game_entries", "game_sessions", "goals", "imported_entries", "session_history", "game_aliases"] {
+            query(&format!("UPDATE {} SET game_id=? WHERE game_id=?;", table))
+                .bind(game_id)
+                .bind(old_game_id)
+                .execute(&mut *transaction).await?;
+        }
+        query("DELETE FROM games WHERE game_id=?;")
+            .bind(old_game_id)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_game(&self, game_id: &i64) -> Result<String> {
+        info!("Deleting game {:?}", game_id);
+        let mut transaction = self.pool.begin().await?;
+        let name: String = query_scalar("SELECT name FROM games WHERE game_id=?;")
+            .bind(game_id)
+                                            .fetch_one(&mut *transaction).await?;
+        for table in ["game_entries", "game_sessions", "session_history", "history_rollup", "goals", "imported_entries", "game_aliases", "games"] {
+            query(&format!("DELETE FROM {} WHERE game_id=?;", table))
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        return Ok(name);
+    }
+
+    async fn rename_game(&self, game_id: &i64, game_name: &str) -> Result<bool> {
+        let name = clean_game_name(game_name);
+        let taken: bool = query_scalar("SELECT EXISTS (SELECT 1 FROM games WHERE LOWER(name)=LOWER(?) AND game_id<>?)
+                                       OR EXISTS (SELECT 1 FROM game_aliases WHERE alias=? AND game_id<>?);")
+            .bind(&name)
+            .bind(game_id)
+            .bind(game_key(&name))
+            .bind(game_id)
+                                            .fetch_one(&self.pool).await?;
+        if taken {
+            return Ok(false);
+        }
+        let mut transaction = self.pool.begin().await?;
+        let old_name: String = query_scalar("SELECT name FROM games WHERE game_id=?;")
+            .bind(game_id)
+                                            .fetch_one(&mut *transaction).await?;
+        query("UPDATE games SET name=? WHERE game_id=?;")
+            .bind(&name)
+            .bind(game_id)
+            .execute(&mut *transaction).await?;
+        for alias in [game_key(&old_name), game_key(&name)] {
+            query("INSERT INTO game_aliases (alias, game_id) VALUES (?, ?) ON DUPLICATE KEY UPDATE game_id=VALUES(game_id);")
+                .bind(alias)
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        info!("Renamed game {:?} from {:?} to {:?}", game_id, old_name, name);
+        return Ok(true);
+    }
+
+    async fn is_ignored(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        return Ok(query_scalar("SELECT EXISTS (SELECT 1 FROM ignored_games WHERE guild_id IN (0, ?) AND name=?);")
+            .bind(guild_id)
+            .bind(game_key(game_name))
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn add_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        query("INSERT IGNORE INTO ignored_games (guild_id, name) VALUES (?, ?);")
+            .bind(guild_id)
+            .bind(game_key(game_name))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query("DELETE FROM ignored_games WHERE guild_id=? AND name=?;")
+            .bind(guild_id)
+            .bind(game_key(game_name))
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_ignored_games(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(query_as("SELECT guild_id, name FROM ignored_games WHERE guild_id IN (0, ?) ORDER BY guild_id, name;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn is_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let key = game_key(game_name);
+        return Ok(query_scalar("SELECT EXISTS (SELECT 1 FROM tracked_games WHERE guild_id=? AND (name=? OR name IN
+                                   (SELECT alias FROM game_aliases WHERE game_id=(SELECT game_id FROM game_aliases WHERE alias=?))));")
+            .bind(guild_id)
+            .bind(&key)
+            .bind(&key)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn add_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        query("INSERT IGNORE INTO tracked_games (guild_id, name) VALUES (?, ?);")
+            .bind(guild_id)
+            .bind(game_key(game_name))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query("DELETE FROM tracked_games WHERE guild_id=? AND name=?;")
+            .bind(guild_id)
+            .bind(game_key(game_name))
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_tracked_games(&self, guild_id: &i64) -> Result<Vec<String>> {
+        return Ok(query_scalar("SELECT name FROM tracked_games WHERE guild_id=? ORDER BY name;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn is_opted_out(&self, user_id: &i64) -> Result<bool> {
+        let opted_out: Option<bool> = query_scalar("SELECT opted_out FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(opted_out.unwrap_or(false));
+    }
+
+    async fn set_opted_out(&self, user_id: &i64, opted_out: bool) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, opted_out) VALUES (?, ?) ON DUPLICATE KEY UPDATE opted_out=VALUES(opted_out);")
+            .bind(user_id)
+            .bind(opted_out)
+            .execute(&self.pool).await?;
+        if opted_out {
+            // Discard the sessions that were running when tracking got disabled
+            for statement in ["DELETE FROM game_sessions WHERE user_id=?;", "DELETE FROM voice_sessions WHERE user_id=? AND endtime IS NULL;",
+                              "DELETE FROM stream_sessions WHERE user_id=?;", "DELETE FROM listen_sessions WHERE user_id=?;"] {
+                query(statement)
+                    .bind(user_id)
+                    .execute(&self.pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_user_locale(&self, user_id: &i64) -> Result<Option<String>> {
+        let locale: Option<Option<String>> = query_scalar("SELECT locale FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(locale.flatten());
+    }
+
+    async fn set_user_locale(&self, user_id: &i64, locale: Option<&str>) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, locale) VALUES (?, ?) ON DUPLICATE KEY UPDATE locale=VALUES(locale);")
+            .bind(user_id)
+            .bind(locale)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_user_timezone(&self, user_id: &i64) -> Result<Option<String>> {
+        let timezone: Option<Option<String>> = query_scalar("SELECT timezone FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(timezone.flatten());
+    }
+
+    async fn set_user_timezone(&self, user_id: &i64, timezone: Option<&str>) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, timezone) VALUES (?, ?) ON DUPLICATE KEY UPDATE timezone=VALUES(timezone);")
+            .bind(user_id)
+            .bind(timezone)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_private_stats(&self, user_id: &i64) -> Result<bool> {
+        let private: Option<bool> = query_scalar("SELECT private_stats FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(private.unwrap_or(false));
+    }
+
+    async fn set_private_stats(&self, user_id: &i64, private: bool) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, private_stats) VALUES (?, ?) ON DUPLICATE KEY UPDATE private_stats=VALUES(private_stats);")
+            .bind(user_id)
+            .bind(private)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_privacy_level(&self, user_id: &i64) -> Result<String> {
+        let level: Option<String> = query_scalar("SELECT privacy_level FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(level.unwrap_or_else(|| "public".to_string()));
+    }
+
+    async fn set_privacy_level(&self, user_id: &i64, level: &str) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, privacy_level) VALUES (?, ?) ON DUPLICATE KEY UPDATE privacy_level=VALUES(privacy_level);")
+            .bind(user_id)
+            .bind(level)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_paused_until(&self, user_id: &i64) -> Result<Option<i64>> {
+        let until: Option<Option<i64>> = query_scalar("SELECT paused_until FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(until.flatten());
+    }
+
+    async fn set_paused_until(&self, user_id: &i64, until: Option<i64>) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, paused_until) VALUES (?, ?) ON DUPLICATE KEY UPDATE paused_until=VALUES(paused_until);")
+            .bind(user_id)
+            .bind(until)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_vacation(&self, user_id: &i64) -> Result<Option<(i64, i64)>> {
+        let row: Option<(Option<i64>, Option<i64>)> = query_as("SELECT vacation_start, vacation_end FROM user_settings WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|(start, end)| start.zip(end)));
+    }
+
+    async fn set_vacation(&self, user_id: &i64, vacation: Option<(i64, i64)>) -> Result<()> {
+        query("INSERT INTO user_settings (user_id, vacation_start, vacation_end) VALUES (?, ?, ?)
+               ON DUPLICATE KEY UPDATE vacation_start=VALUES(vacation_start), vacation_end=VALUES(vacation_end);")
+            .bind(user_id)
+            .bind(vacation.map(|(start, _)| start))
+            .bind(vacation.map(|(_, end)| end))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, user_id: &i64, guild_id: Option<i64>, action: &str, details: &str, actiontime: &i64) -> Result<()> {
+        query("INSERT INTO audit_log (user_id, guild_id, action, details, actiontime) VALUES (?, ?, ?, ?, ?);")
+            .bind(user_id)
+            .bind(guild_id)
+            .bind(action)
+            .bind(details)
+            .bind(actiontime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_audit_log(&self, limit: i64, offset: i64) -> Result<Vec<(i64, String, String, i64)>> {
+        return Ok(query_as("SELECT user_id, action, details, actiontime FROM audit_log ORDER BY entry_id DESC LIMIT ? OFFSET ?;")
+            .bind(limit)
+            .bind(offset)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn count_audit_entries(&self) -> Result<i64> {
+        return Ok(query_scalar("SELECT COUNT(*) FROM audit_log;")
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn dump(&self) -> Result<Value> {
+        let schema_version = self.get_schema_version().await?;
+        let mut rows = serde_json::Map::new();
+        let tables = self.get_tables().await?;
+        let mut connection = self.pool.acquire().await?;
+        for table in tables {
+            let columns = table_columns(&mut connection, &table).await?;
+            // The rows are read one by one, MariaDB truncates the aggregated JSON arrays to group_concat_max_len
+            let table_rows: Vec<String> = query_scalar(&format!("SELECT CAST({} AS CHAR) FROM {};", row_object(&table, &columns, None), table))
+                                            .fetch_all(&mut *connection).await?;
+            let table_rows = table_rows.iter()
+                .map(|row| serde_json::from_str(row))
+                .collect::<serde_json::Result<Vec<Value>>>()?;
+            rows.insert(table, Value::Array(table_rows));
+        }
+        return Ok(json!({
+            "schema_version": schema_version,
+            "tables": rows,
+        }));
+    }
+
+    async fn restore(&self, tables: &serde_json::Map<String, Value>) -> Result<()> {
+        let current_tables = self.get_tables().await?;
+        let mut transaction = self.pool.begin().await?;
+        // The tables can be filled in any order without the foreign keys
+        query("SET FOREIGN_KEY_CHECKS=0;").execute(&mut *transaction).await?;
+        for table in &current_tables {
+            query(&format!("DELETE FROM {};", table)).execute(&mut *transaction).await?;
+        }
+        // Running sessions are dropped, their start times would count the time since the backup
+        for table in current_tables.iter().filter(|table| !RUNNING_SESSION_TABLES.contains(&table.as_str())) {
+            if let Some(Value::Array(rows)) = tables.get(table) {
+                insert_rows(&mut transaction, table, rows, &Duplicates::Fail).await?;
+            }
+        }
+        query("SET FOREIGN_KEY_CHECKS=1;").execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_schema_version(&self) -> Result<Option<i64>> {
+        return Ok(query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success;")
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn export_user(&self, user_id: &i64) -> Result<Value> {
+        let games: Vec<(String, i64)> = query_as("SELECT name, playtime FROM game_entries NATURAL JOIN games WHERE user_id=? ORDER BY playtime DESC;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let open_sessions: Vec<(String, i64)> = query_as("SELECT name, starttime FROM game_sessions NATURAL JOIN games WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let sessions: Vec<(String, i64, i64, i64, Option<String>, Option<String>, Option<i64>)> =
+            query_as("SELECT name, starttime, endtime, duration, state, details, party_size FROM session_history NATURAL JOIN games WHERE user_id=? ORDER BY starttime;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let history_rollup: Vec<(String, i64, i64, i64)> = query_as("SELECT name, day, playtime, sessions FROM history_rollup NATURAL JOIN games WHERE user_id=? ORDER BY day, name;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let voice_sessions: Vec<(i64, i64, i64, Option<i64>)> = query_as("SELECT guild_id, channel_id, starttime, endtime FROM voice_sessions WHERE user_id=? ORDER BY starttime;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let streams: Vec<(String, Option<String>, i64, i64, i64)> = query_as("SELECT game, url, starttime, endtime, duration FROM stream_history WHERE user_id=? ORDER BY starttime;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let listening: Vec<(String, i64)> = query_as("SELECT artist, listentime FROM listen_entries WHERE user_id=? ORDER BY listentime DESC;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let imported: Vec<(String, String, i64)> = query_as("SELECT name, source, playtime FROM imported_entries NATURAL JOIN games WHERE user_id=? ORDER BY source, playtime DESC;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let linked_accounts: Vec<(String, String)> = query_as("SELECT platform, account_id FROM linked_accounts WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let lfg_subscriptions: Vec<(i64, String, i64)> = query_as("SELECT guild_id, game_name, min_players FROM lfg_subscriptions WHERE user_id=? ORDER BY guild_id, game_name;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let levels: Vec<(i64, i64)> = query_as("SELECT guild_id, level FROM levels WHERE user_id=? ORDER BY guild_id;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?;
+        let digest: bool = query_scalar("SELECT EXISTS (SELECT 1 FROM digest_subscribers WHERE user_id=?);")
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(json!({
+            "user_id": user_id.to_string(),
+            "opted_out": self.is_opted_out(user_id).await?,
+            "games": games.into_iter().map(|(name, playtime)| json!({
+                "game": name,
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "open_sessions": open_sessions.into_iter().map(|(name, starttime)| json!({
+                "game": name,
+                "starttime": starttime,
+            })).collect::<Vec<Value>>(),
+            "sessions": sessions.into_iter().map(|(name, starttime, endtime, duration, state, details, party_size)| json!({
+                "game": name,
+                "starttime": starttime,
+                "endtime": endtime,
+                "duration": duration,
+                "state": state,
+                "details": details,
+                "party_size": party_size,
+            })).collect::<Vec<Value>>(),
+            "history_rollup": history_rollup.into_iter().map(|(name, day, playtime, sessions)| json!({
+                "game": name,
+                "day": day,
+                "playtime": playtime,
+                "sessions": sessions,
+            })).collect::<Vec<Value>>(),
+            "voice_sessions": voice_sessions.into_iter().map(|(guild_id, channel_id, starttime, endtime)| json!({
+                "guild_id": guild_id.to_string(),
+                "channel_id": channel_id.to_string(),
+                "starttime": starttime,
+                "endtime": endtime,
+            })).collect::<Vec<Value>>(),
+            "streams": streams.into_iter().map(|(game, url, starttime, endtime, duration)| json!({
+                "game": game,
+                "url": url,
+                "starttime": starttime,
+                "endtime": endtime,
+                "duration": duration,
+            })).collect::<Vec<Value>>(),
+            "listening": listening.into_iter().map(|(artist, listentime)| json!({
+                "artist": artist,
+                "listentime": listentime,
+            })).collect::<Vec<Value>>(),
+            "imported": imported.into_iter().map(|(name, source, playtime)| json!({
+                "game": name,
+                "source": source,
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "linked_accounts": linked_accounts.into_iter().map(|(platform, account_id)| json!({
+                "platform": platform,
+                "account_id": account_id,
+            })).collect::<Vec<Value>>(),
+            "settings": {
+                "locale": self.get_user_locale(user_id).await?,
+                "timezone": self.get_user_timezone(user_id).await?,
+                "privacy_level": self.get_privacy_level(user_id).await?,
+                "private_stats": self.get_private_stats(user_id).await?,
+                "paused_until": self.get_paused_until(user_id).await?,
+                "vacation": self.get_vacation(user_id).await?.map(|(start, end)| json!({"start": start, "end": end})),
+                "digest": digest,
+            },
+            "goals": self.get_user_goals(user_id).await?.into_iter().map(|(name, seconds, is_limit)| json!({
+                "game": name,
+                "seconds": seconds,
+                "is_limit": is_limit,
+            })).collect::<Vec<Value>>(),
+            "watches": self.get_watches(user_id).await?.into_iter().map(|(watched_id, game_name)| json!({
+                "user_id": watched_id.to_string(),
+                "game": game_name,
+            })).collect::<Vec<Value>>(),
+            "lfg_subscriptions": lfg_subscriptions.into_iter().map(|(guild_id, game_name, min_players)| json!({
+                "guild_id": guild_id.to_string(),
+                "game": game_name,
+                "min_players": min_players,
+            })).collect::<Vec<Value>>(),
+            "levels": levels.into_iter().map(|(guild_id, level)| json!({
+                "guild_id": guild_id.to_string(),
+                "level": level,
+            })).collect::<Vec<Value>>(),
+            "badges": self.get_unlocked_badges(user_id).await?.into_iter().map(|(badge, unlocked_at)| json!({
+                "badge": badge,
+                "unlocked_at": unlocked_at,
+            })).collect::<Vec<Value>>(),
+        }));
+    }
+
+    async fn get_all_entries(&self, guild_id: &i64) -> Result<Vec<(i64, String, i64, i64)>> {
+        return Ok(query_as("SELECT entries.user_id, name, playtime, CAST(COALESCE(SUM(history.sessions), 0) AS SIGNED) FROM game_entries entries
+                           JOIN games ON games.game_id=entries.game_id
+                           LEFT JOIN full_history history ON history.user_id=entries.user_id AND history.game_id=entries.game_id
+                           WHERE entries.user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                           AND entries.user_id IN (SELECT user_id FROM guild_members WHERE guild_id=?)
+                           GROUP BY entries.user_id, name, playtime
+                           ORDER BY entries.user_id, playtime DESC;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn dump_guild(&self, guild_id: &i64) -> Result<Value> {
+        let config = self.get_guild_config(guild_id).await?.unwrap_or_default();
+        let games: Vec<(i64, String)> = query_as("SELECT game_id, name FROM games
+                                                  WHERE game_id IN (SELECT game_id FROM game_entries WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)))
+                                                  OR game_id IN (SELECT game_id FROM game_sessions WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)))
+                                                  ORDER BY game_id;")
+            .bind(guild_id)
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?;
+        let aliases: Vec<(i64, String)> = query_as("SELECT game_id, alias FROM game_aliases ORDER BY alias;")
+                                            .fetch_all(&self.pool).await?;
+        let entries: Vec<(i64, i64, i64)> = query_as("SELECT user_id, game_id, playtime FROM game_entries
+                                                      WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                                      ORDER BY user_id, game_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?;
+        let open_sessions: Vec<(i64, i64, i64)> = query_as("SELECT user_id, game_id, starttime FROM game_sessions
+                                                            WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                                            ORDER BY user_id, game_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?;
+        let sessions: Vec<(i64, i64, i64, i64, i64)> = query_as("SELECT user_id, game_id, starttime, endtime, duration FROM session_history
+                                                                 WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=? AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                                                 ORDER BY session_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?;
+        let mut game_aliases: HashMap<i64, Vec<String>> = HashMap::new();
+        for (game_id, alias) in aliases {
+            game_aliases.entry(game_id).or_default().push(alias);
+        }
+        return Ok(json!({
+            "guild_id": guild_id.to_string(),
+            "config": config.to_json(),
+            "games": games.into_iter().map(|(game_id, name)| json!({
+                "game_id": game_id,
+                "name": name,
+                "aliases": game_aliases.remove(&game_id).unwrap_or_default(),
+            })).collect::<Vec<Value>>(),
+            "entries": entries.into_iter().map(|(user_id, game_id, playtime)| json!({
+                "user_id": user_id.to_string(),
+                "game_id": game_id,
+                "playtime": playtime,
+            })).collect::<Vec<Value>>(),
+            "open_sessions": open_sessions.into_iter().map(|(user_id, game_id, starttime)| json!({
+                "user_id": user_id.to_string(),
+                "game_id": game_id,
+                "starttime": starttime,
+            })).collect::<Vec<Value>>(),
+            "sessions": sessions.into_iter().map(|(user_id, game_id, starttime, endtime, duration)| json!({
+                "user_id": user_id.to_string(),
+                "game_id": game_id,
+                "starttime": starttime,
+                "endtime": endtime,
+                "duration": duration,
+            })).collect::<Vec<Value>>(),
+        }));
+    }
+
+    async fn has_admin_role(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<bool> {
+        if role_ids.is_empty() {
+            return Ok(false);
+        }
+        let statement = format!("SELECT EXISTS (SELECT 1 FROM admin_roles WHERE guild_id=? AND role_id IN ({}));", placeholders(role_ids.len()));
+        let mut admin_query = query_scalar(&statement).bind(guild_id);
+        for role_id in &role_ids {
+            admin_query = admin_query.bind(role_id);
+        }
+        return Ok(admin_query.fetch_one(&self.pool).await?);
+    }
+
+    async fn add_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        query("INSERT INTO admin_roles (guild_id, role_id) VALUES (?, ?) ON DUPLICATE KEY UPDATE guild_id=VALUES(guild_id);")
+            .bind(guild_id)
+            .bind(role_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        query("DELETE FROM admin_roles WHERE guild_id=? AND role_id=?;")
+            .bind(guild_id)
+            .bind(role_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_admin_roles(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT role_id FROM admin_roles WHERE guild_id=? ORDER BY role_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn claim_admin_roles(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<()> {
+        if role_ids.is_empty() {
+            return Ok(());
+        }
+        let statement = format!("UPDATE admin_roles SET guild_id=? WHERE guild_id=0 AND role_id IN ({});", placeholders(role_ids.len()));
+        let mut claim_query = query(&statement).bind(guild_id);
+        for role_id in &role_ids {
+            claim_query = claim_query.bind(role_id);
+        }
+        claim_query.execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn is_bot_admin(&self, user_id: &i64) -> Result<bool> {
+        return Ok(query_scalar("SELECT EXISTS (SELECT 1 FROM bot_admins WHERE user_id=?);")
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn add_bot_admin(&self, user_id: &i64) -> Result<()> {
+        query("INSERT IGNORE INTO bot_admins (user_id) VALUES (?);")
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_bot_admin(&self, user_id: &i64) -> Result<()> {
+        query("DELETE FROM bot_admins WHERE user_id=?;")
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_guild_config(&self, guild_id: &i64) -> Result<Option<GuildConfig>> {
+        #[allow(clippy::type_complexity)]
+        let row: Option<(Option<i64>, String, Option<i64>, String, i64, bool, i64, i64, Option<i64>, bool, String, bool, bool, bool, String, i64)> =
+            query_as("SELECT report_channel, report_cadence, min_session_length, locale, embed_color, whitelist_only, xp_per_hour, level_base_xp, level_channel, track_listening,
+                     title_template, show_thumbnails, anonymous_leaderboards, rank_manual_sessions, idle_policy, idle_minutes
+                     FROM guild_config WHERE guild_id=?;")
+            .bind(guild_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map(|row| GuildConfig {
+            report_channel: row.0,
+            report_cadence: row.1,
+            min_session_length: row.2,
+            locale: row.3,
+            embed_color: row.4,
+            whitelist_only: row.5,
+            xp_per_hour: row.6,
+            level_base_xp: row.7,
+            level_channel: row.8,
+            track_listening: row.9,
+            title_template: row.10,
+            show_thumbnails: row.11,
+            anonymous_leaderboards: row.12,
+            rank_manual_sessions: row.13,
+            idle_policy: row.14,
+            idle_minutes: row.15,
+        }));
+    }
+
+    async fn save_guild_config(&self, guild_id: &i64, config: &GuildConfig) -> Result<()> {
+        query("INSERT INTO guild_config (guild_id, report_channel, min_session_length, locale, embed_color, whitelist_only, xp_per_hour, level_base_xp, level_channel, track_listening,
+               title_template, show_thumbnails, report_cadence, anonymous_leaderboards, rank_manual_sessions, idle_policy, idle_minutes)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+               ON DUPLICATE KEY UPDATE report_channel=VALUES(report_channel), report_cadence=VALUES(report_cadence), min_session_length=VALUES(min_session_length),
+               locale=VALUES(locale), embed_color=VALUES(embed_color), whitelist_only=VALUES(whitelist_only),
+               xp_per_hour=VALUES(xp_per_hour), level_base_xp=VALUES(level_base_xp), level_channel=VALUES(level_channel),
+               track_listening=VALUES(track_listening), title_template=VALUES(title_template), show_thumbnails=VALUES(show_thumbnails),
+               anonymous_leaderboards=VALUES(anonymous_leaderboards), rank_manual_sessions=VALUES(rank_manual_sessions),
+               idle_policy=VALUES(idle_policy), idle_minutes=VALUES(idle_minutes);")
+            .bind(guild_id)
+            .bind(config.report_channel)
+            .bind(config.min_session_length)
+            .bind(&config.locale)
+            .bind(config.embed_color)
+            .bind(config.whitelist_only)
+            .bind(config.xp_per_hour)
+            .bind(config.level_base_xp)
+            .bind(config.level_channel)
+            .bind(config.track_listening)
+            .bind(&config.title_template)
+            .bind(config.show_thumbnails)
+            .bind(&config.report_cadence)
+            .bind(config.anonymous_leaderboards)
+            .bind(config.rank_manual_sessions)
+            .bind(&config.idle_policy)
+            .bind(config.idle_minutes)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_digest_subscription(&self, user_id: &i64, subscribed: bool) -> Result<()> {
+        let statement = match subscribed {
+            true => "INSERT IGNORE INTO digest_subscribers (user_id) VALUES (?);",
+            false => "DELETE FROM digest_subscribers WHERE user_id=?;",
+        };
+        query(statement)
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_digest_subscribers(&self) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query_as("SELECT user_id, timezone FROM digest_subscribers LEFT JOIN user_settings USING (user_id) WHERE NOT COALESCE(opted_out, FALSE);")
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn set_goal(&self, user_id: &i64, game_id: &i64, seconds: &i64, is_limit: bool) -> Result<()> {
+        query("INSERT INTO goals (user_id, game_id, seconds, is_limit) VALUES (?, ?, ?, ?)
+               ON DUPLICATE KEY UPDATE seconds=VALUES(seconds), is_limit=VALUES(is_limit), notified_week=NULL;")
+            .bind(user_id)
+            .bind(game_id)
+            .bind(seconds)
+            .bind(is_limit)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_goal(&self, user_id: &i64, game_id: &i64) -> Result<bool> {
+        let result = query("DELETE FROM goals WHERE user_id=? AND game_id=?;")
+            .bind(user_id)
+            .bind(game_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_user_goals(&self, user_id: &i64) -> Result<Vec<(String, i64, bool)>> {
+        return Ok(query_as("SELECT name, seconds, is_limit FROM goals NATURAL JOIN games WHERE user_id=? ORDER BY name;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_goal_users(&self) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query_as("SELECT DISTINCT user_id, timezone FROM goals LEFT JOIN user_settings USING (user_id) WHERE NOT COALESCE(opted_out, FALSE);")
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_reached_goals(&self, user_id: &i64, week_start: &i64, currenttime: &i64) -> Result<Vec<(i64, String, i64, bool)>> {
+        // <=> compares NULLs like IS DISTINCT FROM
+        return Ok(query_as("SELECT goals.game_id, name, seconds, is_limit FROM goals
+                           JOIN games ON games.game_id=goals.game_id
+                           WHERE goals.user_id=? AND NOT notified_week <=> ?
+                           AND COALESCE((SELECT SUM(endtime - GREATEST(starttime, ?)) FROM session_history history
+                                           WHERE history.user_id=goals.user_id AND history.game_id=goals.game_id AND endtime > ?), 0)
+                               + COALESCE((SELECT ? - GREATEST(starttime, ?) FROM game_sessions sessions
+                                           WHERE sessions.user_id=goals.user_id AND sessions.game_id=goals.game_id), 0)
+                               >= seconds;")
+            .bind(user_id)
+            .bind(week_start)
+            .bind(week_start)
+            .bind(week_start)
+            .bind(currenttime)
+            .bind(week_start)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn set_goal_notified(&self, user_id: &i64, game_id: &i64, week_start: &i64) -> Result<()> {
+        query("UPDATE goals SET notified_week=? WHERE user_id=? AND game_id=?;")
+            .bind(week_start)
+            .bind(user_id)
+            .bind(game_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    // The watches of every game are stored with an empty game name
+    async fn add_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let result = query("INSERT IGNORE INTO watches (watcher_id, user_id, game_name) VALUES (?, ?, ?);")
+            .bind(watcher_id)
+            .bind(user_id)
+            .bind(game_name.unwrap_or(""))
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn remove_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let result = query("DELETE FROM watches WHERE watcher_id=? AND user_id=? AND game_name=?;")
+            .bind(watcher_id)
+            .bind(user_id)
+            .bind(game_name.unwrap_or(""))
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_watches(&self, watcher_id: &i64) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query_as("SELECT user_id, NULLIF(game_name, '') FROM watches WHERE watcher_id=? ORDER BY user_id, game_name;")
+            .bind(watcher_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_watchers(&self, user_id: &i64, game_name: &str) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT DISTINCT watcher_id FROM watches WHERE user_id=? AND game_name IN ('', ?);")
+            .bind(user_id)
+            .bind(game_name)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn set_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str, min_players: &i64) -> Result<()> {
+        query("INSERT INTO lfg_subscriptions (guild_id, user_id, game_name, min_players) VALUES (?, ?, ?, ?)
+               ON DUPLICATE KEY UPDATE min_players=VALUES(min_players), notified=FALSE;")
+            .bind(guild_id)
+            .bind(user_id)
+            .bind(game_name)
+            .bind(min_players)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query("DELETE FROM lfg_subscriptions WHERE guild_id=? AND user_id=? AND game_name=?;")
+            .bind(guild_id)
+            .bind(user_id)
+            .bind(game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_lfg_subscriptions(&self, guild_id: &i64, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT game_name, min_players FROM lfg_subscriptions WHERE guild_id=? AND user_id=? ORDER BY game_name;")
+            .bind(guild_id)
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_lfg_subscribers(&self, guild_id: &i64, game_name: &str) -> Result<Vec<(i64, i64, bool)>> {
+        return Ok(query_as("SELECT user_id, min_players, notified FROM lfg_subscriptions WHERE guild_id=? AND game_name=?;")
+            .bind(guild_id)
+            .bind(game_name)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn set_lfg_notified(&self, guild_id: &i64, user_id: &i64, game_name: &str, notified: bool) -> Result<()> {
+        query("UPDATE lfg_subscriptions SET notified=? WHERE guild_id=? AND user_id=? AND game_name=?;")
+            .bind(notified)
+            .bind(guild_id)
+            .bind(user_id)
+            .bind(game_name)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_game_role(&self, guild_id: &i64, game_name: &str, role_id: &i64, channel_id: &i64) -> Result<()> {
+        query("INSERT INTO game_roles (guild_id, game_name, role_id, channel_id) VALUES (?, ?, ?, ?)
+               ON DUPLICATE KEY UPDATE role_id=VALUES(role_id), channel_id=VALUES(channel_id);")
+            .bind(guild_id)
+            .bind(game_name)
+            .bind(role_id)
+            .bind(channel_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_game_role(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query("DELETE FROM game_roles WHERE guild_id=? AND game_name=?;")
+            .bind(guild_id)
+            .bind(game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_game_roles(&self, guild_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query_as("SELECT game_name, role_id, channel_id FROM game_roles WHERE guild_id=? ORDER BY game_name;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_game_role(&self, guild_id: &i64, game_name: &str) -> Result<Option<(i64, i64, Option<i64>)>> {
+        return Ok(query_as("SELECT role_id, channel_id, last_ping FROM game_roles WHERE guild_id=? AND game_name=?;")
+            .bind(guild_id)
+            .bind(game_name)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn set_game_role_pinged(&self, guild_id: &i64, game_name: &str, pingtime: &i64) -> Result<()> {
+        query("UPDATE game_roles SET last_ping=? WHERE guild_id=? AND game_name=?;")
+            .bind(pingtime)
+            .bind(guild_id)
+            .bind(game_name)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_role_reward(&self, guild_id: &i64, role_id: &i64, seconds: &i64) -> Result<()> {
+        query("INSERT INTO role_rewards (guild_id, role_id, seconds) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE seconds=VALUES(seconds);")
+            .bind(guild_id)
+            .bind(role_id)
+            .bind(seconds)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_role_reward(&self, guild_id: &i64, role_id: &i64) -> Result<bool> {
+        let result = query("DELETE FROM role_rewards WHERE guild_id=? AND role_id=?;")
+            .bind(guild_id)
+            .bind(role_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_role_rewards(&self, guild_id: &i64) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT role_id, seconds FROM role_rewards WHERE guild_id=? ORDER BY seconds;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_level(&self, guild_id: &i64, user_id: &i64) -> Result<i64> {
+        let level: Option<i64> = query_scalar("SELECT level FROM levels WHERE guild_id=? AND user_id=?;")
+            .bind(guild_id)
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(level.unwrap_or(0));
+    }
+
+    async fn set_level(&self, guild_id: &i64, user_id: &i64, level: &i64) -> Result<()> {
+        query("INSERT INTO levels (guild_id, user_id, level) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE level=VALUES(level);")
+            .bind(guild_id)
+            .bind(user_id)
+            .bind(level)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_achievement_stats(&self, user_id: &i64) -> Result<AchievementStats> {
+        // The hour is taken in UTC like the Postgres sessions on Shuttle, FROM_UNIXTIME would use the server's time zone
+        let (night_sessions, longest_session, games, playtime): (i64, i64, i64, i64) = query_as("SELECT
+                (SELECT CAST(COALESCE(SUM(night_sessions), 0) AS SIGNED) FROM full_history WHERE user_id=?),
+                (SELECT COALESCE(MAX(longest), 0) FROM full_history WHERE user_id=?),
+                (SELECT COUNT(*) FROM game_entries WHERE user_id=?),
+                (SELECT CAST(COALESCE(SUM(playtime), 0) AS SIGNED) FROM game_entries WHERE user_id=?);")
+            .bind(user_id)
+            .bind(user_id)
+            .bind(user_id)
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(AchievementStats { night_sessions, longest_session, games, playtime });
+    }
+
+    async fn get_unlocked_badges(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query_as("SELECT badge, unlocked_at FROM unlocked_badges WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn unlock_badge(&self, user_id: &i64, badge: &str, unlocked_at: &i64) -> Result<()> {
+        query("INSERT IGNORE INTO unlocked_badges (user_id, badge, unlocked_at) VALUES (?, ?, ?);")
+            .bind(user_id)
+            .bind(badge)
+            .bind(unlocked_at)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn start_voice_session(&self, user_id: &i64, guild_id: &i64, channel_id: &i64, starttime: &i64) -> Result<()> {
+        // The voice state updates of a user come one after the other, checking for an open session is enough
+        query("INSERT INTO voice_sessions (user_id, guild_id, channel_id, starttime)
+               SELECT ?, ?, ?, ? FROM DUAL WHERE NOT EXISTS (SELECT 1 FROM voice_sessions WHERE user_id=? AND endtime IS NULL);")
+            .bind(user_id)
+            .bind(guild_id)
+            .bind(channel_id)
+            .bind(starttime)
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn end_voice_session(&self, user_id: &i64, endtime: &i64) -> Result<()> {
+        query("UPDATE voice_sessions SET endtime=? WHERE user_id=? AND endtime IS NULL;")
+            .bind(endtime)
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn end_all_voice_sessions(&self, endtime: &i64) -> Result<()> {
+        query("UPDATE voice_sessions SET endtime=? WHERE endtime IS NULL;")
+            .bind(endtime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_voice_users(&self) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT user_id FROM voice_sessions WHERE endtime IS NULL;")
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_voice_time(&self, user_id: &i64, currenttime: &i64) -> Result<(i64, i64)> {
+        // Overlapping games are counted once per game, capping at the voice session's length keeps it sane
+        return Ok(query_as("SELECT CAST(COALESCE(SUM(duration), 0) AS SIGNED), CAST(COALESCE(SUM(LEAST(duration, playing)), 0) AS SIGNED) FROM (
+                               SELECT voice.voice_end - voice.starttime AS duration,
+                                   (SELECT COALESCE(SUM(LEAST(voice.voice_end, history.endtime) - GREATEST(voice.starttime, history.starttime)), 0)
+                                       FROM session_history history
+                                       WHERE history.user_id=voice.user_id AND history.endtime > voice.starttime AND history.starttime < voice.voice_end)
+                                   + (SELECT COALESCE(SUM(voice.voice_end - GREATEST(voice.starttime, game.starttime)), 0)
+                                       FROM game_sessions game
+                                       WHERE game.user_id=voice.user_id AND game.starttime < voice.voice_end) AS playing
+                               FROM (SELECT user_id, starttime, COALESCE(endtime, ?) AS voice_end FROM voice_sessions WHERE user_id=?) voice
+                           ) sessions;")
+            .bind(currenttime)
+            .bind(user_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn add_webhook(&self, guild_id: &i64, url: &str) -> Result<()> {
+        query("INSERT IGNORE INTO webhooks (guild_id, url) VALUES (?, ?);")
+            .bind(guild_id)
+            .bind(url)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_webhook(&self, guild_id: &i64, webhook_id: &i64) -> Result<bool> {
+        let result = query("DELETE FROM webhooks WHERE guild_id=? AND webhook_id=?;")
+            .bind(guild_id)
+            .bind(webhook_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_webhooks(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(query_as("SELECT webhook_id, url FROM webhooks WHERE guild_id=? ORDER BY webhook_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_report_channels(&self, cadence: &str) -> Result<Vec<(i64, i64)>> {
+        return Ok(query_as("SELECT guild_id, report_channel FROM guild_config WHERE report_channel IS NOT NULL AND report_cadence=?;")
+            .bind(cadence)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn add_guild_members(&self, guild_id: &i64, user_ids: &[i64], seen: &i64) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        for user_id in user_ids {
+            query("INSERT INTO guild_members (guild_id, user_id, seen) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE seen=VALUES(seen);")
+                .bind(guild_id)
+                .bind(user_id)
+                .bind(seen)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn remove_guild_member(&self, guild_id: &i64, user_id: &i64) -> Result<()> {
+        query("DELETE FROM guild_members WHERE guild_id=? AND user_id=?;")
+            .bind(guild_id)
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn prune_guild_members(&self, guild_id: &i64, before: &i64) -> Result<()> {
+        query("DELETE FROM guild_members WHERE guild_id=? AND seen < ?;")
+            .bind(guild_id)
+            .bind(before)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_guild_members(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT user_id FROM guild_members WHERE guild_id=? ORDER BY user_id;")
+            .bind(guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_user_guilds(&self, user_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT guild_id FROM guild_members WHERE user_id=? ORDER BY guild_id;")
+            .bind(user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn ping(&self) -> Result<()> {
+        query("SELECT 1;").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn count_open_sessions(&self) -> Result<i64> {
+        return Ok(query_scalar("SELECT COUNT(*) FROM game_sessions;")
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn prune_history(&self, before: &i64) -> Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        query("INSERT INTO history_rollup (user_id, game_id, day, playtime, sessions, longest, night_sessions)
+               SELECT user_id, game_id, starttime - MOD(starttime, 86400), SUM(duration), COUNT(*), MAX(duration), SUM(MOD(starttime, 86400) < 5 * 3600)
+               FROM session_history WHERE endtime<?
+               GROUP BY user_id, game_id, starttime - MOD(starttime, 86400)
+               ON DUPLICATE KEY UPDATE playtime=playtime+VALUES(playtime), sessions=sessions+VALUES(sessions),
+               longest=GREATEST(longest, VALUES(longest)), night_sessions=night_sessions+VALUES(night_sessions);")
+            .bind(before)
+            .execute(&mut *transaction).await?;
+        let result = query("DELETE FROM session_history WHERE endtime<?;")
+            .bind(before)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Ok(result.rows_affected());
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!("./migrations_mysql").run(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_session_users(&self) -> Result<Vec<i64>> {
+        return Ok(query_scalar("SELECT user_id FROM game_sessions UNION SELECT user_id FROM stream_sessions UNION SELECT user_id FROM listen_sessions;")
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn update_status(&self, user_id: &i64, away: bool, currenttime: &i64) -> Result<Option<(bool, i64)>> {
+        let previous: Option<(bool, i64)> = query_as("SELECT away, since FROM user_status WHERE user_id=?;")
+            .bind(user_id)
+                                            .fetch_optional(&self.pool).await?;
+        if previous.map(|(was_away, _)| was_away) != Some(away) {
+            query("INSERT INTO user_status (user_id, away, since) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE away=VALUES(away), since=VALUES(since);")
+                .bind(user_id)
+                .bind(away)
+                .bind(currenttime)
+                .execute(&self.pool).await?;
+        }
+        return Ok(previous);
+    }
+
+    async fn resetall(&self) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let reset_id = self.start_reset(&mut transaction, None).await?;
+        // Running sessions aren't archived, they would count the time until the undo
+        for table in RUNNING_SESSION_TABLES {
+            query(&format!("DELETE FROM {};", table)).execute(&mut *transaction).await?;
+        }
+        for (table, _) in ARCHIVED_TABLES.iter().rev().filter(|(table, _)| *table != "linked_accounts") {
+            archive_rows(&mut transaction, &reset_id, table, None).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn reset(&self, user_id: &i64) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let reset_id = self.start_reset(&mut transaction, Some(user_id)).await?;
+        for table in RUNNING_SESSION_TABLES {
+            query(&format!("DELETE FROM {} WHERE user_id=?;", table))
+                .bind(user_id)
+                .execute(&mut *transaction).await?;
+        }
+        for (table, _) in ARCHIVED_TABLES.iter().rev().filter(|(table, _)| !matches!(*table, "games" | "game_aliases" | "goals")) {
+            archive_rows(&mut transaction, &reset_id, table, Some(user_id)).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn undo_reset(&self) -> Result<Option<Option<i64>>> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let row: Option<(i64, Option<i64>)> = query_as("SELECT reset_id, user_id FROM resets WHERE resettime >= ? ORDER BY reset_id DESC LIMIT 1;")
+            .bind(currenttime - RESET_UNDO_WINDOW)
+                                            .fetch_optional(&self.pool).await?;
+        let (reset_id, user_id) = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        info!("Undoing reset {:?}", reset_id);
+        let mut transaction = self.pool.begin().await?;
+        let archived: Vec<(String, String)> = query_as("SELECT table_name, `row` FROM reset_archive WHERE reset_id=?;")
+            .bind(reset_id)
+                                            .fetch_all(&mut *transaction).await?;
+        let mut archived_rows: HashMap<String, Vec<Value>> = HashMap::new();
+        for (table, row) in archived {
+            archived_rows.entry(table).or_default().push(serde_json::from_str(&row)?);
+        }
+        // Games tracked again since the reset are matched by name, the rows of games deleted since are dropped
+        let mut game_ids: HashMap<i64, i64> = HashMap::new();
+        let mut existing_games: HashSet<i64> = HashSet::new();
+        for (table, duplicates) in &ARCHIVED_TABLES {
+            let mut rows = archived_rows.remove(*table).unwrap_or_default();
+            if *table != "games" {
+                for row in rows.iter_mut() {
+                    if let Some(game_id) = row.get("game_id").and_then(Value::as_i64) {
+                        row["game_id"] = json!(game_ids.get(&game_id).copied().unwrap_or(game_id));
+                    }
+                }
+                rows.retain(|row| row.get("game_id").and_then(Value::as_i64).map_or(true, |game_id| existing_games.contains(&game_id)));
+            }
+            insert_rows(&mut transaction, table, &rows, duplicates).await?;
+            if *table == "games" {
+                let games: Vec<(i64, String)> = query_as("SELECT game_id, name FROM games;")
+                                            .fetch_all(&mut *transaction).await?;
+                let names: HashMap<String, i64> = games.into_iter().map(|(game_id, name)| (name, game_id)).collect();
+                for row in &rows {
+                    if let (Some(game_id), Some(name)) = (row["game_id"].as_i64(), row["name"].as_str()) {
+                        if let Some(current_id) = names.get(name) {
+                            game_ids.insert(game_id, *current_id);
+                        }
+                    }
+                }
+                existing_games = names.into_values().collect();
+            }
+        }
+        query("DELETE FROM resets WHERE reset_id=?;")
+            .bind(reset_id)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Ok(Some(user_id));
+    }
+
+    async fn hardreset(&self) -> Result<()> {
+        self.resetall().await?;
+        // The archived game ids would clash with the recreated tables
+        query("DELETE FROM resets;").execute(&self.pool).await?;
+        for table in ["session_history", "history_rollup", "game_entries", "game_sessions", "goals", "game_aliases", "imported_entries", "games"] {
+            query(&format!("DROP TABLE {};", table)).execute(&self.pool).await?;
+        }
+        // Forget the applied migrations so the dropped tables get recreated
+        query("DELETE FROM _sqlx_migrations;").execute(&self.pool).await?;
+        self.migrate().await?;
+        Ok(())
+    }
+}
+
+// `count` comma separated placeholders
+fn placeholders(count: usize) -> String {
+    return vec!["?"; count].join(", ");
+}
+
+// `column NOT IN (...)` with `count` placeholders, an empty list excludes nothing
+fn not_in(column: &str, count: usize) -> String {
+    if count == 0 {
+        return "TRUE".to_string();
+    }
+    return format!("{} NOT IN ({})", column, placeholders(count));
+}
+
+// The column names of a table, in their order
+async fn table_columns(connection: &mut MySqlConnection, table: &str) -> Result<Vec<String>> {
+    return Ok(query_scalar("SELECT CAST(column_name AS CHAR) FROM information_schema.columns
+                           WHERE table_schema=DATABASE() AND table_name=? ORDER BY ordinal_position;")
+        .bind(table)
+                                            .fetch_all(connection).await?);
+}
+
+// Some columns are named after keywords, like the row of reset_archive
+fn quoted(columns: &[String]) -> String {
+    return columns.iter().map(|column| format!("`{}`", column)).collect::<Vec<String>>().join(", ");
+}
+
+// A JSON_OBJECT of the columns of a row of `table`, read from the `alias` of the table when set
+fn row_object(table: &str, columns: &[String], alias: Option<&str>) -> String {
+    let prefix = alias.map(|alias| format!("{}.", alias)).unwrap_or_default();
+    let fields: Vec<String> = columns.iter()
+        .map(|column| match JSON_COLUMNS.contains(&(table, column.as_str())) {
+            true => format!("'{column}', JSON_EXTRACT({prefix}`{column}`, '$')"),
+            false => format!("'{column}', {prefix}`{column}`"),
+        })
+        .collect();
+    return format!("JSON_OBJECT({})", fields.join(", "));
+}
+
+// Binds a value of a JSON row, nested JSON is stored as text
+fn bind_value<'q>(statement: Query<'q, MySql, MySqlArguments>, value: Option<&Value>) -> Query<'q, MySql, MySqlArguments> {
+    return match value {
+        None | Some(Value::Null) => statement.bind(None::<i64>),
+        Some(Value::Bool(value)) => statement.bind(*value),
+        Some(Value::Number(number)) => match number.as_i64() {
+            Some(number) => statement.bind(number),
+            None => statement.bind(number.as_f64()),
+        },
+        Some(Value::String(value)) => statement.bind(value.clone()),
+        Some(value) => statement.bind(value.to_string()),
+    };
+}
+
+// Inserts JSON rows, the columns they lack are left NULL
+async fn insert_rows(transaction: &mut Transaction<'_, MySql>, table: &str, rows: &[Value], duplicates: &Duplicates) -> Result<()> {
+    let columns = table_columns(transaction, table).await?;
+    let row_placeholders = format!("({})", placeholders(columns.len()));
+    for batch in rows.chunks(INSERT_BATCH) {
+        let statement = format!("INSERT {}INTO {} ({}) VALUES {}{};",
+                                if matches!(duplicates, Duplicates::Skip) { "IGNORE " } else { "" },
+                                table, quoted(&columns), vec![row_placeholders.as_str(); batch.len()].join(", "),
+                                match duplicates {
+                                    Duplicates::Update(assignments) => format!(" ON DUPLICATE KEY UPDATE {}", assignments),
+                                    _ => String::new(),
+                                });
+        let mut insert = query(&statement);
+        for row in batch {
+            for column in &columns {
+                insert = bind_value(insert, row.get(column));
+            }
+        }
+        insert.execute(&mut **transaction).await?;
+    }
+    Ok(())
+}
+
+// Moves the rows of `table`, only the user's ones when `user_id` is set, to reset_archive
+async fn archive_rows(transaction: &mut Transaction<'_, MySql>, reset_id: &i64, table: &str, user_id: Option<&i64>) -> Result<()> {
+    let columns = table_columns(transaction, table).await?;
+    let condition = if user_id.is_some() { "WHERE user_id=?" } else { "" };
+    let statement = format!("INSERT INTO reset_archive (reset_id, table_name, `row`) SELECT ?, '{}', {} FROM {} archived {};",
+                            table, row_object(table, &columns, Some("archived")), table, condition);
+    let mut archive = query(&statement).bind(reset_id);
+    if let Some(user_id) = user_id {
+        archive = archive.bind(user_id);
+    }
+    archive.execute(&mut **transaction).await?;
+    let statement = format!("DELETE FROM {} {};", table, condition);
+    let mut delete = query(&statement);
+    if let Some(user_id) = user_id {
+        delete = delete.bind(user_id);
+    }
+    delete.execute(&mut **transaction).await?;
+    Ok(())
+}
+
+// Each test gets its own migrated database on the server of DATABASE_URL
+#[cfg(all(test, feature = "mysql-tests"))]
+mod tests {
+    use super::MySqlStorage;
+    use crate::db::{RichPresence, Storage};
+    use chrono::Utc;
+    use serde_json::json;
+    use sqlx::{query, query_as};
+    use sqlx::mysql::MySqlPool;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const GUILD_ID: i64 = 1;
+
+    fn storage(pool: &MySqlPool) -> MySqlStorage {
+        return MySqlStorage::new(pool.clone(), 60, DAY);
+    }
+
+    // Plays a game for `seconds` up to now and returns the saved playtime
+    async fn play(db: &MySqlStorage, user_id: &i64, game_name: &str, seconds: i64) -> i64 {
+        let (_, started) = db.register_session(user_id, game_name, &(Utc::now().timestamp() - seconds), &RichPresence::default()).await.unwrap();
+        assert!(started);
+        let saved = db.save_session(user_id, &[], None, &Utc::now().timestamp()).await.unwrap();
+        return saved.iter().map(|(_, playtime)| playtime).sum();
+    }
+
+    // Sessions can't start before the previous one ended, this makes room for the next ones
+    async fn move_history_back(pool: &MySqlPool, seconds: i64) {
+        query("UPDATE session_history SET starttime=starttime-?, endtime=endtime-?;").bind(seconds).bind(seconds).execute(pool).await.unwrap();
+    }
+
+    fn assert_near(playtime: i64, expected: i64) {
+        assert!((expected..expected + 5).contains(&playtime), "{} isn't close to {}", playtime, expected);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn registers_a_session_once(pool: MySqlPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 600;
+        let (game_id, started) = db.register_session(&1, "Factorio", &starttime, &RichPresence::default()).await.unwrap();
+        assert!(started);
+        // Every presence update of a running game registers it again
+        assert_eq!(db.register_session(&1, "factorio ", &starttime, &RichPresence::default()).await.unwrap(), (game_id, false));
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!((sessions[0].game_id, sessions[0].name.as_str(), sessions[0].starttime), (game_id, "Factorio", starttime));
+        assert_eq!(db.count_open_sessions().await.unwrap(), 1);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn keeps_the_last_rich_presence_of_a_session(pool: MySqlPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 3600;
+        let queued = RichPresence { state: Some("In Queue".to_string()), details: None, party_size: Some(1) };
+        let (game_id, _) = db.register_session(&1, "VALORANT", &starttime, &queued).await.unwrap();
+        let playing = RichPresence { state: Some("In a Party".to_string()), details: Some("Competitive".to_string()), party_size: Some(5) };
+        db.register_session(&1, "VALORANT", &starttime, &playing).await.unwrap();
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!((sessions[0].details.as_deref(), sessions[0].party_size), (Some("Competitive"), Some(5)));
+        db.save_session(&1, &[], None, &Utc::now().timestamp()).await.unwrap();
+        let details = db.get_top_details(&1, &game_id, 3).await.unwrap();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].0, "Competitive");
+        assert_near(details[0].1, 3600);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn searches_the_names_literally(pool: MySqlPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp();
+        db.register_session(&1, "100% Orange Juice", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "100 Orange Juice", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Half-Life", &starttime, &RichPresence::default()).await.unwrap();
+        assert_eq!(db.search_games("100%").await.unwrap(), vec!["100% Orange Juice".to_string()]);
+        assert!(db.search_games("Half_Life").await.unwrap().is_empty());
+        assert!(db.find_game("_").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn clamps_untrusted_start_times(pool: MySqlPool) {
+        let db = storage(&pool);
+        let currenttime = Utc::now().timestamp();
+        db.register_session(&1, "Factorio", &(currenttime + 600), &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Celeste", &(currenttime - 2 * DAY), &RichPresence::default()).await.unwrap();
+        for session in db.get_open_sessions(&1).await.unwrap() {
+            assert_near(session.starttime, currenttime);
+        }
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn accumulates_the_playtime_of_saved_sessions(pool: MySqlPool) {
+        let db = storage(&pool);
+        assert_near(play(&db, &1, "Factorio", 3600).await, 3600);
+        move_history_back(&pool, DAY).await;
+        assert_near(play(&db, &1, "Factorio", 1800).await, 1800);
+        assert_near(db.get_total_playtime(&1).await.unwrap(), 5400);
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.get_game_stats(&1, &game_id).await.unwrap().sessions, 2);
+        assert!(db.get_open_sessions(&1).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn discards_short_sessions(pool: MySqlPool) {
+        let db = storage(&pool);
+        assert_eq!(play(&db, &1, "Factorio", 30).await, 0);
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), 0);
+        assert!(db.get_open_sessions(&1).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn only_saves_the_closed_games(pool: MySqlPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 3600;
+        let (factorio_id, _) = db.register_session(&1, "Factorio", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Celeste", &starttime, &RichPresence::default()).await.unwrap();
+        let saved = db.save_session(&1, &[factorio_id], None, &Utc::now().timestamp()).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].0, "Celeste");
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].game_id, factorio_id);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn ranks_the_leaderboards(pool: MySqlPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 7200).await;
+        play(&db, &2, "Celeste", 600).await;
+        play(&db, &3, "Celeste", 1800).await;
+        let leaderboard = db.get_leaderboard(None, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![2, 1, 3]);
+        assert_near(leaderboard[0].1, 7800);
+        let (game_id, _) = db.find_game("Celeste").await.unwrap().unwrap();
+        let game_leaderboard = db.get_game_leaderboard(None, &game_id, false).await.unwrap();
+        assert_eq!(game_leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![3, 2]);
+        // Users who opted out aren't ranked
+        db.set_opted_out(&2, true).await.unwrap();
+        let leaderboard = db.get_leaderboard(None, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 3]);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn only_ranks_the_members_of_the_guild(pool: MySqlPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 7200).await;
+        play(&db, &3, "Celeste", 1800).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 3], &(currenttime - DAY)).await.unwrap();
+        db.add_guild_members(&2, &[2], &currenttime).await.unwrap();
+        let leaderboard = db.get_leaderboard(Some(GUILD_ID), false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 3]);
+        let top_games = db.get_server_top_games(Some(GUILD_ID), None, false).await.unwrap();
+        assert_eq!(top_games[0].0, "Factorio");
+        assert_near(top_games[0].1, 3600);
+        // A user who left is still ranked among the members
+        assert_eq!(db.get_user_totals(&2, Some(GUILD_ID)).await.unwrap().map(|totals| (totals.rank, totals.ranked_users)), Some((1, 3)));
+        assert_eq!(db.get_user_rank(&3, Some(GUILD_ID), Some(currenttime - DAY)).await.unwrap(), Some((2, 2)));
+        // The members Discord didn't list since are forgotten
+        db.add_guild_members(&GUILD_ID, &[3], &currenttime).await.unwrap();
+        db.prune_guild_members(&GUILD_ID, &currenttime).await.unwrap();
+        let leaderboard = db.get_leaderboard(Some(GUILD_ID), false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![3]);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn only_lists_the_admin_roles_of_the_guild(pool: MySqlPool) {
+        let db = storage(&pool);
+        db.add_admin_role(&GUILD_ID, &10).await.unwrap();
+        db.add_admin_role(&2, &20).await.unwrap();
+        assert_eq!(db.get_admin_roles(&GUILD_ID).await.unwrap(), vec![10]);
+        assert!(db.has_admin_role(&GUILD_ID, vec![10, 20]).await.unwrap());
+        assert!(!db.has_admin_role(&GUILD_ID, vec![20]).await.unwrap());
+        // The roles added before they were stored per guild have no guild until their guild is seen
+        db.add_admin_role(&0, &30).await.unwrap();
+        db.claim_admin_roles(&GUILD_ID, vec![20, 30]).await.unwrap();
+        assert_eq!(db.get_admin_roles(&GUILD_ID).await.unwrap(), vec![10, 30]);
+        assert_eq!(db.get_admin_roles(&2).await.unwrap(), vec![20]);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn ranks_the_period_leaderboard_by_the_time_in_the_period(pool: MySqlPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 7200).await;
+        play(&db, &2, "Celeste", 3600).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 2], &currenttime).await.unwrap();
+        let leaderboard = db.get_period_leaderboard(&GUILD_ID, &(currenttime - 5400), &currenttime, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 2]);
+        assert!(leaderboard[0].1 <= 5400);
+        let top_games = db.get_period_top_games(&GUILD_ID, &(currenttime - 5400), &currenttime).await.unwrap();
+        assert_eq!(top_games[0].name, "Factorio");
+        assert!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - 3 * DAY), &(currenttime - 2 * DAY), false).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn resets_a_user_until_undone(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 1800).await;
+        db.reset(&1).await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), 0);
+        assert_near(db.get_total_playtime(&2).await.unwrap(), 1800);
+        assert_eq!(db.undo_reset().await.unwrap(), Some(Some(1)));
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), playtime);
+        assert_eq!(db.undo_reset().await.unwrap(), None);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn resets_everyone_until_undone(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        db.register_session(&2, "Celeste", &(Utc::now().timestamp() - 600), &RichPresence::default()).await.unwrap();
+        db.resetall().await.unwrap();
+        assert!(db.get_leaderboard(None, false).await.unwrap().is_empty());
+        assert!(db.find_game("Factorio").await.unwrap().is_none());
+        // Running sessions would count the time until the undo
+        assert_eq!(db.count_open_sessions().await.unwrap(), 0);
+        assert_eq!(db.undo_reset().await.unwrap(), Some(None));
+        assert_eq!(db.get_leaderboard(None, false).await.unwrap(), vec![(1, playtime)]);
+        assert!(db.find_game("Factorio").await.unwrap().is_some());
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn merges_the_games_played_since_the_reset_when_undoing_it(pool: MySqlPool) {
+        let db = storage(&pool);
+        let before = play(&db, &1, "Factorio", 3600).await;
+        db.resetall().await.unwrap();
+        let after = play(&db, &1, "Factorio", 1800).await;
+        db.undo_reset().await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), before + after);
+        assert_eq!(db.search_games("Factorio").await.unwrap(), vec!["Factorio".to_string()]);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn prunes_the_history_but_keeps_the_playtime(pool: MySqlPool) {
+        let db = storage(&pool);
+        let before = play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        let after = play(&db, &1, "Factorio", 1800).await;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 1);
+        assert_eq!(db.get_recent_sessions(&1, 10).await.unwrap().len(), 1);
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), before + after);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn keeps_the_stats_of_the_pruned_history(pool: MySqlPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        play(&db, &1, "Factorio", 1800).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        let (start, end) = (Utc::now().timestamp() - 30 * DAY, Utc::now().timestamp() + DAY);
+        let stats = db.get_game_stats(&1, &game_id).await.unwrap();
+        let playtime = db.get_user_period_playtime(&1, &start, &end).await.unwrap();
+        let longest_session = db.get_achievement_stats(&1).await.unwrap().longest_session;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 1);
+        let pruned_stats = db.get_game_stats(&1, &game_id).await.unwrap();
+        assert_eq!((pruned_stats.sessions, pruned_stats.playtime), (stats.sessions, stats.playtime));
+        assert_eq!(db.get_user_period_playtime(&1, &start, &end).await.unwrap(), playtime);
+        assert_eq!(db.get_achievement_stats(&1).await.unwrap().longest_session, longest_session);
+        assert_eq!(db.get_summary_games(&1, Some(start), 10, 0).await.unwrap()[0].sessions, 2);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn exports_every_table_of_the_user(pool: MySqlPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        db.set_goal(&1, &game_id, &7200, false).await.unwrap();
+        db.add_watch(&1, &2, Some("Factorio")).await.unwrap();
+        db.set_lfg_subscription(&GUILD_ID, &1, "Factorio", &3).await.unwrap();
+        db.set_user_timezone(&1, Some("Europe/Paris")).await.unwrap();
+        db.set_digest_subscription(&1, true).await.unwrap();
+        db.set_level(&GUILD_ID, &1, &2).await.unwrap();
+        db.unlock_badge(&1, "marathon", &0).await.unwrap();
+        let export = db.export_user(&1).await.unwrap();
+        assert_eq!(export.as_object().unwrap().keys().collect::<Vec<&String>>(), ["badges", "games", "goals", "history_rollup", "imported", "levels", "lfg_subscriptions",
+            "linked_accounts", "listening", "open_sessions", "opted_out", "sessions", "settings", "streams", "user_id", "voice_sessions", "watches"]);
+        assert_eq!((&export["settings"]["timezone"], &export["settings"]["digest"]), (&json!("Europe/Paris"), &json!(true)));
+        for key in ["goals", "watches", "lfg_subscriptions", "levels", "badges"] {
+            assert_eq!(export[key].as_array().unwrap().len(), 1, "{}", key);
+        }
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn rolls_the_pruned_history_up_per_day(pool: MySqlPool) {
+        let db = storage(&pool);
+        let first = play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        let second = play(&db, &1, "Factorio", 1800).await;
+        move_history_back(&pool, 10 * DAY).await;
+        play(&db, &1, "Factorio", 600).await;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 2);
+        let rollup: Vec<(i64, i64, i64)> = query_as("SELECT day, playtime, sessions FROM history_rollup WHERE user_id=1 ORDER BY day;")
+                                            .fetch_all(&pool).await.unwrap();
+        assert_eq!(rollup.iter().map(|(_, playtime, sessions)| (*playtime, *sessions)).collect::<Vec<_>>(), [(first, 1), (second, 1)]);
+        assert!(rollup.iter().all(|(day, _, _)| day % DAY == 0));
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn adjusts_the_playtime_without_going_under_zero(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.adjust_playtime(&1, &game_id, &600).await.unwrap(), playtime + 600);
+        assert_eq!(db.adjust_playtime(&1, &game_id, &-(2 * playtime)).await.unwrap(), 0);
+        assert!(db.get_top_games(&1, None, 10, 0).await.unwrap().is_empty());
+        assert_eq!(db.adjust_playtime(&2, &game_id, &1800).await.unwrap(), 1800);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn ranks_the_manual_sessions_only_when_asked(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 2], &currenttime).await.unwrap();
+        assert_eq!(db.log_session(&2, "factorio", &(currenttime - 7200), &7200).await.unwrap(), "Factorio");
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.get_leaderboard(None, false).await.unwrap(), vec![(1, playtime)]);
+        assert_eq!(db.get_leaderboard(None, true).await.unwrap(), vec![(2, 7200), (1, playtime)]);
+        assert_eq!(db.get_game_leaderboard(None, &game_id, false).await.unwrap(), vec![(1, playtime)]);
+        assert_eq!(db.get_game_leaderboard(None, &game_id, true).await.unwrap(), vec![(2, 7200), (1, playtime)]);
+        assert_eq!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - DAY), &(currenttime + 60), false).await.unwrap().len(), 1);
+        assert_eq!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - DAY), &(currenttime + 60), true).await.unwrap().len(), 2);
+        assert_eq!(db.get_imported_playtime(&2).await.unwrap(), vec![("manual".to_string(), 7200)]);
+        assert_eq!(db.get_recent_sessions(&2, 10).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn imports_the_playtime_on_top_of_the_tracked_one(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        db.import_playtime(&[(1, "factorio".to_string(), 600), (2, "Outer Wilds".to_string(), 1800), (2, "Outer Wilds".to_string(), 60)]).await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), playtime + 600);
+        assert_eq!(db.get_total_playtime(&2).await.unwrap(), 1860);
+        assert!(db.find_game("Outer Wilds").await.unwrap().is_some());
+        assert_eq!(db.get_recent_sessions(&2, 10).await.unwrap().len(), 0);
+    }
+
+    // The backups restore into an empty database
+    #[sqlx::test(migrations = "./migrations_mysql")]
+    async fn restores_a_dump(pool: MySqlPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        db.set_opted_out(&2, true).await.unwrap();
+        let dump = db.dump().await.unwrap();
+        db.restore(dump["tables"].as_object().unwrap()).await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), playtime);
+        assert!(db.is_opted_out(&2).await.unwrap());
+        assert_eq!(db.dump().await.unwrap()["tables"], dump["tables"]);
+    }
+}
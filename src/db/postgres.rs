@@ -0,0 +1,2032 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use log::LevelFilter;
+use sqlx::{query, query_as, ConnectOptions, Postgres, Row, PgPool, Transaction};
+use tracing::{info, warn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tokio::sync::RwLock;
+
+use crate::writer::{Ending, PresenceWriter, Registration};
+use super::{clean_game_name, contains_pattern, game_key, AchievementStats, GameEntry, GameMetadata, GameStats, GameSummary, GuildConfig, RichPresence, Session, Storage, UserTotals, RESET_UNDO_WINDOW};
+
+
+// Queries taking longer are logged as warnings
+const SLOW_QUERY: Duration = Duration::from_millis(250);
+
+// Tables of the sessions still running and of the statuses, their rows are only valid until the bot stops
+const RUNNING_SESSION_TABLES: [&str; 5] = ["game_sessions", "voice_sessions", "stream_sessions", "listen_sessions", "user_status"];
+
+// Tables moved to reset_archive by the resets, in the order they are restored,
+// with how a restored row is combined with the one tracked since the reset
+const ARCHIVED_TABLES: [(&str, &str); 12] = [
+    ("games", "DO NOTHING"),
+    ("game_aliases", "DO NOTHING"),
+    ("game_entries", "(user_id, game_id) DO UPDATE SET playtime=game_entries.playtime+EXCLUDED.playtime"),
+    ("session_history", "DO NOTHING"),
+    ("history_rollup", "(user_id, game_id, day) DO UPDATE SET playtime=history_rollup.playtime+EXCLUDED.playtime, sessions=history_rollup.sessions+EXCLUDED.sessions,
+                        longest=GREATEST(history_rollup.longest, EXCLUDED.longest), night_sessions=history_rollup.night_sessions+EXCLUDED.night_sessions"),
+    ("goals", "DO NOTHING"),
+    ("levels", "(guild_id, user_id) DO UPDATE SET level=GREATEST(levels.level, EXCLUDED.level)"),
+    ("unlocked_badges", "DO NOTHING"),
+    ("stream_history", "DO NOTHING"),
+    ("listen_entries", "(user_id, artist) DO UPDATE SET listentime=listen_entries.listentime+EXCLUDED.listentime"),
+    ("imported_entries", "DO NOTHING"),
+    ("linked_accounts", "DO NOTHING"),
+];
+
+pub struct PgStorage {
+    pool: PgPool,
+    // game_aliases as seen by resolve_game, presence bursts mostly hit known games
+    game_ids: RwLock<HashMap<String, i64>>,
+    // Batches the game session writes of the presence updates
+    writer: PresenceWriter,
+    // Sessions shorter than this many seconds are discarded
+    min_session_length: i64,
+    // Sessions longer than this many seconds are clamped
+    max_session_length: i64,
+}
+
+impl PgStorage {
+    pub fn new(pool: PgPool, min_session_length: i64, max_session_length: i64) -> Self {
+        // Every query is logged with its duration at the debug level, the slow ones are warned about.
+        // Only the connections opened from now on use it
+        let connect_options = (*pool.connect_options()).clone()
+            .log_statements(LevelFilter::Debug)
+            .log_slow_statements(LevelFilter::Warn, SLOW_QUERY);
+        pool.set_connect_options(connect_options);
+        let writer = PresenceWriter::start(pool.clone());
+        return PgStorage { pool, game_ids: RwLock::new(HashMap::new()), writer, min_session_length, max_session_length };
+    }
+
+    // Returns the game a presence name counts as, adding it when it was never seen before
+    async fn resolve_game(&self, game_name: &str) -> Result<i64> {
+        let alias = game_key(game_name);
+        if let Some(game_id) = self.game_ids.read().await.get(&alias) {
+            return Ok(*game_id);
+        }
+        let row = query!("SELECT game_id FROM game_aliases WHERE alias=$1;", &alias)
+                                            .fetch_optional(&self.pool).await?;
+        if let Some(row) = row {
+            self.game_ids.write().await.insert(alias, row.game_id);
+            return Ok(row.game_id);
+        }
+        let game_id: i64 = self.add_game(&clean_game_name(game_name)).await?;
+        self.set_alias(&alias, &game_id).await?;
+        return Ok(game_id);
+    }
+
+    async fn set_alias(&self, alias: &str, game_id: &i64) -> Result<()> {
+        query!("INSERT INTO game_aliases (alias, game_id) VALUES ($1, $2) ON CONFLICT (alias) DO UPDATE SET game_id=EXCLUDED.game_id;", alias, game_id)
+            .execute(&self.pool).await?;
+        self.game_ids.write().await.insert(alias.to_string(), *game_id);
+        Ok(())
+    }
+
+    // Called whenever game_aliases or games change outside of set_alias
+    async fn forget_game_ids(&self) {
+        self.game_ids.write().await.clear();
+    }
+
+    // Returns the id of the game, adding it unless it exists
+    // A single statement, two presence updates of a new game would both try to add it otherwise
+    async fn add_game(&self, game_name: &str) -> Result<i64> {
+        let row = query!("INSERT INTO games (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name=EXCLUDED.name RETURNING game_id;", game_name)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.game_id);
+    }
+
+    async fn get_tables(&self) -> Result<Vec<String>> {
+        return Ok(query!(r#"SELECT table_name::TEXT AS "table_name!" FROM information_schema.tables
+                        WHERE table_schema='public' AND table_type='BASE TABLE' AND table_name<>'_sqlx_migrations'
+                        ORDER BY table_name;"#)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.table_name).collect());
+    }
+
+    // Records a reset and forgets the ones that can't be undone anymore
+    async fn start_reset(&self, transaction: &mut Transaction<'_, Postgres>, user_id: Option<&i64>) -> Result<i64> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query!("DELETE FROM resets WHERE resettime < $1;", currenttime - RESET_UNDO_WINDOW)
+            .execute(&mut **transaction).await?;
+        let row = query!("INSERT INTO resets (user_id, resettime) VALUES ($1, $2) RETURNING reset_id;", user_id, currenttime)
+                                            .fetch_one(&mut **transaction).await?;
+        return Ok(row.reset_id);
+    }
+}
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn save_session(&self, user_id: &i64, playing: &[i64], min_session_length: Option<i64>, endtime: &i64) -> Result<Vec<(String, i64)>> {
+        let mut saved: Vec<(String, i64)> = Vec::new();
+        let mut endings: Vec<Ending> = Vec::new();
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let sessions: Vec<Session> = query_as!(Session, "SELECT game_id, name, starttime, state, details, party_size FROM game_sessions NATURAL JOIN games WHERE user_id=$1 AND NOT game_id = ANY($2);", user_id, playing)
+                                            .fetch_all(&self.pool).await?;
+        // The writer adds the playtime of the kept sessions and deletes all of them in a single transaction
+        for session in sessions {
+            let mut playtime: i64 = endtime - session.starttime;
+            // Sessions left open while the bot missed the game being closed would count the whole time
+            if playtime > self.max_session_length {
+                warn!(user_id, game = %session.name, duration = playtime, "Clamping the session to {}s", self.max_session_length);
+                playtime = self.max_session_length;
+            }
+            let kept = playtime >= min_session_length;
+            if kept {
+                info!(user_id, game = %session.name, duration = playtime, "Saving the session");
+                saved.push((session.name, playtime));
+            } else {
+                info!(user_id, game = %session.name, duration = playtime, "Discarding the session, it is shorter than {}s", min_session_length);
+            }
+            let rich_presence = RichPresence { state: session.state, details: session.details, party_size: session.party_size };
+            endings.push(Ending { user_id: *user_id, game_id: session.game_id, starttime: endtime - playtime, endtime: *endtime, kept, rich_presence });
+        }
+        self.writer.end(endings).await?;
+        return Ok(saved);
+    }
+
+    async fn get_top_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameEntry>> {
+        return Ok(match start {
+            Some(start) => query_as!(GameEntry, r#"SELECT name, SUM(endtime - GREATEST(starttime, $2))::BIGINT AS "playtime!" FROM full_history NATURAL JOIN games
+                                    WHERE user_id=$1 AND endtime > $2 GROUP BY name ORDER BY 2 DESC LIMIT $3 OFFSET $4;"#, user_id, start, limit, offset)
+                                            .fetch_all(&self.pool).await?,
+            None => query_as!(GameEntry, "SELECT name, playtime FROM game_entries NATURAL JOIN games WHERE user_id=$1 ORDER BY playtime DESC LIMIT $2 OFFSET $3;", user_id, limit, offset)
+                                            .fetch_all(&self.pool).await?,
+        });
+    }
+
+    async fn get_summary_games(&self, user_id: &i64, start: Option<i64>, limit: i64, offset: i64) -> Result<Vec<GameSummary>> {
+        return Ok(match start {
+            Some(start) => query_as!(GameSummary, r#"SELECT name, SUM(endtime - GREATEST(starttime, $2))::BIGINT AS "playtime!", SUM(sessions)::BIGINT AS "sessions!",
+                                    (SUM(duration) / SUM(sessions))::BIGINT AS "average_session?"
+                                    FROM full_history NATURAL JOIN games
+                                    WHERE user_id=$1 AND endtime > $2 GROUP BY name ORDER BY 2 DESC LIMIT $3 OFFSET $4;"#, user_id, start, limit, offset)
+                                            .fetch_all(&self.pool).await?,
+            // The sessions are counted separately, joining them would repeat the playtime of the entry
+            None => query_as!(GameSummary, r#"SELECT name, playtime, COALESCE(sessions, 0) AS "sessions!", average_session AS "average_session?" FROM game_entries NATURAL JOIN games
+                                    LEFT JOIN (SELECT game_id, SUM(sessions)::BIGINT AS sessions, (SUM(duration) / SUM(sessions))::BIGINT AS average_session FROM full_history
+                                               WHERE user_id=$1 GROUP BY game_id) history USING (game_id)
+                                    WHERE user_id=$1 ORDER BY playtime DESC LIMIT $2 OFFSET $3;"#, user_id, limit, offset)
+                                            .fetch_all(&self.pool).await?,
+        });
+    }
+
+    async fn count_games(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let count = match start {
+            Some(start) => query!(r#"SELECT COUNT(DISTINCT game_id) AS "count!" FROM full_history WHERE user_id=$1 AND endtime > $2;"#, user_id, start)
+                                            .fetch_one(&self.pool).await?.count,
+            None => query!(r#"SELECT COUNT(*) AS "count!" FROM game_entries WHERE user_id=$1;"#, user_id)
+                                            .fetch_one(&self.pool).await?.count,
+        };
+        return Ok(count);
+    }
+
+    async fn get_game_playtime(&self, user_id: &i64, game_id: &i64) -> Result<i64> {
+        return Ok(query!("SELECT playtime FROM game_entries WHERE user_id=$1 AND game_id=$2;", user_id, game_id)
+                                            .fetch_optional(&self.pool).await?
+                                            .map_or(0, |row| row.playtime));
+    }
+
+    async fn adjust_playtime(&self, user_id: &i64, game_id: &i64, seconds: &i64) -> Result<i64> {
+        let mut transaction = self.pool.begin().await?;
+        let playtime = query!("INSERT INTO game_entries (user_id, game_id, playtime) VALUES ($1, $2, GREATEST($3::BIGINT, 0))
+                              ON CONFLICT (user_id, game_id) DO UPDATE SET playtime=GREATEST(game_entries.playtime+$3, 0) RETURNING playtime;",
+            user_id, game_id, seconds)
+                                            .fetch_one(&mut *transaction).await?.playtime;
+        // The game would still be listed with no playtime
+        if playtime == 0 {
+            query!("DELETE FROM game_entries WHERE user_id=$1 AND game_id=$2;", user_id, game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        return Ok(playtime);
+    }
+
+    async fn log_session(&self, user_id: &i64, game_name: &str, starttime: &i64, duration: &i64) -> Result<String> {
+        let game_id = self.resolve_game(game_name).await?;
+        let mut transaction = self.pool.begin().await?;
+        query!("INSERT INTO session_history (user_id, game_id, starttime, endtime, duration, source) VALUES ($1, $2, $3, $4, $5, 'manual');",
+            user_id, game_id, starttime, starttime + duration, duration)
+            .execute(&mut *transaction).await?;
+        query!("INSERT INTO imported_entries (user_id, game_id, source, playtime) VALUES ($1, $2, 'manual', $3)
+                ON CONFLICT (user_id, game_id, source) DO UPDATE SET playtime=imported_entries.playtime+EXCLUDED.playtime;", user_id, game_id, duration)
+            .execute(&mut *transaction).await?;
+        let name = query!("SELECT name FROM games WHERE game_id=$1;", game_id)
+                                            .fetch_one(&mut *transaction).await?.name;
+        transaction.commit().await?;
+        return Ok(name);
+    }
+
+    async fn import_playtime(&self, rows: &[(i64, String, i64)]) -> Result<()> {
+        // The known games are looked up at once, only the new ones are added one by one
+        let mut aliases: Vec<String> = rows.iter().map(|(_, game_name, _)| game_key(game_name)).collect();
+        aliases.sort();
+        aliases.dedup();
+        let mut game_ids: HashMap<String, i64> = query!("SELECT alias, game_id FROM game_aliases WHERE alias = ANY($1);", &aliases)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.alias, row.game_id)).collect();
+        for (_, game_name, _) in rows {
+            if let Entry::Vacant(entry) = game_ids.entry(game_key(game_name)) {
+                entry.insert(self.resolve_game(game_name).await?);
+            }
+        }
+        let mut transaction = self.pool.begin().await?;
+        for (user_id, game_name, playtime) in rows {
+            let game_id = game_ids[&game_key(game_name)];
+            query!("INSERT INTO game_entries (user_id, game_id, playtime) VALUES ($1, $2, $3)
+                    ON CONFLICT (user_id, game_id) DO UPDATE SET playtime=game_entries.playtime+EXCLUDED.playtime;", user_id, game_id, playtime)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_game_stats(&self, user_id: &i64, game_id: &i64) -> Result<GameStats> {
+        return Ok(query_as!(GameStats, r#"SELECT COALESCE(SUM(sessions), 0)::BIGINT AS "sessions!", COALESCE(SUM(duration), 0)::BIGINT AS "playtime!", MIN(starttime) AS first_played, MAX(endtime) AS last_played
+                        FROM full_history WHERE user_id=$1 AND game_id=$2;"#, user_id, game_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_top_details(&self, user_id: &i64, game_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!(r#"SELECT COALESCE(details, state) AS "details!", SUM(duration)::BIGINT AS "playtime!" FROM session_history
+                           WHERE user_id=$1 AND game_id=$2 AND COALESCE(details, state) IS NOT NULL GROUP BY 1 ORDER BY 2 DESC, 1 LIMIT $3;"#, user_id, game_id, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.details, row.playtime)).collect());
+    }
+
+    async fn get_total_playtime(&self, user_id: &i64) -> Result<i64> {
+        let row = query!(r#"SELECT COALESCE(SUM(playtime), 0)::BIGINT AS "playtime!" FROM game_entries WHERE user_id=$1;"#, user_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.playtime);
+    }
+
+    async fn get_open_sessions(&self, user_id: &i64) -> Result<Vec<Session>> {
+        return Ok(query_as!(Session, "SELECT game_id, name, starttime, state, details, party_size FROM game_sessions NATURAL JOIN games WHERE user_id=$1 ORDER BY starttime;", user_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_open_sessions_by_game(&self) -> Result<Vec<(String, Vec<i64>)>> {
+        return Ok(query!(r#"SELECT name, ARRAY_AGG(user_id ORDER BY starttime) AS "user_ids!" FROM game_sessions NATURAL JOIN games GROUP BY name ORDER BY COUNT(*) DESC, name;"#)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.user_ids)).collect());
+    }
+
+    async fn get_recent_sessions(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query!("SELECT name, starttime, duration FROM session_history NATURAL JOIN games WHERE user_id=$1 ORDER BY endtime DESC LIMIT $2;", user_id, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.starttime, row.duration)).collect());
+    }
+
+    async fn get_longest_session(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<(String, i64, i64)>> {
+        return Ok(query!(r#"SELECT name, starttime AS "starttime!", longest AS "longest!" FROM full_history NATURAL JOIN games
+                        WHERE user_id=$1 AND starttime >= $2 AND starttime < $3 ORDER BY longest DESC LIMIT 1;"#, user_id, start, end)
+                                            .fetch_optional(&self.pool).await?
+                                            .map(|row| (row.name, row.starttime, row.longest)));
+    }
+
+    async fn get_session_durations(&self, user_id: &i64, game_id: Option<i64>) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT duration FROM session_history WHERE user_id=$1 AND ($2::BIGINT IS NULL OR game_id=$2) ORDER BY duration;", user_id, game_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.duration).collect());
+    }
+
+    async fn get_period_leaderboard(&self, guild_id: &i64, start: &i64, end: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!(r#"SELECT user_id AS "user_id!", SUM(LEAST(endtime, $2) - GREATEST(starttime, $1))::BIGINT AS "total!" FROM full_history
+                        WHERE endtime > $1 AND starttime < $2 AND (source<>'manual' OR $3) AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$4)
+                        GROUP BY user_id ORDER BY 2 DESC LIMIT 10;"#, start, end, include_manual, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.total)).collect());
+    }
+
+    async fn get_period_top_games(&self, guild_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        return Ok(query_as!(GameEntry, r#"SELECT name, SUM(LEAST(endtime, $2) - GREATEST(starttime, $1))::BIGINT AS "playtime!" FROM full_history NATURAL JOIN games
+                        WHERE endtime > $1 AND starttime < $2 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$3)
+                        GROUP BY name ORDER BY 2 DESC LIMIT 10;"#, start, end, guild_id)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_server_top_games(&self, guild_id: Option<i64>, start: Option<i64>, by_players: bool) -> Result<Vec<(String, i64, i64)>> {
+        // The ranking key comes first so the other one breaks the ties
+        return Ok(match start {
+            Some(start) => query!(r#"SELECT name, SUM(endtime - GREATEST(starttime, $1))::BIGINT AS "playtime!", COUNT(DISTINCT user_id) AS "players!" FROM full_history NATURAL JOIN games
+                        WHERE endtime > $1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND ($3::BIGINT IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$3))
+                        GROUP BY name ORDER BY CASE WHEN $2 THEN COUNT(DISTINCT user_id) ELSE SUM(endtime - GREATEST(starttime, $1)) END DESC, 2 DESC LIMIT 10;"#, start, by_players, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.playtime, row.players)).collect(),
+            None => query!(r#"SELECT name, SUM(playtime)::BIGINT AS "playtime!", COUNT(user_id) AS "players!" FROM game_entries NATURAL JOIN games
+                        WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND ($2::BIGINT IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$2))
+                        GROUP BY name ORDER BY CASE WHEN $1 THEN COUNT(user_id) ELSE SUM(playtime) END DESC, 2 DESC LIMIT 10;"#, by_players, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.playtime, row.players)).collect(),
+        });
+    }
+
+    async fn get_user_period_playtime(&self, user_id: &i64, start: &i64, end: &i64) -> Result<i64> {
+        let row = query!(r#"SELECT COALESCE(SUM(LEAST(endtime, $3) - GREATEST(starttime, $2)), 0)::BIGINT AS "playtime!" FROM full_history
+                        WHERE user_id=$1 AND endtime > $2 AND starttime < $3;"#, user_id, start, end)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.playtime);
+    }
+
+    async fn get_user_period_games(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Vec<GameEntry>> {
+        return Ok(query_as!(GameEntry, r#"SELECT name, SUM(LEAST(endtime, $3) - GREATEST(starttime, $2))::BIGINT AS "playtime!" FROM full_history NATURAL JOIN games
+                        WHERE user_id=$1 AND endtime > $2 AND starttime < $3 GROUP BY name ORDER BY 2 DESC, name;"#, user_id, start, end)
+                                            .fetch_all(&self.pool).await?);
+    }
+
+    async fn get_user_period_top_game(&self, user_id: &i64, start: &i64, end: &i64) -> Result<Option<GameEntry>> {
+        return Ok(query_as!(GameEntry, r#"SELECT name, SUM(LEAST(endtime, $3) - GREATEST(starttime, $2))::BIGINT AS "playtime!" FROM full_history NATURAL JOIN games
+                        WHERE user_id=$1 AND endtime > $2 AND starttime < $3 GROUP BY name ORDER BY 2 DESC LIMIT 1;"#, user_id, start, end)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn get_sessions_since(&self, user_id: &i64, start: &i64) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!(r#"SELECT starttime AS "starttime!", endtime AS "endtime!" FROM full_history WHERE user_id=$1 AND endtime > $2;"#, user_id, start)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.starttime, row.endtime)).collect());
+    }
+
+    async fn get_user_totals(&self, user_id: &i64, guild_id: Option<i64>) -> Result<Option<UserTotals>> {
+        // The user is ranked even after leaving the guild
+        return Ok(query_as!(UserTotals, r#"SELECT total AS "playtime!", games AS "games!", rank AS "rank!", ranked_users AS "ranked_users!" FROM (
+                            SELECT user_id, SUM(playtime)::BIGINT AS total, COUNT(*) AS games,
+                                RANK() OVER (ORDER BY SUM(playtime) DESC) AS rank, COUNT(*) OVER () AS ranked_users
+                            FROM game_entries
+                            WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                            AND ($2::BIGINT IS NULL OR user_id=$1 OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$2))
+                            GROUP BY user_id
+                        ) totals WHERE user_id=$1;"#, user_id, guild_id)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn get_user_rank(&self, user_id: &i64, guild_id: Option<i64>, start: Option<i64>) -> Result<Option<(i64, i64)>> {
+        let rank = match start {
+            Some(start) => query!(r#"SELECT rank AS "rank!", ranked_users AS "ranked_users!" FROM (
+                            SELECT user_id, RANK() OVER (ORDER BY SUM(endtime - GREATEST(starttime, $2)) DESC) AS rank, COUNT(*) OVER () AS ranked_users
+                            FROM full_history
+                            WHERE endtime > $2 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                            AND ($3::BIGINT IS NULL OR user_id=$1 OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$3))
+                            GROUP BY user_id
+                        ) ranks WHERE user_id=$1;"#, user_id, start, guild_id)
+                                            .fetch_optional(&self.pool).await?
+                                            .map(|row| (row.rank, row.ranked_users)),
+            None => self.get_user_totals(user_id, guild_id).await?.map(|totals| (totals.rank, totals.ranked_users)),
+        };
+        return Ok(rank);
+    }
+
+    async fn get_shared_games(&self, user1_id: &i64, user2_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query!("SELECT name, first.playtime AS first_playtime, second.playtime AS second_playtime FROM game_entries first
+                        JOIN game_entries second ON first.game_id=second.game_id
+                        JOIN games ON games.game_id=first.game_id
+                        WHERE first.user_id=$1 AND second.user_id=$2
+                        ORDER BY first.playtime + second.playtime DESC LIMIT 10;", user1_id, user2_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.first_playtime, row.second_playtime)).collect());
+    }
+
+    async fn get_leaderboard(&self, guild_id: Option<i64>, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!(r#"SELECT user_id AS "user_id!", SUM(playtime)::BIGINT AS "total!" FROM
+                        (SELECT user_id, playtime FROM game_entries
+                        UNION ALL SELECT user_id, playtime FROM imported_entries WHERE source='manual' AND $1) entries
+                        WHERE user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND ($2::BIGINT IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$2))
+                        GROUP BY user_id ORDER BY 2 DESC LIMIT 10;"#, include_manual, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.total)).collect());
+    }
+
+    async fn get_game_leaderboard(&self, guild_id: Option<i64>, game_id: &i64, include_manual: bool) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!(r#"SELECT user_id AS "user_id!", SUM(playtime)::BIGINT AS "playtime!" FROM
+                        (SELECT user_id, game_id, playtime FROM game_entries
+                        UNION ALL SELECT user_id, game_id, playtime FROM imported_entries WHERE source='manual' AND $2) entries
+                        WHERE game_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND ($3::BIGINT IS NULL OR user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$3))
+                        GROUP BY user_id ORDER BY 2 DESC LIMIT 10;"#, game_id, include_manual, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.playtime)).collect());
+    }
+
+    async fn find_game(&self, game_name: &str) -> Result<Option<(i64, String)>> {
+        let row = query!("SELECT game_id, name FROM game_aliases NATURAL JOIN games WHERE alias=$1;", game_key(game_name))
+                                            .fetch_optional(&self.pool).await?;
+        if let Some(row) = row {
+            return Ok(Some((row.game_id, row.name)));
+        }
+        let row = query!(r"SELECT game_id, name FROM games WHERE LOWER(name)=LOWER($1) OR name ILIKE $2 ESCAPE '\' ORDER BY LOWER(name)=LOWER($1) DESC, LENGTH(name) LIMIT 1;",
+                        game_name.trim(), contains_pattern(game_name.trim()))
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map(|row| (row.game_id, row.name)));
+    }
+
+    async fn search_games(&self, game_name: &str) -> Result<Vec<String>> {
+        return Ok(query!(r"SELECT name FROM games WHERE name ILIKE $1 ESCAPE '\' ORDER BY name LIMIT 25;", contains_pattern(game_name))
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.name).collect());
+    }
+
+    async fn register_session(&self, user_id: &i64, game_name: &str, starttime: &i64, rich_presence: &RichPresence) -> Result<(i64, bool)> {
+        let game_id: i64 = self.resolve_game(game_name).await?;
+        // The start timestamp comes from the client, don't trust one in the future or older than a session can be
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let mut starttime: i64 = *starttime;
+        if starttime > currenttime || currenttime - starttime > self.max_session_length {
+            warn!("{:?}'s session of {:?} starts at {:?}, starting it now instead", user_id, game_name, starttime);
+            starttime = currenttime;
+        }
+        let started = self.writer.register(Registration { user_id: *user_id, game_id, starttime, rich_presence: rich_presence.clone() }).await?;
+        return Ok((game_id, started));
+    }
+
+    async fn register_stream(&self, user_id: &i64, game_name: &str, url: Option<&str>) -> Result<()> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query!("INSERT INTO stream_sessions (user_id, game, url, starttime) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING;",
+            user_id, clean_game_name(game_name), url, currenttime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn save_stream(&self, user_id: &i64, streaming: Option<&str>, min_session_length: Option<i64>) -> Result<()> {
+        let min_session_length = min_session_length.unwrap_or(self.min_session_length);
+        let stream = query!("SELECT game, url, starttime FROM stream_sessions WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        let stream = match stream {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+        if streaming.map(clean_game_name).as_ref() == Some(&stream.game) {
+            return Ok(());
+        }
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let duration: i64 = std::cmp::min(currenttime - stream.starttime, self.max_session_length);
+        if duration >= min_session_length {
+            info!(user_id, game = %stream.game, duration, "Saving the stream");
+            query!("INSERT INTO stream_history (user_id, game, url, starttime, endtime, duration) VALUES ($1, $2, $3, $4, $5, $6);",
+                user_id, stream.game, stream.url, currenttime - duration, currenttime, duration)
+                .execute(&self.pool).await?;
+        }
+        query!("DELETE FROM stream_sessions WHERE user_id=$1;", user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_stream_time(&self, user_id: &i64, start: Option<i64>) -> Result<i64> {
+        let row = query!(r#"SELECT COALESCE(SUM(endtime - GREATEST(starttime, $2)), 0)::BIGINT AS "stream_time!" FROM stream_history WHERE user_id=$1 AND endtime > $2;"#,
+                        user_id, start.unwrap_or(0))
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.stream_time);
+    }
+
+    async fn get_top_streamed_games(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!(r#"SELECT game, SUM(duration)::BIGINT AS "total!" FROM stream_history WHERE user_id=$1 GROUP BY game ORDER BY 2 DESC LIMIT $2;"#, user_id, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game, row.total)).collect());
+    }
+
+    async fn get_recent_streams(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, Option<String>, i64, i64)>> {
+        return Ok(query!("SELECT game, url, starttime, duration FROM stream_history WHERE user_id=$1 ORDER BY endtime DESC LIMIT $2;", user_id, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game, row.url, row.starttime, row.duration)).collect());
+    }
+
+    async fn register_listen(&self, user_id: &i64, artist: &str) -> Result<()> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        query!("INSERT INTO listen_sessions (user_id, artist, starttime) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING;", user_id, artist, currenttime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn save_listen(&self, user_id: &i64, listening: Option<&str>) -> Result<()> {
+        let session = query!("SELECT artist, starttime FROM listen_sessions WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        let session = match session {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+        if listening == Some(session.artist.as_str()) {
+            return Ok(());
+        }
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let listentime: i64 = std::cmp::min(currenttime - session.starttime, self.max_session_length);
+        query!("INSERT INTO listen_entries (user_id, artist, listentime) VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, artist) DO UPDATE SET listentime=listen_entries.listentime + EXCLUDED.listentime;", user_id, session.artist, listentime)
+            .execute(&self.pool).await?;
+        query!("DELETE FROM listen_sessions WHERE user_id=$1;", user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_top_artists(&self, user_id: &i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!("SELECT artist, listentime FROM listen_entries WHERE user_id=$1 ORDER BY listentime DESC LIMIT $2;", user_id, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.artist, row.listentime)).collect());
+    }
+
+    async fn get_listen_time(&self, user_id: &i64) -> Result<i64> {
+        let row = query!(r#"SELECT COALESCE(SUM(listentime), 0)::BIGINT AS "listentime!" FROM listen_entries WHERE user_id=$1;"#, user_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.listentime);
+    }
+
+    async fn get_games_to_enrich(&self, before: &i64, limit: i64) -> Result<Vec<(i64, String)>> {
+        return Ok(query!("SELECT game_id, name FROM games WHERE metadata_updated IS NULL OR metadata_updated < $1 ORDER BY metadata_updated NULLS FIRST LIMIT $2;", before, limit)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game_id, row.name)).collect());
+    }
+
+    async fn set_game_metadata(&self, game_id: &i64, metadata: Option<&GameMetadata>, updated: &i64) -> Result<()> {
+        query!("UPDATE games SET igdb_id=$2, title=$3, cover_url=$4, genres=$5, summary=$6, release_date=$7, metadata_updated=$8 WHERE game_id=$1;",
+            game_id,
+            metadata.map(|metadata| metadata.igdb_id),
+            metadata.map(|metadata| metadata.title.as_str()),
+            metadata.and_then(|metadata| metadata.cover_url.as_deref()),
+            metadata.map_or(&[] as &[String], |metadata| metadata.genres.as_slice()),
+            metadata.and_then(|metadata| metadata.summary.as_deref()),
+            metadata.and_then(|metadata| metadata.release_date),
+            updated)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_game_metadata(&self, game_id: &i64) -> Result<Option<GameMetadata>> {
+        return Ok(query_as!(GameMetadata, r#"SELECT igdb_id AS "igdb_id!", title AS "title!", cover_url, genres, summary, release_date
+                        FROM games WHERE game_id=$1 AND igdb_id IS NOT NULL;"#, game_id)
+                                            .fetch_optional(&self.pool).await?);
+    }
+
+    async fn get_genre_playtime(&self, user_id: &i64) -> Result<Vec<(Option<String>, i64)>> {
+        return Ok(query!(r#"SELECT genre, SUM(playtime / GREATEST(CARDINALITY(genres), 1))::BIGINT AS "total!" FROM game_entries
+                        JOIN games ON games.game_id=game_entries.game_id,
+                        UNNEST(CASE WHEN CARDINALITY(genres)=0 THEN ARRAY[NULL]::TEXT[] ELSE genres END) AS genre
+                        WHERE user_id=$1 GROUP BY genre ORDER BY 2 DESC;"#, user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.genre, row.total)).collect());
+    }
+
+    async fn get_cover_url(&self, game_name: &str) -> Result<Option<String>> {
+        let row = query!("SELECT cover_url FROM games WHERE name=$1;", game_name)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|row| row.cover_url));
+    }
+
+    async fn get_game_totals(&self, game_id: &i64) -> Result<(i64, i64)> {
+        let row = query!(r#"SELECT COUNT(*) AS "players!", COALESCE(SUM(playtime), 0)::BIGINT AS "playtime!" FROM game_entries
+                        WHERE game_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out);"#, game_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok((row.players, row.playtime));
+    }
+
+    async fn link_account(&self, user_id: &i64, platform: &str, account_id: &str) -> Result<()> {
+        query!("INSERT INTO linked_accounts (user_id, platform, account_id) VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, platform) DO UPDATE SET account_id=EXCLUDED.account_id;", user_id, platform, account_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn unlink_account(&self, user_id: &i64, platform: &str) -> Result<bool> {
+        let result = query!("DELETE FROM linked_accounts WHERE user_id=$1 AND platform=$2;", user_id, platform)
+                                            .execute(&self.pool).await?;
+        query!("DELETE FROM imported_entries WHERE user_id=$1 AND source=$2;", user_id, platform)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_linked_accounts(&self, platform: &str) -> Result<Vec<(i64, String)>> {
+        return Ok(query!("SELECT user_id, account_id FROM linked_accounts WHERE platform=$1;", platform)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.account_id)).collect());
+    }
+
+    async fn replace_imported_entries(&self, user_id: &i64, source: &str, entries: &[(String, i64)]) -> Result<()> {
+        let mut game_ids: Vec<i64> = Vec::new();
+        for (game_name, _) in entries {
+            game_ids.push(self.resolve_game(game_name).await?);
+        }
+        let mut transaction = self.pool.begin().await?;
+        query!("DELETE FROM imported_entries WHERE user_id=$1 AND source=$2;", user_id, source)
+            .execute(&mut *transaction).await?;
+        // Two names of the platform can be aliases of the same game
+        for (game_id, (_, playtime)) in game_ids.iter().zip(entries) {
+            query!("INSERT INTO imported_entries (user_id, game_id, source, playtime) VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (user_id, game_id, source) DO UPDATE SET playtime=imported_entries.playtime+EXCLUDED.playtime;", user_id, game_id, source, playtime)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_imported_playtime(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!(r#"SELECT source, SUM(playtime)::BIGINT AS "playtime!" FROM imported_entries WHERE user_id=$1 GROUP BY source ORDER BY source;"#, user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.source, row.playtime)).collect());
+    }
+
+    async fn merge_alias(&self, alias: &str, game_id: &i64) -> Result<Option<i64>> {
+        let alias = game_key(alias);
+        let old_game_id = query!("SELECT game_id FROM game_aliases WHERE alias=$1;", &alias)
+                                            .fetch_optional(&self.pool).await?
+                                            .map(|row| row.game_id)
+                                            .filter(|old_game_id| old_game_id != game_id);
+        if let Some(old_game_id) = old_game_id {
+            self.merge_games(&old_game_id, game_id).await?;
+        }
+        self.set_alias(&alias, game_id).await?;
+        return Ok(old_game_id);
+    }
+
+    async fn merge_games(&self, old_game_id: &i64, game_id: &i64) -> Result<()> {
+        info!("Merging game {:?} into {:?}", old_game_id, game_id);
+        let mut transaction = self.pool.begin().await?;
+        query!("UPDATE game_entries target SET playtime=target.playtime+merged.playtime FROM game_entries merged
+                WHERE target.game_id=$2 AND merged.game_id=$1 AND target.user_id=merged.user_id;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("DELETE FROM game_entries WHERE game_id=$1 AND user_id IN (SELECT user_id FROM game_entries WHERE game_id=$2);", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE game_entries SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("DELETE FROM game_sessions WHERE game_id=$1 AND user_id IN (SELECT user_id FROM game_sessions WHERE game_id=$2);", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE game_sessions SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("DELETE FROM goals WHERE game_id=$1 AND user_id IN (SELECT user_id FROM goals WHERE game_id=$2);", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE goals SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        // Imports are overwritten by the next sync, the duplicates can be dropped
+        query!("DELETE FROM imported_entries WHERE game_id=$1 AND (user_id, source) IN (SELECT user_id, source FROM imported_entries WHERE game_id=$2);",
+            old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE imported_entries SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE session_history SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("INSERT INTO history_rollup (user_id, game_id, day, playtime, sessions, longest, night_sessions)
+                SELECT user_id, $2, day, playtime, sessions, longest, night_sessions FROM history_rollup WHERE game_id=$1
+                ON CONFLICT (user_id, game_id, day) DO UPDATE SET playtime=history_rollup.playtime+EXCLUDED.playtime, sessions=history_rollup.sessions+EXCLUDED.sessions,
+                longest=GREATEST(history_rollup.longest, EXCLUDED.longest), night_sessions=history_rollup.night_sessions+EXCLUDED.night_sessions;",
+            old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("DELETE FROM history_rollup WHERE game_id=$1;", old_game_id)
+            .execute(&mut *transaction).await?;
+        query!("UPDATE game_aliases SET game_id=$2 WHERE game_id=$1;", old_game_id, game_id)
+            .execute(&mut *transaction).await?;
+        query!("DELETE FROM games WHERE game_id=$1;", old_game_id)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        Ok(())
+    }
+
+    async fn delete_game(&self, game_id: &i64) -> Result<String> {
+        info!("Deleting game {:?}", game_id);
+        let mut transaction = self.pool.begin().await?;
+        let name = query!("SELECT name FROM games WHERE game_id=$1;", game_id)
+                                            .fetch_one(&mut *transaction).await?
+                                            .name;
+        for table in ["game_entries", "game_sessions", "session_history", "history_rollup", "goals", "imported_entries", "game_aliases", "games"] {
+            query(&format!("DELETE FROM {} WHERE game_id=$1;", table))
+                .bind(game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        return Ok(name);
+    }
+
+    async fn rename_game(&self, game_id: &i64, game_name: &str) -> Result<bool> {
+        let name = clean_game_name(game_name);
+        let taken = query!(r#"SELECT EXISTS (SELECT 1 FROM games WHERE LOWER(name)=LOWER($1) AND game_id<>$2)
+                        OR EXISTS (SELECT 1 FROM game_aliases WHERE alias=$3 AND game_id<>$2) AS "taken!";"#, &name, game_id, game_key(&name))
+                                            .fetch_one(&self.pool).await?
+                                            .taken;
+        if taken {
+            return Ok(false);
+        }
+        let mut transaction = self.pool.begin().await?;
+        let old_name = query!("SELECT name FROM games WHERE game_id=$1;", game_id)
+                                            .fetch_one(&mut *transaction).await?
+                                            .name;
+        query!("UPDATE games SET name=$2 WHERE game_id=$1;", game_id, &name)
+            .execute(&mut *transaction).await?;
+        for alias in [game_key(&old_name), game_key(&name)] {
+            query!("INSERT INTO game_aliases (alias, game_id) VALUES ($1, $2) ON CONFLICT (alias) DO UPDATE SET game_id=EXCLUDED.game_id;", alias, game_id)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        info!("Renamed game {:?} from {:?} to {:?}", game_id, old_name, name);
+        return Ok(true);
+    }
+
+    async fn is_ignored(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let row = query!(r#"SELECT EXISTS (SELECT 1 FROM ignored_games WHERE guild_id IN (0, $1) AND name=$2) AS "ignored!";"#, guild_id, game_key(game_name))
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.ignored);
+    }
+
+    async fn add_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        query!("INSERT INTO ignored_games (guild_id, name) VALUES ($1, $2) ON CONFLICT DO NOTHING;", guild_id, game_key(game_name))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_ignored_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query!("DELETE FROM ignored_games WHERE guild_id=$1 AND name=$2;", guild_id, game_key(game_name))
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_ignored_games(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(query!("SELECT guild_id, name FROM ignored_games WHERE guild_id IN (0, $1) ORDER BY guild_id, name;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.guild_id, row.name)).collect());
+    }
+
+    async fn is_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let row = query!(r#"SELECT EXISTS (SELECT 1 FROM tracked_games WHERE guild_id=$1 AND (name=$2 OR name IN
+                            (SELECT alias FROM game_aliases WHERE game_id=(SELECT game_id FROM game_aliases WHERE alias=$2)))) AS "tracked!";"#, guild_id, game_key(game_name))
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.tracked);
+    }
+
+    async fn add_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<()> {
+        query!("INSERT INTO tracked_games (guild_id, name) VALUES ($1, $2) ON CONFLICT DO NOTHING;", guild_id, game_key(game_name))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_tracked_game(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query!("DELETE FROM tracked_games WHERE guild_id=$1 AND name=$2;", guild_id, game_key(game_name))
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_tracked_games(&self, guild_id: &i64) -> Result<Vec<String>> {
+        return Ok(query!("SELECT name FROM tracked_games WHERE guild_id=$1 ORDER BY name;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.name).collect());
+    }
+
+    async fn is_opted_out(&self, user_id: &i64) -> Result<bool> {
+        let row = query!("SELECT opted_out FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map_or(false, |row| row.opted_out));
+    }
+
+    async fn set_opted_out(&self, user_id: &i64, opted_out: bool) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, opted_out) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET opted_out=EXCLUDED.opted_out;",
+            user_id, opted_out)
+            .execute(&self.pool).await?;
+        if opted_out {
+            // Discard the sessions that were running when tracking got disabled
+            query!("DELETE FROM game_sessions WHERE user_id=$1;", user_id)
+                .execute(&self.pool).await?;
+            query!("DELETE FROM voice_sessions WHERE user_id=$1 AND endtime IS NULL;", user_id)
+                .execute(&self.pool).await?;
+            query!("DELETE FROM stream_sessions WHERE user_id=$1;", user_id)
+                .execute(&self.pool).await?;
+            query!("DELETE FROM listen_sessions WHERE user_id=$1;", user_id)
+                .execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_user_locale(&self, user_id: &i64) -> Result<Option<String>> {
+        let row = query!("SELECT locale FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|row| row.locale));
+    }
+
+    async fn set_user_locale(&self, user_id: &i64, locale: Option<&str>) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, locale) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET locale=EXCLUDED.locale;", user_id, locale)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_user_timezone(&self, user_id: &i64) -> Result<Option<String>> {
+        let row = query!("SELECT timezone FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|row| row.timezone));
+    }
+
+    async fn set_user_timezone(&self, user_id: &i64, timezone: Option<&str>) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, timezone) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET timezone=EXCLUDED.timezone;",
+            user_id, timezone)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_private_stats(&self, user_id: &i64) -> Result<bool> {
+        let row = query!("SELECT private_stats FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map_or(false, |row| row.private_stats));
+    }
+
+    async fn set_private_stats(&self, user_id: &i64, private: bool) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, private_stats) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET private_stats=EXCLUDED.private_stats;",
+            user_id, private)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_privacy_level(&self, user_id: &i64) -> Result<String> {
+        let row = query!("SELECT privacy_level FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map_or("public".to_string(), |row| row.privacy_level));
+    }
+
+    async fn set_privacy_level(&self, user_id: &i64, level: &str) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, privacy_level) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET privacy_level=EXCLUDED.privacy_level;",
+            user_id, level)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_paused_until(&self, user_id: &i64) -> Result<Option<i64>> {
+        let row = query!("SELECT paused_until FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|row| row.paused_until));
+    }
+
+    async fn set_paused_until(&self, user_id: &i64, until: Option<i64>) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, paused_until) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET paused_until=EXCLUDED.paused_until;",
+            user_id, until)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_vacation(&self, user_id: &i64) -> Result<Option<(i64, i64)>> {
+        let row = query!("SELECT vacation_start, vacation_end FROM user_settings WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.and_then(|row| row.vacation_start.zip(row.vacation_end)));
+    }
+
+    async fn set_vacation(&self, user_id: &i64, vacation: Option<(i64, i64)>) -> Result<()> {
+        query!("INSERT INTO user_settings (user_id, vacation_start, vacation_end) VALUES ($1, $2, $3)
+                ON CONFLICT (user_id) DO UPDATE SET vacation_start=EXCLUDED.vacation_start, vacation_end=EXCLUDED.vacation_end;",
+            user_id, vacation.map(|(start, _)| start), vacation.map(|(_, end)| end))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, user_id: &i64, guild_id: Option<i64>, action: &str, details: &str, actiontime: &i64) -> Result<()> {
+        query!("INSERT INTO audit_log (user_id, guild_id, action, details, actiontime) VALUES ($1, $2, $3, $4, $5);",
+            user_id, guild_id, action, details, actiontime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_audit_log(&self, limit: i64, offset: i64) -> Result<Vec<(i64, String, String, i64)>> {
+        return Ok(query!("SELECT user_id, action, details, actiontime FROM audit_log ORDER BY entry_id DESC LIMIT $1 OFFSET $2;", limit, offset)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.action, row.details, row.actiontime)).collect());
+    }
+
+    async fn count_audit_entries(&self) -> Result<i64> {
+        let row = query!(r#"SELECT COUNT(*) AS "count!" FROM audit_log;"#)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.count);
+    }
+
+    async fn dump(&self) -> Result<Value> {
+        let schema_version = self.get_schema_version().await?;
+        let mut rows = serde_json::Map::new();
+        for table in self.get_tables().await? {
+            let table_rows = query(&format!("SELECT COALESCE(jsonb_agg(to_jsonb(dumped)), '[]'::JSONB) FROM {} dumped;", table))
+                                            .fetch_one(&self.pool).await?
+                                            .get::<Value, usize>(0);
+            rows.insert(table, table_rows);
+        }
+        return Ok(json!({
+            "schema_version": schema_version,
+            "tables": rows,
+        }));
+    }
+
+    async fn restore(&self, tables: &serde_json::Map<String, Value>) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let current_tables = self.get_tables().await?;
+        query(&format!("TRUNCATE {};", current_tables.join(", "))).execute(&mut *transaction).await?;
+        // Referenced tables are filled first
+        let references: Vec<(String, String)> = query!(r#"SELECT conrelid::regclass::TEXT AS "child!", confrelid::regclass::TEXT AS "parent!" FROM pg_constraint WHERE contype='f';"#)
+                                            .fetch_all(&mut *transaction).await?.into_iter()
+                                            .map(|row| (row.child, row.parent)).collect();
+        let mut pending = current_tables.clone();
+        let mut ordered: Vec<String> = Vec::new();
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<String>, Vec<String>) = pending.into_iter()
+                .partition(|table| references.iter().all(|(child, parent)| child != table || parent == table || ordered.contains(parent)));
+            if ready.is_empty() {
+                return Err(anyhow!("The foreign keys of {:?} are circular", blocked));
+            }
+            ordered.extend(ready);
+            pending = blocked;
+        }
+        // Running sessions are dropped, their start times would count the time since the backup
+        for table in ordered.iter().filter(|table| !RUNNING_SESSION_TABLES.contains(&table.as_str())) {
+            if let Some(rows) = tables.get(table) {
+                query(&format!("INSERT INTO {} SELECT * FROM jsonb_populate_recordset(NULL::{}, $1);", table, table))
+                    .bind(rows)
+                    .execute(&mut *transaction).await?;
+            }
+        }
+        // The sequences continue after the restored ids
+        let serials: Vec<(String, String)> = query!(r#"SELECT table_name::TEXT AS "table_name!", column_name::TEXT AS "column_name!" FROM information_schema.columns
+                                                    WHERE table_schema='public' AND column_default LIKE 'nextval(%';"#)
+                                            .fetch_all(&mut *transaction).await?.into_iter()
+                                            .map(|row| (row.table_name, row.column_name)).collect();
+        for (table, column) in serials {
+            query(&format!("SELECT setval(pg_get_serial_sequence($1, $2), COALESCE((SELECT MAX({}) FROM {}), 0) + 1, false);", column, table))
+                .bind(&table)
+                .bind(&column)
+                .execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        Ok(())
+    }
+
+    async fn get_schema_version(&self) -> Result<Option<i64>> {
+        return Ok(query!("SELECT MAX(version) AS version FROM _sqlx_migrations WHERE success;")
+                                            .fetch_one(&self.pool).await?
+                                            .version);
+    }
+
+    async fn export_user(&self, user_id: &i64) -> Result<Value> {
+        let games: Vec<Value> = query!("SELECT name, playtime FROM game_entries NATURAL JOIN games WHERE user_id=$1 ORDER BY playtime DESC;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.name,
+                                                "playtime": row.playtime,
+                                            })).collect();
+        let open_sessions: Vec<Value> = query!("SELECT name, starttime FROM game_sessions NATURAL JOIN games WHERE user_id=$1;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.name,
+                                                "starttime": row.starttime,
+                                            })).collect();
+        let sessions: Vec<Value> = query!("SELECT name, starttime, endtime, duration, state, details, party_size FROM session_history NATURAL JOIN games WHERE user_id=$1 ORDER BY starttime;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.name,
+                                                "starttime": row.starttime,
+                                                "endtime": row.endtime,
+                                                "duration": row.duration,
+                                                "state": row.state,
+                                                "details": row.details,
+                                                "party_size": row.party_size,
+                                            })).collect();
+        let history_rollup: Vec<Value> = query!("SELECT name, day, playtime, sessions FROM history_rollup NATURAL JOIN games WHERE user_id=$1 ORDER BY day, name;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.name,
+                                                "day": row.day,
+                                                "playtime": row.playtime,
+                                                "sessions": row.sessions,
+                                            })).collect();
+        let voice_sessions: Vec<Value> = query!("SELECT guild_id, channel_id, starttime, endtime FROM voice_sessions WHERE user_id=$1 ORDER BY starttime;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "guild_id": row.guild_id.to_string(),
+                                                "channel_id": row.channel_id.to_string(),
+                                                "starttime": row.starttime,
+                                                "endtime": row.endtime,
+                                            })).collect();
+        let streams: Vec<Value> = query!("SELECT game, url, starttime, endtime, duration FROM stream_history WHERE user_id=$1 ORDER BY starttime;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.game,
+                                                "url": row.url,
+                                                "starttime": row.starttime,
+                                                "endtime": row.endtime,
+                                                "duration": row.duration,
+                                            })).collect();
+        let listening: Vec<Value> = query!("SELECT artist, listentime FROM listen_entries WHERE user_id=$1 ORDER BY listentime DESC;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "artist": row.artist,
+                                                "listentime": row.listentime,
+                                            })).collect();
+        let imported: Vec<Value> = query!("SELECT name, source, playtime FROM imported_entries NATURAL JOIN games WHERE user_id=$1 ORDER BY source, playtime DESC;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game": row.name,
+                                                "source": row.source,
+                                                "playtime": row.playtime,
+                                            })).collect();
+        let linked_accounts: Vec<Value> = query!("SELECT platform, account_id FROM linked_accounts WHERE user_id=$1;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "platform": row.platform,
+                                                "account_id": row.account_id,
+                                            })).collect();
+        let lfg_subscriptions: Vec<Value> = query!("SELECT guild_id, game_name, min_players FROM lfg_subscriptions WHERE user_id=$1 ORDER BY guild_id, game_name;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "guild_id": row.guild_id.to_string(),
+                                                "game": row.game_name,
+                                                "min_players": row.min_players,
+                                            })).collect();
+        let levels: Vec<Value> = query!("SELECT guild_id, level FROM levels WHERE user_id=$1 ORDER BY guild_id;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "guild_id": row.guild_id.to_string(),
+                                                "level": row.level,
+                                            })).collect();
+        let digest = query!(r#"SELECT EXISTS (SELECT 1 FROM digest_subscribers WHERE user_id=$1) AS "subscribed!";"#, user_id)
+                                            .fetch_one(&self.pool).await?.subscribed;
+        return Ok(json!({
+            "user_id": user_id.to_string(),
+            "opted_out": self.is_opted_out(user_id).await?,
+            "games": games,
+            "open_sessions": open_sessions,
+            "sessions": sessions,
+            "history_rollup": history_rollup,
+            "voice_sessions": voice_sessions,
+            "streams": streams,
+            "listening": listening,
+            "imported": imported,
+            "linked_accounts": linked_accounts,
+            "settings": {
+                "locale": self.get_user_locale(user_id).await?,
+                "timezone": self.get_user_timezone(user_id).await?,
+                "privacy_level": self.get_privacy_level(user_id).await?,
+                "private_stats": self.get_private_stats(user_id).await?,
+                "paused_until": self.get_paused_until(user_id).await?,
+                "vacation": self.get_vacation(user_id).await?.map(|(start, end)| json!({"start": start, "end": end})),
+                "digest": digest,
+            },
+            "goals": self.get_user_goals(user_id).await?.into_iter().map(|(name, seconds, is_limit)| json!({
+                "game": name,
+                "seconds": seconds,
+                "is_limit": is_limit,
+            })).collect::<Vec<Value>>(),
+            "watches": self.get_watches(user_id).await?.into_iter().map(|(watched_id, game_name)| json!({
+                "user_id": watched_id.to_string(),
+                "game": game_name,
+            })).collect::<Vec<Value>>(),
+            "lfg_subscriptions": lfg_subscriptions,
+            "levels": levels,
+            "badges": self.get_unlocked_badges(user_id).await?.into_iter().map(|(badge, unlocked_at)| json!({
+                "badge": badge,
+                "unlocked_at": unlocked_at,
+            })).collect::<Vec<Value>>(),
+        }));
+    }
+
+    async fn get_all_entries(&self, guild_id: &i64) -> Result<Vec<(i64, String, i64, i64)>> {
+        return Ok(query!(r#"SELECT entries.user_id, name, playtime, COALESCE(SUM(history.sessions), 0)::BIGINT AS "sessions!" FROM game_entries entries
+                        JOIN games ON games.game_id=entries.game_id
+                        LEFT JOIN full_history history ON history.user_id=entries.user_id AND history.game_id=entries.game_id
+                        WHERE entries.user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)
+                        AND entries.user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1)
+                        GROUP BY entries.user_id, name, playtime
+                        ORDER BY entries.user_id, playtime DESC;"#, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.name, row.playtime, row.sessions)).collect());
+    }
+
+    async fn dump_guild(&self, guild_id: &i64) -> Result<Value> {
+        let config = self.get_guild_config(guild_id).await?.unwrap_or_default();
+        let games: Vec<Value> = query!(r#"SELECT game_id, name, ARRAY(SELECT alias FROM game_aliases WHERE game_aliases.game_id=games.game_id ORDER BY alias) AS "aliases!"
+                                        FROM games
+                                        WHERE game_id IN (SELECT game_id FROM game_entries WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)))
+                                        OR game_id IN (SELECT game_id FROM game_sessions WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out)))
+                                        ORDER BY game_id;"#, guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "game_id": row.game_id,
+                                                "name": row.name,
+                                                "aliases": row.aliases,
+                                            })).collect();
+        let entries: Vec<Value> = query!("SELECT user_id, game_id, playtime FROM game_entries
+                                          WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                          ORDER BY user_id, game_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "user_id": row.user_id.to_string(),
+                                                "game_id": row.game_id,
+                                                "playtime": row.playtime,
+                                            })).collect();
+        let open_sessions: Vec<Value> = query!("SELECT user_id, game_id, starttime FROM game_sessions
+                                                WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                                ORDER BY user_id, game_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "user_id": row.user_id.to_string(),
+                                                "game_id": row.game_id,
+                                                "starttime": row.starttime,
+                                            })).collect();
+        let sessions: Vec<Value> = query!("SELECT user_id, game_id, starttime, endtime, duration FROM session_history
+                                           WHERE user_id IN (SELECT user_id FROM guild_members WHERE guild_id=$1 AND user_id NOT IN (SELECT user_id FROM user_settings WHERE opted_out))
+                                           ORDER BY session_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| json!({
+                                                "user_id": row.user_id.to_string(),
+                                                "game_id": row.game_id,
+                                                "starttime": row.starttime,
+                                                "endtime": row.endtime,
+                                                "duration": row.duration,
+                                            })).collect();
+        return Ok(json!({
+            "guild_id": guild_id.to_string(),
+            "config": config.to_json(),
+            "games": games,
+            "entries": entries,
+            "open_sessions": open_sessions,
+            "sessions": sessions,
+        }));
+    }
+
+    async fn has_admin_role(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<bool> {
+        let row = query!(r#"SELECT EXISTS (SELECT 1 FROM admin_roles WHERE guild_id=$1 AND role_id = ANY($2)) AS "admin!";"#, guild_id, &role_ids)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.admin);
+    }
+
+    async fn add_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        query!("INSERT INTO admin_roles (guild_id, role_id) VALUES ($1, $2) ON CONFLICT (role_id) DO UPDATE SET guild_id=EXCLUDED.guild_id;", guild_id, role_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_admin_role(&self, guild_id: &i64, role_id: &i64) -> Result<()> {
+        query!("DELETE FROM admin_roles WHERE guild_id=$1 AND role_id=$2;", guild_id, role_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_admin_roles(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT role_id FROM admin_roles WHERE guild_id=$1 ORDER BY role_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.role_id).collect());
+    }
+
+    async fn claim_admin_roles(&self, guild_id: &i64, role_ids: Vec<i64>) -> Result<()> {
+        query!("UPDATE admin_roles SET guild_id=$1 WHERE guild_id=0 AND role_id = ANY($2);", guild_id, &role_ids)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn is_bot_admin(&self, user_id: &i64) -> Result<bool> {
+        let row = query!(r#"SELECT EXISTS (SELECT 1 FROM bot_admins WHERE user_id=$1) AS "admin!";"#, user_id)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.admin);
+    }
+
+    async fn add_bot_admin(&self, user_id: &i64) -> Result<()> {
+        query!("INSERT INTO bot_admins (user_id) VALUES ($1) ON CONFLICT DO NOTHING;", user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_bot_admin(&self, user_id: &i64) -> Result<()> {
+        query!("DELETE FROM bot_admins WHERE user_id=$1;", user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_guild_config(&self, guild_id: &i64) -> Result<Option<GuildConfig>> {
+        let row = query!("SELECT report_channel, report_cadence, min_session_length, locale, embed_color, whitelist_only, xp_per_hour, level_base_xp, level_channel, track_listening,
+                        title_template, show_thumbnails, anonymous_leaderboards, rank_manual_sessions, idle_policy, idle_minutes
+                        FROM guild_config WHERE guild_id=$1;", guild_id)
+                                            .fetch_optional(&self.pool).await?;
+        return Ok(row.map(|row| GuildConfig {
+            report_channel: row.report_channel,
+            report_cadence: row.report_cadence,
+            min_session_length: row.min_session_length,
+            locale: row.locale,
+            embed_color: row.embed_color,
+            whitelist_only: row.whitelist_only,
+            xp_per_hour: row.xp_per_hour,
+            level_base_xp: row.level_base_xp,
+            level_channel: row.level_channel,
+            track_listening: row.track_listening,
+            title_template: row.title_template,
+            show_thumbnails: row.show_thumbnails,
+            anonymous_leaderboards: row.anonymous_leaderboards,
+            rank_manual_sessions: row.rank_manual_sessions,
+            idle_policy: row.idle_policy,
+            idle_minutes: row.idle_minutes,
+        }));
+    }
+
+    async fn save_guild_config(&self, guild_id: &i64, config: &GuildConfig) -> Result<()> {
+        query!("INSERT INTO guild_config (guild_id, report_channel, min_session_length, locale, embed_color, whitelist_only, xp_per_hour, level_base_xp, level_channel, track_listening,
+                title_template, show_thumbnails, report_cadence, anonymous_leaderboards, rank_manual_sessions, idle_policy, idle_minutes)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                ON CONFLICT (guild_id) DO UPDATE SET report_channel=EXCLUDED.report_channel, report_cadence=EXCLUDED.report_cadence, min_session_length=EXCLUDED.min_session_length,
+                locale=EXCLUDED.locale, embed_color=EXCLUDED.embed_color, whitelist_only=EXCLUDED.whitelist_only,
+                xp_per_hour=EXCLUDED.xp_per_hour, level_base_xp=EXCLUDED.level_base_xp, level_channel=EXCLUDED.level_channel,
+                track_listening=EXCLUDED.track_listening, title_template=EXCLUDED.title_template, show_thumbnails=EXCLUDED.show_thumbnails,
+                anonymous_leaderboards=EXCLUDED.anonymous_leaderboards, rank_manual_sessions=EXCLUDED.rank_manual_sessions,
+                idle_policy=EXCLUDED.idle_policy, idle_minutes=EXCLUDED.idle_minutes;",
+            guild_id, config.report_channel, config.min_session_length, &config.locale, config.embed_color, config.whitelist_only,
+            config.xp_per_hour, config.level_base_xp, config.level_channel, config.track_listening, &config.title_template, config.show_thumbnails, &config.report_cadence,
+            config.anonymous_leaderboards, config.rank_manual_sessions, &config.idle_policy, config.idle_minutes)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_digest_subscription(&self, user_id: &i64, subscribed: bool) -> Result<()> {
+        if subscribed {
+            query!("INSERT INTO digest_subscribers (user_id) VALUES ($1) ON CONFLICT DO NOTHING;", user_id)
+                .execute(&self.pool).await?;
+        } else {
+            query!("DELETE FROM digest_subscribers WHERE user_id=$1;", user_id)
+                .execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_digest_subscribers(&self) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query!("SELECT user_id, timezone FROM digest_subscribers LEFT JOIN user_settings USING (user_id) WHERE NOT COALESCE(opted_out, false);")
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.timezone)).collect());
+    }
+
+    async fn set_goal(&self, user_id: &i64, game_id: &i64, seconds: &i64, is_limit: bool) -> Result<()> {
+        query!("INSERT INTO goals (user_id, game_id, seconds, is_limit) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, game_id) DO UPDATE SET seconds=EXCLUDED.seconds, is_limit=EXCLUDED.is_limit, notified_week=NULL;", user_id, game_id, seconds, is_limit)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_goal(&self, user_id: &i64, game_id: &i64) -> Result<bool> {
+        let result = query!("DELETE FROM goals WHERE user_id=$1 AND game_id=$2;", user_id, game_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_user_goals(&self, user_id: &i64) -> Result<Vec<(String, i64, bool)>> {
+        return Ok(query!("SELECT name, seconds, is_limit FROM goals NATURAL JOIN games WHERE user_id=$1 ORDER BY name;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.name, row.seconds, row.is_limit)).collect());
+    }
+
+    async fn get_goal_users(&self) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query!(r#"SELECT DISTINCT user_id AS "user_id!", timezone FROM goals LEFT JOIN user_settings USING (user_id) WHERE NOT COALESCE(opted_out, false);"#)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.timezone)).collect());
+    }
+
+    async fn get_reached_goals(&self, user_id: &i64, week_start: &i64, currenttime: &i64) -> Result<Vec<(i64, String, i64, bool)>> {
+        return Ok(query!("SELECT goals.game_id, name, seconds, is_limit FROM goals
+                        JOIN games ON games.game_id=goals.game_id
+                        WHERE goals.user_id=$3 AND notified_week IS DISTINCT FROM $1
+                        AND COALESCE((SELECT SUM(endtime - GREATEST(starttime, $1)) FROM session_history history
+                                        WHERE history.user_id=goals.user_id AND history.game_id=goals.game_id AND endtime > $1), 0)
+                            + COALESCE((SELECT $2 - GREATEST(starttime, $1) FROM game_sessions sessions
+                                        WHERE sessions.user_id=goals.user_id AND sessions.game_id=goals.game_id), 0)
+                            >= seconds;", week_start, currenttime, user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game_id, row.name, row.seconds, row.is_limit)).collect());
+    }
+
+    async fn set_goal_notified(&self, user_id: &i64, game_id: &i64, week_start: &i64) -> Result<()> {
+        query!("UPDATE goals SET notified_week=$3 WHERE user_id=$1 AND game_id=$2;", user_id, game_id, week_start)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let result = query!("INSERT INTO watches (watcher_id, user_id, game_name) VALUES ($1, $2, $3)
+                        ON CONFLICT (watcher_id, user_id, LOWER(COALESCE(game_name, ''))) DO NOTHING;", watcher_id, user_id, game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn remove_watch(&self, watcher_id: &i64, user_id: &i64, game_name: Option<&str>) -> Result<bool> {
+        let result = query!("DELETE FROM watches WHERE watcher_id=$1 AND user_id=$2 AND LOWER(COALESCE(game_name, ''))=LOWER(COALESCE($3, ''));", watcher_id, user_id, game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_watches(&self, watcher_id: &i64) -> Result<Vec<(i64, Option<String>)>> {
+        return Ok(query!("SELECT user_id, game_name FROM watches WHERE watcher_id=$1 ORDER BY user_id, game_name NULLS FIRST;", watcher_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.game_name)).collect());
+    }
+
+    async fn get_watchers(&self, user_id: &i64, game_name: &str) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT DISTINCT watcher_id FROM watches WHERE user_id=$1 AND (game_name IS NULL OR LOWER(game_name)=LOWER($2));", user_id, game_name)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.watcher_id).collect());
+    }
+
+    async fn set_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str, min_players: &i64) -> Result<()> {
+        query!("INSERT INTO lfg_subscriptions (guild_id, user_id, game_name, min_players) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (guild_id, user_id, LOWER(game_name)) DO UPDATE SET min_players=EXCLUDED.min_players, notified=FALSE;", guild_id, user_id, game_name, min_players)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_lfg_subscription(&self, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query!("DELETE FROM lfg_subscriptions WHERE guild_id=$1 AND user_id=$2 AND LOWER(game_name)=LOWER($3);", guild_id, user_id, game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_lfg_subscriptions(&self, guild_id: &i64, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!("SELECT game_name, min_players FROM lfg_subscriptions WHERE guild_id=$1 AND user_id=$2 ORDER BY game_name;", guild_id, user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game_name, row.min_players)).collect());
+    }
+
+    async fn get_lfg_subscribers(&self, guild_id: &i64, game_name: &str) -> Result<Vec<(i64, i64, bool)>> {
+        return Ok(query!("SELECT user_id, min_players, notified FROM lfg_subscriptions WHERE guild_id=$1 AND LOWER(game_name)=LOWER($2);", guild_id, game_name)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.user_id, row.min_players, row.notified)).collect());
+    }
+
+    async fn set_lfg_notified(&self, guild_id: &i64, user_id: &i64, game_name: &str, notified: bool) -> Result<()> {
+        query!("UPDATE lfg_subscriptions SET notified=$4 WHERE guild_id=$1 AND user_id=$2 AND LOWER(game_name)=LOWER($3);", guild_id, user_id, game_name, notified)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_game_role(&self, guild_id: &i64, game_name: &str, role_id: &i64, channel_id: &i64) -> Result<()> {
+        query!("INSERT INTO game_roles (guild_id, game_name, role_id, channel_id) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (guild_id, LOWER(game_name)) DO UPDATE SET role_id=EXCLUDED.role_id, channel_id=EXCLUDED.channel_id;", guild_id, game_name, role_id, channel_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_game_role(&self, guild_id: &i64, game_name: &str) -> Result<bool> {
+        let result = query!("DELETE FROM game_roles WHERE guild_id=$1 AND LOWER(game_name)=LOWER($2);", guild_id, game_name)
+            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_game_roles(&self, guild_id: &i64) -> Result<Vec<(String, i64, i64)>> {
+        return Ok(query!("SELECT game_name, role_id, channel_id FROM game_roles WHERE guild_id=$1 ORDER BY game_name;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.game_name, row.role_id, row.channel_id)).collect());
+    }
+
+    async fn get_game_role(&self, guild_id: &i64, game_name: &str) -> Result<Option<(i64, i64, Option<i64>)>> {
+        return Ok(query!("SELECT role_id, channel_id, last_ping FROM game_roles WHERE guild_id=$1 AND LOWER(game_name)=LOWER($2);", guild_id, game_name)
+                                            .fetch_optional(&self.pool).await?
+                                            .map(|row| (row.role_id, row.channel_id, row.last_ping)));
+    }
+
+    async fn set_game_role_pinged(&self, guild_id: &i64, game_name: &str, pingtime: &i64) -> Result<()> {
+        query!("UPDATE game_roles SET last_ping=$3 WHERE guild_id=$1 AND LOWER(game_name)=LOWER($2);", guild_id, game_name, pingtime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_role_reward(&self, guild_id: &i64, role_id: &i64, seconds: &i64) -> Result<()> {
+        query!("INSERT INTO role_rewards (guild_id, role_id, seconds) VALUES ($1, $2, $3) ON CONFLICT (guild_id, role_id) DO UPDATE SET seconds=EXCLUDED.seconds;", guild_id, role_id, seconds)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_role_reward(&self, guild_id: &i64, role_id: &i64) -> Result<bool> {
+        let result = query!("DELETE FROM role_rewards WHERE guild_id=$1 AND role_id=$2;", guild_id, role_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_role_rewards(&self, guild_id: &i64) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!("SELECT role_id, seconds FROM role_rewards WHERE guild_id=$1 ORDER BY seconds;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.role_id, row.seconds)).collect());
+    }
+
+    async fn get_level(&self, guild_id: &i64, user_id: &i64) -> Result<i64> {
+        return Ok(query!("SELECT level FROM levels WHERE guild_id=$1 AND user_id=$2;", guild_id, user_id)
+                                            .fetch_optional(&self.pool).await?
+                                            .map_or(0, |row| row.level));
+    }
+
+    async fn set_level(&self, guild_id: &i64, user_id: &i64, level: &i64) -> Result<()> {
+        query!("INSERT INTO levels (guild_id, user_id, level) VALUES ($1, $2, $3) ON CONFLICT (guild_id, user_id) DO UPDATE SET level=EXCLUDED.level;", guild_id, user_id, level)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_achievement_stats(&self, user_id: &i64) -> Result<AchievementStats> {
+        return Ok(query_as!(AchievementStats, r#"SELECT
+                            (SELECT COALESCE(SUM(night_sessions), 0)::BIGINT FROM full_history WHERE user_id=$1) AS "night_sessions!",
+                            (SELECT COALESCE(MAX(longest), 0) FROM full_history WHERE user_id=$1) AS "longest_session!",
+                            (SELECT COUNT(*) FROM game_entries WHERE user_id=$1) AS "games!",
+                            (SELECT COALESCE(SUM(playtime), 0)::BIGINT FROM game_entries WHERE user_id=$1) AS "playtime!";"#, user_id)
+                                            .fetch_one(&self.pool).await?);
+    }
+
+    async fn get_unlocked_badges(&self, user_id: &i64) -> Result<Vec<(String, i64)>> {
+        return Ok(query!("SELECT badge, unlocked_at FROM unlocked_badges WHERE user_id=$1;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.badge, row.unlocked_at)).collect());
+    }
+
+    async fn unlock_badge(&self, user_id: &i64, badge: &str, unlocked_at: &i64) -> Result<()> {
+        query!("INSERT INTO unlocked_badges (user_id, badge, unlocked_at) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING;", user_id, badge, unlocked_at)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn start_voice_session(&self, user_id: &i64, guild_id: &i64, channel_id: &i64, starttime: &i64) -> Result<()> {
+        query!("INSERT INTO voice_sessions (user_id, guild_id, channel_id, starttime) VALUES ($1, $2, $3, $4) ON CONFLICT (user_id) WHERE endtime IS NULL DO NOTHING;", user_id, guild_id, channel_id, starttime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn end_voice_session(&self, user_id: &i64, endtime: &i64) -> Result<()> {
+        query!("UPDATE voice_sessions SET endtime=$2 WHERE user_id=$1 AND endtime IS NULL;", user_id, endtime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn end_all_voice_sessions(&self, endtime: &i64) -> Result<()> {
+        query!("UPDATE voice_sessions SET endtime=$1 WHERE endtime IS NULL;", endtime)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_voice_users(&self) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT user_id FROM voice_sessions WHERE endtime IS NULL;")
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.user_id).collect());
+    }
+
+    async fn get_voice_time(&self, user_id: &i64, currenttime: &i64) -> Result<(i64, i64)> {
+        // Overlapping games are counted once per game, capping at the voice session's length keeps it sane
+        let row = query!(r#"SELECT COALESCE(SUM(duration), 0)::BIGINT AS "voice_time!", COALESCE(SUM(LEAST(duration, playing)), 0)::BIGINT AS "playing_time!" FROM (
+                            SELECT COALESCE(voice.endtime, $2) - voice.starttime AS duration,
+                                (SELECT COALESCE(SUM(LEAST(COALESCE(voice.endtime, $2), history.endtime) - GREATEST(voice.starttime, history.starttime)), 0)::BIGINT
+                                    FROM session_history history
+                                    WHERE history.user_id=voice.user_id AND history.endtime > voice.starttime AND history.starttime < COALESCE(voice.endtime, $2))
+                                + (SELECT COALESCE(SUM(COALESCE(voice.endtime, $2) - GREATEST(voice.starttime, game.starttime)), 0)::BIGINT
+                                    FROM game_sessions game
+                                    WHERE game.user_id=voice.user_id AND game.starttime < COALESCE(voice.endtime, $2)) AS playing
+                            FROM voice_sessions voice WHERE voice.user_id=$1) sessions;"#, user_id, currenttime)
+                                            .fetch_one(&self.pool).await?;
+        return Ok((row.voice_time, row.playing_time));
+    }
+
+    async fn add_webhook(&self, guild_id: &i64, url: &str) -> Result<()> {
+        query!("INSERT INTO webhooks (guild_id, url) VALUES ($1, $2) ON CONFLICT DO NOTHING;", guild_id, url)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_webhook(&self, guild_id: &i64, webhook_id: &i64) -> Result<bool> {
+        let result = query!("DELETE FROM webhooks WHERE guild_id=$1 AND webhook_id=$2;", guild_id, webhook_id)
+                                            .execute(&self.pool).await?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_webhooks(&self, guild_id: &i64) -> Result<Vec<(i64, String)>> {
+        return Ok(query!("SELECT webhook_id, url FROM webhooks WHERE guild_id=$1 ORDER BY webhook_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.webhook_id, row.url)).collect());
+    }
+
+    async fn get_report_channels(&self, cadence: &str) -> Result<Vec<(i64, i64)>> {
+        return Ok(query!(r#"SELECT guild_id, report_channel AS "report_channel!" FROM guild_config WHERE report_channel IS NOT NULL AND report_cadence=$1;"#, cadence)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| (row.guild_id, row.report_channel)).collect());
+    }
+
+    async fn add_guild_members(&self, guild_id: &i64, user_ids: &[i64], seen: &i64) -> Result<()> {
+        query!("INSERT INTO guild_members (guild_id, user_id, seen) SELECT $1, UNNEST($2::BIGINT[]), $3
+                ON CONFLICT (guild_id, user_id) DO UPDATE SET seen=EXCLUDED.seen;", guild_id, user_ids, seen)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_guild_member(&self, guild_id: &i64, user_id: &i64) -> Result<()> {
+        query!("DELETE FROM guild_members WHERE guild_id=$1 AND user_id=$2;", guild_id, user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn prune_guild_members(&self, guild_id: &i64, before: &i64) -> Result<()> {
+        query!("DELETE FROM guild_members WHERE guild_id=$1 AND seen < $2;", guild_id, before)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_guild_members(&self, guild_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT user_id FROM guild_members WHERE guild_id=$1 ORDER BY user_id;", guild_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.user_id).collect());
+    }
+
+    async fn get_user_guilds(&self, user_id: &i64) -> Result<Vec<i64>> {
+        return Ok(query!("SELECT guild_id FROM guild_members WHERE user_id=$1 ORDER BY guild_id;", user_id)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.guild_id).collect());
+    }
+
+    async fn ping(&self) -> Result<()> {
+        query("SELECT 1;").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn count_open_sessions(&self) -> Result<i64> {
+        let row = query!(r#"SELECT COUNT(*) AS "count!" FROM game_sessions;"#)
+                                            .fetch_one(&self.pool).await?;
+        return Ok(row.count);
+    }
+
+    async fn prune_history(&self, before: &i64) -> Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        query!("INSERT INTO history_rollup (user_id, game_id, day, playtime, sessions, longest, night_sessions)
+                SELECT user_id, game_id, starttime - starttime % 86400, SUM(duration), COUNT(*), MAX(duration), COUNT(*) FILTER (WHERE starttime % 86400 < 5 * 3600)
+                FROM session_history WHERE endtime<$1
+                GROUP BY user_id, game_id, starttime - starttime % 86400
+                ON CONFLICT (user_id, game_id, day) DO UPDATE SET playtime=history_rollup.playtime+EXCLUDED.playtime, sessions=history_rollup.sessions+EXCLUDED.sessions,
+                longest=GREATEST(history_rollup.longest, EXCLUDED.longest), night_sessions=history_rollup.night_sessions+EXCLUDED.night_sessions;",
+            before)
+            .execute(&mut *transaction).await?;
+        let result = query!("DELETE FROM session_history WHERE endtime<$1;", before)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Ok(result.rows_affected());
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!().run(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_session_users(&self) -> Result<Vec<i64>> {
+        return Ok(query!(r#"SELECT user_id AS "user_id!" FROM game_sessions UNION SELECT user_id FROM stream_sessions UNION SELECT user_id FROM listen_sessions;"#)
+                                            .fetch_all(&self.pool).await?.into_iter()
+                                            .map(|row| row.user_id).collect());
+    }
+
+    async fn update_status(&self, user_id: &i64, away: bool, currenttime: &i64) -> Result<Option<(bool, i64)>> {
+        let previous = query!("SELECT away, since FROM user_status WHERE user_id=$1;", user_id)
+                                            .fetch_optional(&self.pool).await?
+                                            .map(|row| (row.away, row.since));
+        if previous.map(|(was_away, _)| was_away) != Some(away) {
+            query!("INSERT INTO user_status (user_id, away, since) VALUES ($1, $2, $3) ON CONFLICT (user_id) DO UPDATE SET away=EXCLUDED.away, since=EXCLUDED.since;",
+                user_id, away, currenttime)
+                .execute(&self.pool).await?;
+        }
+        return Ok(previous);
+    }
+
+    async fn resetall(&self) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let reset_id = self.start_reset(&mut transaction, None).await?;
+        // Running sessions aren't archived, they would count the time until the undo
+        for table in RUNNING_SESSION_TABLES {
+            query(&format!("DELETE FROM {};", table)).execute(&mut *transaction).await?;
+        }
+        for (table, _) in ARCHIVED_TABLES.iter().rev().filter(|(table, _)| *table != "linked_accounts") {
+            archive_rows(&mut transaction, &reset_id, table, None).await?;
+        }
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        Ok(())
+    }
+
+    async fn reset(&self, user_id: &i64) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let reset_id = self.start_reset(&mut transaction, Some(user_id)).await?;
+        for table in RUNNING_SESSION_TABLES {
+            query(&format!("DELETE FROM {} WHERE user_id=$1;", table))
+                .bind(user_id)
+                .execute(&mut *transaction).await?;
+        }
+        for (table, _) in ARCHIVED_TABLES.iter().rev().filter(|(table, _)| !matches!(*table, "games" | "game_aliases" | "goals")) {
+            archive_rows(&mut transaction, &reset_id, table, Some(user_id)).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn undo_reset(&self) -> Result<Option<Option<i64>>> {
+        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let row = query!("SELECT reset_id, user_id FROM resets WHERE resettime >= $1 ORDER BY reset_id DESC LIMIT 1;", currenttime - RESET_UNDO_WINDOW)
+                                            .fetch_optional(&self.pool).await?;
+        let (reset_id, user_id) = match row {
+            Some(row) => (row.reset_id, row.user_id),
+            None => return Ok(None),
+        };
+        info!("Undoing reset {:?}", reset_id);
+        let mut transaction = self.pool.begin().await?;
+        // Games tracked again since the reset are matched by name, the rows of games deleted since are dropped
+        for (table, conflict) in ARCHIVED_TABLES {
+            query(&format!("INSERT INTO {table}
+                            SELECT (jsonb_populate_record(NULL::{table}, restored.row)).* FROM (
+                                SELECT archived.row || COALESCE((SELECT jsonb_build_object('game_id', games.game_id) FROM reset_archive game
+                                                                    JOIN games ON games.name=game.row->>'name'
+                                                                    WHERE game.reset_id=archived.reset_id AND game.table_name='games'
+                                                                    AND game.row->'game_id'=archived.row->'game_id'), '{{}}') AS row
+                                FROM reset_archive archived WHERE archived.reset_id=$1 AND archived.table_name='{table}'
+                            ) restored
+                            WHERE '{table}'='games' OR restored.row->'game_id' IS NULL
+                            OR EXISTS (SELECT 1 FROM games WHERE games.game_id=(restored.row->>'game_id')::BIGINT)
+                            ON CONFLICT {conflict};"))
+                .bind(reset_id)
+                .execute(&mut *transaction).await?;
+        }
+        query!("DELETE FROM resets WHERE reset_id=$1;", reset_id)
+            .execute(&mut *transaction).await?;
+        transaction.commit().await?;
+        self.forget_game_ids().await;
+        return Ok(Some(user_id));
+    }
+
+    async fn hardreset(&self) -> Result<()> {
+        self.resetall().await?;
+        // The archived game ids would clash with the recreated tables
+        query!("DELETE FROM resets;").execute(&self.pool).await?;
+        query("DROP VIEW full_history;").execute(&self.pool).await?;
+        query("DROP TABLE session_history;").execute(&self.pool).await?;
+        query("DROP TABLE history_rollup;").execute(&self.pool).await?;
+        query("DROP TABLE game_entries;").execute(&self.pool).await?;
+        query("DROP TABLE game_sessions;").execute(&self.pool).await?;
+        query("DROP TABLE goals;").execute(&self.pool).await?;
+        query("DROP TABLE game_aliases;").execute(&self.pool).await?;
+        query("DROP TABLE imported_entries;").execute(&self.pool).await?;
+        query("DROP TABLE games;").execute(&self.pool).await?;
+        // Forget the applied migrations so the dropped tables get recreated
+        query!("DELETE FROM _sqlx_migrations;").execute(&self.pool).await?;
+        self.migrate().await?;
+        self.forget_game_ids().await;
+        Ok(())
+    }
+}
+
+// Moves the rows of `table`, only the user's ones when `user_id` is set, to reset_archive
+async fn archive_rows(transaction: &mut Transaction<'_, Postgres>, reset_id: &i64, table: &str, user_id: Option<&i64>) -> Result<()> {
+    let condition = if user_id.is_some() { "WHERE user_id=$2" } else { "" };
+    let statement = format!("INSERT INTO reset_archive (reset_id, table_name, row) SELECT $1, '{}', to_jsonb(archived) FROM {} archived {};", table, table, condition);
+    let mut archive = query(&statement).bind(reset_id);
+    if let Some(user_id) = user_id {
+        archive = archive.bind(user_id);
+    }
+    archive.execute(&mut **transaction).await?;
+    let statement = format!("DELETE FROM {} {};", table, condition.replace("$2", "$1"));
+    let mut delete = query(&statement);
+    if let Some(user_id) = user_id {
+        delete = delete.bind(user_id);
+    }
+    delete.execute(&mut **transaction).await?;
+    Ok(())
+}
+
+// Each test gets its own migrated database on the server of DATABASE_URL
+#[cfg(all(test, feature = "postgres-tests"))]
+mod tests {
+    use super::PgStorage;
+    use crate::db::{RichPresence, Storage};
+    use chrono::Utc;
+    use serde_json::json;
+    use sqlx::{query, query_as, PgPool};
+
+    const DAY: i64 = 24 * 60 * 60;
+    const GUILD_ID: i64 = 1;
+
+    fn storage(pool: &PgPool) -> PgStorage {
+        return PgStorage::new(pool.clone(), 60, DAY);
+    }
+
+    // Plays a game for `seconds` up to now and returns the saved playtime
+    async fn play(db: &PgStorage, user_id: &i64, game_name: &str, seconds: i64) -> i64 {
+        let (_, started) = db.register_session(user_id, game_name, &(Utc::now().timestamp() - seconds), &RichPresence::default()).await.unwrap();
+        assert!(started);
+        let saved = db.save_session(user_id, &[], None, &Utc::now().timestamp()).await.unwrap();
+        return saved.iter().map(|(_, playtime)| playtime).sum();
+    }
+
+    // Sessions can't start before the previous one ended, this makes room for the next ones
+    async fn move_history_back(pool: &PgPool, seconds: i64) {
+        query("UPDATE session_history SET starttime=starttime-$1, endtime=endtime-$1;").bind(seconds).execute(pool).await.unwrap();
+    }
+
+    fn assert_near(playtime: i64, expected: i64) {
+        assert!((expected..expected + 5).contains(&playtime), "{} isn't close to {}", playtime, expected);
+    }
+
+    #[sqlx::test]
+    async fn registers_a_session_once(pool: PgPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 600;
+        let (game_id, started) = db.register_session(&1, "Factorio", &starttime, &RichPresence::default()).await.unwrap();
+        assert!(started);
+        // Every presence update of a running game registers it again
+        assert_eq!(db.register_session(&1, "factorio ", &starttime, &RichPresence::default()).await.unwrap(), (game_id, false));
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!((sessions[0].game_id, sessions[0].name.as_str(), sessions[0].starttime), (game_id, "Factorio", starttime));
+        assert_eq!(db.count_open_sessions().await.unwrap(), 1);
+    }
+
+    #[sqlx::test]
+    async fn keeps_the_last_rich_presence_of_a_session(pool: PgPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 3600;
+        let queued = RichPresence { state: Some("In Queue".to_string()), details: None, party_size: Some(1) };
+        let (game_id, _) = db.register_session(&1, "VALORANT", &starttime, &queued).await.unwrap();
+        let playing = RichPresence { state: Some("In a Party".to_string()), details: Some("Competitive".to_string()), party_size: Some(5) };
+        db.register_session(&1, "VALORANT", &starttime, &playing).await.unwrap();
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!((sessions[0].details.as_deref(), sessions[0].party_size), (Some("Competitive"), Some(5)));
+        db.save_session(&1, &[], None, &Utc::now().timestamp()).await.unwrap();
+        let details = db.get_top_details(&1, &game_id, 3).await.unwrap();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].0, "Competitive");
+        assert_near(details[0].1, 3600);
+    }
+
+    #[sqlx::test]
+    async fn searches_the_names_literally(pool: PgPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp();
+        db.register_session(&1, "100% Orange Juice", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "100 Orange Juice", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Half-Life", &starttime, &RichPresence::default()).await.unwrap();
+        assert_eq!(db.search_games("100%").await.unwrap(), vec!["100% Orange Juice".to_string()]);
+        assert!(db.search_games("Half_Life").await.unwrap().is_empty());
+        assert!(db.find_game("_").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn clamps_untrusted_start_times(pool: PgPool) {
+        let db = storage(&pool);
+        let currenttime = Utc::now().timestamp();
+        db.register_session(&1, "Factorio", &(currenttime + 600), &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Celeste", &(currenttime - 2 * DAY), &RichPresence::default()).await.unwrap();
+        for session in db.get_open_sessions(&1).await.unwrap() {
+            assert_near(session.starttime, currenttime);
+        }
+    }
+
+    #[sqlx::test]
+    async fn accumulates_the_playtime_of_saved_sessions(pool: PgPool) {
+        let db = storage(&pool);
+        assert_near(play(&db, &1, "Factorio", 3600).await, 3600);
+        move_history_back(&pool, DAY).await;
+        assert_near(play(&db, &1, "Factorio", 1800).await, 1800);
+        assert_near(db.get_total_playtime(&1).await.unwrap(), 5400);
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.get_game_stats(&1, &game_id).await.unwrap().sessions, 2);
+        assert!(db.get_open_sessions(&1).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn discards_short_sessions(pool: PgPool) {
+        let db = storage(&pool);
+        assert_eq!(play(&db, &1, "Factorio", 30).await, 0);
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), 0);
+        assert!(db.get_open_sessions(&1).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn only_saves_the_closed_games(pool: PgPool) {
+        let db = storage(&pool);
+        let starttime = Utc::now().timestamp() - 3600;
+        let (factorio_id, _) = db.register_session(&1, "Factorio", &starttime, &RichPresence::default()).await.unwrap();
+        db.register_session(&1, "Celeste", &starttime, &RichPresence::default()).await.unwrap();
+        let saved = db.save_session(&1, &[factorio_id], None, &Utc::now().timestamp()).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].0, "Celeste");
+        let sessions = db.get_open_sessions(&1).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].game_id, factorio_id);
+    }
+
+    #[sqlx::test]
+    async fn ranks_the_leaderboards(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 7200).await;
+        play(&db, &2, "Celeste", 600).await;
+        play(&db, &3, "Celeste", 1800).await;
+        let leaderboard = db.get_leaderboard(None, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![2, 1, 3]);
+        assert_near(leaderboard[0].1, 7800);
+        let (game_id, _) = db.find_game("Celeste").await.unwrap().unwrap();
+        let game_leaderboard = db.get_game_leaderboard(None, &game_id, false).await.unwrap();
+        assert_eq!(game_leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![3, 2]);
+        // Users who opted out aren't ranked
+        db.set_opted_out(&2, true).await.unwrap();
+        let leaderboard = db.get_leaderboard(None, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 3]);
+    }
+
+    #[sqlx::test]
+    async fn only_ranks_the_members_of_the_guild(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 7200).await;
+        play(&db, &3, "Celeste", 1800).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 3], &(currenttime - DAY)).await.unwrap();
+        db.add_guild_members(&2, &[2], &currenttime).await.unwrap();
+        let leaderboard = db.get_leaderboard(Some(GUILD_ID), false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 3]);
+        let top_games = db.get_server_top_games(Some(GUILD_ID), None, false).await.unwrap();
+        assert_eq!(top_games[0].0, "Factorio");
+        assert_near(top_games[0].1, 3600);
+        // A user who left is still ranked among the members
+        assert_eq!(db.get_user_totals(&2, Some(GUILD_ID)).await.unwrap().map(|totals| (totals.rank, totals.ranked_users)), Some((1, 3)));
+        assert_eq!(db.get_user_rank(&3, Some(GUILD_ID), Some(currenttime - DAY)).await.unwrap(), Some((2, 2)));
+        // The members Discord didn't list since are forgotten
+        db.add_guild_members(&GUILD_ID, &[3], &currenttime).await.unwrap();
+        db.prune_guild_members(&GUILD_ID, &currenttime).await.unwrap();
+        let leaderboard = db.get_leaderboard(Some(GUILD_ID), false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![3]);
+    }
+
+    #[sqlx::test]
+    async fn only_lists_the_admin_roles_of_the_guild(pool: PgPool) {
+        let db = storage(&pool);
+        db.add_admin_role(&GUILD_ID, &10).await.unwrap();
+        db.add_admin_role(&2, &20).await.unwrap();
+        assert_eq!(db.get_admin_roles(&GUILD_ID).await.unwrap(), vec![10]);
+        assert!(db.has_admin_role(&GUILD_ID, vec![10, 20]).await.unwrap());
+        assert!(!db.has_admin_role(&GUILD_ID, vec![20]).await.unwrap());
+        // The roles added before they were stored per guild have no guild until their guild is seen
+        db.add_admin_role(&0, &30).await.unwrap();
+        db.claim_admin_roles(&GUILD_ID, vec![20, 30]).await.unwrap();
+        assert_eq!(db.get_admin_roles(&GUILD_ID).await.unwrap(), vec![10, 30]);
+        assert_eq!(db.get_admin_roles(&2).await.unwrap(), vec![20]);
+    }
+
+    #[sqlx::test]
+    async fn ranks_the_period_leaderboard_by_the_time_in_the_period(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 7200).await;
+        play(&db, &2, "Celeste", 3600).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 2], &currenttime).await.unwrap();
+        let leaderboard = db.get_period_leaderboard(&GUILD_ID, &(currenttime - 5400), &currenttime, false).await.unwrap();
+        assert_eq!(leaderboard.iter().map(|(user_id, _)| *user_id).collect::<Vec<i64>>(), vec![1, 2]);
+        assert!(leaderboard[0].1 <= 5400);
+        let top_games = db.get_period_top_games(&GUILD_ID, &(currenttime - 5400), &currenttime).await.unwrap();
+        assert_eq!(top_games[0].name, "Factorio");
+        assert!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - 3 * DAY), &(currenttime - 2 * DAY), false).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn resets_a_user_until_undone(pool: PgPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        play(&db, &2, "Factorio", 1800).await;
+        db.reset(&1).await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), 0);
+        assert_near(db.get_total_playtime(&2).await.unwrap(), 1800);
+        assert_eq!(db.undo_reset().await.unwrap(), Some(Some(1)));
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), playtime);
+        assert_eq!(db.undo_reset().await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn resets_everyone_until_undone(pool: PgPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        db.register_session(&2, "Celeste", &(Utc::now().timestamp() - 600), &RichPresence::default()).await.unwrap();
+        db.resetall().await.unwrap();
+        assert!(db.get_leaderboard(None, false).await.unwrap().is_empty());
+        assert!(db.find_game("Factorio").await.unwrap().is_none());
+        // Running sessions would count the time until the undo
+        assert_eq!(db.count_open_sessions().await.unwrap(), 0);
+        assert_eq!(db.undo_reset().await.unwrap(), Some(None));
+        assert_eq!(db.get_leaderboard(None, false).await.unwrap(), vec![(1, playtime)]);
+        assert!(db.find_game("Factorio").await.unwrap().is_some());
+    }
+
+    #[sqlx::test]
+    async fn merges_the_games_played_since_the_reset_when_undoing_it(pool: PgPool) {
+        let db = storage(&pool);
+        let before = play(&db, &1, "Factorio", 3600).await;
+        db.resetall().await.unwrap();
+        let after = play(&db, &1, "Factorio", 1800).await;
+        db.undo_reset().await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), before + after);
+        assert_eq!(db.search_games("Factorio").await.unwrap(), vec!["Factorio".to_string()]);
+    }
+
+    // Only save_session ends sessions, restoring the entries of a game being played keeps its session
+    #[sqlx::test]
+    async fn keeps_the_running_sessions_when_undoing_a_reset(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        db.resetall().await.unwrap();
+        db.register_session(&1, "Factorio", &(Utc::now().timestamp() - 600), &RichPresence::default()).await.unwrap();
+        db.undo_reset().await.unwrap();
+        assert_eq!(db.count_open_sessions().await.unwrap(), 1);
+    }
+
+    #[sqlx::test]
+    async fn prunes_the_history_but_keeps_the_playtime(pool: PgPool) {
+        let db = storage(&pool);
+        let before = play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        let after = play(&db, &1, "Factorio", 1800).await;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 1);
+        assert_eq!(db.get_recent_sessions(&1, 10).await.unwrap().len(), 1);
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), before + after);
+    }
+
+    #[sqlx::test]
+    async fn keeps_the_stats_of_the_pruned_history(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        play(&db, &1, "Factorio", 1800).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        let (start, end) = (Utc::now().timestamp() - 30 * DAY, Utc::now().timestamp() + DAY);
+        let stats = db.get_game_stats(&1, &game_id).await.unwrap();
+        let playtime = db.get_user_period_playtime(&1, &start, &end).await.unwrap();
+        let longest_session = db.get_achievement_stats(&1).await.unwrap().longest_session;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 1);
+        let pruned_stats = db.get_game_stats(&1, &game_id).await.unwrap();
+        assert_eq!((pruned_stats.sessions, pruned_stats.playtime), (stats.sessions, stats.playtime));
+        assert_eq!(db.get_user_period_playtime(&1, &start, &end).await.unwrap(), playtime);
+        assert_eq!(db.get_achievement_stats(&1).await.unwrap().longest_session, longest_session);
+        assert_eq!(db.get_summary_games(&1, Some(start), 10, 0).await.unwrap()[0].sessions, 2);
+    }
+
+    #[sqlx::test]
+    async fn exports_every_table_of_the_user(pool: PgPool) {
+        let db = storage(&pool);
+        play(&db, &1, "Factorio", 3600).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        db.set_goal(&1, &game_id, &7200, false).await.unwrap();
+        db.add_watch(&1, &2, Some("Factorio")).await.unwrap();
+        db.set_lfg_subscription(&GUILD_ID, &1, "Factorio", &3).await.unwrap();
+        db.set_user_timezone(&1, Some("Europe/Paris")).await.unwrap();
+        db.set_digest_subscription(&1, true).await.unwrap();
+        db.set_level(&GUILD_ID, &1, &2).await.unwrap();
+        db.unlock_badge(&1, "marathon", &0).await.unwrap();
+        let export = db.export_user(&1).await.unwrap();
+        assert_eq!(export.as_object().unwrap().keys().collect::<Vec<&String>>(), ["badges", "games", "goals", "history_rollup", "imported", "levels", "lfg_subscriptions",
+            "linked_accounts", "listening", "open_sessions", "opted_out", "sessions", "settings", "streams", "user_id", "voice_sessions", "watches"]);
+        assert_eq!((&export["settings"]["timezone"], &export["settings"]["digest"]), (&json!("Europe/Paris"), &json!(true)));
+        for key in ["goals", "watches", "lfg_subscriptions", "levels", "badges"] {
+            assert_eq!(export[key].as_array().unwrap().len(), 1, "{}", key);
+        }
+    }
+
+    #[sqlx::test]
+    async fn rolls_the_pruned_history_up_per_day(pool: PgPool) {
+        let db = storage(&pool);
+        let first = play(&db, &1, "Factorio", 3600).await;
+        move_history_back(&pool, 10 * DAY).await;
+        let second = play(&db, &1, "Factorio", 1800).await;
+        move_history_back(&pool, 10 * DAY).await;
+        play(&db, &1, "Factorio", 600).await;
+        assert_eq!(db.prune_history(&(Utc::now().timestamp() - 5 * DAY)).await.unwrap(), 2);
+        let rollup: Vec<(i64, i64, i64)> = query_as("SELECT day, playtime, sessions FROM history_rollup WHERE user_id=1 ORDER BY day;")
+                                            .fetch_all(&pool).await.unwrap();
+        assert_eq!(rollup.iter().map(|(_, playtime, sessions)| (*playtime, *sessions)).collect::<Vec<_>>(), [(first, 1), (second, 1)]);
+        assert!(rollup.iter().all(|(day, _, _)| day % DAY == 0));
+    }
+
+    #[sqlx::test]
+    async fn adjusts_the_playtime_without_going_under_zero(pool: PgPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.adjust_playtime(&1, &game_id, &600).await.unwrap(), playtime + 600);
+        assert_eq!(db.adjust_playtime(&1, &game_id, &-(2 * playtime)).await.unwrap(), 0);
+        assert!(db.get_top_games(&1, None, 10, 0).await.unwrap().is_empty());
+        assert_eq!(db.adjust_playtime(&2, &game_id, &1800).await.unwrap(), 1800);
+    }
+
+    #[sqlx::test]
+    async fn ranks_the_manual_sessions_only_when_asked(pool: PgPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        let currenttime = Utc::now().timestamp();
+        db.add_guild_members(&GUILD_ID, &[1, 2], &currenttime).await.unwrap();
+        assert_eq!(db.log_session(&2, "factorio", &(currenttime - 7200), &7200).await.unwrap(), "Factorio");
+        let (game_id, _) = db.find_game("Factorio").await.unwrap().unwrap();
+        assert_eq!(db.get_leaderboard(None, false).await.unwrap(), vec![(1, playtime)]);
+        assert_eq!(db.get_leaderboard(None, true).await.unwrap(), vec![(2, 7200), (1, playtime)]);
+        assert_eq!(db.get_game_leaderboard(None, &game_id, false).await.unwrap(), vec![(1, playtime)]);
+        assert_eq!(db.get_game_leaderboard(None, &game_id, true).await.unwrap(), vec![(2, 7200), (1, playtime)]);
+        assert_eq!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - DAY), &(currenttime + 60), false).await.unwrap().len(), 1);
+        assert_eq!(db.get_period_leaderboard(&GUILD_ID, &(currenttime - DAY), &(currenttime + 60), true).await.unwrap().len(), 2);
+        assert_eq!(db.get_imported_playtime(&2).await.unwrap(), vec![("manual".to_string(), 7200)]);
+        assert_eq!(db.get_recent_sessions(&2, 10).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn imports_the_playtime_on_top_of_the_tracked_one(pool: PgPool) {
+        let db = storage(&pool);
+        let playtime = play(&db, &1, "Factorio", 3600).await;
+        db.import_playtime(&[(1, "factorio".to_string(), 600), (2, "Outer Wilds".to_string(), 1800), (2, "Outer Wilds".to_string(), 60)]).await.unwrap();
+        assert_eq!(db.get_total_playtime(&1).await.unwrap(), playtime + 600);
+        assert_eq!(db.get_total_playtime(&2).await.unwrap(), 1860);
+        assert!(db.find_game("Outer Wilds").await.unwrap().is_some());
+        assert_eq!(db.get_recent_sessions(&2, 10).await.unwrap().len(), 0);
+    }
+}
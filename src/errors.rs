@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use reqwest::{Client, Url};
+use serde_json::{json, Map};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Only set when SENTRY_DSN is, the errors are only logged otherwise
+static REPORTER: OnceLock<ErrorReporter> = OnceLock::new();
+
+// Sends the errors to the store endpoint of a Sentry project
+struct ErrorReporter {
+    http: Client,
+    store_url: Url,
+    auth: String,
+    // Makes the event ids of errors reported in the same nanosecond differ
+    events: AtomicU64,
+}
+
+// Who the failure happened to, attached to the reported error
+#[derive(Default)]
+pub struct ErrorContext {
+    pub user_id: Option<u64>,
+    pub guild_id: Option<u64>,
+    pub command: Option<String>,
+}
+
+// Starts reporting the errors and panics to the project of the DSN, formatted as https://<key>@<host>/<project id>
+pub fn init(dsn: &str) -> Result<()> {
+    let dsn = Url::parse(dsn)?;
+    let key = dsn.username();
+    let project = dsn.path_segments().and_then(|mut segments| segments.next_back()).filter(|project| !project.is_empty());
+    let (key, project) = match (key, project) {
+        ("", _) | (_, None) => return Err(anyhow!("The Sentry DSN needs a key and a project id")),
+        (key, Some(project)) => (key, project),
+    };
+    // Projects can be under a path, the API is next to it
+    let prefix = dsn.path().trim_end_matches(project).trim_end_matches('/');
+    let mut store_url = dsn.clone();
+    store_url.set_username("").map_err(|_| anyhow!("Invalid Sentry DSN"))?;
+    store_url.set_path(&format!("{}/api/{}/store/", prefix, project));
+    let reporter = ErrorReporter {
+        http: Client::builder().timeout(REQUEST_TIMEOUT).build()?,
+        store_url,
+        auth: format!("Sentry sentry_version=7, sentry_key={}, sentry_client=gameactivitybot/{}", key, env!("CARGO_PKG_VERSION")),
+        events: AtomicU64::new(0),
+    };
+    REPORTER.set(reporter).map_err(|_| anyhow!("The error reporting is already started"))?;
+    // The default hook still prints the panic
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic| {
+        send("fatal", &panic.to_string(), ErrorContext::default());
+        default_hook(panic);
+    }));
+    Ok(())
+}
+
+pub fn report(message: &str, context: ErrorContext) {
+    send("error", message, context);
+}
+
+fn send(level: &str, message: &str, context: ErrorContext) {
+    let reporter = match REPORTER.get() {
+        Some(reporter) => reporter,
+        None => return,
+    };
+    // Panics outside of the runtime can't be sent
+    let runtime = match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime,
+        Err(_) => return,
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut tags = Map::new();
+    if let Some(guild_id) = context.guild_id {
+        tags.insert("guild_id".to_string(), guild_id.to_string().into());
+    }
+    if let Some(command) = context.command {
+        tags.insert("command".to_string(), command.into());
+    }
+    let event = json!({
+        "event_id": format!("{:016x}{:016x}", now.as_nanos() as u64, reporter.events.fetch_add(1, Ordering::Relaxed)),
+        "timestamp": now.as_secs_f64(),
+        "platform": "other",
+        "level": level,
+        "release": env!("CARGO_PKG_VERSION"),
+        "message": {"formatted": message},
+        "user": context.user_id.map(|user_id| json!({"id": user_id.to_string()})),
+        "tags": tags,
+    });
+    let request = reporter.http.post(reporter.store_url.clone()).header("X-Sentry-Auth", &reporter.auth).json(&event);
+    runtime.spawn(async move {
+        // Not reported, it would fail the same way
+        if let Err(why) = request.send().await.and_then(|response| response.error_for_status()) {
+            warn!("Cannot report an error to Sentry: {:?}", why);
+        }
+    });
+}
@@ -0,0 +1,449 @@
+use anyhow::Result;
+use chrono::Utc;
+use serenity::model::event::GuildMembersChunkEvent;
+use serenity::model::prelude::{Activity, Presence, ActivityType, OnlineStatus, GuildId, RoleId, UserId};
+use serenity::model::voice::VoiceState;
+use serde_json::json;
+use serenity::prelude::Context;
+use std::convert::TryFrom;
+
+use crate::config::ConfigService;
+use crate::db::{Database, GuildConfig, RichPresence};
+use crate::{achievements, cache, levels, reports, rewards, webhooks};
+
+
+pub async fn presence_update(ctx: &Context, db: &Database, config: &ConfigService, new_data: &Presence) -> Result<()> {
+    let user_id = i64::try_from(*new_data.user.id.as_u64())?;
+    if db.is_opted_out(&user_id).await? {
+        return Ok(());
+    }
+    let guild_id = match new_data.guild_id {
+        Some(guild_id) => i64::try_from(*guild_id.as_u64())?,
+        None => 0,
+    };
+    let guild_config = match new_data.guild_id {
+        Some(_) => config.get(&guild_id).await?,
+        None => GuildConfig::default(),
+    };
+    // Sessions are global, the policies of every guild the user is in apply, not only the one whose presence update noticed the change
+    let mut guilds: Vec<(i64, GuildConfig)> = Vec::new();
+    for member_guild_id in db.get_user_guilds(&user_id).await? {
+        if member_guild_id != guild_id {
+            guilds.push((member_guild_id, config.get(&member_guild_id).await?));
+        }
+    }
+    // Presences received outside of a guild only match the global ignore list, unless the user shares a guild with the bot
+    if new_data.guild_id.is_some() || guilds.is_empty() {
+        guilds.push((guild_id, guild_config.clone()));
+    }
+    let (started, saved) = track_activities(db, &guilds, &user_id, new_data.status, &new_data.activities).await?;
+    if new_data.guild_id.is_none() {
+        return Ok(());
+    }
+    for game_name in &started {
+        // Every guild of the user gets the webhook events
+        for (webhook_guild, _) in &guilds {
+            webhooks::dispatch(ctx, db, webhook_guild, &user_id, "session_start", json!({"game": game_name})).await?;
+        }
+        reports::notify_watchers(ctx, db, &guild_id, &user_id, game_name).await?;
+        // Each guild gives its own game roles
+        for (role_guild, _) in &guilds {
+            reports::announce_game_role(ctx, db, role_guild, &user_id, game_name).await?;
+        }
+    }
+    for (game_name, playtime) in &saved {
+        for (webhook_guild, _) in &guilds {
+            webhooks::dispatch(ctx, db, webhook_guild, &user_id, "session_end", json!({"game": game_name, "duration": playtime})).await?;
+        }
+    }
+    // Both starting and ending sessions change how many members play together
+    let changed: Vec<&str> = started.iter().copied().chain(saved.iter().map(|(game_name, _)| game_name.as_str())).collect();
+    if !changed.is_empty() {
+        // The players are counted per guild
+        for (lfg_guild, _) in &guilds {
+            reports::notify_lfg(ctx, db, lfg_guild, &changed).await?;
+        }
+    }
+    if !saved.is_empty() {
+        cache::invalidate(ctx).await;
+        for (reward_guild, _) in &guilds {
+            rewards::grant_role_rewards(ctx, db, reward_guild, &user_id).await?;
+        }
+        // The levels are kept per guild, with each guild's XP rate
+        for (level_guild, level_config) in &guilds {
+            levels::check_level_up(ctx, db, level_config, level_guild, &user_id).await?;
+        }
+        achievements::check_achievements(ctx, db, &guild_config, &guild_id, &user_id).await?;
+    }
+    Ok(())
+}
+
+// Starts and saves the sessions of the activities, returns the started games and the saved (game, playtime)
+// The sessions are shared by the user's `guilds`, a game is tracked as long as one of them tracks it
+async fn track_activities<'a>(db: &Database, guilds: &[(i64, GuildConfig)], user_id: &i64, status: OnlineStatus, activities: &'a [Activity]) -> Result<(Vec<&'a str>, Vec<(String, i64)>)> {
+    let currenttime = Utc::now().timestamp();
+    // Nothing is tracked during a pause, the games still running when it ends count from then
+    let resumed_at = db.get_paused_until(user_id).await?.unwrap_or(0);
+    if resumed_at > currenttime {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    // The status is recorded whatever the policy, a guild changing it applies from the next update
+    let away = matches!(status, OnlineStatus::Idle | OnlineStatus::DoNotDisturb);
+    let (was_away, since) = db.update_status(user_id, away, &currenttime).await?.unwrap_or((false, 0));
+    // The play only stops counting once no guild counts it anymore, from the latest of their cutoffs
+    let cutoffs: Vec<Option<i64>> = guilds.iter().map(|(_, guild_config)| idle_cutoff(guild_config, away, was_away, since, currenttime)).collect();
+    let cutoff = if cutoffs.iter().all(Option::is_some) { cutoffs.into_iter().flatten().max() } else { None };
+    // The most lenient override, the bot wide minimum as soon as a guild has none
+    let min_session_length = guilds.iter().map(|(_, guild_config)| guild_config.min_session_length).min().flatten();
+    let mut saved: Vec<(String, i64)> = Vec::new();
+    let mut tracked_since = resumed_at;
+    if let Some(cutoff) = cutoff {
+        // The time away past the grace period isn't played, the games still running count again from now
+        saved = db.save_session(user_id, &[], min_session_length, &cutoff).await?;
+        tracked_since = std::cmp::max(resumed_at, currenttime);
+    }
+    // A user can play several games at once, alongside other activities like listening to Spotify
+    let mut playing: Vec<i64> = Vec::new();
+    let mut started: Vec<&str> = Vec::new();
+    for user_activity in activities.iter().filter(|activity| activity.kind == ActivityType::Playing) {
+        if away && cutoff.is_some() {
+            break;
+        }
+        if !is_tracked(db, guilds, &user_activity.name).await? {
+            continue;
+        }
+        let start = user_activity.timestamps.as_ref().and_then(|timestamps| timestamps.start);
+        if let Some(start) = start {
+            let starttime = std::cmp::max(i64::try_from(std::time::Duration::from_millis(start).as_secs())?, tracked_since);
+            let rich_presence = RichPresence {
+                state: user_activity.state.clone(),
+                details: user_activity.details.clone(),
+                party_size: user_activity.party.as_ref().and_then(|party| party.size).and_then(|[size, _]| i64::try_from(size).ok()),
+            };
+            let (game_id, is_new) = db.register_session(user_id, &user_activity.name, &starttime, &rich_presence).await?;
+            playing.push(game_id);
+            if is_new {
+                started.push(&user_activity.name);
+            }
+        }
+    }
+    saved.extend(db.save_session(user_id, &playing, min_session_length, &currenttime).await?);
+    // The streamed game is in the state, the activity name is the platform
+    let streaming = activities.iter().find(|activity| activity.kind == ActivityType::Streaming);
+    let streamed_game = streaming.map(|activity| activity.state.as_deref().unwrap_or(&activity.name));
+    db.save_stream(user_id, streamed_game, min_session_length).await?;
+    if let (Some(streaming), Some(game_name)) = (streaming, streamed_game) {
+        db.register_stream(user_id, game_name, streaming.url.as_ref().map(|url| url.as_str())).await?;
+    }
+    // Guilds that don't track listening leave the sessions to the ones that do
+    if guilds.iter().any(|(_, guild_config)| guild_config.track_listening) {
+        // The artists are in the state, the song in the details
+        let listening = activities.iter()
+            .find(|activity| activity.kind == ActivityType::Listening)
+            .map(|activity| activity.state.as_deref().unwrap_or(&activity.name));
+        db.save_listen(user_id, listening).await?;
+        if let Some(artist) = listening {
+            db.register_listen(user_id, artist).await?;
+        }
+    }
+    return Ok((started, saved));
+}
+
+// Whether one of the guilds tracks the game: it isn't on their ignore list, nor left out by their whitelist
+async fn is_tracked(db: &Database, guilds: &[(i64, GuildConfig)], game_name: &str) -> Result<bool> {
+    for (guild_id, guild_config) in guilds {
+        if db.is_ignored(guild_id, game_name).await? {
+            continue;
+        }
+        if guild_config.whitelist_only && !db.is_tracked_game(guild_id, game_name).await? {
+            continue;
+        }
+        return Ok(true);
+    }
+    return Ok(false);
+}
+
+// Keeps the sessions of users still in the same game and saves the others
+pub async fn reconcile_sessions(ctx: &Context, db: &Database, config: &ConfigService, presences: &[Presence]) -> Result<()> {
+    let mut online: Vec<i64> = Vec::new();
+    for presence in presences {
+        presence_update(ctx, db, config, presence).await?;
+        online.push(i64::try_from(*presence.user.id.as_u64())?);
+    }
+    for user_id in db.get_session_users().await? {
+        if !online.contains(&user_id) {
+            if !db.save_session(&user_id, &[], None, &Utc::now().timestamp()).await?.is_empty() {
+                cache::invalidate(ctx).await;
+            }
+            db.save_stream(&user_id, None, None).await?;
+            db.save_listen(&user_id, None).await?;
+        }
+    }
+    Ok(())
+}
+
+// Voice sessions are split when a user moves to another channel, muting doesn't change anything
+pub async fn voice_state_update(db: &Database, old: Option<&VoiceState>, new: &VoiceState) -> Result<()> {
+    if old.map_or(false, |old| old.channel_id == new.channel_id) {
+        return Ok(());
+    }
+    let user_id = i64::try_from(*new.user_id.as_u64())?;
+    let currenttime = Utc::now().timestamp();
+    db.end_voice_session(&user_id, &currenttime).await?;
+    if db.is_opted_out(&user_id).await? || is_paused(db, &user_id).await? {
+        return Ok(());
+    }
+    if let (Some(guild_id), Some(channel_id)) = (new.guild_id, new.channel_id) {
+        db.start_voice_session(&user_id, &i64::try_from(*guild_id.as_u64())?, &i64::try_from(*channel_id.as_u64())?, &currenttime).await?;
+    }
+    Ok(())
+}
+
+// Starts the sessions of the users already in a voice channel and ends the ones of users who left while the bot was offline
+pub async fn reconcile_voice_sessions(db: &Database, voice_states: &[VoiceState]) -> Result<()> {
+    let mut connected: Vec<i64> = Vec::new();
+    for voice_state in voice_states.iter().filter(|voice_state| voice_state.channel_id.is_some()) {
+        let user_id = i64::try_from(*voice_state.user_id.as_u64())?;
+        if db.is_opted_out(&user_id).await? || is_paused(db, &user_id).await? {
+            continue;
+        }
+        if let (Some(guild_id), Some(channel_id)) = (voice_state.guild_id, voice_state.channel_id) {
+            db.start_voice_session(&user_id, &i64::try_from(*guild_id.as_u64())?, &i64::try_from(*channel_id.as_u64())?, &Utc::now().timestamp()).await?;
+            connected.push(user_id);
+        }
+    }
+    for user_id in db.get_voice_users().await? {
+        if !connected.contains(&user_id) {
+            db.end_voice_session(&user_id, &Utc::now().timestamp()).await?;
+        }
+    }
+    Ok(())
+}
+
+// Records the members Discord listed, the rankings of a guild only count its members
+// The nonce of the chunks is when they were requested, the members missing from all of them left while the bot was away
+pub async fn guild_members_chunk(db: &Database, chunk: &GuildMembersChunkEvent) -> Result<()> {
+    let guild_id = i64::try_from(*chunk.guild_id.as_u64())?;
+    let user_ids = chunk.members.keys()
+        .map(|user_id| i64::try_from(*user_id.as_u64()))
+        .collect::<Result<Vec<i64>, _>>()?;
+    db.add_guild_members(&guild_id, &user_ids, &Utc::now().timestamp()).await?;
+    if chunk.chunk_index + 1 == chunk.chunk_count {
+        if let Some(requested) = chunk.nonce.as_ref().and_then(|nonce| nonce.parse::<i64>().ok()) {
+            db.prune_guild_members(&guild_id, &requested).await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn guild_member_addition(db: &Database, guild_id: GuildId, user_id: UserId) -> Result<()> {
+    db.add_guild_members(&i64::try_from(*guild_id.as_u64())?, &[i64::try_from(*user_id.as_u64())?], &Utc::now().timestamp()).await
+}
+
+// Without `user_id`, the bot left the guild and forgets all its members
+pub async fn guild_member_removal(db: &Database, guild_id: GuildId, user_id: Option<UserId>) -> Result<()> {
+    let guild_id = i64::try_from(*guild_id.as_u64())?;
+    return match user_id {
+        Some(user_id) => db.remove_guild_member(&guild_id, &i64::try_from(*user_id.as_u64())?).await,
+        None => db.prune_guild_members(&guild_id, &i64::MAX).await,
+    };
+}
+
+// The admin roles added before they were stored per guild belong to the guild that has them
+pub async fn claim_admin_roles(db: &Database, guild_id: GuildId, role_ids: Vec<RoleId>) -> Result<()> {
+    let role_ids = role_ids.iter()
+        .map(|role_id| i64::try_from(*role_id.as_u64()))
+        .collect::<Result<Vec<i64>, _>>()?;
+    db.claim_admin_roles(&i64::try_from(*guild_id.as_u64())?, role_ids).await
+}
+
+// When the play stopped counting because the user was idle or DND, None while it counts
+// "pause" gives a grace period of idle_minutes, "exclude" stops counting right away
+fn idle_cutoff(guild_config: &GuildConfig, away: bool, was_away: bool, since: i64, currenttime: i64) -> Option<i64> {
+    let grace = match guild_config.idle_policy.as_str() {
+        "pause" => guild_config.idle_minutes * 60,
+        "exclude" => 0,
+        _ => return None,
+    };
+    let away_start = if away && was_away {
+        since
+    } else if away {
+        currenttime
+    } else if was_away && currenttime - since > grace {
+        since
+    } else {
+        return None;
+    };
+    if currenttime - away_start < grace {
+        return None;
+    }
+    return Some(away_start + grace);
+}
+
+// Whether the user paused their tracking with /pause
+async fn is_paused(db: &Database, user_id: &i64) -> Result<bool> {
+    return Ok(db.get_paused_until(user_id).await?.map_or(false, |until| until > Utc::now().timestamp()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{track_activities, voice_state_update};
+    use crate::db::{Database, GuildConfig, MemoryStorage};
+    use chrono::Utc;
+    use serde_json::json;
+    use serenity::model::prelude::{Activity, OnlineStatus};
+    use serenity::model::voice::VoiceState;
+    use std::sync::Arc;
+
+    const GUILD_ID: i64 = 1;
+    const USER_ID: i64 = 2;
+
+    fn database() -> Database {
+        return Arc::new(MemoryStorage::new(60, 24 * 60 * 60));
+    }
+
+    // A game started `seconds` ago
+    fn playing(game_name: &str, seconds: i64) -> Activity {
+        let start = (Utc::now().timestamp() - seconds) * 1000;
+        return serde_json::from_value(json!({"name": game_name, "type": 0, "timestamps": {"start": start}})).unwrap();
+    }
+
+    fn voice_state(channel_id: Option<u64>) -> VoiceState {
+        return serde_json::from_value(json!({
+            "channel_id": channel_id.map(|channel_id| channel_id.to_string()),
+            "guild_id": GUILD_ID.to_string(),
+            "user_id": USER_ID.to_string(),
+            "session_id": "session",
+            "deaf": false, "mute": false, "self_deaf": false, "self_mute": false, "self_video": false, "suppress": false,
+        })).unwrap();
+    }
+
+    #[tokio::test]
+    async fn saves_the_playtime_when_a_game_is_closed() {
+        let db = database();
+        let config = GuildConfig::default();
+        let activities = [playing("Factorio", 3600)];
+        let (started, saved) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+        assert!(saved.is_empty());
+        // The next update of the same game keeps the session
+        let (started, _) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert!(started.is_empty());
+        assert_eq!(db.get_open_sessions(&USER_ID).await.unwrap().len(), 1);
+
+        let (_, saved) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &[]).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].0, "Factorio");
+        assert!((3600..3610).contains(&saved[0].1));
+        assert_eq!(db.get_total_playtime(&USER_ID).await.unwrap(), saved[0].1);
+        assert!(db.get_open_sessions(&USER_ID).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn discards_sessions_shorter_than_the_minimum() {
+        let db = database();
+        let config = GuildConfig::default();
+        track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &[playing("Factorio", 10)]).await.unwrap();
+        let (_, saved) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &[]).await.unwrap();
+        assert!(saved.is_empty());
+        assert_eq!(db.get_total_playtime(&USER_ID).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn skips_ignored_games() {
+        let db = database();
+        db.add_ignored_game(&GUILD_ID, "Wallpaper Engine").await.unwrap();
+        let activities = [playing("Wallpaper Engine", 3600), playing("Factorio", 3600)];
+        let (started, _) = track_activities(&db, &[(GUILD_ID, GuildConfig::default())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+    }
+
+    #[tokio::test]
+    async fn only_tracks_tracked_games_with_the_whitelist() {
+        let db = database();
+        db.add_tracked_game(&GUILD_ID, "Factorio").await.unwrap();
+        let config = GuildConfig { whitelist_only: true, ..GuildConfig::default() };
+        let activities = [playing("Wallpaper Engine", 3600), playing("Factorio", 3600)];
+        let (started, _) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+    }
+
+    #[tokio::test]
+    async fn tracks_the_games_one_of_the_guilds_tracks() {
+        let db = database();
+        db.add_ignored_game(&GUILD_ID, "Factorio").await.unwrap();
+        let excluding = GuildConfig { idle_policy: "exclude".to_string(), ..GuildConfig::default() };
+        let guilds = [(GUILD_ID, excluding), (3, GuildConfig::default())];
+        let activities = [playing("Factorio", 3600)];
+        let (started, _) = track_activities(&db, &guilds, &USER_ID, OnlineStatus::Idle, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+        // The other guild still counts the play of idle users
+        let (_, saved) = track_activities(&db, &guilds, &USER_ID, OnlineStatus::Idle, &activities).await.unwrap();
+        assert!(saved.is_empty());
+        // Once no guild tracks the game, its session ends
+        let (_, saved) = track_activities(&db, &guilds[..1], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(saved.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resumes_tracking_after_a_pause() {
+        let db = database();
+        let config = GuildConfig::default();
+        let currenttime = Utc::now().timestamp();
+        let activities = [playing("Factorio", 3600)];
+        db.set_paused_until(&USER_ID, Some(currenttime + 600)).await.unwrap();
+        let (started, _) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert!(started.is_empty());
+        assert!(db.get_open_sessions(&USER_ID).await.unwrap().is_empty());
+        // The game started during the pause only counts from its end
+        db.set_paused_until(&USER_ID, Some(currenttime - 600)).await.unwrap();
+        let (started, _) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+        assert_eq!(db.get_open_sessions(&USER_ID).await.unwrap()[0].starttime, currenttime - 600);
+    }
+
+    #[tokio::test]
+    async fn records_the_rich_presence_of_the_sessions() {
+        let db = database();
+        let start = (Utc::now().timestamp() - 600) * 1000;
+        let activity: Activity = serde_json::from_value(json!({
+            "name": "VALORANT", "type": 0, "timestamps": {"start": start},
+            "details": "Competitive", "state": "In a Party", "party": {"size": [3, 5]},
+        })).unwrap();
+        track_activities(&db, &[(GUILD_ID, GuildConfig::default())], &USER_ID, OnlineStatus::Online, &[activity]).await.unwrap();
+        let session = &db.get_open_sessions(&USER_ID).await.unwrap()[0];
+        assert_eq!((session.details.as_deref(), session.state.as_deref(), session.party_size), (Some("Competitive"), Some("In a Party"), Some(3)));
+    }
+
+    #[tokio::test]
+    async fn stops_counting_the_play_of_idle_users() {
+        let db = database();
+        let activities = [playing("Factorio", 3600)];
+        // Counted by default
+        let config = GuildConfig::default();
+        track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Idle, &activities).await.unwrap();
+        assert_eq!(db.get_open_sessions(&USER_ID).await.unwrap().len(), 1);
+        // Excluded, the session ends when the user went idle
+        let config = GuildConfig { idle_policy: "exclude".to_string(), ..GuildConfig::default() };
+        let (_, saved) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::DoNotDisturb, &activities).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert!(db.get_open_sessions(&USER_ID).await.unwrap().is_empty());
+        // Back online, the game counts again from now
+        let (started, _) = track_activities(&db, &[(GUILD_ID, config.clone())], &USER_ID, OnlineStatus::Online, &activities).await.unwrap();
+        assert_eq!(started, vec!["Factorio"]);
+        assert!(db.get_open_sessions(&USER_ID).await.unwrap()[0].starttime >= Utc::now().timestamp() - 5);
+    }
+
+    #[tokio::test]
+    async fn splits_voice_sessions_by_channel() {
+        let db = database();
+        voice_state_update(&db, None, &voice_state(Some(10))).await.unwrap();
+        assert_eq!(db.get_voice_users().await.unwrap(), vec![USER_ID]);
+        // Muting doesn't end the session
+        voice_state_update(&db, Some(&voice_state(Some(10))), &voice_state(Some(10))).await.unwrap();
+        voice_state_update(&db, Some(&voice_state(Some(10))), &voice_state(Some(11))).await.unwrap();
+        assert_eq!(db.get_voice_users().await.unwrap(), vec![USER_ID]);
+        voice_state_update(&db, Some(&voice_state(Some(11))), &voice_state(None)).await.unwrap();
+        assert!(db.get_voice_users().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// The catalogs are compiled in, a locale is supported once it has one here
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+// Messages by locale then key
+static MESSAGES: OnceLock<HashMap<&'static str, HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+fn messages() -> &'static HashMap<&'static str, HashMap<&'static str, &'static str>> {
+    return MESSAGES.get_or_init(|| CATALOGS.iter()
+        .map(|(locale, catalog)| (*locale, parse_catalog(catalog)))
+        .collect());
+}
+
+// Reads the `key = text` lines of a catalog, skipping the comments
+fn parse_catalog(catalog: &'static str) -> HashMap<&'static str, &'static str> {
+    return catalog.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, text)| (key.trim(), text.trim()))
+        .collect();
+}
+
+pub fn locales() -> impl Iterator<Item = &'static str> {
+    return CATALOGS.iter().map(|(locale, _)| *locale);
+}
+
+pub fn is_supported(locale: &str) -> bool {
+    return CATALOGS.iter().any(|(supported, _)| *supported == locale);
+}
+
+// Returns the message only when `locale` translates it
+pub fn translation(locale: &str, key: &str) -> Option<&'static str> {
+    return messages().get(locale).and_then(|catalog| catalog.get(key)).copied();
+}
+
+// Returns the message in `locale`, in English when it isn't translated, or the key when it doesn't exist
+pub fn t(locale: &str, key: &str) -> String {
+    return tr(locale, key, &[]);
+}
+
+// Same as `t`, replacing the `{ $name }` placeholders with `args`
+pub fn tr(locale: &str, key: &str, args: &[(&str, String)]) -> String {
+    let text = translation(locale, key)
+        .or_else(|| translation(DEFAULT_LOCALE, key))
+        .unwrap_or(key);
+    let mut message = text.to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${} }}", name), value);
+    }
+    return message;
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{info, warn};
+
+use crate::db::{Database, GameMetadata};
+
+
+// Metadata older than this is looked up again
+const METADATA_MAX_AGE: i64 = 30 * 24 * 60 * 60;
+const GAMES_PER_REFRESH: i64 = 50;
+// IGDB allows 4 requests per second
+const REQUEST_DELAY: Duration = Duration::from_millis(300);
+
+// Client of the IGDB API, authenticated with a Twitch application
+pub struct Igdb {
+    http: Client,
+    client_id: String,
+    client_secret: String,
+    // The app access token and when it expires
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl Igdb {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        return Igdb { http: Client::new(), client_id, client_secret, token: Mutex::new(None) };
+    }
+
+    // Returns the cached token, requesting a new one a minute before it expires
+    async fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some((access_token, expires_at)) = token.as_ref() {
+            if Instant::now() + Duration::from_secs(60) < *expires_at {
+                return Ok(access_token.clone());
+            }
+        }
+        let response: Value = self.http.post("https://id.twitch.tv/oauth2/token")
+            .query(&[("client_id", self.client_id.as_str()), ("client_secret", self.client_secret.as_str()), ("grant_type", "client_credentials")])
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        let access_token = response["access_token"].as_str().ok_or_else(|| anyhow!("No access token in the Twitch response"))?.to_string();
+        let expires_in = response["expires_in"].as_u64().unwrap_or(0);
+        *token = Some((access_token.clone(), Instant::now() + Duration::from_secs(expires_in)));
+        return Ok(access_token);
+    }
+
+    // Returns the metadata of the best match for a game name
+    pub async fn search(&self, game_name: &str) -> Result<Option<GameMetadata>> {
+        // Apicalypse strings can't contain quotes
+        let body = format!("search \"{}\"; fields name, cover.image_id, genres.name, summary, first_release_date; where category = 0; limit 1;", game_name.replace('"', ""));
+        let response: Value = self.http.post("https://api.igdb.com/v4/games")
+            .header("Client-ID", &self.client_id)
+            .bearer_auth(self.token().await?)
+            .body(body)
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        let game = match response.as_array().and_then(|games| games.first()) {
+            Some(game) => game,
+            None => return Ok(None),
+        };
+        return Ok(Some(GameMetadata {
+            igdb_id: game["id"].as_i64().ok_or_else(|| anyhow!("No id in the IGDB response"))?,
+            title: game["name"].as_str().unwrap_or(game_name).to_string(),
+            cover_url: game["cover"]["image_id"].as_str()
+                .map(|image_id| format!("https://images.igdb.com/igdb/image/upload/t_cover_big/{}.jpg", image_id)),
+            genres: game["genres"].as_array().map_or(Vec::new(), |genres| genres.iter()
+                .filter_map(|genre| genre["name"].as_str().map(|name| name.to_string()))
+                .collect()),
+            summary: game["summary"].as_str().map(|summary| summary.to_string()),
+            release_date: game["first_release_date"].as_i64(),
+        }));
+    }
+}
+
+// Looks up the games added since the last run and refreshes the outdated metadata
+pub async fn refresh_metadata(db: &Database, igdb: &Igdb) -> Result<()> {
+    let currenttime = Utc::now().timestamp();
+    let games = db.get_games_to_enrich(&(currenttime - METADATA_MAX_AGE), GAMES_PER_REFRESH).await?;
+    if !games.is_empty() {
+        info!("Refreshing the metadata of {} games", games.len());
+    }
+    for (game_id, game_name) in games {
+        match igdb.search(&game_name).await {
+            Ok(metadata) => db.set_game_metadata(&game_id, metadata.as_ref(), &currenttime).await?,
+            // Left for the next run, the API might just be down
+            Err(why) => warn!("Cannot look up {:?} on IGDB: {:?}", game_name, why),
+        }
+        sleep(REQUEST_DELAY).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde_json::json;
+use serenity::model::id::ChannelId;
+use serenity::prelude::Context;
+use tracing::error;
+
+use crate::db::{Database, GuildConfig};
+use crate::webhooks;
+
+
+const PROGRESS_BAR_WIDTH: i64 = 10;
+
+pub fn xp(playtime: i64, xp_per_hour: i64) -> i64 {
+    return playtime * xp_per_hour / 3600;
+}
+
+// Reaching level n takes base_xp * n² XP
+pub fn xp_for_level(level: i64, base_xp: i64) -> i64 {
+    return base_xp * level * level;
+}
+
+pub fn level_for_xp(xp: i64, base_xp: i64) -> i64 {
+    let mut level = ((xp as f64 / base_xp as f64).sqrt()) as i64;
+    // The float square root can be off by one around perfect squares
+    while xp_for_level(level + 1, base_xp) <= xp {
+        level += 1;
+    }
+    while level > 0 && xp_for_level(level, base_xp) > xp {
+        level -= 1;
+    }
+    return level;
+}
+
+pub fn progress_bar(current: i64, total: i64) -> String {
+    let filled = if total > 0 { (current * PROGRESS_BAR_WIDTH / total).clamp(0, PROGRESS_BAR_WIDTH) } else { 0 };
+    return format!("{}{}", "▰".repeat(filled as usize), "▱".repeat((PROGRESS_BAR_WIDTH - filled) as usize));
+}
+
+// Announces the member's new level in the guild's level channel when their playtime got them one
+pub async fn check_level_up(ctx: &Context, db: &Database, config: &GuildConfig, guild_id: &i64, user_id: &i64) -> Result<()> {
+    let level = level_for_xp(xp(db.get_total_playtime(user_id).await?, config.xp_per_hour), config.level_base_xp);
+    if level <= db.get_level(guild_id, user_id).await? {
+        return Ok(());
+    }
+    db.set_level(guild_id, user_id, &level).await?;
+    webhooks::dispatch(ctx, db, guild_id, user_id, "level_up", json!({"level": level})).await?;
+    if let Some(channel_id) = config.level_channel {
+        let content = format!("🎉 <@{}> reached level {}!", user_id, level);
+        if let Err(why) = ChannelId(u64::try_from(channel_id)?).say(&ctx.http, content).await {
+            error!("Cannot announce {}'s level up: {:?}", user_id, why);
+        }
+    }
+    Ok(())
+}
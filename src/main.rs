@@ -1,302 +1,422 @@
+mod achievements;
+mod api;
+mod backup;
+mod cache;
+mod chart;
+mod commands;
+mod config;
+mod db;
+mod errors;
+mod handlers;
+mod i18n;
+mod igdb;
+mod levels;
+mod ratelimit;
+mod reports;
+mod rewards;
+mod scheduler;
+mod status;
+mod steam;
+mod webhooks;
+mod writer;
+mod xbox;
+
 use anyhow::anyhow;
-use chrono::{Utc, TimeZone, Duration};
-use serenity::builder::CreateEmbed;
-use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::{Interaction, InteractionResponseType, Presence, ActivityType, Activity, UserId};
+use serenity::model::application::interaction::Interaction;
+use serenity::model::event::GuildMembersChunkEvent;
+use serenity::client::bridge::gateway::ChunkGuildFilter;
+use serenity::model::guild::{Member, UnavailableGuild};
+use serenity::model::prelude::Presence;
 use serenity::model::user::User;
-use serenity::utils::Colour;
+use serenity::model::voice::VoiceState;
 use serenity::{async_trait, model::prelude::GuildId};
-use sqlx::{query, Row, PgPool};
-use shuttle_service::ResourceBuilder;
-use sqlx::postgres::PgRow;
+use sqlx::PgPool;
+use serenity::model::application::command::Command;
 use serenity::model::gateway::Ready;
+use serenity::model::guild::Guild;
 use serenity::prelude::*;
+#[cfg(feature = "shuttle")]
 use shuttle_secrets::SecretStore;
-use tracing::info;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::convert::TryFrom;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{debug, error, info, info_span, Instrument};
+
+use backup::BackupStorage;
+use cache::Cache;
+use config::ConfigService;
+use db::{Database, PgStorage};
+#[cfg(feature = "mysql")]
+use db::MySqlStorage;
+#[cfg(feature = "sqlite")]
+use db::SqliteStorage;
+use errors::ErrorContext;
+use igdb::Igdb;
+use ratelimit::RateLimiter;
+use scheduler::HistoryRetention;
+use status::{ShardManagerContainer, StartedAt};
+use steam::Steam;
+use webhooks::WebhookQueue;
+use xbox::Xbox;
 
 
 struct Bot {
-    pool: PgPool
+    db: Database,
+    config: Arc<ConfigService>,
+    // Only set when the IGDB credentials are configured
+    igdb: Option<Arc<Igdb>>,
+    // cache_ready fires again after a reconnection
+    scheduler_started: AtomicBool,
+    // Registers the commands globally instead of in each guild
+    global_commands: bool,
 }
 
-impl Bot {
-    async fn save_session(&self, user_id: &i64) {
-        let row = query("SELECT game_id, starttime FROM game_sessions WHERE user_id=$1;")
-                                            .bind(user_id)
-                                            .fetch_optional(&self.pool).await.unwrap();
-        if row.is_none() {
-            return;
+#[async_trait]
+impl EventHandler for Bot {
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+        if let Err(why) = self.db.migrate().await {
+            error!("Cannot migrate the database: {:?}", why);
+            errors::report(&format!("Cannot migrate the database: {:?}", why), ErrorContext::default());
+        }
+
+        // Guild commands are available right away, global ones can take an hour to show up.
+        // Registering overwrites every command, the ones of the other mode are cleared so they don't show up twice
+        let global_commands = if self.global_commands {
+            Command::set_global_application_commands(&ctx.http, |commands| commands::register(commands)).await
+        } else {
+            Command::set_global_application_commands(&ctx.http, |commands| commands).await
+        };
+        if let Err(why) = global_commands {
+            error!("Cannot register the global slash commands: {:?}", why);
+        }
+        for guild in &ready.guilds {
+            register_commands(&ctx, guild.id, !self.global_commands).await;
         }
-        info!("Saving {:?}'s session", user_id);
-        let row: PgRow = row.unwrap();
-        let game_id: i64 = row.get::<i64, usize>(0);
-        let starttime: i64 = row.get::<i64, usize>(1);
-        let currenttime: i64 = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()).unwrap();
-        let playtime: i64 = currenttime - starttime;
-        info!("Playtime: {:?}s", playtime);
-        self.add_playtime(user_id, &game_id, &playtime).await;
     }
-    
-    async fn get_summary(&self, user: &User) -> CreateEmbed {
-
-        let user_id = i64::try_from(*user.id.as_u64()).unwrap();
-        let mut embed = CreateEmbed::default()
-            .colour(Colour::TEAL)
-            .title(format!("{}'s playtime summary", user.name)).to_owned();
-
-        for row in query("SELECT name, playtime FROM game_entries NATURAL JOIN games WHERE user_id=$1 ORDER BY playtime DESC LIMIT 10;")
-                                            .bind(user_id)
-                                            .fetch_all(&self.pool).await.unwrap() {
-            let game_name: &str = row.get::<&str, usize>(0);
-            let playtime = Duration::seconds(row.get::<i64, usize>(1));
-            let tmp_datetime = Utc.with_ymd_and_hms(1337, 1, 1, 0, 0, 0).unwrap() + playtime;
-            let formated_playtime = tmp_datetime.format("%X").to_string();
-            embed.field(game_name, formated_playtime, true);
+
+    // Guilds joined while the bot is running weren't in `ready`
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: bool) {
+        if is_new && !self.global_commands {
+            register_commands(&ctx, guild.id, true).await;
+        }
+        // Large guilds only come with their online members, all of them are listed in chunks
+        ctx.shard.chunk_guild(guild.id, None, ChunkGuildFilter::None, Some(chrono::Utc::now().timestamp().to_string()));
+        if let Err(why) = handlers::claim_admin_roles(&self.db, guild.id, guild.roles.keys().copied().collect()).await {
+            error!("Cannot attribute the admin roles of {}: {:?}", guild.id, why);
         }
-        return embed;
     }
-    
-    async fn is_game_in_db(&self, game_name: &String) -> bool {
-        let row = query("SELECT * FROM games WHERE name=$1;")
-                                            .bind(game_name)
-                                            .fetch_optional(&self.pool).await.unwrap();
-        return row.is_some();
+
+    async fn guild_members_chunk(&self, _ctx: Context, chunk: GuildMembersChunkEvent) {
+        if let Err(why) = handlers::guild_members_chunk(&self.db, &chunk).await {
+            error!("Cannot record the members of {}: {:?}", chunk.guild_id, why);
+        }
     }
-    
-    async fn register_session(&self, user_id: &i64, game_name: &String, starttime: &i64) {
-        if !self.is_game_in_db(game_name).await {
-            info!("Adding {:?} to db", game_name);
-            self.add_game(game_name).await;
+
+    async fn guild_member_addition(&self, _ctx: Context, new_member: Member) {
+        if let Err(why) = handlers::guild_member_addition(&self.db, new_member.guild_id, new_member.user.id).await {
+            error!("Cannot record {} joining {}: {:?}", new_member.user.id, new_member.guild_id, why);
         }
-        info!("Registering {:?}'s session", user_id);
-        let game_id: i64 = self.get_game_id(game_name).await;
-        query("INSERT INTO game_sessions (user_id, game_id, starttime) VALUES ($1, $2, $3);")
-            .bind(user_id)
-            .bind(game_id)
-            .bind(starttime)
-            .execute(&self.pool).await.unwrap();
     }
-    
-    async fn get_game_id(&self, game_name: &String) -> i64 {
-        let row = query("SELECT game_id FROM games WHERE name=$1;")
-                                            .bind(game_name)
-                                            .fetch_one(&self.pool).await.unwrap();
-        return row.get::<i64, usize>(0);
+
+    async fn guild_member_removal(&self, _ctx: Context, guild_id: GuildId, user: User, _member: Option<Member>) {
+        if let Err(why) = handlers::guild_member_removal(&self.db, guild_id, Some(user.id)).await {
+            error!("Cannot record {} leaving {}: {:?}", user.id, guild_id, why);
+        }
     }
-    
-    async fn add_playtime(&self, user_id: &i64, game_id: &i64, playtime: &i64) {
-        let row = query("SELECT * FROM game_entries WHERE user_id=$1 AND game_id=$2;")
-                                            .bind(user_id)
-                                            .bind(game_id)
-                                            .fetch_optional(&self.pool).await.unwrap();
-        if row.is_none() {
-            query("INSERT INTO game_entries (user_id, game_id, playtime) VALUES ($1, $2, $3);")
-                .bind(user_id)
-                .bind(game_id)
-                .bind(playtime)
-                .execute(&self.pool).await.unwrap();
-        } else {
-            query("UPDATE game_entries SET playtime=playtime+$1 WHERE user_id=$2 AND game_id=$3;")
-                .bind(playtime)
-                .bind(user_id)
-                .bind(game_id)
-                .execute(&self.pool).await.unwrap();
+
+    // An unavailable guild is an outage, otherwise the bot was removed and nobody is a member anymore
+    async fn guild_delete(&self, _ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        if incomplete.unavailable {
+            return;
+        }
+        if let Err(why) = handlers::guild_member_removal(&self.db, incomplete.id, None).await {
+            error!("Cannot forget the members of {}: {:?}", incomplete.id, why);
         }
     }
-    
-    async fn add_game(&self, game_name: &String) {
-        query("INSERT INTO games (name) VALUES ($1);")
-            .bind(game_name)
-            .execute(&self.pool).await.unwrap();
+
+    // Presences are only known once the guilds are cached, the sessions opened before a restart are reconciled with them
+    async fn cache_ready(&self, ctx: Context, guilds: Vec<GuildId>) {
+        let presences: Vec<Presence> = guilds.iter()
+            .filter_map(|guild_id| ctx.cache.guild_field(guild_id, |guild| guild.presences.values().cloned().collect::<Vec<Presence>>()))
+            .flatten()
+            .collect();
+        if let Err(why) = handlers::reconcile_sessions(&ctx, &self.db, &self.config, &presences).await {
+            error!("Cannot reconcile the sessions: {:?}", why);
+        }
+        let voice_states: Vec<VoiceState> = guilds.iter()
+            .filter_map(|guild_id| ctx.cache.guild_field(guild_id, |guild| guild.voice_states.values().cloned().collect::<Vec<VoiceState>>()))
+            .flatten()
+            .collect();
+        if let Err(why) = handlers::reconcile_voice_sessions(&self.db, &voice_states).await {
+            error!("Cannot reconcile the voice sessions: {:?}", why);
+        }
+        if !self.scheduler_started.swap(true, Ordering::SeqCst) {
+            scheduler::start(ctx, self.db.clone(), self.config.clone(), self.igdb.clone());
+        }
     }
-    
-    async fn build_db(&self) {
-        query(
-            "CREATE TABLE IF NOT EXISTS games (
-                game_id BIGSERIAL PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE
-            );").execute(&self.pool).await.unwrap();
-        query(
-            "CREATE TABLE IF NOT EXISTS game_entries (
-                user_id BIGINT NOT NULL,
-                game_id BIGINT NOT NULL,
-                playtime BIGINT NOT NULL,
-                PRIMARY KEY (user_id, game_id),
-                FOREIGN KEY (game_id) REFERENCES games(game_id)
-            );").execute(&self.pool).await.unwrap();
-        query(   
-            "CREATE TABLE IF NOT EXISTS game_sessions (
-                user_id BIGINT NOT NULL,
-                game_id BIGINT NOT NULL,
-                starttime BIGINT NOT NULL,
-                PRIMARY KEY (user_id, game_id),
-                FOREIGN KEY (game_id) REFERENCES games(game_id)
-            );").execute(&self.pool).await.unwrap();
-        query( 
-            "DELETE FROM game_sessions;"
-        ).execute(&self.pool).await.unwrap();
-        query(
-            "CREATE OR REPLACE FUNCTION remove_session()
-                RETURNS TRIGGER 
-                AS
-                $$
-                BEGIN
-                    DELETE FROM game_sessions WHERE user_id = NEW.user_id AND game_id = NEW.game_id;
-                    RETURN NEW;
-                END;
-            $$ LANGUAGE plpgsql;"
-        ).execute(&self.pool).await.unwrap();
-        query(
-            "CREATE OR REPLACE TRIGGER trigger_clear_sessions
-                AFTER INSERT ON game_entries
-                FOR EACH ROW
-                EXECUTE PROCEDURE remove_session();"
-        ).execute(&self.pool).await.unwrap();
+
+    // `interaction_create` runs when the user interacts with the bot
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        // Commands report their own errors
+        let (result, context) = match interaction {
+            Interaction::ApplicationCommand(command) => {
+                let span = info_span!("command", name = %command.data.name, user_id = %command.user.id, guild_id = ?command.guild_id.map(|guild_id| guild_id.0));
+                commands::run(&self.db, &ctx, &command).instrument(span).await;
+                (Ok(()), ErrorContext::default())
+            },
+            Interaction::Autocomplete(autocomplete) => (commands::autocomplete(&self.db, &ctx, &autocomplete).await, ErrorContext {
+                user_id: Some(autocomplete.user.id.0),
+                guild_id: autocomplete.guild_id.map(|guild_id| guild_id.0),
+                command: Some(autocomplete.data.name.clone()),
+            }),
+            Interaction::MessageComponent(component) => (commands::component(&self.db, &ctx, &component).await, ErrorContext {
+                user_id: Some(component.user.id.0),
+                guild_id: component.guild_id.map(|guild_id| guild_id.0),
+                ..ErrorContext::default()
+            }),
+            _ => (Ok(()), ErrorContext::default()),
+        };
+        if let Err(why) = result {
+            error!("Cannot handle interaction: {:?}", why);
+            errors::report(&format!("Cannot handle interaction: {:?}", why), context);
+        }
     }
 
-    async fn resetall(&self) {
-        query("DELETE FROM game_entries;").execute(&self.pool).await.unwrap();
-        query("DELETE FROM game_sessions;").execute(&self.pool).await.unwrap();
-        query("DELETE FROM games;").execute(&self.pool).await.unwrap();
+    // The queries and sessions of an update are logged in its span
+    async fn presence_update(&self, ctx: Context, new_data: Presence) {
+        let span = info_span!("presence_update", user_id = %new_data.user.id, guild_id = ?new_data.guild_id.map(|guild_id| guild_id.0));
+        let started = Instant::now();
+        let result = handlers::presence_update(&ctx, &self.db, &self.config, &new_data).instrument(span.clone()).await;
+        let _entered = span.enter();
+        match result {
+            Ok(()) => debug!(elapsed = ?started.elapsed(), "Handled the presence update"),
+            Err(why) => {
+                error!(elapsed = ?started.elapsed(), "Cannot handle {}'s presence update: {:?}", new_data.user.id, why);
+                errors::report(&format!("Cannot handle a presence update: {:?}", why), ErrorContext {
+                    user_id: Some(new_data.user.id.0),
+                    guild_id: new_data.guild_id.map(|guild_id| guild_id.0),
+                    ..ErrorContext::default()
+                });
+            },
+        }
     }
 
-    async fn reset(&self, user_id: &i64) {
-        query("DELETE FROM game_entries WHERE user_id=$1;")
-            .bind(user_id)
-            .execute(&self.pool).await.unwrap();
-        query("DELETE FROM game_sessions WHERE user_id=$1;")
-            .bind(user_id)
-            .execute(&self.pool).await.unwrap();
+    async fn voice_state_update(&self, _ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        if let Err(why) = handlers::voice_state_update(&self.db, old.as_ref(), &new).await {
+            error!("Cannot handle {}'s voice state update: {:?}", new.user_id, why);
+            errors::report(&format!("Cannot handle a voice state update: {:?}", why), ErrorContext {
+                user_id: Some(new.user_id.0),
+                guild_id: new.guild_id.map(|guild_id| guild_id.0),
+                ..ErrorContext::default()
+            });
+        }
     }
+}
 
-    async fn hardreset(&self) {
-        self.resetall().await;
-        query("DROP TABLE game_entries;").execute(&self.pool).await.unwrap();
-        query("DROP TABLE game_sessions;").execute(&self.pool).await.unwrap();
-        query("DROP TABLE games;").execute(&self.pool).await.unwrap();
-        self.build_db().await;
+
+// Without `enabled`, the commands of the guild are removed
+async fn register_commands(ctx: &Context, guild_id: GuildId, enabled: bool) {
+    let result = guild_id.set_application_commands(&ctx.http, |commands| if enabled { commands::register(commands) } else { commands }).await;
+    if let Err(why) = result {
+        error!("Cannot register slash commands in {}: {:?}", guild_id, why);
     }
 }
 
-#[async_trait]
-impl EventHandler for Bot {
+// The settings of the deployment, from `Secrets.toml` on Shuttle or from the environment
+trait Secrets {
+    fn get(&self, key: &str) -> Option<String>;
+}
 
-    async fn ready(&self, ctx: Context, ready: Ready) {
-        info!("{} is connected!", ready.user.name);
-        let guild_id = GuildId(1063039820575801385);
-        self.build_db().await;
-
-        GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
-            commands
-                .create_application_command(|command| { command.name("summarize").description("Shows the 10 most played games of a user") 
-                    .create_option(|option| {option.name("user").description("The target").kind(CommandOptionType::User).required(true)}) })
-                .create_application_command(|command| { command.name("reset").description("Resets the player's playtimes") 
-                    .create_option(|option| {option.name("user").description("The target").kind(CommandOptionType::User).required(true)}) })
-                .create_application_command(|command| { command.name("resetall").description("Resets all playtimes and games")})
-                .create_application_command(|command| { command.name("hardreset").description("Destroys the database")})  
-        }).await.unwrap();
+#[cfg(feature = "shuttle")]
+impl Secrets for SecretStore {
+    fn get(&self, key: &str) -> Option<String> {
+        return SecretStore::get(self, key);
     }
+}
 
-       // `interaction_create` runs when the user interacts with the bot
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        // check if the interaction is a command
-        if let Interaction::ApplicationCommand(command) = interaction {
-
-             match command.data.name.as_str() {
-                "summarize" => async { 
-                    let user_id = command.data.options[0].value.as_ref().unwrap().as_str().unwrap().parse::<u64>().unwrap(); 
-                    let user = UserId(user_id).to_user(&ctx.http).await.unwrap();
-                    let embed = self.get_summary(&user).await;
-                    command.create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|message| message.set_embed(embed))
-                    })
-                        .await.expect("Cannot respond to slash command");
-                }.await,
-                "reset" => async {
-                    let mut message_str = "You don't have the permission to use this command.".to_string();
-                    if command.user.id.to_string() == "618355400038940682" {
-                        let user_id = command.data.options[0].value.as_ref().unwrap().as_str().unwrap().parse::<u64>().unwrap(); 
-                        let user = UserId(user_id).to_user(&ctx.http).await.unwrap();
-                        self.reset(&i64::try_from(*user.id.as_u64()).unwrap()).await;
-                        message_str = format!("Successfully reseted {}'s playtimes.", user.mention());
-                    }
-                    
-                    command.create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|message| message.ephemeral(true).content(message_str))
-                    })
-                        .await.expect("Cannot respond to slash command");
-                }.await,
-                "resetall" => async {
-                    let mut message_str = "You don't have the permission to use this command.".to_string();
-                    if command.user.id.to_string() == "618355400038940682" {
-                        self.resetall().await;
-                        message_str = "Successfully reseted all playtimes and games.".to_string();
-                    }
-                    command.create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|message| message.ephemeral(true).content(message_str))
-                    })
-                        .await.expect("Cannot respond to slash command");
-                }.await,
-                "hardreset" => async {
-                    let mut message_str = "You don't have the permission to use this command.".to_string();
-                    if command.user.id.to_string() == "618355400038940682" {
-                        self.hardreset().await;
-                        message_str = "Successfully reconstructed the database".to_string();
-                    }
-                    command.create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|message| message.ephemeral(true).content(message_str))
-                    })
-                        .await.expect("Cannot respond to slash command");
-                }.await,
-                command => unreachable!("Command don't have a handler: {}", command),
-            };
-        }
+#[cfg(feature = "standalone")]
+struct EnvSecrets;
+
+#[cfg(feature = "standalone")]
+impl Secrets for EnvSecrets {
+    fn get(&self, key: &str) -> Option<String> {
+        return std::env::var(key).ok();
     }
+}
 
-    async fn presence_update(&self, _ctx: Context, new_data: Presence) {
-        let user_id = i64::try_from(*new_data.user.id.as_u64()).unwrap();
-        if new_data.activities.len() == 0 {
-            self.save_session(&user_id).await;
-            return;
-        }
-        let user_activity: &Activity = &new_data.activities[0];
-        let game_name: &String = &user_activity.name;
-        if user_activity.kind == ActivityType::Playing {
-            let starttime = i64::try_from(std::time::Duration::from_millis(user_activity.timestamps.as_ref().unwrap().start.unwrap()).as_secs()).unwrap();
-            self.register_session(&user_id, game_name, &starttime).await;
-        }
+// The minimum and maximum session lengths in seconds
+fn session_lengths(secret_store: &dyn Secrets) -> anyhow::Result<(i64, i64)> {
+    // Sessions shorter than this are only alt-tabbing into a launcher
+    let min_session_length = number_secret(secret_store, "MIN_SESSION_LENGTH", 60)?;
+    let max_session_length = number_secret(secret_store, "MAX_SESSION_LENGTH", 24 * 60 * 60)?;
+    return Ok((min_session_length, max_session_length));
+}
+
+// Reads an optional numeric secret, `default` is used when it isn't set
+fn number_secret(secret_store: &dyn Secrets, key: &str, default: i64) -> anyhow::Result<i64> {
+    return match secret_store.get(key) {
+        Some(value) => value.parse::<i64>().map_err(|why| anyhow!("'{}' is not a number: {}", key, why)),
+        None => Ok(default),
+    };
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Err listening for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
     }
+}
 
-    
+#[cfg(not(unix))]
+async fn wait_for_shutdown() {
+    tokio::signal::ctrl_c().await.expect("Err listening for Ctrl-C");
 }
 
+#[cfg(not(any(feature = "shuttle", feature = "standalone")))]
+compile_error!("Either the `shuttle` or the `standalone` feature is needed");
 
+#[cfg(all(feature = "shuttle", not(feature = "standalone")))]
 #[shuttle_runtime::main]
 async fn serenity(
     #[shuttle_secrets::Secrets] secret_store: SecretStore, #[shuttle_shared_db::Postgres] pool: PgPool,
 ) -> shuttle_serenity::ShuttleSerenity {
-    // Get the discord token set in `Secrets.toml`
+    let (min_session_length, max_session_length) = session_lengths(&secret_store)?;
+    let db: Database = Arc::new(PgStorage::new(pool, min_session_length, max_session_length));
+    Ok(build_client(&secret_store, db).await?.into())
+}
+
+// Runs without Shuttle, the settings and the database URL are read from the environment
+#[cfg(feature = "standalone")]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+    let url = EnvSecrets.get("DATABASE_URL").ok_or_else(|| anyhow!("'DATABASE_URL' was not found"))?;
+    let (min_session_length, max_session_length) = session_lengths(&EnvSecrets)?;
+    let db = connect_database(&url, min_session_length, max_session_length).await?;
+    let mut client = build_client(&EnvSecrets, db).await?;
+    client.start_autosharded().await?;
+    Ok(())
+}
+
+// A `sqlite:` URL opens a SQLite file, a `mysql:` one a MySQL or MariaDB server, any other one a Postgres server
+#[cfg(feature = "standalone")]
+async fn connect_database(url: &str, min_session_length: i64, max_session_length: i64) -> anyhow::Result<Database> {
+    if url.starts_with("sqlite:") {
+        #[cfg(feature = "sqlite")]
+        return Ok(Arc::new(SqliteStorage::connect(url, min_session_length, max_session_length).await?));
+        #[cfg(not(feature = "sqlite"))]
+        return Err(anyhow!("'DATABASE_URL' is a SQLite database, the bot needs to be built with the `sqlite` feature"));
+    }
+    if url.starts_with("mysql:") {
+        #[cfg(feature = "mysql")]
+        return Ok(Arc::new(MySqlStorage::connect(url, min_session_length, max_session_length).await?));
+        #[cfg(not(feature = "mysql"))]
+        return Err(anyhow!("'DATABASE_URL' is a MySQL database, the bot needs to be built with the `mysql` feature"));
+    }
+    let pool = PgPool::connect(url).await?;
+    return Ok(Arc::new(PgStorage::new(pool, min_session_length, max_session_length)));
+}
+
+async fn build_client(secret_store: &dyn Secrets, db: Database) -> anyhow::Result<Client> {
+    let started_at = Instant::now();
+    // Get the discord token set in `Secrets.toml` or the environment
     let token = if let Some(token) = secret_store.get("DISCORD_TOKEN") {
         token
     } else {
-        return Err(anyhow!("'DISCORD_TOKEN' was not found").into());
+        return Err(anyhow!("'DISCORD_TOKEN' was not found"));
     };
+    chart::register_fonts()?;
+    // Errors and panics are only logged without it
+    match secret_store.get("SENTRY_DSN") {
+        Some(dsn) => errors::init(&dsn)?,
+        None => info!("'SENTRY_DSN' isn't set, errors won't be reported"),
+    }
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILD_PRESENCES;
+    // The members are needed to scope the rankings to a guild
+    let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILD_PRESENCES | GatewayIntents::GUILD_VOICE_STATES
+        | GatewayIntents::GUILD_MEMBERS;
+    // Game metadata comes from IGDB, which authenticates with a Twitch application
+    let igdb = match (secret_store.get("IGDB_CLIENT_ID"), secret_store.get("IGDB_CLIENT_SECRET")) {
+        (Some(client_id), Some(client_secret)) => Some(Arc::new(Igdb::new(client_id, client_secret))),
+        _ => {
+            info!("IGDB credentials aren't set, games won't get metadata");
+            None
+        },
+    };
+    // Commands reach the config service through the context's data
+    let config = Arc::new(ConfigService::new(db.clone()));
+    // Public deployments register the commands globally, they then work in every guild the bot joins
+    let global_commands = secret_store.get("GLOBAL_COMMANDS").map_or(false, |value| value == "true");
     let client = Client::builder(&token, intents)
-        .event_handler(Bot{pool})
+        .event_handler(Bot{db: db.clone(), config: config.clone(), igdb, scheduler_started: AtomicBool::new(false), global_commands})
+        .type_map_insert::<ConfigService>(config)
+        .type_map_insert::<StartedAt>(started_at)
+        .type_map_insert::<WebhookQueue>(Arc::new(WebhookQueue::start()))
+        .type_map_insert::<RateLimiter>(Arc::new(RateLimiter::new()))
         .await
         .expect("Err creating client");
+    // Steam and Xbox imports are only offered when their API key is set
+    match secret_store.get("STEAM_API_KEY") {
+        Some(api_key) => { client.data.write().await.insert::<Steam>(Arc::new(Steam::new(api_key))); },
+        None => info!("'STEAM_API_KEY' isn't set, Steam playtime can't be imported"),
+    }
+    match secret_store.get("OPENXBL_API_KEY") {
+        Some(api_key) => { client.data.write().await.insert::<Xbox>(Arc::new(Xbox::new(api_key))); },
+        None => info!("'OPENXBL_API_KEY' isn't set, Xbox playtime can't be imported"),
+    }
+    match secret_store.get("REDIS_URL") {
+        Some(url) => { client.data.write().await.insert::<Cache>(Arc::new(Cache::connect(&url).await?)); },
+        None => info!("'REDIS_URL' isn't set, the leaderboards won't be cached"),
+    }
+    // The playtimes are kept either way, only the history of the sessions is pruned
+    match secret_store.get("HISTORY_RETENTION_DAYS") {
+        Some(_) => {
+            let days = number_secret(secret_store, "HISTORY_RETENTION_DAYS", 0)?;
+            if days < 1 {
+                return Err(anyhow!("'HISTORY_RETENTION_DAYS' must be at least 1"));
+            }
+            client.data.write().await.insert::<HistoryRetention>(days);
+        },
+        None => info!("'HISTORY_RETENTION_DAYS' isn't set, the session history will be kept forever"),
+    }
+    // Without a storage, backups are attached to the response
+    if let Some(url) = secret_store.get("BACKUP_UPLOAD_URL") {
+        client.data.write().await.insert::<BackupStorage>(Arc::new(BackupStorage::new(url, secret_store.get("BACKUP_UPLOAD_TOKEN"))));
+    }
+
+    // The health check is always served, the stats API only when an API key is set
+    let port = u16::try_from(number_secret(secret_store, "API_PORT", 8080)?).map_err(|why| anyhow!("'API_PORT' is not a port: {}", why))?;
+    let api_db = db.clone();
+    let api_key = secret_store.get("API_KEY");
+    tokio::spawn(async move {
+        if let Err(why) = api::serve(api_db, api_key, port, started_at).await {
+            error!("The HTTP server stopped: {:?}", why);
+        }
+    });
+    // /status reads the shard latencies from it
+    client.data.write().await.insert::<ShardManagerContainer>(client.shard_manager.clone());
+
+    // Save the running sessions before exiting, otherwise every redeploy loses the playtime of everyone online
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        wait_for_shutdown().await;
+        info!("Shutting down, saving the open sessions");
+        if let Err(why) = db.save_all_sessions().await {
+            error!("Cannot save the open sessions: {:?}", why);
+        }
+        if let Err(why) = db.end_all_voice_sessions(&chrono::Utc::now().timestamp()).await {
+            error!("Cannot end the voice sessions: {:?}", why);
+        }
+        shard_manager.lock().await.shutdown_all().await;
+    });
 
-    Ok(client.into())
+    return Ok(client);
 }
@@ -0,0 +1,53 @@
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+
+// Commands a user can run back to back
+const BURST: f64 = 3.0;
+// Seconds to get one more command back
+const REFILL_INTERVAL: f64 = 10.0;
+// Full buckets are dropped past this many users and commands
+const MAX_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// Token buckets keyed by user and command, so spamming a command can't hammer the database
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(u64, String), Bucket>>,
+}
+
+impl TypeMapKey for RateLimiter {
+    type Value = Arc<RateLimiter>;
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        return RateLimiter { buckets: Mutex::new(HashMap::new()) };
+    }
+
+    // Takes a token from the user's bucket of the command, returns how long to wait when it's empty
+    pub fn acquire(&self, user_id: u64, command: &str) -> Option<Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_BUCKETS {
+            buckets.retain(|_, bucket| refilled(bucket, now) < BURST);
+        }
+        let bucket = buckets.entry((user_id, command.to_string())).or_insert(Bucket { tokens: BURST, updated_at: now });
+        bucket.tokens = refilled(bucket, now);
+        bucket.updated_at = now;
+        if bucket.tokens < 1.0 {
+            return Some(Duration::from_secs_f64((1.0 - bucket.tokens) * REFILL_INTERVAL));
+        }
+        bucket.tokens -= 1.0;
+        return None;
+    }
+}
+
+fn refilled(bucket: &Bucket, now: Instant) -> f64 {
+    return (bucket.tokens + now.duration_since(bucket.updated_at).as_secs_f64() / REFILL_INTERVAL).min(BURST);
+}
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use chrono::{Datelike, Months, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::prelude::Context;
+use tracing::error;
+
+use crate::chart::{DAY, WEEK};
+use crate::commands::{anonymous_users, apply_appearance, can_view, format_playtime, format_ranking, guild_config, local_midnight, locale, period_start, user_timezone};
+use crate::config::ConfigService;
+use crate::db::{Database, GuildConfig};
+use crate::i18n;
+
+
+// Seconds between two mentions of a game's role
+const GAME_ROLE_COOLDOWN: i64 = 30 * 60;
+
+// The report cadences a guild can choose from
+pub const REPORT_CADENCES: [&str; 3] = ["daily", "weekly", "monthly"];
+
+// Posts the reports of the days, weeks and months ending at `end`, a midnight UTC
+pub async fn post_reports(ctx: &Context, db: &Database, config: &ConfigService, end: &i64) -> Result<()> {
+    let day = Utc.timestamp_opt(*end, 0).unwrap().date_naive();
+    for cadence in REPORT_CADENCES {
+        let start = match cadence {
+            "daily" => end - DAY,
+            "weekly" if day.weekday() == Weekday::Mon => end - WEEK,
+            "monthly" if day.day() == 1 => local_midnight(day - Months::new(1), Tz::UTC),
+            _ => continue,
+        };
+        post_cadence_reports(ctx, db, config, cadence, &start, end).await?;
+    }
+    Ok(())
+}
+
+// Posts the top players and games between `start` and `end` to the report channel of every guild with that cadence
+async fn post_cadence_reports(ctx: &Context, db: &Database, config: &ConfigService, cadence: &str, start: &i64, end: &i64) -> Result<()> {
+    let channels = db.get_report_channels(cadence).await?;
+    if channels.is_empty() {
+        return Ok(());
+    }
+    let title_key = match cadence {
+        "daily" => "report-daily",
+        "monthly" => "report-monthly",
+        _ => "report-weekly",
+    };
+    for (guild_id, channel_id) in channels {
+        let guild_config = config.get(&guild_id).await?;
+        // Posted to the whole guild, in its language
+        let locale = &guild_config.locale;
+        // Each guild's report only ranks its members
+        let ranking = db.get_period_leaderboard(&guild_id, start, end, guild_config.rank_manual_sessions).await?;
+        let games: Vec<String> = db.get_period_top_games(&guild_id, start, end).await?.iter()
+            .enumerate()
+            .map(|(rank, game)| format!("**#{}** {} — {}", rank + 1, game.name, format_playtime(game.playtime)))
+            .collect();
+        let anonymous = anonymous_users(db, ctx, None, Some(GuildId(u64::try_from(guild_id)?)), &ranking).await?;
+        let mut embed = CreateEmbed::default();
+        embed.title(i18n::t(locale, title_key))
+            .description(i18n::tr(locale, "report-period", &[("start", start.to_string()), ("end", end.to_string())]))
+            .field(i18n::t(locale, "report-top-players"), format_ranking(locale, &ranking, &anonymous), false)
+            .field(i18n::t(locale, "report-top-games"), if games.is_empty() { i18n::t(locale, "report-no-games") } else { games.join("\n") }, false);
+        apply_appearance(&mut embed, &guild_config)?;
+        // A deleted channel or missing permission in one guild shouldn't stop the other reports
+        if let Err(why) = ChannelId(u64::try_from(channel_id)?).send_message(&ctx.http, |message| message.set_embed(embed)).await {
+            error!("Cannot post the {} report of {}: {:?}", cadence, guild_id, why);
+        }
+    }
+    Ok(())
+}
+
+// DMs the subscribers whose week started in their timezone after `since` their playtime of the past week compared to the week before
+pub async fn send_digests(ctx: &Context, db: &Database, since: &i64, currenttime: &i64) -> Result<()> {
+    for (user_id, timezone) in db.get_digest_subscribers().await? {
+        let end = period_start("week", user_timezone(timezone.as_deref())).unwrap();
+        if end <= *since || end > *currenttime {
+            continue;
+        }
+        // Nothing is sent during a vacation
+        if db.get_vacation(&user_id).await?.map_or(false, |(vacation_start, vacation_end)| vacation_start <= end && end < vacation_end) {
+            continue;
+        }
+        let start = &(end - WEEK);
+        let end = &end;
+        let locale = locale(db, ctx, &UserId(u64::try_from(user_id)?), None).await?;
+        let playtime = db.get_user_period_playtime(&user_id, start, end).await?;
+        let previous_playtime = db.get_user_period_playtime(&user_id, &(start - WEEK), start).await?;
+        let change = if playtime >= previous_playtime {
+            format!("+{}", format_playtime(playtime - previous_playtime))
+        } else {
+            format!("-{}", format_playtime(previous_playtime - playtime))
+        };
+        let top_game = db.get_user_period_top_game(&user_id, start, end).await?
+            .map_or(i18n::t(&locale, "digest-nothing"), |game| format!("{} ({})", game.name, format_playtime(game.playtime)));
+        let mut embed = CreateEmbed::default();
+        embed.title(i18n::t(&locale, "digest-title"))
+            .description(i18n::tr(&locale, "report-period", &[("start", start.to_string()), ("end", end.to_string())]))
+            .field(i18n::t(&locale, "digest-playtime"), format_playtime(playtime), true)
+            .field(i18n::t(&locale, "digest-change"), change, true)
+            .field(i18n::t(&locale, "digest-top-game"), top_game, true)
+            .footer(|footer| footer.text(i18n::t(&locale, "digest-footer")));
+        // DMs aren't tied to a guild
+        apply_appearance(&mut embed, &GuildConfig::default())?;
+        // Users with closed DMs are skipped
+        let sent = match UserId(u64::try_from(user_id)?).create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel.send_message(&ctx.http, |message| message.set_embed(embed)).await.map(|_| ()),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = sent {
+            error!("Cannot send {}'s digest: {:?}", user_id, why);
+        }
+    }
+    Ok(())
+}
+
+// DMs the users watching the user play the game that just started, unless the user since hid their stats from them
+pub async fn notify_watchers(ctx: &Context, db: &Database, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<()> {
+    let watchers = db.get_watchers(user_id, game_name).await?;
+    if watchers.is_empty() {
+        return Ok(());
+    }
+    let user = UserId(u64::try_from(*user_id)?).to_user(&ctx.http).await?;
+    let guild = Some(GuildId(u64::try_from(*guild_id)?));
+    for watcher_id in watchers {
+        let watcher = UserId(u64::try_from(watcher_id)?);
+        if !can_view(db, ctx, Some(watcher), guild, user.id).await? {
+            continue;
+        }
+        let content = i18n::tr(&locale(db, ctx, &watcher, guild).await?, "watch-started", &[("user", user.name.clone()), ("game", game_name.to_string())]);
+        let sent = match UserId(u64::try_from(watcher_id)?).create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel.send_message(&ctx.http, |message| message.content(&content)).await.map(|_| ()),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = sent {
+            error!("Cannot notify {} that {} started playing: {:?}", watcher_id, user_id, why);
+        }
+    }
+    Ok(())
+}
+
+// Announces the session start in the channel of the game's role, the role is only mentioned once per cooldown
+pub async fn announce_game_role(ctx: &Context, db: &Database, guild_id: &i64, user_id: &i64, game_name: &str) -> Result<()> {
+    let (role_id, channel_id, last_ping) = match db.get_game_role(guild_id, game_name).await? {
+        Some(game_role) => game_role,
+        None => return Ok(()),
+    };
+    // The announcement is seen by the whole guild
+    if !can_view(db, ctx, None, Some(GuildId(u64::try_from(*guild_id)?)), UserId(u64::try_from(*user_id)?)).await? {
+        return Ok(());
+    }
+    let currenttime = Utc::now().timestamp();
+    let ping = last_ping.map_or(true, |last_ping| currenttime - last_ping >= GAME_ROLE_COOLDOWN);
+    let guild_locale = guild_config(ctx, Some(GuildId(u64::try_from(*guild_id)?))).await?.locale;
+    let mut content = i18n::tr(&guild_locale, "game-role-started", &[("user", format!("<@{}>", user_id)), ("game", game_name.to_string())]);
+    let mut roles: Vec<RoleId> = Vec::new();
+    if ping {
+        content.push_str(&format!(" <@&{}>", role_id));
+        roles.push(RoleId(u64::try_from(role_id)?));
+        db.set_game_role_pinged(guild_id, game_name, &currenttime).await?;
+    }
+    // The player isn't pinged, only the role when its cooldown is over
+    let sent = ChannelId(u64::try_from(channel_id)?).send_message(&ctx.http, |message| message.content(content)
+        .allowed_mentions(|mentions| mentions.empty_parse().roles(roles))).await;
+    if let Err(why) = sent {
+        error!("Cannot announce {}'s session of {}: {:?}", user_id, game_name, why);
+    }
+    Ok(())
+}
+
+// DMs the subscribers of the games once enough members of the guild play them at the same time,
+// they are told again after the players dropped below their threshold
+pub async fn notify_lfg(ctx: &Context, db: &Database, guild_id: &i64, game_names: &[&str]) -> Result<()> {
+    let guild = GuildId(u64::try_from(*guild_id)?);
+    // Sessions aren't stored per server, the online members are the ones with a cached presence
+    let members: Vec<i64> = ctx.cache.guild_field(guild, |guild| guild.presences.keys()
+            .filter_map(|user_id| i64::try_from(*user_id.as_u64()).ok())
+            .collect())
+        .unwrap_or_default();
+    let open_sessions = db.get_open_sessions_by_game().await?;
+    for game_name in game_names {
+        let subscribers = db.get_lfg_subscribers(guild_id, game_name).await?;
+        if subscribers.is_empty() {
+            continue;
+        }
+        let players: Vec<i64> = open_sessions.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(game_name))
+            .map_or(Vec::new(), |(_, user_ids)| user_ids.iter().filter(|user_id| members.contains(user_id)).copied().collect());
+        for (user_id, min_players, notified) in subscribers {
+            let enough = i64::try_from(players.len())? >= min_players;
+            if enough == notified {
+                continue;
+            }
+            db.set_lfg_notified(guild_id, &user_id, game_name, enough).await?;
+            // Subscribers already in the game don't need to be told
+            if !enough || players.contains(&user_id) {
+                continue;
+            }
+            let locale = locale(db, ctx, &UserId(u64::try_from(user_id)?), Some(guild)).await?;
+            let guild_name = ctx.cache.guild_field(guild, |guild| guild.name.clone()).unwrap_or_else(|| i18n::t(&locale, "lfg-the-server"));
+            let args = [("players", players.len().to_string()), ("guild", guild_name), ("game", game_name.to_string())];
+            let content = i18n::tr(&locale, "lfg-playing", &args);
+            let sent = match UserId(u64::try_from(user_id)?).create_dm_channel(&ctx.http).await {
+                Ok(channel) => channel.send_message(&ctx.http, |message| message.content(content)).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                error!("Cannot notify {} of the players of {}: {:?}", user_id, game_name, why);
+            }
+        }
+    }
+    Ok(())
+}
+
+// DMs the users whose weekly goal was reached or whose limit was exceeded, once per week of their timezone
+pub async fn notify_goals(ctx: &Context, db: &Database, currenttime: &i64) -> Result<()> {
+    for (user_id, timezone) in db.get_goal_users().await? {
+        let week_start = period_start("week", user_timezone(timezone.as_deref())).unwrap();
+        notify_user_goals(ctx, db, &user_id, &week_start, currenttime).await?;
+    }
+    Ok(())
+}
+
+async fn notify_user_goals(ctx: &Context, db: &Database, user_id: &i64, week_start: &i64, currenttime: &i64) -> Result<()> {
+    let reached = db.get_reached_goals(user_id, week_start, currenttime).await?;
+    if reached.is_empty() {
+        return Ok(());
+    }
+    let locale = locale(db, ctx, &UserId(u64::try_from(*user_id)?), None).await?;
+    for (game_id, game_name, seconds, is_limit) in reached {
+        let key = if is_limit { "goal-limit-exceeded" } else { "goal-reached" };
+        let content = i18n::tr(&locale, key, &[("playtime", format_playtime(seconds)), ("game", game_name)]);
+        let sent = match UserId(u64::try_from(*user_id)?).create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel.send_message(&ctx.http, |message| message.content(content)).await.map(|_| ()),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = sent {
+            error!("Cannot notify {} of their goal: {:?}", user_id, why);
+        }
+        // Marked even when the DM failed, otherwise it would be retried every check
+        db.set_goal_notified(user_id, &game_id, week_start).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use serde_json::json;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::Context;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::webhooks;
+
+
+// Gives the member the reward roles whose threshold their total playtime reached
+pub async fn grant_role_rewards(ctx: &Context, db: &Database, guild_id: &i64, user_id: &i64) -> Result<()> {
+    let playtime = db.get_total_playtime(user_id).await?;
+    let rewards: Vec<i64> = db.get_role_rewards(guild_id).await?.iter()
+        .filter(|(_, seconds)| *seconds <= playtime)
+        .map(|(role_id, _)| *role_id)
+        .collect();
+    if rewards.is_empty() {
+        return Ok(());
+    }
+    let member = GuildId(u64::try_from(*guild_id)?).member(ctx, UserId(u64::try_from(*user_id)?)).await?;
+    for role_id in rewards {
+        let role = RoleId(u64::try_from(role_id)?);
+        if member.roles.contains(&role) {
+            continue;
+        }
+        info!("Giving the role {} to {}", role_id, user_id);
+        // A role the bot can't give doesn't keep the others from being given
+        if let Err(why) = ctx.http.add_member_role(*member.guild_id.as_u64(), *member.user.id.as_u64(), *role.as_u64(), Some("Playtime milestone reached")).await {
+            error!("Cannot give the role {} to {}: {:?}", role_id, user_id, why);
+            continue;
+        }
+        webhooks::dispatch(ctx, db, guild_id, user_id, "milestone", json!({"role": role_id.to_string()})).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use serenity::prelude::{Context, TypeMapKey};
+use std::sync::Arc;
+use tokio::time::{interval, sleep, Duration};
+use tracing::{error, info};
+
+use crate::chart::DAY;
+use crate::commands::period_start;
+use crate::config::ConfigService;
+use crate::db::Database;
+use crate::errors::{self, ErrorContext};
+use crate::igdb::{self, Igdb};
+use crate::reports;
+use crate::steam::{self, Steam};
+use crate::xbox::{self, Xbox};
+
+
+// Seconds between two checks of the playtime goals
+const GOAL_CHECK_INTERVAL: u64 = 15 * 60;
+// Seconds between two checks of the weeks starting in the subscribers' timezones
+const DIGEST_CHECK_INTERVAL: u64 = 15 * 60;
+// Seconds between two lookups of the new games on IGDB
+const METADATA_REFRESH_INTERVAL: u64 = 60 * 60;
+// Seconds between two syncs of the linked Steam and Xbox accounts
+const IMPORT_SYNC_INTERVAL: u64 = 24 * 60 * 60;
+// Seconds between two prunings of the session history
+const PRUNE_INTERVAL: u64 = 24 * 60 * 60;
+
+// Days the session history is kept for, it isn't pruned without it
+pub struct HistoryRetention;
+
+impl TypeMapKey for HistoryRetention {
+    type Value = i64;
+}
+
+// Runs the periodic jobs in the background, the reports are posted at midnight UTC
+// and the digests and goals on Monday at midnight in each user's timezone
+pub fn start(ctx: Context, db: Database, config: Arc<ConfigService>, igdb: Option<Arc<Igdb>>) {
+    if let Some(igdb) = igdb {
+        let metadata_db = db.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(Duration::from_secs(METADATA_REFRESH_INTERVAL));
+            loop {
+                interval.tick().await;
+                if let Err(why) = igdb::refresh_metadata(&metadata_db, &igdb).await {
+                    error!("Cannot refresh the game metadata: {:?}", why);
+                    errors::report(&format!("Cannot refresh the game metadata: {:?}", why), ErrorContext::default());
+                }
+            }
+        });
+    }
+    let steam_ctx = ctx.clone();
+    let steam_db = db.clone();
+    tokio::spawn(async move {
+        let steam = match steam_ctx.data.read().await.get::<Steam>().cloned() {
+            Some(steam) => steam,
+            None => return,
+        };
+        let mut interval = interval(Duration::from_secs(IMPORT_SYNC_INTERVAL));
+        loop {
+            interval.tick().await;
+            if let Err(why) = steam::refresh_imports(&steam_db, &steam).await {
+                error!("Cannot sync the Steam accounts: {:?}", why);
+                errors::report(&format!("Cannot sync the Steam accounts: {:?}", why), ErrorContext::default());
+            }
+        }
+    });
+    let xbox_ctx = ctx.clone();
+    let xbox_db = db.clone();
+    tokio::spawn(async move {
+        let xbox = match xbox_ctx.data.read().await.get::<Xbox>().cloned() {
+            Some(xbox) => xbox,
+            None => return,
+        };
+        let mut interval = interval(Duration::from_secs(IMPORT_SYNC_INTERVAL));
+        loop {
+            interval.tick().await;
+            if let Err(why) = xbox::refresh_imports(&xbox_db, &xbox).await {
+                error!("Cannot sync the Xbox accounts: {:?}", why);
+                errors::report(&format!("Cannot sync the Xbox accounts: {:?}", why), ErrorContext::default());
+            }
+        }
+    });
+    let prune_ctx = ctx.clone();
+    let prune_db = db.clone();
+    tokio::spawn(async move {
+        let retention = match prune_ctx.data.read().await.get::<HistoryRetention>().copied() {
+            Some(retention) => retention,
+            None => return,
+        };
+        let mut interval = interval(Duration::from_secs(PRUNE_INTERVAL));
+        loop {
+            interval.tick().await;
+            match prune_db.prune_history(&(Utc::now().timestamp() - retention * DAY)).await {
+                Ok(pruned) => info!("Pruned {} sessions older than {} days", pruned, retention),
+                Err(why) => {
+                    error!("Cannot prune the session history: {:?}", why);
+                    errors::report(&format!("Cannot prune the session history: {:?}", why), ErrorContext::default());
+                },
+            }
+        }
+    });
+    let goals_ctx = ctx.clone();
+    let goals_db = db.clone();
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(GOAL_CHECK_INTERVAL));
+        loop {
+            interval.tick().await;
+            if let Err(why) = reports::notify_goals(&goals_ctx, &goals_db, &Utc::now().timestamp()).await {
+                error!("Cannot check the goals: {:?}", why);
+                errors::report(&format!("Cannot check the goals: {:?}", why), ErrorContext::default());
+            }
+        }
+    });
+    let digests_ctx = ctx.clone();
+    let digests_db = db.clone();
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(DIGEST_CHECK_INTERVAL));
+        // Weeks which started before the bot did are skipped
+        let mut since = Utc::now().timestamp();
+        loop {
+            interval.tick().await;
+            let currenttime = Utc::now().timestamp();
+            if let Err(why) = reports::send_digests(&digests_ctx, &digests_db, &since, &currenttime).await {
+                error!("Cannot send the weekly digests: {:?}", why);
+                errors::report(&format!("Cannot send the weekly digests: {:?}", why), ErrorContext::default());
+            }
+            since = currenttime;
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            let next_day = period_start("today", Tz::UTC).unwrap() + DAY;
+            let wait = u64::try_from(next_day - Utc::now().timestamp()).unwrap_or(0);
+            info!("Next reports in {}s", wait);
+            sleep(Duration::from_secs(wait)).await;
+            if let Err(why) = reports::post_reports(&ctx, &db, &config, &next_day).await {
+                error!("Cannot post the reports: {:?}", why);
+                errors::report(&format!("Cannot post the reports: {:?}", why), ErrorContext::default());
+            }
+        }
+    });
+}
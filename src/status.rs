@@ -0,0 +1,19 @@
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::prelude::{Mutex, TypeMapKey};
+use std::sync::Arc;
+use std::time::Instant;
+
+
+// Gives the commands access to the shards, for their latency
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+// When the bot was started, for its uptime
+pub struct StartedAt;
+
+impl TypeMapKey for StartedAt {
+    type Value = Instant;
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::Value;
+use serenity::prelude::TypeMapKey;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::db::Database;
+
+
+// The source tag of the playtime imported from Steam
+pub const STEAM: &str = "steam";
+
+// Client of the Steam Web API
+pub struct Steam {
+    http: Client,
+    api_key: String,
+}
+
+impl TypeMapKey for Steam {
+    type Value = Arc<Steam>;
+}
+
+impl Steam {
+    pub fn new(api_key: String) -> Self {
+        return Steam { http: Client::new(), api_key };
+    }
+
+    // Accepts a SteamID64 or the custom name of a profile URL
+    pub async fn resolve_id(&self, steam_id: &str) -> Result<Option<String>> {
+        if steam_id.len() == 17 && steam_id.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Some(steam_id.to_string()));
+        }
+        let response: Value = self.http.get("https://api.steampowered.com/ISteamUser/ResolveVanityURL/v1/")
+            .query(&[("key", self.api_key.as_str()), ("vanityurl", steam_id)])
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        return Ok(response["response"]["steamid"].as_str().map(|steam_id| steam_id.to_string()));
+    }
+
+    // Returns (game name, playtime in seconds) pairs of the played games, None when the profile's game details are private
+    pub async fn get_playtime(&self, steam_id: &str) -> Result<Option<Vec<(String, i64)>>> {
+        let response: Value = self.http.get("https://api.steampowered.com/IPlayerService/GetOwnedGames/v1/")
+            .query(&[("key", self.api_key.as_str()), ("steamid", steam_id), ("include_appinfo", "1"), ("include_played_free_games", "1")])
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        let games = match response["response"]["games"].as_array() {
+            Some(games) => games,
+            None => return Ok(None),
+        };
+        // Steam counts the playtime in minutes
+        return Ok(Some(games.iter()
+            .filter_map(|game| Some((game["name"].as_str()?.to_string(), game["playtime_forever"].as_i64()? * 60)))
+            .filter(|(_, playtime)| *playtime > 0)
+            .collect()));
+    }
+}
+
+// Replaces the user's Steam playtime with the current one
+// Returns how many games were imported and their playtime, None when the profile's game details are private
+pub async fn import(db: &Database, steam: &Steam, user_id: &i64, steam_id: &str) -> Result<Option<(usize, i64)>> {
+    let entries = match steam.get_playtime(steam_id).await? {
+        Some(entries) => entries,
+        None => return Ok(None),
+    };
+    db.replace_imported_entries(user_id, STEAM, &entries).await?;
+    info!("Imported {} Steam games of {:?}", entries.len(), user_id);
+    return Ok(Some((entries.len(), entries.iter().map(|(_, playtime)| playtime).sum())));
+}
+
+// Imports the playtime of every linked Steam account again
+pub async fn refresh_imports(db: &Database, steam: &Steam) -> Result<()> {
+    for (user_id, steam_id) in db.get_linked_accounts(STEAM).await? {
+        if db.is_opted_out(&user_id).await? {
+            continue;
+        }
+        // A profile turned private keeps its last import
+        match import(db, steam, &user_id, &steam_id).await {
+            Ok(Some(_)) => {},
+            Ok(None) => warn!("The Steam profile of {:?} is private", user_id),
+            Err(why) => warn!("Cannot import the Steam playtime of {:?}: {:?}", user_id, why),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::{redirect, Client, Url};
+use serde_json::{json, Value};
+use serenity::model::id::UserId;
+use serenity::prelude::{Context, TypeMapKey};
+use std::convert::TryFrom;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::{sleep, Duration};
+use tracing::{error, warn};
+
+use crate::commands::can_view;
+use crate::db::Database;
+
+
+const MAX_ATTEMPTS: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Doubled after every failed attempt
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+struct Delivery {
+    url: String,
+    payload: Value,
+    attempt: u32,
+}
+
+// Sends the events to the guilds' webhooks one at a time, failed deliveries are queued again later
+pub struct WebhookQueue {
+    sender: UnboundedSender<Delivery>,
+}
+
+impl TypeMapKey for WebhookQueue {
+    type Value = Arc<WebhookQueue>;
+}
+
+impl WebhookQueue {
+    pub fn start() -> Self {
+        let (sender, mut receiver) = unbounded_channel::<Delivery>();
+        let retry_sender = sender.clone();
+        tokio::spawn(async move {
+            // A redirect could lead to a host the URL was checked not to be
+            let http = Client::builder().timeout(REQUEST_TIMEOUT).redirect(redirect::Policy::none()).build().expect("Err creating the webhook client");
+            while let Some(delivery) = receiver.recv().await {
+                // The domain may resolve to another address since the webhook was added
+                if !is_allowed(&delivery.url).await {
+                    error!("Not delivering to the webhook {}, it doesn't resolve to a public address", delivery.url);
+                    continue;
+                }
+                let result = http.post(&delivery.url).json(&delivery.payload).send().await
+                    .and_then(|response| response.error_for_status());
+                if let Err(why) = result {
+                    if delivery.attempt + 1 >= MAX_ATTEMPTS {
+                        error!("Giving up on the webhook {}: {:?}", delivery.url, why);
+                        continue;
+                    }
+                    warn!("Cannot deliver to the webhook {}, retrying: {:?}", delivery.url, why);
+                    let delay = RETRY_DELAY * 2u32.pow(delivery.attempt);
+                    let retry_sender = retry_sender.clone();
+                    tokio::spawn(async move {
+                        sleep(delay).await;
+                        let _ = retry_sender.send(Delivery { attempt: delivery.attempt + 1, ..delivery });
+                    });
+                }
+            }
+        });
+        return WebhookQueue { sender };
+    }
+
+    fn send(&self, url: String, payload: Value) {
+        let _ = self.sender.send(Delivery { url, payload, attempt: 0 });
+    }
+}
+
+// Webhooks must use HTTPS and reach a public address, not the bot's own network like localhost or the cloud metadata address
+pub async fn is_allowed(url: &str) -> bool {
+    let url = match Url::parse(url) {
+        Ok(url) if url.scheme() == "https" => url,
+        _ => return false,
+    };
+    let host = match url.host_str() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_lowercase(),
+        None => return false,
+    };
+    if host == "localhost" || host.ends_with(".localhost") {
+        return false;
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return is_public(ip);
+    }
+    // Every address of the domain must be public, nothing is sent when it doesn't resolve
+    return match tokio::net::lookup_host((host.as_str(), url.port_or_known_default().unwrap_or(443))).await {
+        Ok(addresses) => {
+            let addresses: Vec<IpAddr> = addresses.map(|address| address.ip()).collect();
+            !addresses.is_empty() && addresses.into_iter().all(is_public)
+        },
+        Err(_) => false,
+    };
+}
+
+// Loopback, private, link-local and other reserved addresses aren't public
+fn is_public(ip: IpAddr) -> bool {
+    return match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 100.64.0.0/10 is shared by carrier-grade NATs
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+                || ip.is_documentation() || first == 0 || (first == 100 && second & 0xc0 == 64))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            // fc00::/7 are the unique local addresses, fe80::/10 the link-local ones
+            None => !(ip.is_loopback() || ip.is_unspecified() || ip.segments()[0] & 0xfe00 == 0xfc00 || ip.segments()[0] & 0xffc0 == 0xfe80),
+        },
+    };
+}
+
+// Queues an event for every webhook of the guild, `data` is merged into the payload
+pub async fn dispatch(ctx: &Context, db: &Database, guild_id: &i64, user_id: &i64, event: &str, data: Value) -> Result<()> {
+    let queue = match ctx.data.read().await.get::<WebhookQueue>().cloned() {
+        Some(queue) => queue,
+        None => return Ok(()),
+    };
+    let webhooks = db.get_webhooks(guild_id).await?;
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+    // The events leave Discord, so only the ones of users whose stats are public are sent
+    if !can_view(db, ctx, None, None, UserId(u64::try_from(*user_id)?)).await? {
+        return Ok(());
+    }
+    let mut payload = json!({
+        "event": event,
+        "guild_id": guild_id.to_string(),
+        "user_id": user_id.to_string(),
+        "timestamp": Utc::now().timestamp(),
+    });
+    if let (Some(payload), Value::Object(data)) = (payload.as_object_mut(), data) {
+        payload.extend(data);
+    }
+    for (_, url) in webhooks {
+        queue.send(url, payload.clone());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_allowed;
+
+    #[tokio::test]
+    async fn only_allows_https_urls_of_public_hosts() {
+        assert!(is_allowed("https://93.184.216.34/hook").await);
+        assert!(!is_allowed("http://93.184.216.34/hook").await);
+        assert!(!is_allowed("https://localhost/hook").await);
+        assert!(!is_allowed("https://127.0.0.1:8080/hook").await);
+        assert!(!is_allowed("https://10.0.0.1/hook").await);
+        assert!(!is_allowed("https://192.168.1.1/hook").await);
+        assert!(!is_allowed("https://169.254.169.254/latest/meta-data").await);
+        assert!(!is_allowed("https://[::1]/hook").await);
+        assert!(!is_allowed("https://[fd00::1]/hook").await);
+        assert!(!is_allowed("https://[::ffff:127.0.0.1]/hook").await);
+        assert!(!is_allowed("not a url").await);
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Result};
+use sqlx::{query, PgPool};
+use std::collections::HashSet;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+use crate::db::RichPresence;
+
+
+// Requests written by a single transaction at most
+const MAX_BATCH_SIZE: usize = 500;
+
+// The rich presence of a running session is updated by each registration
+pub struct Registration {
+    pub user_id: i64,
+    pub game_id: i64,
+    pub starttime: i64,
+    pub rich_presence: RichPresence,
+}
+
+// A session being saved, its playtime is only added when `kept` is set
+pub struct Ending {
+    pub user_id: i64,
+    pub game_id: i64,
+    pub starttime: i64,
+    pub endtime: i64,
+    pub kept: bool,
+    pub rich_presence: RichPresence,
+}
+
+enum Request {
+    // Replies whether the session was started
+    Register(Registration, oneshot::Sender<Result<bool>>),
+    End(Vec<Ending>, oneshot::Sender<Result<()>>),
+}
+
+// Writes the game sessions of the presence updates, the requests queued while a batch is written go in the next one
+// so a busy server writes a few multi-row statements instead of several queries per presence update
+#[derive(Clone)]
+pub struct PresenceWriter {
+    sender: UnboundedSender<Request>,
+}
+
+impl PresenceWriter {
+    pub fn start(pool: PgPool) -> Self {
+        let (sender, mut receiver) = unbounded_channel::<Request>();
+        tokio::spawn(async move {
+            while let Some(request) = receiver.recv().await {
+                let mut batch = vec![request];
+                while batch.len() < MAX_BATCH_SIZE {
+                    match receiver.try_recv() {
+                        Ok(request) => batch.push(request),
+                        Err(_) => break,
+                    }
+                }
+                write_batch(&pool, batch).await;
+            }
+        });
+        return PresenceWriter { sender };
+    }
+
+    // Returns whether a session was started, an already running session is kept as is
+    pub async fn register(&self, registration: Registration) -> Result<bool> {
+        let (reply, response) = oneshot::channel();
+        self.sender.send(Request::Register(registration, reply)).map_err(|_| anyhow!("The presence writer stopped"))?;
+        return response.await?;
+    }
+
+    // Removes the sessions and adds the playtime of the kept ones
+    pub async fn end(&self, endings: Vec<Ending>) -> Result<()> {
+        if endings.is_empty() {
+            return Ok(());
+        }
+        let (reply, response) = oneshot::channel();
+        self.sender.send(Request::End(endings, reply)).map_err(|_| anyhow!("The presence writer stopped"))?;
+        return response.await?;
+    }
+}
+
+async fn write_batch(pool: &PgPool, batch: Vec<Request>) {
+    let size = batch.len();
+    let mut registrations: Vec<(Registration, oneshot::Sender<Result<bool>>)> = Vec::new();
+    let mut endings: Vec<(Vec<Ending>, oneshot::Sender<Result<()>>)> = Vec::new();
+    for request in batch {
+        match request {
+            Request::Register(registration, reply) => registrations.push((registration, reply)),
+            Request::End(ending, reply) => endings.push((ending, reply)),
+        }
+    }
+    let registered: Vec<&Registration> = registrations.iter().map(|(registration, _)| registration).collect();
+    let ended: Vec<&Ending> = endings.iter().flat_map(|(endings, _)| endings).collect();
+    match write(pool, &registered, &ended).await {
+        Ok(mut started) => {
+            for (registration, reply) in registrations {
+                // Only the first of the same registrations started the session
+                let _ = reply.send(Ok(started.remove(&(registration.user_id, registration.game_id))));
+            }
+            for (_, reply) in endings {
+                let _ = reply.send(Ok(()));
+            }
+        },
+        Err(why) => {
+            error!("Cannot write a batch of {} presence updates: {:?}", size, why);
+            for (_, reply) in registrations {
+                let _ = reply.send(Err(anyhow!("Cannot register the session: {}", why)));
+            }
+            for (_, reply) in endings {
+                let _ = reply.send(Err(anyhow!("Cannot save the sessions: {}", why)));
+            }
+        },
+    }
+}
+
+// Returns the (user_id, game_id) of the started sessions
+async fn write(pool: &PgPool, registrations: &[&Registration], endings: &[&Ending]) -> Result<HashSet<(i64, i64)>> {
+    let mut transaction = pool.begin().await?;
+    // Sessions are ended first, a game restarted in the same batch gets a new session
+    if !endings.is_empty() {
+        let kept: Vec<&&Ending> = endings.iter().filter(|ending| ending.kept).collect();
+        if !kept.is_empty() {
+            let user_ids: Vec<i64> = kept.iter().map(|ending| ending.user_id).collect();
+            let game_ids: Vec<i64> = kept.iter().map(|ending| ending.game_id).collect();
+            let starttimes: Vec<i64> = kept.iter().map(|ending| ending.starttime).collect();
+            let endtimes: Vec<i64> = kept.iter().map(|ending| ending.endtime).collect();
+            let states: Vec<Option<String>> = kept.iter().map(|ending| ending.rich_presence.state.clone()).collect();
+            let details: Vec<Option<String>> = kept.iter().map(|ending| ending.rich_presence.details.clone()).collect();
+            let party_sizes: Vec<Option<i64>> = kept.iter().map(|ending| ending.rich_presence.party_size).collect();
+            query!("INSERT INTO session_history (user_id, game_id, starttime, endtime, duration, state, details, party_size)
+                   SELECT user_id, game_id, starttime, endtime, endtime-starttime, state, details, party_size
+                   FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::BIGINT[], $4::BIGINT[], $5::TEXT[], $6::TEXT[], $7::BIGINT[])
+                   AS ended(user_id, game_id, starttime, endtime, state, details, party_size);", &user_ids, &game_ids, &starttimes, &endtimes, &states as &[Option<String>], &details as &[Option<String>], &party_sizes as &[Option<i64>])
+                .execute(&mut *transaction).await?;
+            // A row can't be updated twice by the same statement, the playtimes of a game are summed first
+            query!("INSERT INTO game_entries (user_id, game_id, playtime)
+                   SELECT user_id, game_id, SUM(endtime-starttime)::BIGINT
+                   FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::BIGINT[], $4::BIGINT[]) AS ended(user_id, game_id, starttime, endtime)
+                   GROUP BY user_id, game_id
+                   ON CONFLICT (user_id, game_id) DO UPDATE SET playtime=game_entries.playtime+EXCLUDED.playtime;", &user_ids, &game_ids, &starttimes, &endtimes)
+                .execute(&mut *transaction).await?;
+        }
+        // The sessions are deleted with the playtime they added, the discarded ones as well
+        let user_ids: Vec<i64> = endings.iter().map(|ending| ending.user_id).collect();
+        let game_ids: Vec<i64> = endings.iter().map(|ending| ending.game_id).collect();
+        query!("DELETE FROM game_sessions WHERE (user_id, game_id) IN (SELECT * FROM UNNEST($1::BIGINT[], $2::BIGINT[]));", &user_ids, &game_ids)
+            .execute(&mut *transaction).await?;
+    }
+    let mut started: HashSet<(i64, i64)> = HashSet::new();
+    if !registrations.is_empty() {
+        let user_ids: Vec<i64> = registrations.iter().map(|registration| registration.user_id).collect();
+        let game_ids: Vec<i64> = registrations.iter().map(|registration| registration.game_id).collect();
+        let starttimes: Vec<i64> = registrations.iter().map(|registration| registration.starttime).collect();
+        // A session can't start before the previous one ended, the presence start is still the same after a restart that saved it
+        let rows = query!("INSERT INTO game_sessions (user_id, game_id, starttime)
+                          SELECT registered.user_id, registered.game_id, GREATEST(registered.starttime, MAX(session_history.endtime))
+                          FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::BIGINT[]) AS registered(user_id, game_id, starttime)
+                          LEFT JOIN session_history ON session_history.user_id=registered.user_id AND session_history.game_id=registered.game_id
+                          GROUP BY registered.user_id, registered.game_id, registered.starttime
+                          ON CONFLICT DO NOTHING
+                          RETURNING user_id, game_id;", &user_ids, &game_ids, &starttimes)
+                                            .fetch_all(&mut *transaction).await?;
+        for row in rows {
+            info!(user_id = row.user_id, game_id = row.game_id, "Registered the session");
+            started.insert((row.user_id, row.game_id));
+        }
+        // Every registered session takes the latest rich presence, a game changing it twice in a batch keeps either one
+        let states: Vec<Option<String>> = registrations.iter().map(|registration| registration.rich_presence.state.clone()).collect();
+        let details: Vec<Option<String>> = registrations.iter().map(|registration| registration.rich_presence.details.clone()).collect();
+        let party_sizes: Vec<Option<i64>> = registrations.iter().map(|registration| registration.rich_presence.party_size).collect();
+        query!("UPDATE game_sessions SET state=registered.state, details=registered.details, party_size=registered.party_size
+               FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::TEXT[], $4::TEXT[], $5::BIGINT[]) AS registered(user_id, game_id, state, details, party_size)
+               WHERE game_sessions.user_id=registered.user_id AND game_sessions.game_id=registered.game_id;", &user_ids, &game_ids, &states as &[Option<String>], &details as &[Option<String>], &party_sizes as &[Option<i64>])
+            .execute(&mut *transaction).await?;
+    }
+    transaction.commit().await?;
+    return Ok(started);
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::{json, Value};
+use serenity::prelude::TypeMapKey;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::db::Database;
+
+
+// The source tag of the playtime imported from Xbox
+pub const XBOX: &str = "xbox";
+
+// Client of the OpenXBL API, which proxies Xbox Live for a single API key
+pub struct Xbox {
+    http: Client,
+    api_key: String,
+}
+
+impl TypeMapKey for Xbox {
+    type Value = Arc<Xbox>;
+}
+
+impl Xbox {
+    pub fn new(api_key: String) -> Self {
+        return Xbox { http: Client::new(), api_key };
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        return Ok(self.http.get(format!("https://xbl.io/api/v2/{}", path))
+            .header("X-Authorization", &self.api_key)
+            .send().await?
+            .error_for_status()?
+            .json().await?);
+    }
+
+    // Returns the XUID of a gamertag
+    pub async fn resolve_xuid(&self, gamertag: &str) -> Result<Option<String>> {
+        let response = self.get(&format!("search/{}", gamertag)).await?;
+        return Ok(response["people"].as_array()
+            .and_then(|people| people.iter().find(|person| person["gamertag"].as_str().map_or(false, |name| name.eq_ignore_ascii_case(gamertag))))
+            .and_then(|person| person["xuid"].as_str())
+            .map(|xuid| xuid.to_string()));
+    }
+
+    // Returns (game name, playtime in seconds) pairs of the played games, empty when the profile is private
+    pub async fn get_playtime(&self, xuid: &str) -> Result<Vec<(String, i64)>> {
+        let history = self.get(&format!("player/titleHistory/{}", xuid)).await?;
+        let titles: Vec<(String, String)> = history["titles"].as_array().map_or(Vec::new(), |titles| titles.iter()
+            .filter_map(|title| Some((title["titleId"].as_str()?.to_string(), title["name"].as_str()?.to_string())))
+            .collect());
+        if titles.is_empty() {
+            return Ok(Vec::new());
+        }
+        // The title history has no playtime, it is a stat of each title
+        let body = json!({
+            "arrangebyfield": "xuid",
+            "xuids": [xuid],
+            "stats": titles.iter().map(|(title_id, _)| json!({"name": "MinutesPlayed", "titleid": title_id})).collect::<Vec<Value>>(),
+        });
+        let stats: Value = self.http.post("https://xbl.io/api/v2/player/stats")
+            .header("X-Authorization", &self.api_key)
+            .json(&body)
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        let minutes: Vec<(String, i64)> = stats["statlistscollection"][0]["stats"].as_array().map_or(Vec::new(), |stats| stats.iter()
+            .filter_map(|stat| Some((stat["titleid"].as_str()?.to_string(), stat["value"].as_str()?.parse::<i64>().ok()?)))
+            .collect());
+        return Ok(titles.into_iter()
+            .filter_map(|(title_id, name)| minutes.iter()
+                .find(|(stat_title_id, _)| *stat_title_id == title_id)
+                .map(|(_, minutes)| (name, minutes * 60)))
+            .filter(|(_, playtime)| *playtime > 0)
+            .collect());
+    }
+}
+
+// Replaces the user's Xbox playtime with the current one
+// Returns how many games were imported and their playtime
+pub async fn import(db: &Database, xbox: &Xbox, user_id: &i64, xuid: &str) -> Result<(usize, i64)> {
+    let entries = xbox.get_playtime(xuid).await?;
+    // An empty history is more likely a private profile than no game at all, keep the last import
+    if !entries.is_empty() {
+        db.replace_imported_entries(user_id, XBOX, &entries).await?;
+        info!("Imported {} Xbox games of {:?}", entries.len(), user_id);
+    }
+    return Ok((entries.len(), entries.iter().map(|(_, playtime)| playtime).sum()));
+}
+
+// Imports the playtime of every linked Xbox account again
+pub async fn refresh_imports(db: &Database, xbox: &Xbox) -> Result<()> {
+    for (user_id, xuid) in db.get_linked_accounts(XBOX).await? {
+        if db.is_opted_out(&user_id).await? {
+            continue;
+        }
+        if let Err(why) = import(db, xbox, &user_id, &xuid).await {
+            warn!("Cannot import the Xbox playtime of {:?}: {:?}", user_id, why);
+        }
+    }
+    Ok(())
+}